@@ -35,7 +35,7 @@ async fn upgrade_device_certs_to_creds(
     creds_path: &str,
 ) -> Result<Device> {
     // ---8<--- [start: upgrade_device_certs_to_creds]
-    let device = Device::from_path(creds_path);
+    let device = Device::from_path(creds_path)?;
     let upgraded = device.upgrade(scheduler, signer).await?;
     save_to_file("credentials_upgraded.gfs", &upgraded.to_bytes())?;
     // ---8<--- [end: upgrade_device_certs_to_creds]
@@ -88,7 +88,7 @@ async fn register_node(seed: Vec<u8>, developer_creds: Nobody) -> Result<(Schedu
     // ownership of the `node_id`
     let registration_response = scheduler.register(&signer, None).await?;
     // ---8<--- [start: device_creds]
-    let device_creds = Device::from_bytes(registration_response.creds);
+    let device_creds = Device::from_bytes(registration_response.creds)?;
     save_to_file("credentials.gfs", &device_creds.to_bytes())?;
     // ---8<--- [end: device_creds]
     // ---8<--- [end: register_node]
@@ -104,7 +104,7 @@ async fn get_node(scheduler: &Scheduler<Device>) -> Result<ClnClient> {
 
 async fn start_node(device_creds_file_path: &str) -> Result<(cln::GetinfoResponse, cln::ListpeersResponse, cln::InvoiceResponse)> {
     // ---8<--- [start: start_node]
-    let creds = Device::from_path(device_creds_file_path);
+    let creds = Device::from_path(device_creds_file_path)?;
     let scheduler = Scheduler::new(NETWORK, creds.clone()).await?;
     let mut node: ClnClient = scheduler.node().await?;
     // ---8<--- [end: start_node]
@@ -149,7 +149,7 @@ async fn recover_node(dev_creds: Nobody) -> Result<(Scheduler<Nobody>, Device, S
     let scheduler = Scheduler::new(NETWORK, dev_creds).await?;
     let recover_response = scheduler.recover(&signer).await?;
     // ---8<--- [end: recover_node]
-    let device_creds = Device::from_bytes(recover_response.creds);
+    let device_creds = Device::from_bytes(recover_response.creds)?;
     save_to_file("credentials.gfs", &device_creds.to_bytes())?;
     Ok((scheduler, device_creds, signer))
 }
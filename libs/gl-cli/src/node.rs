@@ -279,7 +279,7 @@ async fn init_handler<P: AsRef<Path>>(config: Config<P>, mnemonic: Option<String
 
 async fn get_node<P: AsRef<Path>>(config: Config<P>) -> Result<gl_client::node::ClnClient> {
     let creds_path = config.data_dir.as_ref().join(CREDENTIALS_FILE_NAME);
-    let creds = match util::read_credentials(&creds_path) {
+    let creds = match util::read_credentials(&creds_path)? {
         Some(c) => c,
         None => {
             return Err(Error::CredentialsNotFoundError(format!(
@@ -298,7 +298,7 @@ async fn get_node<P: AsRef<Path>>(config: Config<P>) -> Result<gl_client::node::
 
 async fn log<P: AsRef<Path>>(config: Config<P>) -> Result<()> {
     let creds_path = config.data_dir.as_ref().join(CREDENTIALS_FILE_NAME);
-    let creds = match util::read_credentials(&creds_path) {
+    let creds = match util::read_credentials(&creds_path)? {
         Some(c) => c,
         None => {
             return Err(Error::CredentialsNotFoundError(format!(
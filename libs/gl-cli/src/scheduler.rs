@@ -124,7 +124,8 @@ async fn register_handler<P: AsRef<Path>>(
 
     // Save the device credentials to file.
     let creds_path = config.data_dir.as_ref().join(CREDENTIALS_FILE_NAME);
-    let device_creds = credentials::Device::from_bytes(res.creds);
+    let device_creds = credentials::Device::from_bytes(res.creds)
+        .map_err(|e| Error::custom(format!("Received invalid credentials: {}", e)))?;
     util::write_credentials(&creds_path, &device_creds.to_bytes())?;
     println!("Credentials saved at {}", creds_path.display());
 
@@ -165,7 +166,8 @@ async fn recover_handler<P: AsRef<Path>>(config: Config<P>) -> Result<()> {
 
     // Save the device credentials to file.
     let creds_path = config.data_dir.as_ref().join(CREDENTIALS_FILE_NAME);
-    let device_creds = credentials::Device::from_bytes(res.creds);
+    let device_creds = credentials::Device::from_bytes(res.creds)
+        .map_err(|e| Error::custom(format!("Received invalid credentials: {}", e)))?;
     util::write_credentials(&creds_path, &device_creds.to_bytes())?;
     println!("Credentials saved at {}", creds_path.display());
 
@@ -174,7 +176,7 @@ async fn recover_handler<P: AsRef<Path>>(config: Config<P>) -> Result<()> {
 
 async fn schedule_handler<P: AsRef<Path>>(config: Config<P>) -> Result<()> {
     let creds_path = config.data_dir.as_ref().join(CREDENTIALS_FILE_NAME);
-    let creds = util::read_credentials(&creds_path);
+    let creds = util::read_credentials(&creds_path)?;
     if creds.is_none() {
         println!("Could not find credentials at {}", creds_path.display());
         return Err(Error::credentials_not_found(format!(
@@ -280,7 +282,7 @@ async fn pair_device_handler<P: AsRef<Path>>(
 
 async fn export_handler<P: AsRef<Path>>(config: Config<P>) -> Result<()> {
     let creds_path = config.data_dir.as_ref().join(CREDENTIALS_FILE_NAME);
-    let creds = util::read_credentials(&creds_path);
+    let creds = util::read_credentials(&creds_path)?;
     if creds.is_none() {
         println!("Could not find credentials at {}", creds_path.display());
         return Err(Error::credentials_not_found(format!(
@@ -337,7 +339,7 @@ async fn approve_pairing_handler<P: AsRef<Path>>(
     pairing_data: &str,
 ) -> Result<()> {
     let creds_path = config.data_dir.as_ref().join(CREDENTIALS_FILE_NAME);
-    let creds = util::read_credentials(&creds_path);
+    let creds = util::read_credentials(&creds_path)?;
     if creds.is_none() {
         println!("Could not find credentials at {}", creds_path.display());
         return Err(Error::credentials_not_found(format!(
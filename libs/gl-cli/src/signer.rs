@@ -1,7 +1,10 @@
 use crate::error::{Error, Result};
 use crate::util;
-use clap::{Subcommand, ValueEnum};
+use clap::{Args, Subcommand, ValueEnum};
 use core::fmt::Debug;
+use gl_client::signer::policy::SpendingPolicy;
+use gl_client::signer::audit::{self, AuditConfig, AuditEntry, Decision};
+use gl_client::signer::store::FileStateStore;
 use gl_client::signer::{
     RecoverableChannel, CLNBackup, CLNBackupOptions, Signer,
     SignerBackupConfig, SignerBackupSnapshot, SignerBackupStrategy, SignerConfig,
@@ -10,6 +13,7 @@ use gl_client::signer::{
 use lightning_signer::bitcoin::Network;
 use serde::Serialize;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use tokio::{join, signal};
 use util::{CREDENTIALS_FILE_NAME, SEED_FILE_NAME};
 
@@ -60,6 +64,29 @@ impl Default for BackupInspectFormat {
     }
 }
 
+#[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum)]
+pub enum AuditFormat {
+    Json,
+    Text,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum)]
+pub enum AuditDecisionArg {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+impl From<AuditDecisionArg> for Decision {
+    fn from(value: AuditDecisionArg) -> Self {
+        match value {
+            AuditDecisionArg::Pending => Decision::Pending,
+            AuditDecisionArg::Approved => Decision::Approved,
+            AuditDecisionArg::Rejected => Decision::Rejected,
+        }
+    }
+}
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum)]
 pub enum BackupConvertFormat {
     Cln,
@@ -71,22 +98,68 @@ impl Default for BackupConvertFormat {
     }
 }
 
+/// Options of the `run` command.
+#[derive(Args, Debug)]
+pub struct RunArgs {
+    #[arg(long, value_enum, default_value_t = StateSignatureModeArg::Soft)]
+    pub state_signature_mode: StateSignatureModeArg,
+    #[arg(long = "state-override")]
+    pub state_override: Option<String>,
+    #[arg(long = "state-override-note")]
+    pub state_override_note: Option<String>,
+    #[arg(long = "backup-path")]
+    pub backup_path: Option<PathBuf>,
+    #[arg(long = "backup-strategy", value_enum)]
+    pub backup_strategy: Option<BackupStrategyArg>,
+    #[arg(long = "backup-periodic-updates")]
+    pub backup_periodic_updates: Option<u32>,
+    /// Path to a TOML or JSON file with spending limits the
+    /// signer enforces
+    #[arg(long = "spending-policy")]
+    pub spending_policy: Option<PathBuf>,
+    /// Path to a file the signer persists its state in, to
+    /// detect a node that sends stale state
+    #[arg(long = "state-store")]
+    pub state_store: Option<PathBuf>,
+    /// Refuse requests with state older than the persisted state
+    #[arg(long = "reject-stale-state", requires = "state_store")]
+    pub reject_stale_state: bool,
+    /// Identifies this signer to the node, when running multiple
+    /// signers for the same node
+    #[arg(long = "signer-id")]
+    pub signer_id: Option<String>,
+    /// Directory to keep a hash-chained log of every approved and
+    /// rejected request in
+    #[arg(long = "audit-log")]
+    pub audit_log: Option<PathBuf>,
+}
+
 #[derive(Subcommand, Debug)]
 pub enum Command {
     /// Starts a signer that connects to greenlight
     Run {
-        #[arg(long, value_enum, default_value_t = StateSignatureModeArg::Soft)]
-        state_signature_mode: StateSignatureModeArg,
-        #[arg(long = "state-override")]
-        state_override: Option<String>,
-        #[arg(long = "state-override-note")]
-        state_override_note: Option<String>,
-        #[arg(long = "backup-path")]
-        backup_path: Option<PathBuf>,
-        #[arg(long = "backup-strategy", value_enum)]
-        backup_strategy: Option<BackupStrategyArg>,
-        #[arg(long = "backup-periodic-updates")]
-        backup_periodic_updates: Option<u32>,
+        #[command(flatten)]
+        args: RunArgs,
+    },
+    /// Queries and verifies the signer audit log
+    Audit {
+        /// Directory of the audit log
+        #[arg(long)]
+        path: PathBuf,
+        /// Only show entries with this decision
+        #[arg(long, value_enum)]
+        decision: Option<AuditDecisionArg>,
+        /// Only show entries for this `hsmd` message type
+        #[arg(long = "request-type")]
+        request_type: Option<u16>,
+        /// Only show the most recent entries
+        #[arg(long)]
+        limit: Option<usize>,
+        /// Check that the retained entries form an unbroken hash chain
+        #[arg(long)]
+        verify: bool,
+        #[arg(long, value_enum, default_value_t = AuditFormat::Json)]
+        format: AuditFormat,
     },
     /// Inspects a local signer backup file
     InspectBackup {
@@ -112,25 +185,15 @@ pub enum Command {
 
 pub async fn command_handler<P: AsRef<Path>>(cmd: Command, config: Config<P>) -> Result<()> {
     match cmd {
-        Command::Run {
-            state_signature_mode,
-            state_override,
-            state_override_note,
-            backup_path,
-            backup_strategy,
-            backup_periodic_updates,
-        } => {
-            run_handler(
-                config,
-                state_signature_mode,
-                state_override,
-                state_override_note,
-                backup_path,
-                backup_strategy,
-                backup_periodic_updates,
-            )
-            .await
-        }
+        Command::Run { args } => run_handler(config, args).await,
+        Command::Audit {
+            path,
+            decision,
+            request_type,
+            limit,
+            verify,
+            format,
+        } => audit_handler(&path, decision, request_type, limit, verify, format),
         Command::InspectBackup { path, format } => inspect_backup(&path, format),
         Command::ConvertBackup {
             path,
@@ -154,6 +217,84 @@ pub struct BackupInspectionReport {
     pub channels: Vec<RecoverableChannel>,
 }
 
+fn audit_handler(
+    path: &Path,
+    decision: Option<AuditDecisionArg>,
+    request_type: Option<u16>,
+    limit: Option<usize>,
+    verify: bool,
+    format: AuditFormat,
+) -> Result<()> {
+    let entries = audit::read(path).map_err(|e| {
+        Error::custom(format!(
+            "failed to read signer audit log {}: {}",
+            path.display(),
+            e
+        ))
+    })?;
+    if verify {
+        audit::verify(&entries)
+            .map_err(|e| Error::custom(format!("signer audit log failed verification: {}", e)))?;
+        eprintln!("Verified {} audit log entries", entries.len());
+    }
+
+    let entries = filter_audit_entries(entries, decision, request_type, limit);
+    match format {
+        AuditFormat::Json => {
+            let output = serde_json::to_string_pretty(&entries).map_err(Error::custom)?;
+            println!("{output}");
+        }
+        AuditFormat::Text => print!("{}", format_audit_entries_text(&entries)),
+    }
+    Ok(())
+}
+
+fn filter_audit_entries(
+    entries: Vec<AuditEntry>,
+    decision: Option<AuditDecisionArg>,
+    request_type: Option<u16>,
+    limit: Option<usize>,
+) -> Vec<AuditEntry> {
+    let mut entries: Vec<AuditEntry> = entries
+        .into_iter()
+        .filter(|e| decision.map(|d| e.decision == d.into()).unwrap_or(true))
+        .filter(|e| request_type.is_none() || e.request_type == request_type)
+        .collect();
+    if let Some(limit) = limit {
+        entries.drain(..entries.len().saturating_sub(limit));
+    }
+    entries
+}
+
+fn format_audit_entries_text(entries: &[AuditEntry]) -> String {
+    let mut output = String::new();
+    for entry in entries {
+        let decision = match entry.decision {
+            Decision::Pending => "pending",
+            Decision::Approved => "approved",
+            Decision::Rejected => "rejected",
+        };
+        let request_type = entry
+            .request_type
+            .map(|t| t.to_string())
+            .unwrap_or_else(|| "-".to_string());
+        output.push_str(&format!(
+            "{} {} request_id={} type={} {}\n",
+            entry.seq, entry.timestamp, entry.request_id, request_type, decision
+        ));
+        if let Some(resolution) = &entry.resolution {
+            output.push_str(&format!("  resolution: {resolution}\n"));
+        }
+        if let Some(reason) = &entry.reason {
+            output.push_str(&format!("  reason: {reason}\n"));
+        }
+        for ctx in &entry.context {
+            output.push_str(&format!("  context: {} rune_id={}\n", ctx.uri, ctx.rune_id));
+        }
+    }
+    output
+}
+
 fn inspect_backup(path: &Path, format: BackupInspectFormat) -> Result<()> {
     let report = inspect_backup_report(path)?;
 
@@ -315,15 +456,21 @@ fn format_strategy(strategy: SignerBackupStrategy) -> String {
     }
 }
 
-async fn run_handler<P: AsRef<Path>>(
-    config: Config<P>,
-    state_signature_mode: StateSignatureModeArg,
-    state_override: Option<String>,
-    state_override_note: Option<String>,
-    backup_path: Option<PathBuf>,
-    backup_strategy: Option<BackupStrategyArg>,
-    backup_periodic_updates: Option<u32>,
-) -> Result<()> {
+async fn run_handler<P: AsRef<Path>>(config: Config<P>, args: RunArgs) -> Result<()> {
+    let RunArgs {
+        state_signature_mode,
+        state_override,
+        state_override_note,
+        backup_path,
+        backup_strategy,
+        backup_periodic_updates,
+        spending_policy,
+        state_store,
+        reject_stale_state,
+        signer_id,
+        audit_log,
+    } = args;
+
     // Check if we can find a seed file, if we can not find one, we need to register first.
     let seed_path = config.data_dir.as_ref().join(SEED_FILE_NAME);
     let seed = util::read_seed(&seed_path);
@@ -339,7 +486,7 @@ async fn run_handler<P: AsRef<Path>>(
 
     // Initialize a signer and scheduler with default credentials.
     let creds_path = config.data_dir.as_ref().join(CREDENTIALS_FILE_NAME);
-    let creds = match util::read_credentials(&creds_path) {
+    let creds = match util::read_credentials(&creds_path)? {
         Some(c) => c,
         None => {
             return Err(Error::CredentialsNotFoundError(format!(
@@ -362,6 +509,10 @@ async fn run_handler<P: AsRef<Path>>(
         }
     });
     let backup = backup_config_from_args(backup_path, backup_strategy, backup_periodic_updates)?;
+    let spending_policy = spending_policy
+        .map(|path| SpendingPolicy::from_path(&path))
+        .transpose()
+        .map_err(|e| Error::custom(format!("Failed to load spending policy: {}", e)))?;
 
     let signer = Signer::new_with_config(
         seed,
@@ -370,10 +521,21 @@ async fn run_handler<P: AsRef<Path>>(
         SignerConfig {
             state_signature_mode: state_signature_mode.into(),
             state_signature_override,
+            spending_policy,
+            reject_stale_state,
+            signer_id,
+            audit: audit_log.map(AuditConfig::new),
             backup,
         },
     )
     .map_err(|e| Error::custom(format!("Failed to create signer: {}", e)))?;
+    let signer = match state_store {
+        Some(path) => signer
+            .with_state_store(Arc::new(FileStateStore::new(path)))
+            .await
+            .map_err(|e| Error::custom(format!("Failed to load signer state: {}", e)))?,
+        None => signer,
+    };
 
     let (tx, rx) = tokio::sync::mpsc::channel(1);
     let handle = tokio::spawn(async move {
@@ -428,9 +590,10 @@ fn backup_config_from_args(
 #[cfg(test)]
 mod tests {
     use super::{
-        backup_config_from_args, convert_backup_output, format_backup_report_text,
-        inspect_backup_report, BackupConvertFormat, BackupInspectFormat, BackupStrategyArg,
-        Command, SignerBackupStrategy, StateSignatureModeArg,
+        backup_config_from_args, convert_backup_output, filter_audit_entries,
+        format_audit_entries_text, format_backup_report_text, inspect_backup_report,
+        AuditDecisionArg, AuditEntry, AuditFormat, BackupConvertFormat, BackupInspectFormat,
+        BackupStrategyArg, Command, Decision, SignerBackupStrategy, StateSignatureModeArg,
     };
     use clap::{Parser, Subcommand};
     use serde_json::json;
@@ -452,20 +615,18 @@ mod tests {
     fn parse_run_mode_flag() {
         let cli = TestCli::parse_from(["test", "run", "--state-signature-mode", "hard"]);
         match cli.cmd {
-            Command::Run {
-                state_signature_mode,
-                state_override,
-                state_override_note,
-                backup_path,
-                backup_strategy,
-                backup_periodic_updates,
-            } => {
-                assert_eq!(state_signature_mode, StateSignatureModeArg::Hard);
-                assert!(state_override.is_none());
-                assert!(state_override_note.is_none());
-                assert!(backup_path.is_none());
-                assert!(backup_strategy.is_none());
-                assert!(backup_periodic_updates.is_none());
+            Command::Run { args } => {
+                assert_eq!(args.state_signature_mode, StateSignatureModeArg::Hard);
+                assert!(args.state_override.is_none());
+                assert!(args.state_override_note.is_none());
+                assert!(args.backup_path.is_none());
+                assert!(args.backup_strategy.is_none());
+                assert!(args.backup_periodic_updates.is_none());
+                assert!(args.spending_policy.is_none());
+                assert!(args.state_store.is_none());
+                assert!(!args.reject_stale_state);
+                assert!(args.signer_id.is_none());
+                assert!(args.audit_log.is_none());
             }
             _ => panic!("expected run command"),
         }
@@ -475,20 +636,18 @@ mod tests {
     fn run_mode_defaults_to_soft() {
         let cli = TestCli::parse_from(["test", "run"]);
         match cli.cmd {
-            Command::Run {
-                state_signature_mode,
-                state_override,
-                state_override_note,
-                backup_path,
-                backup_strategy,
-                backup_periodic_updates,
-            } => {
-                assert_eq!(state_signature_mode, StateSignatureModeArg::Soft);
-                assert!(state_override.is_none());
-                assert!(state_override_note.is_none());
-                assert!(backup_path.is_none());
-                assert!(backup_strategy.is_none());
-                assert!(backup_periodic_updates.is_none());
+            Command::Run { args } => {
+                assert_eq!(args.state_signature_mode, StateSignatureModeArg::Soft);
+                assert!(args.state_override.is_none());
+                assert!(args.state_override_note.is_none());
+                assert!(args.backup_path.is_none());
+                assert!(args.backup_strategy.is_none());
+                assert!(args.backup_periodic_updates.is_none());
+                assert!(args.spending_policy.is_none());
+                assert!(args.state_store.is_none());
+                assert!(!args.reject_stale_state);
+                assert!(args.signer_id.is_none());
+                assert!(args.audit_log.is_none());
             }
             _ => panic!("expected run command"),
         }
@@ -505,20 +664,18 @@ mod tests {
         let cli =
             WrapperCli::parse_from(["test", "signer", "run", "--state-signature-mode", "off"]);
         match cli.cmd {
-            RootCommand::Signer(Command::Run {
-                state_signature_mode,
-                state_override,
-                state_override_note,
-                backup_path,
-                backup_strategy,
-                backup_periodic_updates,
-            }) => {
-                assert_eq!(state_signature_mode, StateSignatureModeArg::Off);
-                assert!(state_override.is_none());
-                assert!(state_override_note.is_none());
-                assert!(backup_path.is_none());
-                assert!(backup_strategy.is_none());
-                assert!(backup_periodic_updates.is_none());
+            RootCommand::Signer(Command::Run { args }) => {
+                assert_eq!(args.state_signature_mode, StateSignatureModeArg::Off);
+                assert!(args.state_override.is_none());
+                assert!(args.state_override_note.is_none());
+                assert!(args.backup_path.is_none());
+                assert!(args.backup_strategy.is_none());
+                assert!(args.backup_periodic_updates.is_none());
+                assert!(args.spending_policy.is_none());
+                assert!(args.state_store.is_none());
+                assert!(!args.reject_stale_state);
+                assert!(args.signer_id.is_none());
+                assert!(args.audit_log.is_none());
             }
             _ => panic!("expected signer run"),
         }
@@ -537,23 +694,64 @@ mod tests {
             "debug session",
         ]);
         match cli.cmd {
-            Command::Run {
-                state_signature_mode,
-                state_override,
-                state_override_note,
-                backup_path,
-                backup_strategy,
-                backup_periodic_updates,
-            } => {
-                assert_eq!(state_signature_mode, StateSignatureModeArg::Hard);
+            Command::Run { args } => {
+                assert_eq!(args.state_signature_mode, StateSignatureModeArg::Hard);
                 assert_eq!(
-                    state_override.as_deref(),
+                    args.state_override.as_deref(),
                     Some("I_ACCEPT_OPERATOR_ASSISTED_STATE_OVERRIDE")
                 );
-                assert_eq!(state_override_note.as_deref(), Some("debug session"));
-                assert!(backup_path.is_none());
-                assert!(backup_strategy.is_none());
-                assert!(backup_periodic_updates.is_none());
+                assert_eq!(args.state_override_note.as_deref(), Some("debug session"));
+                assert!(args.backup_path.is_none());
+                assert!(args.backup_strategy.is_none());
+                assert!(args.backup_periodic_updates.is_none());
+                assert!(args.spending_policy.is_none());
+                assert!(args.state_store.is_none());
+                assert!(!args.reject_stale_state);
+                assert!(args.signer_id.is_none());
+                assert!(args.audit_log.is_none());
+            }
+            _ => panic!("expected run command"),
+        }
+    }
+
+    #[test]
+    fn parse_run_spending_policy_path() {
+        let cli = TestCli::parse_from(["test", "run", "--spending-policy", "policy.toml"]);
+        match cli.cmd {
+            Command::Run { args } => {
+                assert_eq!(args.spending_policy, Some(PathBuf::from("policy.toml")));
+            }
+            _ => panic!("expected run command"),
+        }
+    }
+
+    #[test]
+    fn parse_run_state_store() {
+        let cli = TestCli::parse_from([
+            "test",
+            "run",
+            "--state-store",
+            "signer_state.json",
+            "--reject-stale-state",
+        ]);
+        match cli.cmd {
+            Command::Run { args } => {
+                assert_eq!(args.state_store, Some(PathBuf::from("signer_state.json")));
+                assert!(args.reject_stale_state);
+            }
+            _ => panic!("expected run command"),
+        }
+
+        // Rejecting stale state needs a persisted state to compare with.
+        assert!(TestCli::try_parse_from(["test", "run", "--reject-stale-state"]).is_err());
+    }
+
+    #[test]
+    fn parse_run_signer_id() {
+        let cli = TestCli::parse_from(["test", "run", "--signer-id", "backup"]);
+        match cli.cmd {
+            Command::Run { args } => {
+                assert_eq!(args.signer_id.as_deref(), Some("backup"));
             }
             _ => panic!("expected run command"),
         }
@@ -563,15 +761,10 @@ mod tests {
     fn parse_run_backup_path_defaults_to_new_channels_only() {
         let cli = TestCli::parse_from(["test", "run", "--backup-path", "backup.json"]);
         match cli.cmd {
-            Command::Run {
-                backup_path,
-                backup_strategy,
-                backup_periodic_updates,
-                ..
-            } => {
-                assert_eq!(backup_path.as_deref(), Some(Path::new("backup.json")));
-                assert!(backup_strategy.is_none());
-                assert!(backup_periodic_updates.is_none());
+            Command::Run { args } => {
+                assert_eq!(args.backup_path.as_deref(), Some(Path::new("backup.json")));
+                assert!(args.backup_strategy.is_none());
+                assert!(args.backup_periodic_updates.is_none());
             }
             _ => panic!("expected run command"),
         }
@@ -588,15 +781,13 @@ mod tests {
             "new-channels-only",
         ]);
         match cli.cmd {
-            Command::Run {
-                backup_path,
-                backup_strategy,
-                backup_periodic_updates,
-                ..
-            } => {
-                assert_eq!(backup_path.as_deref(), Some(Path::new("backup.json")));
-                assert_eq!(backup_strategy, Some(BackupStrategyArg::NewChannelsOnly));
-                assert!(backup_periodic_updates.is_none());
+            Command::Run { args } => {
+                assert_eq!(args.backup_path.as_deref(), Some(Path::new("backup.json")));
+                assert_eq!(
+                    args.backup_strategy,
+                    Some(BackupStrategyArg::NewChannelsOnly)
+                );
+                assert!(args.backup_periodic_updates.is_none());
             }
             _ => panic!("expected run command"),
         }
@@ -615,15 +806,10 @@ mod tests {
             "10",
         ]);
         match cli.cmd {
-            Command::Run {
-                backup_path,
-                backup_strategy,
-                backup_periodic_updates,
-                ..
-            } => {
-                assert_eq!(backup_path.as_deref(), Some(Path::new("backup.json")));
-                assert_eq!(backup_strategy, Some(BackupStrategyArg::Periodic));
-                assert_eq!(backup_periodic_updates, Some(10));
+            Command::Run { args } => {
+                assert_eq!(args.backup_path.as_deref(), Some(Path::new("backup.json")));
+                assert_eq!(args.backup_strategy, Some(BackupStrategyArg::Periodic));
+                assert_eq!(args.backup_periodic_updates, Some(10));
             }
             _ => panic!("expected run command"),
         }
@@ -703,6 +889,76 @@ mod tests {
         assert!(zero_updates.contains("periodic signer backup updates must be greater than zero"));
     }
 
+    #[test]
+    fn parse_audit_filters() {
+        let cli = TestCli::parse_from([
+            "test",
+            "audit",
+            "--path",
+            "audit",
+            "--decision",
+            "rejected",
+            "--limit",
+            "5",
+            "--verify",
+        ]);
+        match cli.cmd {
+            Command::Audit {
+                path,
+                decision,
+                request_type,
+                limit,
+                verify,
+                format,
+            } => {
+                assert_eq!(path, Path::new("audit"));
+                assert_eq!(decision, Some(AuditDecisionArg::Rejected));
+                assert!(request_type.is_none());
+                assert_eq!(limit, Some(5));
+                assert!(verify);
+                assert_eq!(format, AuditFormat::Json);
+            }
+            _ => panic!("expected audit command"),
+        }
+    }
+
+    #[test]
+    fn filter_audit_entries_by_decision_and_limit() {
+        let entry = |seq, decision| AuditEntry {
+            seq,
+            timestamp: 0,
+            request_id: seq as u32,
+            request_type: Some(5),
+            context: vec![],
+            resolution: None,
+            decision,
+            reason: None,
+            prev_hash: String::new(),
+            hash: String::new(),
+        };
+        let entries = vec![
+            entry(0, Decision::Rejected),
+            entry(1, Decision::Approved),
+            entry(2, Decision::Rejected),
+            entry(3, Decision::Rejected),
+        ];
+
+        let filtered = filter_audit_entries(
+            entries.clone(),
+            Some(AuditDecisionArg::Rejected),
+            None,
+            Some(2),
+        );
+        assert_eq!(
+            filtered.iter().map(|e| e.seq).collect::<Vec<_>>(),
+            vec![2, 3]
+        );
+        assert!(filter_audit_entries(entries.clone(), None, Some(6), None).is_empty());
+
+        let text = format_audit_entries_text(&filtered);
+        assert!(text.contains("2 0 request_id=2 type=5 rejected"));
+    }
+
     #[test]
     fn parse_inspect_backup_defaults_to_json() {
         let cli = TestCli::parse_from(["test", "inspect-backup", "--path", "backup.json"]);
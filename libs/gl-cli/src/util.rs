@@ -56,13 +56,20 @@ pub fn write_credentials(file_path: impl AsRef<Path>, creds: impl AsRef<[u8]>) -
     Ok(())
 }
 
-pub fn read_credentials(file_path: impl AsRef<Path>) -> Option<credentials::Device> {
-    let cred_data = fs::read(file_path).ok();
+/// Returns `None` if there is no credentials file, and an error if
+/// the file does not contain valid credentials.
+pub fn read_credentials(file_path: impl AsRef<Path>) -> Result<Option<credentials::Device>> {
+    let cred_data = fs::read(&file_path).ok();
     if let Some(data) = cred_data {
-        let creds = credentials::Device::from_bytes(data);
-        return Some(creds);
+        let creds = credentials::Device::from_bytes(data).map_err(|e| {
+            UtilsError::custom(format!(
+                "invalid credentials in {}: {e}",
+                file_path.as_ref().display()
+            ))
+        })?;
+        return Ok(Some(creds));
     }
-    None
+    Ok(None)
 }
 
 // -- Misc
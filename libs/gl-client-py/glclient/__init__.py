@@ -410,12 +410,14 @@ class Node(object):
         trampoline_node_id: bytes,
         amount_msat: Optional[int] = None,
         label: Optional[str] = None,
+        trampoline_node_ids: Optional[List[bytes]] = None,
     ):
         res = self.inner.trampoline_pay(
             bolt11=bolt11,
             trampoline_node_id=trampoline_node_id,
             amount_msat=amount_msat,
             label=label,
+            trampoline_node_ids=trampoline_node_ids,
         )
         return nodepb.TrampolinePayResponse.FromString(bytes(res))
 
@@ -89,6 +89,10 @@ class Node:
         trampoline_node_id: bytes,
         amount_msat: Optional[int] = None,
         label: Optional[str] = None,
+        maxfeepercent: Optional[float] = None,
+        maxdelay: Optional[int] = None,
+        description: Optional[str] = None,
+        trampoline_node_ids: Optional[List[bytes]] = None,
     ) -> bytes: ...
     def configure(self, payload: bytes) -> None: ...
 
@@ -110,17 +110,17 @@ impl Credentials {
     }
 
     #[staticmethod]
-    pub fn from_path(path: &str) -> Self {
-        let inner = UnifiedCredentials::Device(gl_client::credentials::Device::from_path(path));
+    pub fn from_path(path: &str) -> Result<Self> {
+        let inner = UnifiedCredentials::Device(gl_client::credentials::Device::from_path(path)?);
         log::debug!("Created device credentials");
-        Self { inner }
+        Ok(Self { inner })
     }
 
     #[staticmethod]
-    pub fn from_bytes(data: &[u8]) -> Self {
-        let inner = UnifiedCredentials::Device(gl_client::credentials::Device::from_bytes(data));
+    pub fn from_bytes(data: &[u8]) -> Result<Self> {
+        let inner = UnifiedCredentials::Device(gl_client::credentials::Device::from_bytes(data)?);
         log::debug!("Created device credentials");
-        Self { inner }
+        Ok(Self { inner })
     }
 
     #[staticmethod]
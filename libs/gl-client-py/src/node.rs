@@ -74,6 +74,7 @@ impl Node {
         maxfeepercent: Option<f32>,
         maxdelay: Option<u32>,
         description: Option<String>,
+        trampoline_node_ids: Option<Vec<Vec<u8>>>,
     ) -> PyResult<Vec<u8>> {
         let req = pb::TrampolinePayRequest {
             bolt11,
@@ -83,6 +84,7 @@ impl Node {
             maxfeepercent: maxfeepercent.unwrap_or_default(),
             maxdelay: maxdelay.unwrap_or_default(),
             description: description.unwrap_or_default(),
+            trampoline_node_ids: trampoline_node_ids.unwrap_or_default(),
         };
         let res = exec(async { self.client.clone().trampoline_pay(req).await })
             .map_err(error_calling_remote_method)?
@@ -7,6 +7,7 @@ from io import BytesIO
 
 INVOICE_TYPE = 33001
 AMOUNT_TYPE = 33003
+PARTIAL_TYPE = 33005
 
 plugin = Plugin(
     dynamic=False,
@@ -16,6 +17,7 @@ plugin = Plugin(
 plugin.check_invoice = None
 plugin.check_amount = None
 plugin.payment_key = None
+plugin.min_fee_msat = None
 
 
 @plugin.hook("htlc_accepted")
@@ -50,6 +52,29 @@ def on_htlc_accepted(htlc, onion, plugin, **kwargs):
     plugin.log(f"got invoice={invoice_value.decode('utf-8')}")
     plugin.log(f"got amount_msat={int.from_bytes(amount_msat_value, 'big')}")
 
+    # The part of the amount we forward, if the payment is split
+    # across several trampolines.
+    if payment_metadata.tell() < len(payment_metadata.getvalue()):
+        partial_type = varint_decode(payment_metadata)
+        partial_length = varint_decode(payment_metadata)
+        partial_value = payment_metadata.read(partial_length)
+        assert partial_type == PARTIAL_TYPE
+        plugin.log(f"got partial_msat={int.from_bytes(partial_value, 'big')}")
+
+    if plugin.min_fee_msat is not None:
+        fee_msat = int(htlc["amount_msat"]) - int.from_bytes(amount_msat_value, "big")
+        if fee_msat < plugin.min_fee_msat:
+            plugin.log(f"fee {fee_msat}msat is below {plugin.min_fee_msat}msat")
+            # trampoline_fee_insufficient (NODE|51), followed by the
+            # fee_base_msat, fee_proportional_millionths and
+            # cltv_expiry_delta we ask for.
+            requested = (
+                plugin.min_fee_msat.to_bytes(4, "big")
+                + (0).to_bytes(4, "big")
+                + (0).to_bytes(2, "big")
+            )
+            return {"result": "fail", "failure_message": "2033" + requested.hex()}
+
     if plugin.payment_key is not None:
         plugin.log(f"resolving with payment_key {plugin.payment_key}")
         return {
@@ -80,11 +105,18 @@ def setcheckamount(plugin, amount_msat):
     plugin.check_amount = amount_msat
 
 
+@plugin.method("setminfee")
+def setminfee(plugin, fee_msat):
+    """Fails htlcs that leave less than fee_msat to the trampoline"""
+    plugin.min_fee_msat = fee_msat
+
+
 @plugin.method("unsetchecks")
 def unsetchecks(plugin):
     """Unsets all checks"""
     plugin.check_invoice = None
     plugin.check_amount = None
+    plugin.min_fee_msat = None
 
 
 plugin.run()
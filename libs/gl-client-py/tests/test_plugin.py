@@ -115,7 +115,9 @@ def test_trampoline_pay(bitcoind, clients, node_factory):
     ch = n1.list_peer_channels(bytes.fromhex(l2.info["id"])).channels[0]
     assert res2 == res
 
-    assert ch.to_us_msat.msat == (1000000000 - (50000000 + 0.005 * 50000000))
+    # Without a fee it asked for before, the trampoline is offered no
+    # fee at all, which it accepts.
+    assert ch.to_us_msat.msat == 1000000000 - 50000000
     assert len(ch.htlcs) == 0
 
     # new unknown unconnected node without the trampoline featurebit.
@@ -144,6 +146,58 @@ def test_trampoline_pay(bitcoind, clients, node_factory):
         res = n1.trampoline_pay(inv["bolt11"], bytes.fromhex(l3.info["id"]))
 
 
+def test_trampoline_fee_discovery(bitcoind, clients, node_factory):
+    """The payment is retried at the fee the trampoline asks for,
+    instead of always paying the maximum, and the next payment starts
+    at that fee."""
+    c1 = clients.new()
+    c1.register()
+    s1 = c1.signer()
+    s1.run_in_thread()
+    n1 = c1.node()
+
+    addr = n1.new_address().bech32
+    txid = bitcoind.rpc.sendtoaddress(addr, 0.1)
+    bitcoind.generate_block(1, wait_for_mempool=[txid])
+    wait_for(lambda: txid in [o.txid.hex() for o in n1.list_funds().outputs])
+
+    l2 = node_factory.get_node(options={"plugin": trmp_plugin_path})
+    n1.connect_peer(l2.info["id"], f"localhost:{l2.port}")
+    n1.fund_channel(
+        bytes.fromhex(l2.info["id"]),
+        clnpb.AmountOrAll(amount=clnpb.Amount(msat=1000000000)),
+    )
+    bitcoind.generate_block(6, wait_for_mempool=1)
+    wait_for(
+        lambda: l2.info["id"]
+        in [c.peer_id.hex() for c in n1.list_funds().channels if c.state == 2]
+    )
+
+    # The trampoline rejects the payment without a fee and asks for
+    # 150000msat, below the 0.5% maximum of 250000msat.
+    l2.rpc.setminfee(150000)
+    inv = l2.rpc.invoice(
+        amount_msat=50000000,
+        label="trampoline-fee-test",
+        description="trampoline-fee-test",
+    )
+    res = n1.trampoline_pay(inv["bolt11"], bytes.fromhex(l2.info["id"]))
+    assert res.amount_msat == 50000000
+    assert res.amount_sent_msat == 50000000 + 150000
+
+    # The next payment is offered the fee the trampoline asked for
+    # right away.
+    inv = l2.rpc.invoice(
+        amount_msat=50000000,
+        label="trampoline-fee-test-2",
+        description="trampoline-fee-test-2",
+    )
+    res = n1.trampoline_pay(inv["bolt11"], bytes.fromhex(l2.info["id"]))
+    assert res.amount_sent_msat == 50000000 + 150000
+    assert l2.daemon.is_in_log("fee 0msat is below 150000msat")
+    assert not l2.daemon.is_in_log("fee 150000msat is below")
+
+
 def test_trampoline_multi_htlc(bitcoind, clients, node_factory):
     c1 = clients.new()
     c1.register()
@@ -202,7 +256,121 @@ def test_trampoline_multi_htlc(bitcoind, clients, node_factory):
 
     res = n1.trampoline_pay(inv["bolt11"], bytes.fromhex(l2.info["id"]))
     assert res
-    assert res.parts == 2
+    assert res.parts >= 2
+
+
+def test_trampoline_pay_falls_back(bitcoind, clients, node_factory):
+    """A trampoline that can't forward the payment is skipped in
+    favour of the next one in `trampoline_node_ids`."""
+    c1 = clients.new()
+    c1.register()
+    s1 = c1.signer()
+    s1.run_in_thread()
+    n1 = c1.node()
+
+    # Fund greenlight node.
+    addr = n1.new_address().bech32
+    txid = bitcoind.rpc.sendtoaddress(addr, 0.1)
+    bitcoind.generate_block(1, wait_for_mempool=[txid])
+    wait_for(lambda: txid in [o.txid.hex() for o in n1.list_funds().outputs])
+
+    # l2 is a trampoline we share a channel with, l3 is a connected
+    # peer without trampoline support.
+    l2 = node_factory.get_node(options={"plugin": trmp_plugin_path})
+    l3 = node_factory.get_node(options={"disable-plugin": "cln-grpc"})
+    n1.connect_peer(l2.info["id"], f"localhost:{l2.port}")
+    n1.connect_peer(l3.info["id"], f"localhost:{l3.port}")
+    n1.fund_channel(
+        bytes.fromhex(l2.info["id"]),
+        clnpb.AmountOrAll(amount=clnpb.Amount(msat=1000000000)),
+    )
+    bitcoind.generate_block(6, wait_for_mempool=1)
+    wait_for(
+        lambda: l2.info["id"]
+        in [c.peer_id.hex() for c in n1.list_funds().channels if c.state == 2]
+    )
+
+    inv = l2.rpc.invoice(
+        amount_msat=1000000,
+        label="trampoline-fallback-test",
+        description="trampoline-fallback-test",
+    )
+
+    # On its own, l3 fails the payment.
+    with pytest.raises(
+        expected_exception=ValueError,
+        match="Peer doesn't support trampoline payments",
+    ):
+        n1.trampoline_pay(inv["bolt11"], bytes.fromhex(l3.info["id"]))
+
+    res = n1.trampoline_pay(
+        inv["bolt11"],
+        bytes.fromhex(l3.info["id"]),
+        trampoline_node_ids=[bytes.fromhex(l2.info["id"])],
+    )
+    assert res
+    assert len(res.payment_hash) == 32
+
+
+def test_trampoline_pay_split(bitcoind, clients, node_factory):
+    """A payment no single trampoline can carry is split across the
+    channels to several trampolines."""
+    c1 = clients.new()
+    c1.register()
+    s1 = c1.signer()
+    s1.run_in_thread()
+    n1 = c1.node()
+
+    addr = n1.new_address().bech32
+    txid = bitcoind.rpc.sendtoaddress(addr, 0.1)
+    bitcoind.generate_block(1, wait_for_mempool=[txid])
+    wait_for(lambda: txid in [o.txid.hex() for o in n1.list_funds().outputs])
+
+    l2 = node_factory.get_node(options={"plugin": trmp_plugin_path})
+    l3 = node_factory.get_node(options={"plugin": trmp_plugin_path})
+    for l in [l2, l3]:
+        n1.connect_peer(l.info["id"], f"localhost:{l.port}")
+        n1.fund_channel(
+            bytes.fromhex(l.info["id"]),
+            clnpb.AmountOrAll(amount=clnpb.Amount(msat=70000000)),
+        )
+        bitcoind.generate_block(6, wait_for_mempool=1)
+    wait_for(lambda: len([c for c in n1.list_funds().channels if c.state == 2]) == 2)
+
+    spendable = {
+        l.info["id"]: sum(
+            c.spendable_msat.msat
+            for c in n1.list_peer_channels(bytes.fromhex(l.info["id"])).channels
+        )
+        for l in [l2, l3]
+    }
+
+    # More than either channel can carry. Both trampolines resolve
+    # their share, as if they had forwarded it.
+    amount_msat = max(spendable.values()) + 1000000
+    invoice_preimage = (
+        "27b08f669513b7379728fc1abcea5eaf3448bc1eba55a68ca2cd1843409cdc04"
+    )
+    inv = l2.rpc.invoice(
+        amount_msat=amount_msat,
+        label="trampoline-split-test",
+        description="trampoline-split-test",
+        preimage=invoice_preimage,
+    )
+    for l in [l2, l3]:
+        l.rpc.setpaymentkey(invoice_preimage)
+        l.rpc.setcheckamount(amount_msat)
+
+    res = n1.trampoline_pay(
+        inv["bolt11"],
+        bytes.fromhex(l2.info["id"]),
+        trampoline_node_ids=[bytes.fromhex(l3.info["id"])],
+    )
+    assert res.amount_msat == amount_msat
+    assert res.parts >= 2
+    # Each trampoline was told the share it forwards.
+    assert l2.daemon.is_in_log("got partial_msat=")
+    assert l3.daemon.is_in_log("got partial_msat=")
 
 
 def test_lsps_plugin_calls(clients, bitcoind, node_factory, lsps_server):
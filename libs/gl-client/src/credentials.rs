@@ -39,6 +39,14 @@ pub enum Error {
     ReadFromFileError(#[from] std::io::Error),
     #[error("could not fetch default nobody credentials: {}", .0)]
     FetchDefaultNobodyCredentials(#[source] anyhow::Error),
+    #[error("credentials are encrypted, a passphrase or seed is required to load them")]
+    EncryptedCredentials,
+    #[error("credentials are missing the {}", .0)]
+    IncompleteCredentials(&'static str),
+    #[error("could not encrypt credentials: {}", .0)]
+    EncryptCredentialsError(String),
+    #[error("could not decrypt credentials: {}", .0)]
+    DecryptCredentialsError(String),
 }
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -147,35 +155,62 @@ pub struct Device {
 
 impl Device {
     /// Creates a new set of `Device` credentials from the given
-    /// credentials data blob. It defaults to the nobody credentials set.
-    pub fn from_bytes(data: impl AsRef<[u8]>) -> Self {
-        let mut creds = Self::default();
+    /// credentials data blob. Returns an error if the blob can not be
+    /// decoded, is encrypted, or lacks the certificate or private key.
+    pub fn from_bytes(data: impl AsRef<[u8]>) -> Result<Self> {
         log::trace!("Build authenticated credentials from: {:?}", data.as_ref());
-        if let Ok(data) = model::Data::try_from(data.as_ref()) {
-            creds.version = data.version;
-            if let Some(cert) = data.cert {
-                creds.cert = cert
-            }
-            if let Some(key) = data.key {
-                creds.key = key
-            }
-            if let Some(ca) = data.ca {
-                creds.ca = ca
-            }
-            if let Some(rune) = data.rune {
-                creds.rune = rune
-            }
+        if envelope::is_encrypted(data.as_ref()) {
+            return Err(Error::EncryptedCredentials);
         }
-        creds
+        let data = model::Data::try_from(data.as_ref())?;
+        let cert = data
+            .cert
+            .filter(|c| !c.is_empty())
+            .ok_or(Error::IncompleteCredentials("certificate"))?;
+        let key = data
+            .key
+            .filter(|k| !k.is_empty())
+            .ok_or(Error::IncompleteCredentials("private key"))?;
+        let ca = match data.ca {
+            Some(ca) => ca,
+            None => Self::default().ca,
+        };
+        Ok(Device {
+            version: data.version,
+            cert,
+            key,
+            ca,
+            rune: data.rune.unwrap_or_default(),
+        })
     }
 
     /// Creates a new set of `Device` credentials from a path that
-    /// contains a credentials data blob. Defaults to the nobody
-    /// credentials set.
-    pub fn from_path(path: impl AsRef<Path>) -> Self {
+    /// contains a credentials data blob. Returns an error if the file
+    /// can not be read or does not contain valid credentials.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self> {
         debug!("Read credentials data from {:?}", path.as_ref());
-        let data = std::fs::read(path).unwrap_or_default();
-        Device::from_bytes(data)
+        Device::from_bytes(std::fs::read(path)?)
+    }
+
+    /// Returns `true` if `data` is an encrypted credentials blob, as
+    /// returned by [`Device::to_encrypted_bytes`].
+    pub fn is_encrypted(data: impl AsRef<[u8]>) -> bool {
+        envelope::is_encrypted(data.as_ref())
+    }
+
+    /// Decrypts credentials encrypted with
+    /// [`Device::to_encrypted_bytes`]. Fails if `secret` is not the
+    /// one they were encrypted with, or the blob was tampered with.
+    pub fn from_encrypted_bytes(data: impl AsRef<[u8]>, secret: Secret<'_>) -> Result<Self> {
+        let plain = envelope::open(data.as_ref(), secret)?;
+        Device::from_bytes(plain)
+    }
+
+    /// Returns the credentials encrypted with a key derived from
+    /// `secret`, so that a copy of the file (e.g., in a backup) can
+    /// not be used without it.
+    pub fn to_encrypted_bytes(&self, secret: Secret<'_>) -> Result<Vec<u8>> {
+        envelope::seal(&self.to_bytes(), secret, envelope::PBKDF2_ITERATIONS)
     }
 
     /// Creates a new set of `Device` credentials from a complete set of
@@ -337,6 +372,162 @@ mod model {
     }
 }
 
+/// The secret used to encrypt `Device` credentials at rest.
+#[derive(Clone, Copy)]
+pub enum Secret<'a> {
+    /// A passphrase chosen by the user, stretched with
+    /// PBKDF2-HMAC-SHA256.
+    Passphrase(&'a str),
+    /// The node's seed, or another high-entropy secret, expanded with
+    /// HKDF-SHA256.
+    Seed(&'a [u8]),
+}
+
+/// The encrypted credentials format. The blob consists of a header
+/// followed by the ChaCha20-Poly1305 encrypted credentials:
+///
+/// ```text
+/// magic "GLCE" | version (1) | kdf (1) | iterations (4, BE) | salt (16) | nonce (12) | ciphertext
+/// ```
+///
+/// The header is authenticated as associated data, so the KDF
+/// parameters can not be altered without detection.
+mod envelope {
+    use super::{Error, Result, Secret};
+    use ring::{aead, hkdf, pbkdf2, rand::SecureRandom};
+    use std::num::NonZeroU32;
+
+    const MAGIC: &[u8; 4] = b"GLCE";
+    const VERSION: u8 = 1;
+    const KDF_PBKDF2_SHA256: u8 = 1;
+    const KDF_HKDF_SHA256: u8 = 2;
+    const SALT_LEN: usize = 16;
+    const NONCE_LEN: usize = aead::NONCE_LEN;
+    const HEADER_LEN: usize = MAGIC.len() + 1 + 1 + 4 + SALT_LEN + NONCE_LEN;
+    const HKDF_INFO: &[u8] = b"greenlight credentials encryption v1";
+
+    /// The number of PBKDF2 iterations for new blobs.
+    pub(super) const PBKDF2_ITERATIONS: u32 = 600_000;
+
+    /// Upper bound on the iterations accepted when decrypting, so a
+    /// crafted header can not stall the application.
+    const MAX_PBKDF2_ITERATIONS: u32 = 10_000_000;
+
+    pub(super) fn is_encrypted(data: &[u8]) -> bool {
+        data.starts_with(MAGIC)
+    }
+
+    pub(super) fn seal(plain: &[u8], secret: Secret<'_>, iterations: u32) -> Result<Vec<u8>> {
+        let rng = ring::rand::SystemRandom::new();
+        let mut salt = [0u8; SALT_LEN];
+        let mut nonce = [0u8; NONCE_LEN];
+        rng.fill(&mut salt)
+            .and_then(|_| rng.fill(&mut nonce))
+            .map_err(|_| Error::EncryptCredentialsError("no randomness available".into()))?;
+
+        let (kdf, iterations) = match secret {
+            Secret::Passphrase(_) => (KDF_PBKDF2_SHA256, iterations),
+            Secret::Seed(_) => (KDF_HKDF_SHA256, 0),
+        };
+        let mut out = Vec::with_capacity(HEADER_LEN + plain.len() + aead::MAX_TAG_LEN);
+        out.extend_from_slice(MAGIC);
+        out.push(VERSION);
+        out.push(kdf);
+        out.extend_from_slice(&iterations.to_be_bytes());
+        out.extend_from_slice(&salt);
+        out.extend_from_slice(&nonce);
+
+        let key = derive_key(secret, iterations, &salt)
+            .map_err(|e| Error::EncryptCredentialsError(e.to_string()))?;
+        let mut in_out = plain.to_vec();
+        key.seal_in_place_append_tag(
+            aead::Nonce::assume_unique_for_key(nonce),
+            aead::Aad::from(&out[..]),
+            &mut in_out,
+        )
+        .map_err(|_| Error::EncryptCredentialsError("encryption failed".into()))?;
+        out.extend_from_slice(&in_out);
+        Ok(out)
+    }
+
+    pub(super) fn open(data: &[u8], secret: Secret<'_>) -> Result<Vec<u8>> {
+        let err = |msg: &str| Error::DecryptCredentialsError(msg.to_string());
+        if !is_encrypted(data) {
+            return Err(err("not an encrypted credentials blob"));
+        }
+        if data.len() < HEADER_LEN + aead::MAX_TAG_LEN {
+            return Err(err("blob is truncated"));
+        }
+        let (header, ciphertext) = data.split_at(HEADER_LEN);
+        let version = header[4];
+        if version != VERSION {
+            return Err(Error::DecryptCredentialsError(format!(
+                "unsupported version {}",
+                version
+            )));
+        }
+        let kdf = header[5];
+        let iterations = u32::from_be_bytes(header[6..10].try_into().unwrap());
+        let salt = &header[10..10 + SALT_LEN];
+        let nonce: [u8; NONCE_LEN] = header[10 + SALT_LEN..].try_into().unwrap();
+
+        let expected_kdf = match secret {
+            Secret::Passphrase(_) => KDF_PBKDF2_SHA256,
+            Secret::Seed(_) => KDF_HKDF_SHA256,
+        };
+        if kdf != expected_kdf {
+            return Err(match kdf {
+                KDF_PBKDF2_SHA256 => err("credentials are encrypted with a passphrase"),
+                KDF_HKDF_SHA256 => err("credentials are encrypted with a seed"),
+                _ => Error::DecryptCredentialsError(format!("unsupported kdf {}", kdf)),
+            });
+        }
+        if kdf == KDF_PBKDF2_SHA256 && iterations > MAX_PBKDF2_ITERATIONS {
+            return Err(Error::DecryptCredentialsError(format!(
+                "too many kdf iterations {}",
+                iterations
+            )));
+        }
+
+        let key = derive_key(secret, iterations, salt)?;
+        let mut in_out = ciphertext.to_vec();
+        let plain = key
+            .open_in_place(
+                aead::Nonce::assume_unique_for_key(nonce),
+                aead::Aad::from(header),
+                &mut in_out,
+            )
+            .map_err(|_| err("wrong passphrase or seed, or the blob was modified"))?;
+        Ok(plain.to_vec())
+    }
+
+    fn derive_key(secret: Secret<'_>, iterations: u32, salt: &[u8]) -> Result<aead::LessSafeKey> {
+        let alg = &aead::CHACHA20_POLY1305;
+        let unbound = match secret {
+            Secret::Passphrase(passphrase) => {
+                let iterations = NonZeroU32::new(iterations)
+                    .ok_or_else(|| Error::DecryptCredentialsError("zero kdf iterations".into()))?;
+                let mut key = [0u8; 32];
+                pbkdf2::derive(
+                    pbkdf2::PBKDF2_HMAC_SHA256,
+                    iterations,
+                    salt,
+                    passphrase.as_bytes(),
+                    &mut key,
+                );
+                aead::UnboundKey::new(alg, &key)
+                    .map_err(|_| Error::DecryptCredentialsError("invalid key".into()))?
+            }
+            Secret::Seed(seed) => hkdf::Salt::new(hkdf::HKDF_SHA256, salt)
+                .extract(seed)
+                .expand(&[HKDF_INFO], alg)
+                .map(aead::UnboundKey::from)
+                .map_err(|_| Error::DecryptCredentialsError("invalid key".into()))?,
+        };
+        Ok(aead::LessSafeKey::new(unbound))
+    }
+}
+
 /// Tries to load nobody credentials from a file that is passed by an envvar and
 /// defaults to the nobody cert and key paths that have been set during build-
 /// time.
@@ -397,4 +588,89 @@ mod tests {
         assert!(data.ca.is_some_and(|d| d == vec![95, 94]));
         assert!(data.rune.is_some_and(|d| d == *"non_functional_rune"));
     }
+
+    fn device() -> Device {
+        Device {
+            version: CRED_VERSION,
+            cert: vec![99, 98],
+            key: vec![97, 96],
+            ca: vec![95, 94],
+            rune: "non_functional_rune".to_string(),
+        }
+    }
+
+    fn assert_same(a: &Device, b: &Device) {
+        assert_eq!(a.cert, b.cert);
+        assert_eq!(a.key, b.key);
+        assert_eq!(a.ca, b.ca);
+        assert_eq!(a.rune, b.rune);
+    }
+
+    #[test]
+    fn test_from_bytes_errors_instead_of_falling_back() {
+        let creds = Device::from_bytes(device().to_bytes()).unwrap();
+        assert_same(&creds, &device());
+
+        assert!(matches!(
+            Device::from_bytes(b""),
+            Err(Error::IncompleteCredentials(_))
+        ));
+        assert!(matches!(
+            Device::from_bytes(b"invalid data"),
+            Err(Error::DecodeCredentialsError(_))
+        ));
+        assert!(Device::from_path("/nonexistent/credentials.glc").is_err());
+    }
+
+    #[test]
+    fn test_encrypted_roundtrip_with_passphrase() {
+        // Few iterations to keep the test fast.
+        let blob = envelope::seal(&device().to_bytes(), Secret::Passphrase("hunter2"), 10).unwrap();
+        assert!(Device::is_encrypted(&blob));
+        let rune = device().rune.into_bytes();
+        assert!(!blob.windows(rune.len()).any(|w| w == rune));
+
+        let creds = Device::from_encrypted_bytes(&blob, Secret::Passphrase("hunter2")).unwrap();
+        assert_same(&creds, &device());
+
+        assert!(matches!(
+            Device::from_encrypted_bytes(&blob, Secret::Passphrase("hunter3")),
+            Err(Error::DecryptCredentialsError(_))
+        ));
+        assert!(Device::from_encrypted_bytes(&blob, Secret::Seed(&[1; 32])).is_err());
+    }
+
+    #[test]
+    fn test_encrypted_roundtrip_with_seed() {
+        let seed = [7u8; 32];
+        let blob = device().to_encrypted_bytes(Secret::Seed(&seed)).unwrap();
+        let creds = Device::from_encrypted_bytes(&blob, Secret::Seed(&seed)).unwrap();
+        assert_same(&creds, &device());
+        assert!(Device::from_encrypted_bytes(&blob, Secret::Seed(&[8; 32])).is_err());
+    }
+
+    #[test]
+    fn test_encrypted_header_is_authenticated() {
+        let blob = envelope::seal(&device().to_bytes(), Secret::Passphrase("pw"), 10).unwrap();
+
+        // Changing the KDF parameters breaks the authentication.
+        let mut tampered = blob.clone();
+        tampered[9] ^= 1;
+        assert!(Device::from_encrypted_bytes(&tampered, Secret::Passphrase("pw")).is_err());
+
+        let mut future = blob.clone();
+        future[4] = 2;
+        assert!(Device::from_encrypted_bytes(&future, Secret::Passphrase("pw")).is_err());
+
+        assert!(Device::from_encrypted_bytes(&blob[..20], Secret::Passphrase("pw")).is_err());
+    }
+
+    #[test]
+    fn test_encrypted_credentials_are_not_loaded_as_plain() {
+        let blob = device().to_encrypted_bytes(Secret::Seed(&[7; 32])).unwrap();
+        assert!(matches!(
+            Device::from_bytes(&blob),
+            Err(Error::EncryptedCredentials)
+        ));
+    }
 }
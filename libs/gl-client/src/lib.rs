@@ -65,6 +65,9 @@ pub enum Error {
 
     #[error("Illegal argument: {0}")]
     IllegalArgument(String),
+
+    #[error("Spending policy violation: {0}")]
+    PolicyViolation(String),
 }
 
 pub use lightning_signer::bitcoin;
@@ -0,0 +1,227 @@
+//! LNURL-auth (LUD-04) with LUD-05 linking key derivation.
+//!
+//! A login URL carries everything needed in its query string
+//! (`tag=login`, `k1` and an optional `action`), so no HTTP request
+//! is needed to classify it. The wallet derives a per-domain linking
+//! key from its seed, signs `k1` with it and calls the URL back with
+//! the signature and the linking public key.
+use super::models::{LnUrlAuthRequestData, LnUrlHttpClient};
+use anyhow::{anyhow, ensure, Result};
+use lightning_signer::bitcoin::bip32::{ChildNumber, Xpriv};
+use lightning_signer::bitcoin::hashes::{hmac, sha256, Hash, HashEngine};
+use lightning_signer::bitcoin::secp256k1::{Message, PublicKey, Secp256k1, SecretKey};
+use lightning_signer::bitcoin::Network;
+use reqwest::Url;
+
+/// The BIP32 purpose used for LNURL-auth keys (LUD-05).
+const LNURL_AUTH_PURPOSE: u32 = 138;
+
+/// Actions a service may announce in the login URL (LUD-04).
+const VALID_ACTIONS: [&str; 4] = ["register", "login", "link", "auth"];
+
+/// Parse a decoded LNURL into an auth request, if it is one.
+///
+/// Returns `Ok(None)` if the URL is not a login URL, and an error if
+/// it is one but is malformed.
+pub fn parse_auth_url(url: &str) -> Result<Option<LnUrlAuthRequestData>> {
+    let parsed = Url::parse(url).map_err(|e| anyhow!("Invalid LNURL-auth url: {}", e))?;
+    let param = |name: &str| {
+        parsed
+            .query_pairs()
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.into_owned())
+    };
+
+    if param("tag").as_deref() != Some("login") {
+        return Ok(None);
+    }
+
+    let k1 = param("k1").ok_or_else(|| anyhow!("LNURL-auth url is missing k1"))?;
+    let k1_bytes = hex::decode(&k1).map_err(|e| anyhow!("LNURL-auth k1 is not hex: {}", e))?;
+    ensure!(k1_bytes.len() == 32, "LNURL-auth k1 must be 32 bytes");
+
+    let action = param("action");
+    if let Some(action) = &action {
+        ensure!(
+            VALID_ACTIONS.contains(&action.as_str()),
+            "Unknown LNURL-auth action '{}'",
+            action
+        );
+    }
+
+    let domain = parsed
+        .host_str()
+        .ok_or_else(|| anyhow!("LNURL-auth url has no host"))?
+        .to_string();
+
+    Ok(Some(LnUrlAuthRequestData {
+        k1,
+        action,
+        domain,
+        url: url.to_string(),
+    }))
+}
+
+/// Derive the linking key for `domain` from `seed` (LUD-05).
+///
+/// The hashing key at `m/138'/0` keys an HMAC-SHA256 over the domain
+/// name, whose first 16 bytes select the linking key at
+/// `m/138'/<long1>/<long2>/<long3>/<long4>`.
+pub fn derive_linking_key(seed: &[u8], domain: &str) -> Result<SecretKey> {
+    let secp = Secp256k1::new();
+    let master = Xpriv::new_master(Network::Bitcoin, seed)
+        .map_err(|e| anyhow!("Failed to derive master key: {}", e))?;
+    let purpose = ChildNumber::from_hardened_idx(LNURL_AUTH_PURPOSE)?;
+
+    let hashing_key = master.derive_priv(&secp, &[purpose, ChildNumber::from_normal_idx(0)?])?;
+    let mut engine = hmac::HmacEngine::<sha256::Hash>::new(&hashing_key.private_key[..]);
+    engine.input(domain.as_bytes());
+    let derivation_material = hmac::Hmac::<sha256::Hash>::from_engine(engine).to_byte_array();
+
+    let mut path = vec![purpose];
+    for chunk in derivation_material[..16].chunks(4) {
+        let index = u32::from_be_bytes(chunk.try_into().expect("chunks of 4 bytes"));
+        path.push(ChildNumber::from(index));
+    }
+
+    Ok(master.derive_priv(&secp, &path)?.private_key)
+}
+
+/// Sign the `k1` challenge with the linking key and build the URL the
+/// service expects to be called back on.
+pub fn build_auth_callback_url(
+    linking_key: &SecretKey,
+    request: &LnUrlAuthRequestData,
+) -> Result<String> {
+    let secp = Secp256k1::signing_only();
+    let k1 = hex::decode(&request.k1)?;
+    let msg = Message::from_digest_slice(&k1)?;
+    let sig = secp.sign_ecdsa(&msg, linking_key);
+    let key = PublicKey::from_secret_key(&secp, linking_key);
+
+    let mut url = Url::parse(&request.url)?;
+    url.query_pairs_mut()
+        .append_pair("sig", &hex::encode(sig.serialize_der()))
+        .append_pair("key", &hex::encode(key.serialize()));
+    Ok(url.to_string())
+}
+
+/// Perform the LNURL-auth flow: sign the challenge with the
+/// `linking_key` for the service's domain and call the service back.
+///
+/// Returns an error carrying the service's `reason` if it rejects
+/// the login.
+pub async fn perform_auth<T: LnUrlHttpClient>(
+    http_client: &T,
+    linking_key: &SecretKey,
+    request: &LnUrlAuthRequestData,
+) -> Result<()> {
+    let callback_url = build_auth_callback_url(linking_key, request)?;
+    let response = http_client.get_json(&callback_url).await?;
+
+    match response.get("status").and_then(|s| s.as_str()) {
+        Some(s) if s.eq_ignore_ascii_case("OK") => Ok(()),
+        _ => {
+            let reason = response
+                .get("reason")
+                .and_then(|r| r.as_str())
+                .unwrap_or("unknown error");
+            Err(anyhow!(
+                "{}{}",
+                super::pay::LNURL_SERVICE_ERROR_PREFIX,
+                reason
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lnurl::models::MockLnUrlHttpClient;
+    use lightning_signer::bitcoin::secp256k1::ecdsa::Signature;
+    use serde_json::json;
+
+    const K1: &str = "e2af6254a8df433264fa23f67eb8188635d15ce883e8fc020989d5f82ae6f11e";
+
+    fn login_url() -> String {
+        format!("https://site.com/auth?tag=login&k1={}&action=login", K1)
+    }
+
+    #[test]
+    fn test_parse_auth_url() {
+        let req = parse_auth_url(&login_url()).unwrap().unwrap();
+        assert_eq!(req.k1, K1);
+        assert_eq!(req.action.as_deref(), Some("login"));
+        assert_eq!(req.domain, "site.com");
+    }
+
+    #[test]
+    fn test_parse_auth_url_ignores_other_tags() {
+        assert!(parse_auth_url("https://site.com/lnurlp?tag=payRequest")
+            .unwrap()
+            .is_none());
+        assert!(parse_auth_url("https://site.com/lnurlp").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_parse_auth_url_rejects_malformed_requests() {
+        assert!(parse_auth_url("https://site.com/auth?tag=login").is_err());
+        assert!(parse_auth_url("https://site.com/auth?tag=login&k1=abcd").is_err());
+        let url = format!("https://site.com/auth?tag=login&k1={}&action=steal", K1);
+        assert!(parse_auth_url(&url).is_err());
+    }
+
+    #[test]
+    fn test_linking_key_is_per_domain_and_deterministic() {
+        let seed = [1u8; 32];
+        let a = derive_linking_key(&seed, "site.com").unwrap();
+        assert_eq!(a, derive_linking_key(&seed, "site.com").unwrap());
+        assert_ne!(a, derive_linking_key(&seed, "other.com").unwrap());
+        assert_ne!(a, derive_linking_key(&[2u8; 32], "site.com").unwrap());
+    }
+
+    #[test]
+    fn test_callback_url_signature_verifies() {
+        let request = parse_auth_url(&login_url()).unwrap().unwrap();
+        let key = derive_linking_key(&[1u8; 32], &request.domain).unwrap();
+        let callback = build_auth_callback_url(&key, &request).unwrap();
+
+        let url = Url::parse(&callback).unwrap();
+        let param = |name: &str| {
+            url.query_pairs()
+                .find(|(k, _)| k == name)
+                .map(|(_, v)| v.into_owned())
+                .unwrap()
+        };
+        assert_eq!(param("k1"), K1);
+
+        let secp = Secp256k1::verification_only();
+        let sig = Signature::from_der(&hex::decode(param("sig")).unwrap()).unwrap();
+        let pubkey = PublicKey::from_slice(&hex::decode(param("key")).unwrap()).unwrap();
+        let msg = Message::from_digest_slice(&hex::decode(K1).unwrap()).unwrap();
+        assert!(secp.verify_ecdsa(&msg, &sig, &pubkey).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_perform_auth_reports_service_errors() {
+        let request = parse_auth_url(&login_url()).unwrap().unwrap();
+        let key = derive_linking_key(&[1u8; 32], &request.domain).unwrap();
+
+        let mut client = MockLnUrlHttpClient::new();
+        client.expect_get_json().returning(|url| {
+            assert!(url.contains("sig=") && url.contains("key="));
+            Box::pin(async { Ok(json!({"status": "OK"})) })
+        });
+        assert!(perform_auth(&client, &key, &request).await.is_ok());
+
+        let mut client = MockLnUrlHttpClient::new();
+        client.expect_get_json().returning(|_| {
+            Box::pin(async { Ok(json!({"status": "ERROR", "reason": "expired k1"})) })
+        });
+        let err = perform_auth(&client, &key, &request)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("expired k1"));
+    }
+}
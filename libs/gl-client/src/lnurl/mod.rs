@@ -1,19 +1,24 @@
+pub mod auth;
 pub mod models;
 pub mod pay;
 pub mod utils;
 pub mod withdraw;
 
-use self::models::{LnUrlHttpClient, PayRequestResponse, WithdrawRequestResponse};
+use self::models::{
+    LnUrlAuthRequestData, LnUrlHttpClient, PayRequestResponse, WithdrawRequestResponse,
+};
 use self::utils::parse_lnurl;
 use crate::node::ClnClient;
 use crate::pb::cln::{amount_or_any, Amount, AmountOrAny};
 use anyhow::{anyhow, Result};
 use models::LnUrlHttpClearnetClient;
 
-/// Result of resolving an LNURL endpoint via HTTP.
+/// Result of resolving an LNURL endpoint.
 pub enum LnUrlResponse {
     Pay(PayRequestResponse),
     Withdraw(WithdrawRequestResponse),
+    /// A login request, resolved from the URL alone.
+    Auth(LnUrlAuthRequestData),
 }
 
 pub struct LNURL<T: LnUrlHttpClient> {
@@ -33,8 +38,14 @@ impl<T: LnUrlHttpClient> LNURL<T> {
     /// Resolve an LNURL to its endpoint data with a single HTTP GET.
     ///
     /// Decodes the bech32, fetches the URL, inspects the `tag` field,
-    /// and returns the appropriate typed response.
+    /// and returns the appropriate typed response. Login URLs
+    /// (LUD-04) carry their tag in the query string and are resolved
+    /// without a request.
     pub async fn resolve(&self, url: &str) -> Result<LnUrlResponse> {
+        if let Some(auth) = auth::parse_auth_url(url)? {
+            return Ok(LnUrlResponse::Auth(auth));
+        }
+
         let json = self.http_client.get_json(url).await?;
 
         let tag = json
@@ -77,6 +88,16 @@ impl<T: LnUrlHttpClient> LNURL<T> {
         .map_err(|e| anyhow!(e))
     }
 
+    /// Log in to the service behind an LNURL-auth `lnurl`, using the
+    /// `signer`'s linking key for the service's domain.
+    pub async fn auth(&self, lnurl: &str, signer: &crate::signer::Signer) -> Result<()> {
+        let url = parse_lnurl(lnurl)?;
+        let request = auth::parse_auth_url(&url)?
+            .ok_or_else(|| anyhow!("LNURL is not an LNURL-auth request"))?;
+        let linking_key = signer.lnurl_auth_linking_key(&request.domain)?;
+        auth::perform_auth(&self.http_client, &linking_key, &request).await
+    }
+
     pub async fn withdraw(
         &self,
         lnurl: &str,
@@ -56,6 +56,21 @@ pub struct WithdrawRequestResponse {
     pub max_withdrawable: u64,
 }
 
+/// A login request (LUD-04), decoded from the query string of an
+/// LNURL with `tag=login`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LnUrlAuthRequestData {
+    /// The hex encoded 32 byte challenge to sign.
+    pub k1: String,
+    /// The optional action the service announces: `register`,
+    /// `login`, `link` or `auth`.
+    pub action: Option<String>,
+    /// The domain of the service, used to derive the linking key.
+    pub domain: String,
+    /// The decoded URL, which is also the callback URL.
+    pub url: String,
+}
+
 /// Raw success action from an LNURL-pay callback response (LUD-09/10).
 ///
 /// Deserialized directly from the service's JSON. For the AES variant,
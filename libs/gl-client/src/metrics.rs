@@ -41,3 +41,246 @@ pub fn savings_percent(full_wire_bytes: usize, diff_wire_bytes: usize) -> usize
         .saturating_mul(100)
         / full_wire_bytes
 }
+
+/// Upper bounds of the default latency histogram buckets, in seconds.
+pub const LATENCY_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0,
+];
+
+type Labels = Vec<(String, String)>;
+
+fn labels(labels: &[(&str, &str)]) -> Labels {
+    labels
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
+
+#[derive(Clone, Debug)]
+enum Series {
+    Value(f64),
+    Histogram {
+        counts: Vec<u64>,
+        sum: f64,
+        count: u64,
+    },
+}
+
+#[derive(Debug)]
+struct Family {
+    name: String,
+    help: String,
+    kind: &'static str,
+    buckets: Vec<f64>,
+    series: std::collections::BTreeMap<Labels, Series>,
+}
+
+type FamilyRef = std::sync::Arc<std::sync::Mutex<Family>>;
+
+/// A set of metrics that can be rendered in the Prometheus text
+/// exposition format. Clones share the same metrics.
+#[derive(Clone, Debug, Default)]
+pub struct Registry {
+    families: std::sync::Arc<std::sync::Mutex<Vec<FamilyRef>>>,
+}
+
+impl Registry {
+    fn family(&self, name: &str, help: &str, kind: &'static str, buckets: &[f64]) -> FamilyRef {
+        let mut families = self.families.lock().unwrap();
+        if let Some(f) = families.iter().find(|f| f.lock().unwrap().name == name) {
+            return f.clone();
+        }
+        let family = std::sync::Arc::new(std::sync::Mutex::new(Family {
+            name: name.to_string(),
+            help: help.to_string(),
+            kind,
+            buckets: buckets.to_vec(),
+            series: Default::default(),
+        }));
+        families.push(family.clone());
+        family
+    }
+
+    /// Register a counter, or return the one registered under `name`.
+    pub fn counter(&self, name: &str, help: &str) -> Counter {
+        Counter(self.family(name, help, "counter", &[]))
+    }
+
+    /// Register a gauge, or return the one registered under `name`.
+    pub fn gauge(&self, name: &str, help: &str) -> Gauge {
+        Gauge(self.family(name, help, "gauge", &[]))
+    }
+
+    /// Register a histogram with the given bucket upper bounds, or
+    /// return the one registered under `name`.
+    pub fn histogram(&self, name: &str, help: &str, buckets: &[f64]) -> Histogram {
+        Histogram(self.family(name, help, "histogram", buckets))
+    }
+
+    /// Render all metrics in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        use std::fmt::Write;
+        let mut out = String::new();
+        for family in self.families.lock().unwrap().iter() {
+            let f = family.lock().unwrap();
+            let _ = writeln!(out, "# HELP {} {}", f.name, f.help);
+            let _ = writeln!(out, "# TYPE {} {}", f.name, f.kind);
+            for (labels, series) in f.series.iter() {
+                match series {
+                    Series::Value(v) => {
+                        let _ = writeln!(out, "{}{} {}", f.name, render_labels(labels, None), v);
+                    }
+                    Series::Histogram { counts, sum, count } => {
+                        let mut cumulative = 0;
+                        for (le, c) in f.buckets.iter().zip(counts) {
+                            cumulative += c;
+                            let _ = writeln!(
+                                out,
+                                "{}_bucket{} {}",
+                                f.name,
+                                render_labels(labels, Some(&le.to_string())),
+                                cumulative
+                            );
+                        }
+                        let _ = writeln!(
+                            out,
+                            "{}_bucket{} {}",
+                            f.name,
+                            render_labels(labels, Some("+Inf")),
+                            count
+                        );
+                        let _ =
+                            writeln!(out, "{}_sum{} {}", f.name, render_labels(labels, None), sum);
+                        let _ = writeln!(
+                            out,
+                            "{}_count{} {}",
+                            f.name,
+                            render_labels(labels, None),
+                            count
+                        );
+                    }
+                }
+            }
+        }
+        out
+    }
+}
+
+fn render_labels(labels: &Labels, le: Option<&str>) -> String {
+    let escape = |v: &str| {
+        v.replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n")
+    };
+    let mut rendered: Vec<String> = labels
+        .iter()
+        .map(|(k, v)| format!("{}=\"{}\"", k, escape(v)))
+        .collect();
+    if let Some(le) = le {
+        rendered.push(format!("le=\"{}\"", le));
+    }
+    match rendered.is_empty() {
+        true => String::new(),
+        false => format!("{{{}}}", rendered.join(",")),
+    }
+}
+
+/// A monotonically increasing value.
+#[derive(Clone, Debug)]
+pub struct Counter(FamilyRef);
+
+impl Counter {
+    pub fn inc(&self, labels: &[(&str, &str)]) {
+        self.inc_by(labels, 1.0)
+    }
+
+    pub fn inc_by(&self, labels: &[(&str, &str)], v: f64) {
+        let mut f = self.0.lock().unwrap();
+        if let Series::Value(total) = f
+            .series
+            .entry(self::labels(labels))
+            .or_insert(Series::Value(0.0))
+        {
+            *total += v;
+        }
+    }
+}
+
+/// A value that can go up and down.
+#[derive(Clone, Debug)]
+pub struct Gauge(FamilyRef);
+
+impl Gauge {
+    pub fn set(&self, labels: &[(&str, &str)], v: f64) {
+        let mut f = self.0.lock().unwrap();
+        f.series.insert(self::labels(labels), Series::Value(v));
+    }
+}
+
+/// A distribution of observed values, counted in buckets.
+#[derive(Clone, Debug)]
+pub struct Histogram(FamilyRef);
+
+impl Histogram {
+    pub fn observe(&self, labels: &[(&str, &str)], v: f64) {
+        let mut f = self.0.lock().unwrap();
+        let buckets = f.buckets.len();
+        let bucket = f.buckets.iter().position(|le| v <= *le);
+        let series = f
+            .series
+            .entry(self::labels(labels))
+            .or_insert_with(|| Series::Histogram {
+                counts: vec![0; buckets],
+                sum: 0.0,
+                count: 0,
+            });
+        if let Series::Histogram { counts, sum, count } = series {
+            if let Some(b) = bucket {
+                counts[b] += 1;
+            }
+            *sum += v;
+            *count += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_registry() {
+        let registry = Registry::default();
+        let requests = registry.counter("requests_total", "Processed requests.");
+        requests.inc(&[("outcome", "ok")]);
+        requests.inc(&[("outcome", "ok")]);
+        // Registering the same name again returns the same metric.
+        registry
+            .counter("requests_total", "Processed requests.")
+            .inc(&[("outcome", "rejected")]);
+        registry.gauge("pending", "Pending requests.").set(&[], 3.0);
+        let latency = registry.histogram("latency_seconds", "Latency.", &[0.1, 1.0]);
+        latency.observe(&[("signer", "a\"b")], 0.05);
+        latency.observe(&[("signer", "a\"b")], 0.5);
+        latency.observe(&[("signer", "a\"b")], 5.0);
+
+        assert_eq!(
+            registry.render(),
+            "# HELP requests_total Processed requests.
+# TYPE requests_total counter
+requests_total{outcome=\"ok\"} 2
+requests_total{outcome=\"rejected\"} 1
+# HELP pending Pending requests.
+# TYPE pending gauge
+pending 3
+# HELP latency_seconds Latency.
+# TYPE latency_seconds histogram
+latency_seconds_bucket{signer=\"a\\\"b\",le=\"0.1\"} 1
+latency_seconds_bucket{signer=\"a\\\"b\",le=\"1\"} 2
+latency_seconds_bucket{signer=\"a\\\"b\",le=\"+Inf\"} 3
+latency_seconds_sum{signer=\"a\\\"b\"} 5.55
+latency_seconds_count{signer=\"a\\\"b\"} 3
+"
+        );
+    }
+}
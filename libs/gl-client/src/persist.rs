@@ -29,6 +29,8 @@ const CHANNEL_PREFIX: &str = "channels";
 const ALLOWLIST_PREFIX: &str = "allowlists";
 const TRACKER_PREFIX: &str = "trackers";
 const PEER_PREFIX: &str = "peers";
+const SPEND_LEDGER_KEY: &str = "policies/ledger";
+const RUNE_SPEND_LEDGER_KEY: &str = "runes/ledger";
 const TOMBSTONE_VERSION: u64 = u64::MAX;
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -314,6 +316,54 @@ impl State {
         Ok(())
     }
 
+    /// The ledger of payments approved under the spending policy.
+    pub(crate) fn spend_ledger(&self) -> crate::signer::policy::SpendLedger {
+        if self.is_tombstone(SPEND_LEDGER_KEY) {
+            return Default::default();
+        }
+        self.values
+            .get(SPEND_LEDGER_KEY)
+            .and_then(|e| serde_json::from_value(e.value.clone()).ok())
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn set_spend_ledger(
+        &mut self,
+        ledger: &crate::signer::policy::SpendLedger,
+    ) -> Result<(), Error> {
+        self.ensure_not_tombstone(SPEND_LEDGER_KEY)?;
+        let version = self.next_version(SPEND_LEDGER_KEY);
+        let value = serde_json::to_value(ledger).unwrap();
+        self.values
+            .insert(SPEND_LEDGER_KEY.to_owned(), StateEntry::new(version, value));
+        Ok(())
+    }
+
+    /// The amounts spent with each rune, see `auth::RuneSpendLedger`.
+    pub(crate) fn rune_spend_ledger(&self) -> crate::signer::auth::RuneSpendLedger {
+        if self.is_tombstone(RUNE_SPEND_LEDGER_KEY) {
+            return Default::default();
+        }
+        self.values
+            .get(RUNE_SPEND_LEDGER_KEY)
+            .and_then(|e| serde_json::from_value(e.value.clone()).ok())
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn set_rune_spend_ledger(
+        &mut self,
+        ledger: &crate::signer::auth::RuneSpendLedger,
+    ) -> Result<(), Error> {
+        self.ensure_not_tombstone(RUNE_SPEND_LEDGER_KEY)?;
+        let version = self.next_version(RUNE_SPEND_LEDGER_KEY);
+        let value = serde_json::to_value(ledger).unwrap();
+        self.values.insert(
+            RUNE_SPEND_LEDGER_KEY.to_owned(),
+            StateEntry::new(version, value),
+        );
+        Ok(())
+    }
+
     pub fn clear(&mut self) -> Result<(), Error> {
         self.values.clear();
         Ok(())
@@ -997,9 +1047,10 @@ mod tests {
 
     use super::{
         PeerEntry, State, StateEntry, StateSketch, ALLOWLIST_PREFIX, CHANNEL_PREFIX, NODE_PREFIX,
-        NODE_STATE_PREFIX, PEER_PREFIX, TRACKER_PREFIX,
+        NODE_STATE_PREFIX, PEER_PREFIX, SPEND_LEDGER_KEY, TRACKER_PREFIX,
     };
     use crate::pb::SignerStateEntry;
+    use crate::signer::policy::{SpendEntry, SpendLedger};
     use serde_json::json;
     use std::collections::BTreeMap;
 
@@ -1509,6 +1560,28 @@ mod tests {
         assert_tombstone(&state, &live_key);
     }
 
+    #[test]
+    fn spend_ledger_roundtrips_and_bumps_version() {
+        let mut state = State::new();
+        assert_eq!(state.spend_ledger(), SpendLedger::default());
+
+        let mut ledger = SpendLedger {
+            entries: vec![SpendEntry {
+                payment_hash: "aa".to_string(),
+                timestamp: 1,
+                amount_msat: 1000,
+            }],
+        };
+        state.set_spend_ledger(&ledger).unwrap();
+        assert_eq!(state.spend_ledger(), ledger);
+        assert_eq!(state.values.get(SPEND_LEDGER_KEY).unwrap().version, 0);
+
+        ledger.entries.clear();
+        state.set_spend_ledger(&ledger).unwrap();
+        assert_eq!(state.spend_ledger(), ledger);
+        assert_eq!(state.values.get(SPEND_LEDGER_KEY).unwrap().version, 1);
+    }
+
     #[test]
     fn insert_or_update_peer_keeps_last_known_address() {
         let mut state = State::new();
@@ -57,14 +57,29 @@ impl RuneFactory {
 #[derive(Clone, Copy)]
 pub enum DefRules<'a> {
     /// Represents a rule set where only read operations are allowed. This
-    /// translates to a `Restriction` that is "method^Get|method^List".
+    /// translates to a `Restriction` that is "method^get|method^list".
+    /// The signer compares against the lowercased method name.
     ReadOnly,
     /// Represents a rule set where only the `pay` method is allowed. This
     /// translates to a `Restriction` that is "method=pay".
     Pay,
+    /// Limits the amount of a single payment to at most the given
+    /// msat value. Requests that do not spend funds are unaffected.
+    /// This translates to a `Restriction` that is
+    /// "amount_msat!|amount_msat<{msat + 1}".
+    PayMax(u64),
+    /// Limits the total amount spent over the lifetime of the rune to
+    /// at most the given msat value. This translates to a
+    /// `Restriction` that is "spent_msat!|spent_msat<{msat + 1}".
+    SpendMax(u64),
+    /// Only allows payments to the given node id (hex encoded). For
+    /// BOLT11 payments the destination is the invoice payee. This
+    /// translates to a `Restriction` that is
+    /// "destination!|destination={id}".
+    PayToNode(&'a str),
     /// A special rule that adds the alternatives of the given `DefRules`
     /// in a disjunctive set. Example: Add(vec![ReadOnly, Pay]) translates
-    /// to a `Restriction` that is "method^Get|method^List|method=pay".
+    /// to a `Restriction` that is "method^get|method^list|method=pay".
     Add(&'a [DefRules<'a>]),
 }
 
@@ -79,8 +94,8 @@ impl<'a> Restrictor for DefRules<'a> {
         match self {
             DefRules::ReadOnly => {
                 let a: Vec<Restriction> = vec![Restriction::new(vec![
-                    alternative("method", Condition::BeginsWith, "Get").unwrap(),
-                    alternative("method", Condition::BeginsWith, "List").unwrap(),
+                    alternative("method", Condition::BeginsWith, "get").unwrap(),
+                    alternative("method", Condition::BeginsWith, "list").unwrap(),
                 ])
                 .unwrap()];
                 Ok(a)
@@ -93,6 +108,12 @@ impl<'a> Restrictor for DefRules<'a> {
                     .unwrap()];
                 Ok(a)
             }
+            DefRules::PayMax(msat) => Ok(vec![upper_bound("amount_msat", msat)?]),
+            DefRules::SpendMax(msat) => Ok(vec![upper_bound("spent_msat", msat)?]),
+            DefRules::PayToNode(id) => Ok(vec![Restriction::new(vec![
+                alternative("destination", Condition::Missing, "")?,
+                alternative("destination", Condition::Equal, &id.to_lowercase())?,
+            ])?]),
             DefRules::Add(rules) => {
                 let alt_set =
                     rules
@@ -118,6 +139,9 @@ impl<'a> Display for DefRules<'a> {
         match self {
             DefRules::ReadOnly => write!(f, "readonly"),
             DefRules::Pay => write!(f, "pay"),
+            DefRules::PayMax(msat) => write!(f, "paymax({})", msat),
+            DefRules::SpendMax(msat) => write!(f, "spendmax({})", msat),
+            DefRules::PayToNode(id) => write!(f, "paytonode({})", id),
             DefRules::Add(rules) => {
                 write!(
                     f,
@@ -153,6 +177,20 @@ fn alternative(field: &str, cond: Condition, value: &str) -> Result<Alternative,
     Alternative::new(field.to_string(), cond, value.to_string(), false)
 }
 
+/// Creates a `Restriction` that allows `field` to be missing or to be
+/// at most `max`. Runes only know a strict "less than", so the bound
+/// is shifted by one.
+fn upper_bound(field: &str, max: u64) -> Result<Restriction, RuneError> {
+    let bound = i64::try_from(max)
+        .ok()
+        .and_then(|m| m.checked_add(1))
+        .ok_or_else(|| RuneError::ValueError(format!("{} bound {} is too large", field, max)))?;
+    Restriction::new(vec![
+        alternative(field, Condition::Missing, "")?,
+        alternative(field, Condition::IntLT, &bound.to_string())?,
+    ])
+}
+
 /// A context struct that holds information relevant to check a command against
 /// a rune.
 #[derive(Clone)]
@@ -168,6 +206,18 @@ pub struct Context {
     // Todo (nepet): Add param field that uses enum or serde to store the params  of a call.
 }
 
+impl Context {
+    /// Creates a context for a request that carries no parameters.
+    pub fn new(method: String, pubkey: String, unique_id: String, time: SystemTime) -> Self {
+        Context {
+            method,
+            pubkey,
+            unique_id,
+            time,
+        }
+    }
+}
+
 /// Implementation of the `Check` trait for the `Context` struct, allowing it to
 /// perform checks on rune alternatives.
 impl Check for Context {
@@ -199,9 +249,77 @@ impl Check for Context {
     }
 }
 
+/// A [`Context`] extended with the spending parameters of a request,
+/// used to check runes that restrict what a request may spend.
+/// Fields that are not set are treated as missing by the rune checks.
+#[derive(Clone)]
+pub struct SpendContext {
+    ctx: Context,
+    amount_msat: Option<u64>,
+    destination: Option<String>,
+    payee: Option<String>,
+    spent_msat: Option<u64>,
+}
+
+impl SpendContext {
+    /// Creates a spend context for a request that carries no spending
+    /// parameters yet.
+    pub fn new(ctx: Context) -> Self {
+        SpendContext {
+            ctx,
+            amount_msat: None,
+            destination: None,
+            payee: None,
+            spent_msat: None,
+        }
+    }
+
+    /// Sets the amount in msat the request would spend.
+    pub fn amount_msat(mut self, amount_msat: u64) -> Self {
+        self.amount_msat = Some(amount_msat);
+        self
+    }
+
+    /// Sets the destination (hex node id or on-chain address) the
+    /// request would pay to.
+    pub fn destination(mut self, destination: impl Into<String>) -> Self {
+        self.destination = Some(destination.into());
+        self
+    }
+
+    /// Sets the payee (hex) of the bolt11 invoice of the request.
+    pub fn payee(mut self, payee: impl Into<String>) -> Self {
+        self.payee = Some(payee.into());
+        self
+    }
+
+    /// Sets the total amount in msat spent with the rune, including
+    /// this request.
+    pub fn spent_msat(mut self, spent_msat: u64) -> Self {
+        self.spent_msat = Some(spent_msat);
+        self
+    }
+}
+
+impl Check for SpendContext {
+    fn check_alternative(&self, alt: &Alternative) -> anyhow::Result<(), RuneError> {
+        let value = match alt.get_field().as_str() {
+            "amount_msat" => self.amount_msat.map(|a| a.to_string()),
+            "destination" => self.destination.clone(),
+            "payee" => self.payee.clone(),
+            "spent_msat" => self.spent_msat.map(|a| a.to_string()),
+            _ => return self.ctx.check_alternative(alt),
+        };
+        ConditionChecker {
+            value: value.unwrap_or_default(),
+        }
+        .check_alternative(alt)
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{Context, DefRules, RuneFactory};
+    use super::{Context, DefRules, RuneFactory, SpendContext};
     use base64::{engine::general_purpose, Engine as _};
     use runeauth::{Alternative, Condition, Restriction, Rune};
     use std::time::SystemTime;
@@ -216,7 +334,7 @@ mod tests {
 
         let carved_byt = general_purpose::URL_SAFE.decode(&carved).unwrap();
         let carved_restr = String::from_utf8(carved_byt[32..].to_vec()).unwrap(); // Strip off the authcode to inspect the restrictions.
-        assert_eq!(carved_restr, *"method^Get|method^List");
+        assert_eq!(carved_restr, *"method^get|method^list");
 
         let carved_rune = Rune::from_base64(&carved).unwrap();
         assert!(mr.is_authorized(&carved_rune));
@@ -234,12 +352,92 @@ mod tests {
 
         let carved_byt = general_purpose::URL_SAFE.decode(&carved).unwrap();
         let carved_restr = String::from_utf8(carved_byt[32..].to_vec()).unwrap(); // Strip off the authcode to inspect the restrictions.
-        assert_eq!(carved_restr, *"method^Get|method^List|method=pay");
+        assert_eq!(carved_restr, *"method^get|method^list|method=pay");
 
         let carved_rune = Rune::from_base64(&carved).unwrap();
         assert!(mr.is_authorized(&carved_rune));
     }
 
+    #[test]
+    fn test_carve_spend_limited_rune() {
+        let seed = [0; 32];
+        let mr = Rune::new_master_rune(&seed, vec![], None, None).unwrap();
+
+        let carved = RuneFactory::carve(
+            &mr,
+            &[
+                DefRules::Pay,
+                DefRules::PayMax(1000),
+                DefRules::SpendMax(5000),
+                DefRules::PayToNode("02AB"),
+            ],
+        )
+        .unwrap();
+
+        let carved_byt = general_purpose::URL_SAFE.decode(&carved).unwrap();
+        let carved_restr = String::from_utf8(carved_byt[32..].to_vec()).unwrap();
+        assert_eq!(
+            carved_restr,
+            *"method=pay&amount_msat!|amount_msat<1001&spent_msat!|spent_msat<5001&destination!|destination=02ab"
+        );
+
+        assert!(RuneFactory::carve(&mr, &[DefRules::PayMax(u64::MAX)]).is_err());
+    }
+
+    #[test]
+    fn test_context_check_spend_params() {
+        let seed = [0; 32];
+        let mr = Rune::new_master_rune(&seed, vec![], None, None).unwrap();
+        let carved = RuneFactory::carve(
+            &mr,
+            &[
+                DefRules::PayMax(1000),
+                DefRules::SpendMax(5000),
+                DefRules::PayToNode("02ab"),
+            ],
+        )
+        .unwrap();
+        let rune = Rune::from_base64(&carved).unwrap();
+
+        let ctx = |amount: Option<u64>, dest: Option<&str>, spent: Option<u64>| {
+            let mut ctx = SpendContext::new(Context::new(
+                String::from("pay"),
+                String::new(),
+                String::new(),
+                SystemTime::now(),
+            ));
+            if let Some(a) = amount {
+                ctx = ctx.amount_msat(a);
+            }
+            if let Some(d) = dest {
+                ctx = ctx.destination(d);
+            }
+            if let Some(s) = spent {
+                ctx = ctx.spent_msat(s);
+            }
+            ctx
+        };
+
+        // Requests that do not spend are unaffected.
+        assert!(rune.are_restrictions_met(ctx(None, None, None)).is_ok());
+        // Within all limits.
+        assert!(rune
+            .are_restrictions_met(ctx(Some(1000), Some("02ab"), Some(5000)))
+            .is_ok());
+        // Single payment too large.
+        assert!(rune
+            .are_restrictions_met(ctx(Some(1001), Some("02ab"), Some(1001)))
+            .is_err());
+        // Total spend exceeded.
+        assert!(rune
+            .are_restrictions_met(ctx(Some(10), Some("02ab"), Some(5001)))
+            .is_err());
+        // Unknown destination.
+        assert!(rune
+            .are_restrictions_met(ctx(Some(10), Some("03cd"), Some(10)))
+            .is_err());
+    }
+
     #[test]
     fn test_defrules_display() {
         let r = DefRules::Pay;
@@ -248,6 +446,8 @@ mod tests {
         assert_eq!(format!("{}", r), "pay");
         let r = DefRules::Add(&[DefRules::Pay, DefRules::ReadOnly]);
         assert_eq!(format!("{}", r), "pay|readonly");
+        let r = DefRules::Add(&[DefRules::PayMax(10), DefRules::PayToNode("02ab")]);
+        assert_eq!(format!("{}", r), "paymax(10)|paytonode(02ab)");
     }
 
     #[test]
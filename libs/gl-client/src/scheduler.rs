@@ -345,7 +345,7 @@ impl<Creds> Scheduler<Creds> {
     /// let secret = vec![0, 0, 0, 0];
     /// let signer = Signer::new(secret, network, creds).unwrap(); // Create or obtain a signer instance
     /// let registration_response = scheduler_unauthed.register(&signer, None).await.unwrap();
-    /// let creds = Device::from_bytes(registration_response.creds);
+    /// let creds = Device::from_bytes(registration_response.creds).unwrap();
     /// let scheduler_authed = scheduler_unauthed.authenticate(creds);
     /// # }
     /// ```
@@ -390,7 +390,7 @@ where
     /// # async fn example() {
     /// let node_id = vec![0, 1, 2, 3];
     /// let network = Network::Regtest;
-    /// let creds = Device::from_path("my/path/to/credentials.glc");
+    /// let creds = Device::from_path("my/path/to/credentials.glc").unwrap();
     /// let scheduler = Scheduler::new(network, creds.clone()).await.unwrap();
     /// let info = scheduler.schedule().await.unwrap();
     /// let node_client: Client  = Node::new(node_id, creds).unwrap().connect(info.grpc_uri).await.unwrap();
@@ -420,7 +420,7 @@ where
     /// # async fn example() {
     /// let node_id = vec![0, 1, 2, 3];
     /// let network = Network::Regtest;
-    /// let creds = Device::from_path("my/path/to/credentials.glc");
+    /// let creds = Device::from_path("my/path/to/credentials.glc").unwrap();
     /// let scheduler = Scheduler::new(network, creds.clone()).await.unwrap();
     /// let node_client: Client  = scheduler.node().await.unwrap();
     /// # }
@@ -0,0 +1,394 @@
+//! An append-only log of every request the signer approved or
+//! rejected, and why. A `Pending` entry is written before a request is
+//! signed, and signing is aborted if it cannot be written.
+//!
+//! Each entry carries the hash of the previous entry, and its own hash
+//! covers its contents and that link, so removing, reordering or
+//! altering entries is detected by [`verify`]. The log is written as
+//! JSON lines to `audit.jsonl` in the configured directory, and
+//! rotated to `audit-<seq>.jsonl` once it grows beyond
+//! [`AuditConfig::max_file_bytes`]. The chain continues across
+//! rotated files.
+use anyhow::{anyhow, Context, Result};
+use ring::digest::{digest, SHA256};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+const CURRENT_FILE: &str = "audit.jsonl";
+const ROTATED_PREFIX: &str = "audit-";
+const ROTATED_SUFFIX: &str = ".jsonl";
+/// The `prev_hash` of the very first entry.
+const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AuditConfig {
+    /// Directory the log files are kept in.
+    pub path: PathBuf,
+    /// Rotate the current file once it is larger than this.
+    pub max_file_bytes: u64,
+    /// Delete the oldest rotated files beyond this count. `None`
+    /// keeps all of them.
+    pub max_files: Option<usize>,
+}
+
+impl AuditConfig {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            max_file_bytes: 10 * 1024 * 1024,
+            max_files: None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Decision {
+    /// The request passed the checks and is about to be signed. Always
+    /// followed by an `Approved` or `Rejected` entry for the request,
+    /// unless the signer stopped while signing.
+    Pending,
+    Approved,
+    Rejected,
+}
+
+/// A request from the request context the signer used to decide.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContextRequest {
+    /// The gRPC method that was called.
+    pub uri: String,
+    /// The id of the rune that authorized the call, the hex-encoded
+    /// SHA256 of its authcode. The authcode itself would allow
+    /// deriving new runes, so it is never logged.
+    pub rune_id: String,
+    /// The decoded request.
+    pub request: String,
+}
+
+/// What is known about a request before the signer decided on it.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Record {
+    pub(crate) request_id: u32,
+    pub(crate) request_type: Option<u16>,
+    pub(crate) context: Vec<ContextRequest>,
+    pub(crate) resolution: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// Position in the log, starting at 0.
+    pub seq: u64,
+    /// Seconds since the UNIX epoch.
+    pub timestamp: u64,
+    pub request_id: u32,
+    /// The `hsmd` wire message type, if the request had one.
+    pub request_type: Option<u16>,
+    pub context: Vec<ContextRequest>,
+    /// How the request was justified, e.g., the context request it
+    /// matched. `None` if the signer never got to resolving it.
+    pub resolution: Option<String>,
+    pub decision: Decision,
+    /// Why the request was rejected.
+    pub reason: Option<String>,
+    /// Hex-encoded hash of the previous entry.
+    pub prev_hash: String,
+    /// Hex-encoded SHA256 of this entry, with `hash` left empty.
+    pub hash: String,
+}
+
+impl AuditEntry {
+    fn compute_hash(&self) -> Result<String> {
+        let unhashed = AuditEntry {
+            hash: String::new(),
+            ..self.clone()
+        };
+        let raw = serde_json::to_vec(&unhashed)?;
+        Ok(hex::encode(digest(&SHA256, &raw)))
+    }
+}
+
+struct Tail {
+    file: fs::File,
+    size: u64,
+    next_seq: u64,
+    last_hash: String,
+}
+
+/// The writer side of the audit log.
+pub struct AuditLog {
+    config: AuditConfig,
+    tail: Mutex<Tail>,
+}
+
+impl std::fmt::Debug for AuditLog {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AuditLog")
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+impl AuditLog {
+    /// Open the log in `config.path`, continuing the chain of the
+    /// existing entries.
+    pub fn open(config: AuditConfig) -> Result<Self> {
+        fs::create_dir_all(&config.path)
+            .with_context(|| format!("creating audit log directory {}", config.path.display()))?;
+        let (next_seq, last_hash) = match read(&config.path)?.last() {
+            Some(e) => (e.seq + 1, e.hash.clone()),
+            None => (0, GENESIS_HASH.to_string()),
+        };
+        let file = open_current(&config.path)?;
+        let size = file.metadata()?.len();
+        Ok(AuditLog {
+            config,
+            tail: Mutex::new(Tail {
+                file,
+                size,
+                next_seq,
+                last_hash,
+            }),
+        })
+    }
+
+    /// Append the decision on a request to the log.
+    pub(crate) fn append(
+        &self,
+        record: Record,
+        decision: Decision,
+        reason: Option<String>,
+    ) -> Result<AuditEntry> {
+        let mut tail = self
+            .tail
+            .lock()
+            .map_err(|e| anyhow!("audit log lock poisoned: {e}"))?;
+        let mut entry = AuditEntry {
+            seq: tail.next_seq,
+            timestamp: SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)?
+                .as_secs(),
+            request_id: record.request_id,
+            request_type: record.request_type,
+            context: record.context,
+            resolution: record.resolution,
+            decision,
+            reason,
+            prev_hash: tail.last_hash.clone(),
+            hash: String::new(),
+        };
+        entry.hash = entry.compute_hash()?;
+
+        if tail.size >= self.config.max_file_bytes {
+            self.rotate(&mut tail, entry.seq)?;
+        }
+        let mut line = serde_json::to_vec(&entry)?;
+        line.push(b'\n');
+        tail.file.write_all(&line)?;
+        tail.file.flush()?;
+        tail.size += line.len() as u64;
+        tail.next_seq += 1;
+        tail.last_hash = entry.hash.clone();
+        Ok(entry)
+    }
+
+    fn rotate(&self, tail: &mut Tail, next_seq: u64) -> Result<()> {
+        // Name the rotated file after its first entry, so the file
+        // names sort in log order.
+        let first_seq = next_seq.saturating_sub(count_lines(&self.config.path.join(CURRENT_FILE))?);
+        fs::rename(
+            self.config.path.join(CURRENT_FILE),
+            self.config
+                .path
+                .join(format!("{ROTATED_PREFIX}{first_seq:020}{ROTATED_SUFFIX}")),
+        )?;
+        tail.file = open_current(&self.config.path)?;
+        tail.size = 0;
+
+        if let Some(max_files) = self.config.max_files {
+            let rotated = rotated_files(&self.config.path)?;
+            for old in rotated.iter().take(rotated.len().saturating_sub(max_files)) {
+                fs::remove_file(old)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn open_current(dir: &Path) -> Result<fs::File> {
+    let path = dir.join(CURRENT_FILE);
+    fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("opening audit log {}", path.display()))
+}
+
+fn count_lines(path: &Path) -> Result<u64> {
+    let file = fs::File::open(path)?;
+    // `lines()` keeps yielding the same error, e.g., for a directory,
+    // so stop at the first one.
+    let count = std::io::BufReader::new(file)
+        .lines()
+        .try_fold(0, |n, line| line.map(|_| n + 1))?;
+    Ok(count)
+}
+
+fn rotated_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| {
+            p.file_name()
+                .and_then(|n| n.to_str())
+                .map(|n| n.starts_with(ROTATED_PREFIX) && n.ends_with(ROTATED_SUFFIX))
+                .unwrap_or(false)
+        })
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+/// Read all retained entries in `dir`, oldest first.
+pub fn read(dir: &Path) -> Result<Vec<AuditEntry>> {
+    let mut files = rotated_files(dir)?;
+    let current = dir.join(CURRENT_FILE);
+    if current.exists() {
+        files.push(current);
+    }
+
+    let mut entries = vec![];
+    for path in files {
+        let file = fs::File::open(&path)?;
+        for (i, line) in std::io::BufReader::new(file).lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            entries.push(serde_json::from_str(&line).with_context(|| {
+                format!("parsing line {} of audit log {}", i + 1, path.display())
+            })?);
+        }
+    }
+    Ok(entries)
+}
+
+/// Check that the entries form an unbroken chain. The first entry may
+/// link to entries that were deleted by rotation, unless it is the
+/// very first entry of the log.
+pub fn verify(entries: &[AuditEntry]) -> Result<()> {
+    let mut prev: Option<&AuditEntry> = None;
+    for entry in entries {
+        if entry.compute_hash()? != entry.hash {
+            return Err(anyhow!("entry {} does not match its hash", entry.seq));
+        }
+        match prev {
+            Some(p) if entry.seq != p.seq + 1 || entry.prev_hash != p.hash => {
+                return Err(anyhow!(
+                    "entry {} does not follow entry {}",
+                    entry.seq,
+                    p.seq
+                ));
+            }
+            None if entry.seq == 0 && entry.prev_hash != GENESIS_HASH => {
+                return Err(anyhow!("entry 0 does not start the chain"));
+            }
+            _ => {}
+        }
+        prev = Some(entry);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(request_id: u32) -> Record {
+        Record {
+            request_id,
+            request_type: Some(5),
+            context: vec![ContextRequest {
+                uri: "/cln.Node/Pay".to_string(),
+                rune_id: "00".to_string(),
+                request: "PayRequest".to_string(),
+            }],
+            resolution: Some("no context required".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_audit_log_chain_survives_reopening_and_rotation() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = AuditConfig {
+            max_file_bytes: 1,
+            ..AuditConfig::new(dir.path())
+        };
+
+        let log = AuditLog::open(config.clone()).unwrap();
+        log.append(record(1), Decision::Approved, None).unwrap();
+        log.append(record(2), Decision::Rejected, Some("no".to_string()))
+            .unwrap();
+        drop(log);
+
+        let log = AuditLog::open(config).unwrap();
+        let entry = log.append(record(3), Decision::Approved, None).unwrap();
+        assert_eq!(entry.seq, 2);
+
+        let entries = read(dir.path()).unwrap();
+        assert_eq!(
+            entries.iter().map(|e| e.request_id).collect::<Vec<_>>(),
+            vec![1, 2, 3]
+        );
+        assert_eq!(entries[0].prev_hash, GENESIS_HASH);
+        assert_eq!(entries[1].reason.as_deref(), Some("no"));
+        assert_eq!(rotated_files(dir.path()).unwrap().len(), 2);
+        verify(&entries).unwrap();
+    }
+
+    #[test]
+    fn test_audit_log_detects_tampering() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = AuditLog::open(AuditConfig::new(dir.path())).unwrap();
+        for i in 0..3 {
+            log.append(record(i), Decision::Approved, None).unwrap();
+        }
+
+        let entries = read(dir.path()).unwrap();
+        let mut altered = entries.clone();
+        altered[1].decision = Decision::Rejected;
+        assert!(verify(&altered).is_err());
+
+        let mut removed = entries.clone();
+        removed.remove(1);
+        assert!(verify(&removed).is_err());
+
+        // Retained entries after the oldest ones were rotated away
+        // still verify.
+        verify(&entries[1..]).unwrap();
+    }
+
+    #[test]
+    fn test_audit_log_retention() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = AuditLog::open(AuditConfig {
+            max_file_bytes: 1,
+            max_files: Some(1),
+            ..AuditConfig::new(dir.path())
+        })
+        .unwrap();
+        for i in 0..4 {
+            log.append(record(i), Decision::Approved, None).unwrap();
+        }
+
+        let entries = read(dir.path()).unwrap();
+        assert_eq!(
+            entries.iter().map(|e| e.seq).collect::<Vec<_>>(),
+            vec![2, 3]
+        );
+        verify(&entries).unwrap();
+    }
+}
@@ -1,7 +1,12 @@
 //! Utilities used to authorize a signature request based on pending RPCs
+use crate::pb::cln::{amount_or_all, AmountOrAll};
 use crate::signer::model::Request;
 use crate::Error;
-use lightning_signer::invoice::Invoice;
+use lightning_signer::invoice::{Invoice, InvoiceAttributes};
+use ring::digest::{digest, SHA256};
+use runeauth::{Restriction, Rune};
+use serde::{Deserialize, Serialize};
+use std::collections::{btree_map, BTreeMap};
 use std::str::FromStr;
 use vls_protocol_signer::approver::Approval;
 
@@ -16,21 +21,12 @@ impl Authorizer for GreenlightAuthorizer {
         let mut approvals = Vec::new();
         for request in requests.iter() {
             match request {
-                Request::Pay(req) => {
-                    let inv = req.bolt11.clone().to_lowercase();
-                    let inv = inv.strip_prefix("lightning:").unwrap_or(&inv);
-                    match Invoice::from_str(inv) {
-                        Ok(invoice) => {
-                            approvals.push(Approval::Invoice(invoice));
-                        }
-                        Err(e) => {
-                            return Err(crate::Error::IllegalArgument(format!(
-                                "Failed to parse invoice from Pay request: {:?}",
-                                e
-                            )));
-                        }
-                    }
-                }
+                // An invoice we can't parse is not approved, which
+                // fails that payment but not the other requests.
+                Request::Pay(req) => match parse_invoice(&req.bolt11) {
+                    Ok(invoice) => approvals.push(Approval::Invoice(invoice)),
+                    Err(e) => log::warn!("Not approving Pay request: {}", e),
+                },
                 _ => {}
             }
         }
@@ -38,12 +34,250 @@ impl Authorizer for GreenlightAuthorizer {
     }
 }
 
+/// The spending related parameters of a pending RPC request, used to
+/// check runes that restrict what a request may do.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct SpendParams {
+    /// The amount in msat the request would send.
+    pub amount_msat: Option<u64>,
+    /// The node id (hex) the request would send to.
+    pub destination: Option<String>,
+    /// The payee (hex) of the bolt11 invoice in the request.
+    pub payee: Option<String>,
+    /// The request moves funds in a way the fields above do not
+    /// capture, e.g. it sweeps the whole wallet or broadcasts a
+    /// transaction prepared earlier. Runes that restrict spending
+    /// must reject such requests.
+    pub unbounded: bool,
+}
+
+impl SpendParams {
+    fn unbounded() -> Self {
+        SpendParams {
+            unbounded: true,
+            ..Default::default()
+        }
+    }
+}
+
+/// Extracts the [`SpendParams`] from a decoded request. Requests that
+/// do not move funds return empty params.
+pub(crate) fn spend_params(request: &Request) -> Result<SpendParams, Error> {
+    match request {
+        Request::Pay(req) => invoice_params(&req.bolt11, req.amount_msat.as_ref().map(|a| a.msat)),
+        Request::TrampolinePay(req) => {
+            invoice_params(&req.bolt11, Some(req.amount_msat).filter(|a| *a != 0))
+        }
+        Request::KeySend(req) => Ok(SpendParams {
+            amount_msat: Some(req.amount_msat.as_ref().map(|a| a.msat).unwrap_or_default()),
+            destination: Some(hex::encode(&req.destination)),
+            payee: None,
+            unbounded: false,
+        }),
+        Request::SendPay(req) => {
+            let last = req.route.last();
+            let amount = req
+                .amount_msat
+                .as_ref()
+                .or(last.and_then(|h| h.amount_msat.as_ref()))
+                .map(|a| a.msat)
+                .unwrap_or_default();
+            let mut params = match &req.bolt11 {
+                Some(bolt11) => invoice_params(bolt11, Some(amount))?,
+                None => SpendParams::default(),
+            };
+            params.amount_msat = Some(amount);
+            if params.destination.is_none() {
+                params.destination = last.map(|h| hex::encode(&h.id));
+            }
+            Ok(params)
+        }
+        Request::Withdraw(req) => Ok(match onchain_amount(&req.satoshi) {
+            Some(amount) => SpendParams {
+                amount_msat: Some(amount),
+                destination: Some(req.destination.clone()),
+                payee: None,
+                unbounded: false,
+            },
+            None => SpendParams::unbounded(),
+        }),
+        Request::FundChannel(req) => Ok(match onchain_amount(&req.amount) {
+            Some(amount) => SpendParams {
+                amount_msat: Some(amount),
+                destination: Some(hex::encode(&req.id)),
+                payee: None,
+                unbounded: false,
+            },
+            None => SpendParams::unbounded(),
+        }),
+        Request::TxPrepare(req) => {
+            let amount = req
+                .outputs
+                .iter()
+                .try_fold(0u64, |sum, o| sum.checked_add(o.amount.as_ref()?.msat));
+            let mut addresses = req.outputs.iter().map(|o| &o.address);
+            let destination = addresses
+                .next()
+                .filter(|first| addresses.all(|a| a == *first));
+            Ok(match (amount, destination) {
+                (Some(amount), Some(destination)) => SpendParams {
+                    amount_msat: Some(amount),
+                    destination: Some(destination.clone()),
+                    payee: None,
+                    unbounded: false,
+                },
+                _ => SpendParams::unbounded(),
+            })
+        }
+        Request::Close(req) if req.destination.is_some() => Ok(SpendParams::unbounded()),
+        Request::TxSend(_)
+        | Request::SendPsbt(_)
+        | Request::SignPsbt(_)
+        | Request::FundPsbt(_)
+        | Request::UtxoPsbt(_)
+        | Request::SendOnion(_) => Ok(SpendParams::unbounded()),
+        _ => Ok(SpendParams::default()),
+    }
+}
+
+/// The amount in msat of an on-chain request, or `None` if it spends
+/// everything or the amount would overflow.
+fn onchain_amount(amount: &Option<AmountOrAll>) -> Option<u64> {
+    match amount {
+        Some(AmountOrAll {
+            value: Some(amount_or_all::Value::Amount(a)),
+        }) => Some(a.msat),
+        _ => None,
+    }
+}
+
+/// Parses the `bolt11` field of a request, which also carries BOLT12
+/// invoices (`lni1...`), as `pay` accepts both.
+fn parse_invoice(bolt11: &str) -> Result<Invoice, Error> {
+    let inv = bolt11.to_lowercase();
+    let inv = inv.strip_prefix("lightning:").unwrap_or(&inv);
+    Invoice::from_str(inv)
+        .map_err(|e| crate::Error::IllegalArgument(format!("Failed to parse invoice: {:?}", e)))
+}
+
+/// Decodes a BOLT11 or BOLT12 invoice into [`SpendParams`]. An
+/// explicit amount takes precedence over the invoice amount, as is the
+/// case for amountless invoices.
+fn invoice_params(bolt11: &str, amount_msat: Option<u64>) -> Result<SpendParams, Error> {
+    let invoice = parse_invoice(bolt11)?;
+    let payee = hex::encode(invoice.payee_pub_key().serialize());
+    let amount = match amount_msat {
+        Some(a) => a,
+        None => invoice.amount_milli_satoshis(),
+    };
+    Ok(SpendParams {
+        amount_msat: Some(amount),
+        destination: Some(payee.clone()),
+        payee: Some(payee),
+        unbounded: false,
+    })
+}
+
+/// Identifies a rune without revealing its authcode, which is the
+/// secret that authorizes the rune.
+pub(crate) fn rune_id(rune: &Rune) -> String {
+    hex::encode(digest(&SHA256, &rune.authcode()))
+}
+
+/// The rune fields that restrict what a request may spend.
+const SPEND_FIELDS: [&str; 4] = ["amount_msat", "destination", "payee", "spent_msat"];
+
+/// Whether any restriction of `rune` refers to a spending field. Runes
+/// that can not be parsed are assumed to restrict spending.
+pub(crate) fn restricts_spending(rune: &Rune) -> bool {
+    let s = rune.to_string();
+    let mut rest = s.get(65..).unwrap_or_default();
+    while !rest.is_empty() {
+        match Restriction::decode(rest, true) {
+            Ok((r, tail)) => {
+                if r.alternatives
+                    .iter()
+                    .any(|a| SPEND_FIELDS.contains(&a.get_field().as_str()))
+                {
+                    return true;
+                }
+                rest = tail;
+            }
+            Err(_) => return true,
+        }
+    }
+    false
+}
+
+/// How long the signatures of recorded requests are remembered. A
+/// request that is still pending after this is counted again.
+const SEEN_RETENTION_SECS: u64 = 24 * 60 * 60;
+
+/// Keeps track of how much was spent with each rune, so that runes
+/// can restrict the total amount spent via `spent_msat`. The ledger
+/// is stored in the signer `State`, and therefore survives restarts.
+///
+/// Pending requests are attached to every signer request that is
+/// processed while they are in flight, so each request is only
+/// accounted for once, identified by its signature.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub(crate) struct RuneSpendLedger {
+    runes: BTreeMap<String, RuneSpend>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+struct RuneSpend {
+    spent_msat: u64,
+    /// The signatures (hex) of the recorded requests, mapped to when
+    /// they were recorded, in seconds since the epoch.
+    seen: BTreeMap<String, u64>,
+}
+
+/// A spend accepted by the rune checks, to be recorded once the
+/// signer request it was attached to succeeded.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct PendingSpend {
+    pub rune_id: String,
+    pub signature: Vec<u8>,
+    pub amount_msat: u64,
+}
+
+impl RuneSpendLedger {
+    /// Returns the total spent with `rune_id` if the request with
+    /// `signature` and `amount_msat` were to be accepted.
+    pub fn projected(&self, rune_id: &str, signature: &[u8], amount_msat: u64) -> u64 {
+        match self.runes.get(rune_id) {
+            Some(s) if s.seen.contains_key(&hex::encode(signature)) => s.spent_msat,
+            Some(s) => s.spent_msat.saturating_add(amount_msat),
+            None => amount_msat,
+        }
+    }
+
+    /// Accounts for a spend at `now`. Requests that were already
+    /// recorded are ignored, and signatures older than the retention
+    /// period are forgotten.
+    pub fn record(&mut self, spend: &PendingSpend, now: u64) {
+        let entry = self.runes.entry(spend.rune_id.clone()).or_default();
+        entry
+            .seen
+            .retain(|_, ts| ts.saturating_add(SEEN_RETENTION_SECS) > now);
+        if let btree_map::Entry::Vacant(e) = entry.seen.entry(hex::encode(&spend.signature)) {
+            e.insert(now);
+            entry.spent_msat = entry.spent_msat.saturating_add(spend.amount_msat);
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use crate::signer::auth::Authorizer;
     use crate::signer::auth::GreenlightAuthorizer;
+    use crate::signer::auth::{spend_params, PendingSpend, RuneSpendLedger, SpendParams};
     use crate::signer::model::Request;
-    use cln_grpc::pb::PayRequest;
+    use cln_grpc::pb::{
+        amount_or_all, Amount, AmountOrAll, FundchannelRequest, GetinfoRequest, KeysendRequest,
+        OutputDesc, PayRequest, TxprepareRequest, TxsendRequest, WithdrawRequest,
+    };
 
     #[test]
     fn test_prefix_stripping() {
@@ -63,5 +297,196 @@ mod test {
             let approvals = dbg!(auth.authorize(&reqs).unwrap());
             assert_eq!(approvals.len(), 1);
         }
+
+        // An invalid invoice is not approved, without failing the
+        // other requests.
+        let reqs = vec![
+            Request::Pay(PayRequest {
+                bolt11: "lnbcnotaninvoice".to_string(),
+                ..Default::default()
+            }),
+            Request::Pay(PayRequest {
+                bolt11: INVOICE.to_string(),
+                ..Default::default()
+            }),
+        ];
+        assert_eq!(auth.authorize(&reqs).unwrap().len(), 1);
+    }
+
+    const INVOICE: &str = "lnbc80u1p4zzlrqdqqpp5uncavtdcq8k9rw0ef0q0d6a7vr0cktxrgu5azs0glssvtx6l64escqzp2sp55d0jdhcgffylnh04kawkn3926quhlegsa0zf9renpvkpq9ptc6dq9qyysgqxqyz5vqnp4qwxa7dsnsmy72v39u4qj9x2ly7k57ye3ytuvjpmdc4fwnwkn5yqgzrzjqv22wafr68wtchd4vzq7mj7zf2uzpv67xsaxcemfzak7wp7p0r29wr8aasqqtrcqqvqqqqqqqqqqhwqqfq6e5scsajygx83z5kqaswk0ulzkuqgp22c5ltc9jrw4shrtszw4fzcgx38fmr6e06r8s3rl5z4ck0pl3gyzmfglv3efth304330ccu3sqc9uz8q";
+
+    #[test]
+    fn test_spend_params_pay() {
+        let params = spend_params(&Request::Pay(PayRequest {
+            bolt11: format!("lightning:{}", INVOICE),
+            ..Default::default()
+        }))
+        .unwrap();
+        assert_eq!(params.amount_msat, Some(8_000_000));
+        assert!(params.payee.is_some());
+        assert_eq!(params.destination, params.payee);
+
+        // An explicit amount overrides the invoice amount.
+        let params = spend_params(&Request::Pay(PayRequest {
+            bolt11: INVOICE.to_string(),
+            amount_msat: Some(Amount { msat: 42 }),
+            ..Default::default()
+        }))
+        .unwrap();
+        assert_eq!(params.amount_msat, Some(42));
+
+        assert!(spend_params(&Request::Pay(PayRequest {
+            bolt11: "lnbcnotaninvoice".to_string(),
+            ..Default::default()
+        }))
+        .is_err());
+    }
+
+    // A BOLT12 invoice, as returned by `fetchinvoice`, for 2msat.
+    const BOLT12_INVOICE: &str = "lni1qqgf8ene6trt4n9mmrejx50c6v30cq3qqc3xu3s3rg94nj40zfsy866mhu5vxne6tcej5878k2mneuvgjy8ssqgzpg9hx6tdwpkx2gr5v4ehg93pqdwjkyvjm7apxnssu4qgwhfkd67ghs6n6k48v6uqczgt88p6tky965pqqc3xu3s3rg94nj40zfsy866mhu5vxne6tcej5878k2mneuvgjy84sggravpsmwr0rxjdwzvj3ltcg95eklxftgfw8njx2dd3v9eat2k8q8g6pxqrt543ryklhgf5uy89gzr46dnwhj9ux5744fmxhqxqjzeecwja3pwsxz392f64zmwkh5p9hygu8gvt3lpfrn7ehs53d6ylasgcyppwdr6pqypde4glecqn4h2ydg7e56xq3n0p0jxzpw9v89qw7n9encppxqt037qqx2s4d5007pqgecutjv9x6gr793gqsc2svc9a2k3l62klfcny8ca8z60eptrhahvy9aypymralep23vvvkw3pcqqqqqqqqqqqqqqq2qqqqqqqqqqqqqwjfvkl43fqqqqqqzjqgepvjh02sg8u5wx8nat9vgux9cvr8fe9c337706k08xrnl03dmwaglxr46yglz4qzq4syyp462c3jt0m5y6wzrj5pp6axehtez7r20265antsrqfpvuu8fwcsh0sgzm7pttfeuz5snjhmks67afze5klpew503kn98x4zt24dcsurm9wch699ucgw9sh5ww85gu2fy598hdne0gp5msx0shu4kqqc9z6hhk7";
+
+    #[test]
+    fn test_bolt12_invoice() {
+        let req = Request::Pay(PayRequest {
+            bolt11: BOLT12_INVOICE.to_string(),
+            ..Default::default()
+        });
+        let approvals = GreenlightAuthorizer {}
+            .authorize(&vec![req.clone()])
+            .unwrap();
+        assert_eq!(approvals.len(), 1);
+
+        let params = spend_params(&req).unwrap();
+        assert_eq!(params.amount_msat, Some(2));
+        assert_eq!(
+            params.payee.as_deref(),
+            Some("035d2b1192dfba134e10e540875d366ebc8bc353d5aa766b80c090b39c3a5d885d")
+        );
+        assert_eq!(params.destination, params.payee);
+    }
+
+    #[test]
+    fn test_spend_params_keysend_and_others() {
+        let params = spend_params(&Request::KeySend(KeysendRequest {
+            destination: vec![2u8; 33],
+            amount_msat: Some(Amount { msat: 1000 }),
+            ..Default::default()
+        }))
+        .unwrap();
+        assert_eq!(params.amount_msat, Some(1000));
+        assert_eq!(params.destination, Some("02".repeat(33)));
+        assert_eq!(params.payee, None);
+
+        let params = spend_params(&Request::Getinfo(GetinfoRequest {})).unwrap();
+        assert_eq!(params, SpendParams::default());
+    }
+
+    #[test]
+    fn test_spend_params_onchain() {
+        let amount = |msat| {
+            Some(AmountOrAll {
+                value: Some(amount_or_all::Value::Amount(Amount { msat })),
+            })
+        };
+        let all = Some(AmountOrAll {
+            value: Some(amount_or_all::Value::All(true)),
+        });
+
+        let params = spend_params(&Request::Withdraw(WithdrawRequest {
+            destination: "bc1qaddr".to_string(),
+            satoshi: amount(5000),
+            ..Default::default()
+        }))
+        .unwrap();
+        assert_eq!(params.amount_msat, Some(5000));
+        assert_eq!(params.destination, Some("bc1qaddr".to_string()));
+        assert!(!params.unbounded);
+
+        let params = spend_params(&Request::Withdraw(WithdrawRequest {
+            destination: "bc1qaddr".to_string(),
+            satoshi: all.clone(),
+            ..Default::default()
+        }))
+        .unwrap();
+        assert!(params.unbounded);
+
+        let params = spend_params(&Request::FundChannel(FundchannelRequest {
+            id: vec![2u8; 33],
+            amount: amount(7000),
+            ..Default::default()
+        }))
+        .unwrap();
+        assert_eq!(params.amount_msat, Some(7000));
+        assert_eq!(params.destination, Some("02".repeat(33)));
+        assert!(
+            spend_params(&Request::FundChannel(FundchannelRequest {
+                amount: all,
+                ..Default::default()
+            }))
+            .unwrap()
+            .unbounded
+        );
+
+        let output = |address: &str, msat| OutputDesc {
+            address: address.to_string(),
+            amount: Some(Amount { msat }),
+        };
+        let params = spend_params(&Request::TxPrepare(TxprepareRequest {
+            outputs: vec![output("bc1qa", 1000), output("bc1qa", 2000)],
+            ..Default::default()
+        }))
+        .unwrap();
+        assert_eq!(params.amount_msat, Some(3000));
+        assert_eq!(params.destination, Some("bc1qa".to_string()));
+
+        // Several destinations or an overflowing total can not be
+        // expressed.
+        for outputs in [
+            vec![output("bc1qa", 1000), output("bc1qb", 2000)],
+            vec![output("bc1qa", u64::MAX), output("bc1qa", 1)],
+        ] {
+            let params = spend_params(&Request::TxPrepare(TxprepareRequest {
+                outputs,
+                ..Default::default()
+            }))
+            .unwrap();
+            assert!(params.unbounded);
+        }
+
+        assert!(
+            spend_params(&Request::TxSend(TxsendRequest { txid: vec![0; 32] }))
+                .unwrap()
+                .unbounded
+        );
+    }
+
+    #[test]
+    fn test_rune_spend_ledger_counts_requests_once() {
+        let spend = |sig: &[u8], amount_msat| PendingSpend {
+            rune_id: "r1".to_string(),
+            signature: sig.to_vec(),
+            amount_msat,
+        };
+        let mut ledger = RuneSpendLedger::default();
+        assert_eq!(ledger.projected("r1", b"sig1", 100), 100);
+        ledger.record(&spend(b"sig1", 100), 1);
+
+        // The same request is attached again, it must not be counted twice.
+        assert_eq!(ledger.projected("r1", b"sig1", 100), 100);
+        ledger.record(&spend(b"sig1", 100), 2);
+
+        assert_eq!(ledger.projected("r1", b"sig2", 50), 150);
+        ledger.record(&spend(b"sig2", 50), 3);
+        assert_eq!(ledger.projected("r1", b"sig3", 0), 150);
+
+        // Runes are accounted for separately.
+        assert_eq!(ledger.projected("r2", b"sig1", 10), 10);
+
+        // Old signatures are pruned, but the total is kept.
+        ledger.record(&spend(b"sig3", 0), 3 + super::SEEN_RETENTION_SECS);
+        let seen = &ledger.runes["r1"].seen;
+        assert_eq!(seen.len(), 1);
+        assert!(seen.contains_key(&hex::encode(b"sig3")));
+        assert_eq!(ledger.projected("r1", b"sig4", 0), 150);
     }
 }
@@ -1,5 +1,5 @@
 use crate::credentials::{RuneProvider, TlsConfigProvider};
-use crate::metrics::{savings_percent, signer_state_response_wire_bytes};
+use crate::metrics::{self, savings_percent, signer_state_response_wire_bytes};
 use crate::pb::scheduler::{scheduler_client::SchedulerClient, NodeInfoRequest, UpgradeRequest};
 use crate::pb::scheduler::{
     signer_request, signer_response, ApprovePairingRequest, ApprovePairingResponse, SignerResponse,
@@ -10,7 +10,7 @@ use crate::pb::scheduler::{
 use crate::pb::{node_client::NodeClient, Empty, HsmRequest, HsmRequestContext, HsmResponse};
 use crate::pb::{PendingRequest, SignerStateEntry};
 use crate::runes;
-use crate::signer::resolve::Resolver;
+use crate::signer::resolve::{Resolution, Resolver};
 use crate::tls::TlsConfig;
 use crate::{node, node::Client};
 use anyhow::{anyhow, Result};
@@ -46,7 +46,8 @@ use vls_protocol_signer::handler;
 use vls_protocol_signer::handler::Handler;
 
 mod approver;
-mod auth;
+pub mod audit;
+pub(crate) mod auth;
 #[cfg(feature = "backup")]
 mod backup;
 #[cfg(feature = "backup")]
@@ -57,8 +58,10 @@ pub use backup::{
     SignerBackupStrategy,
 };
 pub mod model;
+pub mod policy;
 mod report;
 mod resolve;
+pub mod store;
 
 const VERSION: &str = "v26.06";
 const GITHASH: &str = env!("GIT_HASH");
@@ -70,6 +73,10 @@ const STATE_SIGNING_DOMAIN: &[u8] = b"greenlight/state-signing/v1\0";
 const STATE_SIGNATURE_OVERRIDE_ACK: &str = "I_ACCEPT_OPERATOR_ASSISTED_STATE_OVERRIDE";
 const COMPACT_SIGNATURE_LEN: usize = 64;
 
+/// Metadata header carrying `SignerConfig::signer_id` when streaming
+/// requests, so the node knows which signers are attached.
+pub const SIGNER_ID_HEADER: &str = "glsignerid";
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum StateSignatureMode {
     Off,
@@ -93,10 +100,59 @@ pub struct StateSignatureOverrideConfig {
 pub struct SignerConfig {
     pub state_signature_mode: StateSignatureMode,
     pub state_signature_override: Option<StateSignatureOverrideConfig>,
+    /// Spending limits enforced by the signer, see
+    /// `policy::SpendingPolicy::from_path` to load them from a file.
+    pub spending_policy: Option<policy::SpendingPolicy>,
+    /// Refuse requests whose state is older than the state the
+    /// signer persisted, see `Signer::with_state_store`. Without a
+    /// state store stale state is only logged.
+    pub reject_stale_state: bool,
+    /// Identifies this signer in its responses, so the node can tell
+    /// multiple signers for the same node apart.
+    pub signer_id: Option<String>,
+    /// Record every approved and rejected request in a hash-chained
+    /// audit log, see `audit::AuditLog`.
+    pub audit: Option<audit::AuditConfig>,
     #[cfg(feature = "backup")]
     pub backup: Option<SignerBackupConfig>,
 }
 
+/// Metrics collected by the signer, see `Signer::metrics`.
+#[derive(Clone, Debug)]
+struct SignerMetrics {
+    registry: metrics::Registry,
+    requests: metrics::Counter,
+    latency: metrics::Histogram,
+    state_diff_bytes: metrics::Counter,
+    state_full_bytes: metrics::Counter,
+}
+
+impl SignerMetrics {
+    fn new() -> Self {
+        let registry = metrics::Registry::default();
+        SignerMetrics {
+            requests: registry.counter(
+                "gl_signer_requests_total",
+                "Requests processed by the signer, by outcome.",
+            ),
+            latency: registry.histogram(
+                "gl_signer_request_duration_seconds",
+                "Time the signer took to process a request.",
+                metrics::LATENCY_BUCKETS,
+            ),
+            state_diff_bytes: registry.counter(
+                "gl_signer_state_diff_bytes_total",
+                "Bytes of signer state returned to the node.",
+            ),
+            state_full_bytes: registry.counter(
+                "gl_signer_state_full_bytes_total",
+                "Bytes the signer state would have taken without diffing.",
+            ),
+            registry,
+        }
+    }
+}
+
 #[derive(Debug, Default)]
 struct OverrideSignatureUsage {
     missing_keys: Vec<String>,
@@ -127,6 +183,12 @@ pub struct Signer {
 
     network: Network,
     state: Arc<Mutex<crate::persist::State>>,
+    policy_approver: Option<Arc<policy::PolicyApprover>>,
+    state_store: Option<Arc<dyn store::StateStore>>,
+    reject_stale_state: bool,
+    signer_id: Option<String>,
+    metrics: SignerMetrics,
+    audit_log: Option<Arc<audit::AuditLog>>,
     #[cfg(feature = "backup")]
     backup_runtime: Arc<Mutex<backup::BackupRuntime>>,
 }
@@ -192,6 +254,11 @@ impl Signer {
 
         info!("Initializing signer for {VERSION} ({GITHASH}) (VLS)");
         let state_signature_mode = config.state_signature_mode;
+        let audit_log = config
+            .audit
+            .map(audit::AuditLog::open)
+            .transpose()?
+            .map(Arc::new);
         #[cfg(feature = "backup")]
         let backup_config = {
             if let Some(backup) = &config.backup {
@@ -330,6 +397,15 @@ impl Signer {
             PublicKey::from_secret_key(&Secp256k1::signing_only(), &state_signing_secret);
 
         trace!("Initialized signer for node_id={}", hex::encode(&id));
+        let policy_approver = match config.spending_policy {
+            Some(p) => Some(Arc::new(policy::PolicyApprover::new(
+                Arc::new(p),
+                network,
+                persister.state(),
+            )?)),
+            None => None,
+        };
+
         Ok(Signer {
             secret: sec,
             state_signing_secret,
@@ -344,6 +420,12 @@ impl Signer {
             init,
             network,
             state: persister.state(),
+            policy_approver,
+            state_store: None,
+            reject_stale_state: config.reject_stale_state,
+            signer_id: config.signer_id,
+            metrics: SignerMetrics::new(),
+            audit_log,
             #[cfg(feature = "backup")]
             backup_runtime: Arc::new(Mutex::new(
                 backup_config
@@ -353,6 +435,39 @@ impl Signer {
         })
     }
 
+    /// Persist the signer state in `store`. The state stored by a
+    /// previous run is merged into the current state, and the state
+    /// is written back after every request, so the signer remembers
+    /// the latest state across restarts and can detect a node that
+    /// sends an older one.
+    pub async fn with_state_store(
+        mut self,
+        store: Arc<dyn store::StateStore>,
+    ) -> Result<Signer, anyhow::Error> {
+        let stored = store
+            .read()
+            .await
+            .map_err(|e| anyhow!("Failed to read signer state from store: {e}"))?;
+        let state = {
+            let mut state = self
+                .state
+                .lock()
+                .map_err(|e| anyhow!("Failed to acquire state lock: {:?}", e))?;
+            let res = state.merge(&stored)?;
+            debug!(
+                "Merged {} entries from the signer state store",
+                res.changes.len()
+            );
+            state.clone()
+        };
+        store
+            .write(state)
+            .await
+            .map_err(|e| anyhow!("Failed to write signer state to store: {e}"))?;
+        self.state_store = Some(store);
+        Ok(self)
+    }
+
     fn init_handler(&self) -> Result<handler::InitHandler, anyhow::Error> {
         let h = handler::HandlerBuilder::new(
             self.network,
@@ -572,11 +687,12 @@ impl Signer {
     /// against.
     ///
     /// Returns an error if a signature failed verification or if the
-    /// rune verification failed.
+    /// rune verification failed. Accepted requests come with the spend
+    /// they would account for against their rune.
     fn check_request_auth(
         &self,
         requests: Vec<crate::pb::PendingRequest>,
-    ) -> Vec<Result<crate::pb::PendingRequest, anyhow::Error>> {
+    ) -> Vec<Result<(crate::pb::PendingRequest, Option<auth::PendingSpend>), anyhow::Error>> {
         // Filter out requests lacking a required field. They are unverifiable anyway.
         // Todo: partition results to provide more detailed errors.
         requests
@@ -597,7 +713,7 @@ impl Signer {
                     .map_err(|e| anyhow!("signature verification failed: {}", e))?;
 
                 self.verify_rune(r.clone())
-                    .map(|_| r)
+                    .map(|spend| (r, spend))
                     .map_err(|e| anyhow!("rune verification failed: {}", e))
             })
             .collect()
@@ -605,8 +721,14 @@ impl Signer {
 
     /// Verifies that the public key of the request and the signers rune version
     /// match the corresponding restrictions of the rune.
-    fn verify_rune(&self, request: crate::pb::PendingRequest) -> Result<(), anyhow::Error> {
-        let rune64 = general_purpose::URL_SAFE.encode(request.rune);
+    ///
+    /// Returns the spend to record if the request is processed
+    /// successfully.
+    fn verify_rune(
+        &self,
+        request: crate::pb::PendingRequest,
+    ) -> Result<Option<auth::PendingSpend>, anyhow::Error> {
+        let rune64 = general_purpose::URL_SAFE.encode(&request.rune);
         let rune = Rune::from_base64(&rune64)?;
 
         // A valid gl-rune must contain a pubkey field as this  is bound to the
@@ -620,7 +742,7 @@ impl Signer {
         // for delegation in the future but we could also set the public
         // key as the unique_id in the future and add a method that allows
         // to create new empty runes.
-        let unique_id = rune.get_id();
+        let unique_id = rune.clone().get_id();
         let ver_id = match unique_id {
             Some(id) => format!("{}-{}", id, RUNE_VERSION),
             None => String::default(),
@@ -652,17 +774,89 @@ impl Signer {
             }
         };
 
-        let ctx = runes::Context {
-            method,
-            pubkey: hex::encode(request.pubkey),
-            time: SystemTime::now(),
-            unique_id: ver_id,
+        // Decode the request payload to expose its spending
+        // parameters to the rune checks. Requests we can not decode,
+        // including compressed or truncated frames, may spend funds, so
+        // they are treated like unbounded spends.
+        let params = match decode_request(request.clone()) {
+            Ok(req) => auth::spend_params(&req)?,
+            Err(e) => {
+                trace!("not decoding parameters of {}: {}", request.uri, e);
+                auth::SpendParams {
+                    unbounded: true,
+                    ..Default::default()
+                }
+            }
+        };
+
+        if params.unbounded && auth::restricts_spending(&rune) {
+            return Err(anyhow!(
+                "method {} may spend funds beyond the limits of the rune",
+                method
+            ));
+        }
+
+        let rune_id = auth::rune_id(&rune);
+        let spent_msat = match params.amount_msat {
+            Some(a) => Some(
+                self.state
+                    .lock()
+                    .map_err(|e| anyhow!("Failed to acquire state lock: {:?}", e))?
+                    .rune_spend_ledger()
+                    .projected(&rune_id, &request.signature, a),
+            ),
+            None => None,
         };
 
-        match self.master_rune.check_with_reason(&rune64, ctx) {
-            Ok(_) => Ok(()),
-            Err(e) => Err(e.into()),
+        let mut ctx = runes::SpendContext::new(runes::Context::new(
+            method,
+            hex::encode(request.pubkey),
+            ver_id,
+            SystemTime::now(),
+        ));
+        if let Some(amount) = params.amount_msat {
+            ctx = ctx.amount_msat(amount);
+        }
+        if let Some(destination) = params.destination {
+            ctx = ctx.destination(destination);
         }
+        if let Some(payee) = params.payee {
+            ctx = ctx.payee(payee);
+        }
+        if let Some(spent) = spent_msat {
+            ctx = ctx.spent_msat(spent);
+        }
+
+        self.master_rune.check_with_reason(&rune64, ctx)?;
+
+        Ok(params.amount_msat.map(|amount_msat| auth::PendingSpend {
+            rune_id,
+            signature: request.signature,
+            amount_msat,
+        }))
+    }
+
+    /// Accounts for the spends of the requests that were attached to
+    /// a successfully processed signer request.
+    fn record_rune_spends(&self, spends: &[auth::PendingSpend]) -> Result<(), anyhow::Error> {
+        if spends.is_empty() {
+            return Ok(());
+        }
+        let now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        let mut state = self
+            .state
+            .lock()
+            .map_err(|e| anyhow!("Failed to acquire state lock: {:?}", e))?;
+        let mut ledger = state.rune_spend_ledger();
+        for spend in spends {
+            ledger.record(spend, now);
+        }
+        state
+            .set_rune_spend_ledger(&ledger)
+            .map_err(|e| anyhow!("Failed to store rune spend ledger: {:?}", e))
     }
 
     /// Given the URI of the running node, connect to it and stream
@@ -687,10 +881,14 @@ impl Signer {
 
         let mut client = NodeClient::new(c);
 
-        let mut stream = client
-            .stream_hsm_requests(Request::new(Empty::default()))
-            .await?
-            .into_inner();
+        let mut request = Request::new(Empty::default());
+        if let Some(signer_id) = self.signer_id.as_deref() {
+            let value = signer_id
+                .parse()
+                .map_err(|e| Error::Other(anyhow!("invalid signer_id {:?}: {}", signer_id, e)))?;
+            request.metadata_mut().insert(SIGNER_ID_HEADER, value);
+        }
+        let mut stream = client.stream_hsm_requests(request).await?.into_inner();
 
         info!("Starting to stream signer requests");
         loop {
@@ -710,7 +908,19 @@ impl Signer {
             let signer_state = req.signer_state.clone();
             trace!("Received request {}", hex_req);
 
-            match self.process_request(req.clone()).await {
+            let start = std::time::Instant::now();
+            let res = self.process_request(req.clone()).await;
+            self.metrics
+                .latency
+                .observe(&[], start.elapsed().as_secs_f64());
+            self.metrics.requests.inc(&[(
+                "outcome",
+                match res {
+                    Ok(_) => "ok",
+                    Err(_) => "rejected",
+                },
+            )]);
+            match res {
                 Ok(response) => {
                     trace!("Sending response {}", hex::encode(&response.raw));
                     client
@@ -735,6 +945,7 @@ impl Signer {
                         request_id,
                         error: format!("{:?}", e),
                         signer_state: vec![],
+                        signer_id: self.signer_id.clone().unwrap_or_default(),
                     };
                     client
                         .respond_hsm_request(response)
@@ -753,7 +964,7 @@ impl Signer {
         &self,
         msg: &vls_protocol::msgs::Message,
         reqs: &Vec<model::Request>,
-    ) -> Result<(), Error> {
+    ) -> Result<Resolution, Error> {
         log::trace!(
             "Resolving signature request against pending grpc commands: {:?}",
             reqs
@@ -761,12 +972,41 @@ impl Signer {
 
         // Quick path out of here: we can't find a resolution for a
         // request, then abort!
-        Resolver::try_resolve(msg, &reqs)?;
-
-        Ok(())
+        Resolver::try_resolve(msg, &reqs)
     }
 
     async fn process_request(&self, req: HsmRequest) -> Result<HsmResponse, Error> {
+        let mut record = audit::Record {
+            request_id: req.request_id,
+            request_type: match req.raw.as_slice() {
+                &[h, l, ..] => Some(((h as u16) << 8) | (l as u16)),
+                _ => None,
+            },
+            ..Default::default()
+        };
+        let res = self.process_request_inner(req, &mut record).await;
+
+        let audit_log = match &self.audit_log {
+            Some(l) => l,
+            None => return res,
+        };
+        let (decision, reason) = match &res {
+            Ok(_) => (audit::Decision::Approved, None),
+            Err(e) => (audit::Decision::Rejected, Some(e.to_string())),
+        };
+        // Refuse to sign what we could not record.
+        if let Err(e) = audit_log.append(record, decision, reason) {
+            error!("Failed to write to the signer audit log: {:?}", e);
+            return Err(Error::Other(anyhow!("Failed to write audit log: {e}")));
+        }
+        res
+    }
+
+    async fn process_request_inner(
+        &self,
+        req: HsmRequest,
+        record: &mut audit::Record,
+    ) -> Result<HsmResponse, Error> {
         debug!("Processing request {:?}", req);
         let req = req;
 
@@ -797,10 +1037,22 @@ impl Signer {
                 Error::Other(anyhow!("Failed to merge signer state: {:?}", e))
             })?;
             if merge_res.has_conflicts() {
-                debug!(
-                    "State merge ignored stale versions (count={})",
-                    merge_res.conflict_count
-                );
+                if self.state_store.is_none() {
+                    debug!(
+                        "State merge ignored stale versions (count={})",
+                        merge_res.conflict_count
+                    );
+                } else if self.reject_stale_state {
+                    return Err(Error::Other(anyhow!(
+                        "Node sent signer state older than the persisted state (count={})",
+                        merge_res.conflict_count
+                    )));
+                } else {
+                    warn!(
+                        "Node sent signer state older than the persisted state (count={})",
+                        merge_res.conflict_count
+                    );
+                }
             }
             trace!("Processing request {}", hex::encode(&req.raw));
             #[cfg(feature = "backup")]
@@ -825,34 +1077,72 @@ impl Signer {
             }
         }
 
-        let ctxrequests: Vec<model::Request> = self
+        // Requests that break the spending policy are left out of the
+        // context, so that only the signing they justify is refused.
+        let policy = self
+            .policy_approver
+            .as_ref()
+            .map(|p| policy::PolicyAuthorizer::new(auth::GreenlightAuthorizer {}, p.clone()));
+        let mut rune_spends = vec![];
+        let ctx: Vec<(PendingRequest, model::Request)> = self
             .check_request_auth(req.requests.clone())
             .into_iter()
             .filter_map(|r| r.ok())
-            .map(|r| decode_request(r))
-            .filter_map(|r| match r {
-                Ok(r) => Some(r),
+            .filter_map(|(r, spend)| match decode_request(r.clone()) {
+                Ok(d) => Some((r, spend, d)),
                 Err(e) => {
                     log::error!("Unable to decode request in context: {}", e);
                     None
                 }
             })
-            .collect::<Vec<model::Request>>();
+            .filter(|(r, _, d)| match policy.as_ref().map(|p| p.check(d)) {
+                Some(Err(e)) => {
+                    warn!("Ignoring request {} in context: {}", r.uri, e);
+                    false
+                }
+                _ => true,
+            })
+            .map(|(r, spend, d)| {
+                rune_spends.extend(spend);
+                (r, d)
+            })
+            .collect();
+        record.context = ctx
+            .iter()
+            .map(|(r, d)| audit::ContextRequest {
+                uri: r.uri.clone(),
+                rune_id: Rune::from_base64(&general_purpose::URL_SAFE.encode(&r.rune))
+                    .map(|rune| auth::rune_id(&rune))
+                    .unwrap_or_default(),
+                request: format!("{:?}", d),
+            })
+            .collect();
+        let ctxrequests: Vec<model::Request> = ctx.into_iter().map(|(_, d)| d).collect();
 
         let msg = vls_protocol::msgs::from_vec(req.raw.clone()).map_err(|e| Error::Protocol(e))?;
         log::debug!("Handling message {:?}", msg);
         log::trace!("Signer state {}", prestate_log);
 
-        if let Err(e) = self.authenticate_request(&msg, &ctxrequests) {
-            report::Reporter::report(crate::pb::scheduler::SignerRejection {
-                msg: e.to_string(),
-                request: Some(req.clone()),
-                git_version: GITHASH.to_string(),
-                node_id: self.node_id(),
-            })
-            .await;
-            #[cfg(not(feature = "permissive"))]
-            return Err(Error::Resolver(req.raw, ctxrequests));
+        match self.authenticate_request(&msg, &ctxrequests) {
+            Ok(Resolution::NoContextRequired) => {
+                record.resolution = Some("no context required".to_string());
+            }
+            Ok(Resolution::Context(i)) => {
+                record.resolution =
+                    Some(format!("matched context request {}", record.context[i].uri));
+            }
+            Err(e) => {
+                record.resolution = Some(format!("unresolved: {}", e));
+                report::Reporter::report(crate::pb::scheduler::SignerRejection {
+                    msg: e.to_string(),
+                    request: Some(req.clone()),
+                    git_version: GITHASH.to_string(),
+                    node_id: self.node_id(),
+                })
+                .await;
+                #[cfg(not(feature = "permissive"))]
+                return Err(Error::Resolver(req.raw, ctxrequests));
+            }
         };
 
         // If present, add the close_to_addr to the allowlist
@@ -875,17 +1165,24 @@ impl Signer {
         }
 
         use auth::Authorizer;
-        let auth = auth::GreenlightAuthorizer {};
-        let approvals = auth.authorize(&ctxrequests).map_err(|e| Error::Auth(e))?;
+        let approvals = match &policy {
+            Some(p) => p.authorize(&ctxrequests),
+            None => auth::GreenlightAuthorizer {}.authorize(&ctxrequests),
+        }
+        .map_err(Error::Auth)?;
         debug!("Current approvals: {:?}", approvals);
 
-        let approver = Arc::new(MemoApprover::new(approver::ReportingApprover::new(
+        let approver = MemoApprover::new(approver::ReportingApprover::new(
             #[cfg(feature = "permissive")]
             vls_protocol_signer::approver::PositiveApprover(),
             #[cfg(not(feature = "permissive"))]
             vls_protocol_signer::approver::NegativeApprover(),
-        )));
+        ));
         approver.approve(approvals);
+        let approver: Arc<dyn Approve> = match &self.policy_approver {
+            Some(p) => Arc::new(p.wrap(approver, &ctxrequests)),
+            None => Arc::new(approver),
+        };
         let root_handler = self.handler_with_approver(approver)?;
 
         log::trace!("Updating state from context");
@@ -905,6 +1202,14 @@ impl Signer {
         }
         log::trace!("State updated");
 
+        // Never sign anything that is not in the audit log.
+        if let Some(audit_log) = &self.audit_log {
+            if let Err(e) = audit_log.append(record.clone(), audit::Decision::Pending, None) {
+                error!("Failed to write to the signer audit log: {:?}", e);
+                return Err(Error::Other(anyhow!("Failed to write audit log: {e}")));
+            }
+        }
+
         // Match over root and client handler.
         let response = match req.context.clone() {
             Some(HsmRequestContext { dbid: 0, .. }) | None => {
@@ -940,10 +1245,13 @@ impl Signer {
             }
         };
 
+        self.record_rune_spends(&rune_spends)
+            .map_err(Error::Other)?;
+
         #[cfg(feature = "backup")]
         let post_backup_state;
 
-        let signer_state = {
+        let (signer_state, stored_state) = {
             debug!("Serializing state changes to report to node");
             let mut state = self.state.lock().map_err(|e| {
                 Error::Other(anyhow!(
@@ -964,6 +1272,12 @@ impl Signer {
             let diff_entries: Vec<crate::pb::SignerStateEntry> = diff_state.into();
             let diff_wire_bytes = signer_state_response_wire_bytes(&diff_entries);
             let saved_percent = savings_percent(full_wire_bytes, diff_wire_bytes);
+            self.metrics
+                .state_diff_bytes
+                .inc_by(&[], diff_wire_bytes as f64);
+            self.metrics
+                .state_full_bytes
+                .inc_by(&[], full_wire_bytes as f64);
             trace!(
                 "Signer state diff entries={}, wire_bytes={}, full_wire_bytes={}, saved {}% bandwidth syncing the state",
                 diff_entries.len(),
@@ -975,8 +1289,20 @@ impl Signer {
             {
                 post_backup_state = state.clone();
             }
-            diff_entries
+            (
+                diff_entries,
+                self.state_store.as_ref().map(|_| state.clone()),
+            )
         };
+
+        // Persist before responding, so the node can never be ahead
+        // of the stored state.
+        if let (Some(store), Some(state)) = (&self.state_store, stored_state) {
+            store
+                .write(state)
+                .await
+                .map_err(|e| Error::Other(anyhow!("Failed to persist signer state: {e}")))?;
+        }
         #[cfg(feature = "backup")]
         {
             match self.backup_runtime.lock() {
@@ -995,6 +1321,7 @@ impl Signer {
             request_id: req.request_id,
             signer_state,
             error: "".to_owned(),
+            signer_id: self.signer_id.clone().unwrap_or_default(),
         })
     }
 
@@ -1002,6 +1329,18 @@ impl Signer {
         self.id.clone()
     }
 
+    /// The signer's metrics in the Prometheus text exposition format.
+    pub fn metrics(&self) -> String {
+        self.metrics.registry.render()
+    }
+
+    /// Derive the LNURL-auth linking key for `domain` (LUD-05). The
+    /// key is deterministic per domain, so services recognise the
+    /// same wallet across logins without learning its node id.
+    pub fn lnurl_auth_linking_key(&self, domain: &str) -> Result<SecretKey, anyhow::Error> {
+        crate::lnurl::auth::derive_linking_key(&self.secret, domain)
+    }
+
     pub fn get_init(&self) -> Vec<u8> {
         self.init.clone()
     }
@@ -1610,9 +1949,12 @@ fn decode_request(r: crate::pb::PendingRequest) -> Result<model::Request, anyhow
     // https://github.com/grpc/grpc/blob/master/doc/PROTOCOL-HTTP2.md#requests
     // for technical details.
     //
-    // Notice that we assume that the compression flag is off.
-    assert_eq!(r.request[0], 0u8);
-    let payload = &r.request[5..];
+    // Compressed frames are not supported.
+    let payload = match r.request.as_slice() {
+        [0, _, _, _, _, payload @ ..] => payload,
+        [_, _, _, _, _, ..] => return Err(anyhow!("compressed request frames are not supported")),
+        _ => return Err(anyhow!("request frame is too short")),
+    };
 
     crate::signer::model::cln::decode_request(&r.uri, payload)
         .or_else(|_| crate::signer::model::greenlight::decode_request(&r.uri, payload))
@@ -1702,6 +2044,10 @@ mod tests {
             SignerConfig {
                 state_signature_mode: StateSignatureMode::Soft,
                 state_signature_override: None,
+                spending_policy: None,
+                reject_stale_state: false,
+                signer_id: None,
+                audit: None,
                 backup: Some(SignerBackupConfig {
                     path: "backup.json".into(),
                     strategy: SignerBackupStrategy::Periodic { updates: 0 },
@@ -1799,6 +2145,138 @@ mod tests {
         assert_eq!(repaired.signature.len(), COMPACT_SIGNATURE_LEN);
     }
 
+    #[tokio::test]
+    async fn test_state_store_detects_stale_state_after_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("signer_state.json");
+        let key = "state/test";
+        let req = |version| HsmRequest {
+            request_id: 0,
+            context: None,
+            raw: heartbeat_raw(),
+            signer_state: vec![mk_state_entry(key, version, json!({"v": version}))],
+            requests: vec![],
+        };
+
+        let signer = mk_signer(StateSignatureMode::Soft)
+            .with_state_store(Arc::new(store::FileStateStore::new(&path)))
+            .await
+            .unwrap();
+        signer.process_request(req(2)).await.unwrap();
+
+        let restarted = Signer::new_with_config(
+            vec![0u8; 32],
+            Network::Bitcoin,
+            credentials::Nobody::default(),
+            SignerConfig {
+                reject_stale_state: true,
+                ..SignerConfig::default()
+            },
+        )
+        .unwrap()
+        .with_state_store(Arc::new(store::FileStateStore::new(&path)))
+        .await
+        .unwrap();
+
+        let err = restarted.process_request(req(1)).await.unwrap_err();
+        assert!(err.to_string().contains("older than the persisted state"));
+        assert!(restarted.process_request(req(3)).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_audit_log_records_decisions() {
+        let dir = tempfile::tempdir().unwrap();
+        let signer = Signer::new_with_config(
+            vec![0u8; 32],
+            Network::Bitcoin,
+            credentials::Nobody::default(),
+            SignerConfig {
+                audit: Some(audit::AuditConfig::new(dir.path())),
+                ..SignerConfig::default()
+            },
+        )
+        .unwrap();
+        let req = |request_id, raw| HsmRequest {
+            request_id,
+            context: None,
+            raw,
+            signer_state: vec![],
+            requests: vec![],
+        };
+
+        signer.process_request(req(1, heartbeat_raw())).await.unwrap();
+        let sign_message = hex::decode("0017000B48656c6c6f20776f726c64").unwrap();
+        assert!(signer.process_request(req(2, sign_message)).await.is_err());
+
+        let entries = audit::read(dir.path()).unwrap();
+        audit::verify(&entries).unwrap();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].decision, audit::Decision::Pending);
+        assert_eq!(entries[1].request_id, 1);
+        assert_eq!(entries[1].decision, audit::Decision::Approved);
+        assert_eq!(
+            entries[1].resolution.as_deref(),
+            Some("no context required")
+        );
+        assert_eq!(entries[2].request_id, 2);
+        assert_eq!(entries[2].request_type, Some(23));
+        assert_eq!(entries[2].decision, audit::Decision::Rejected);
+        assert!(entries[2].reason.as_ref().unwrap().contains("sign-message"));
+    }
+
+    #[tokio::test]
+    async fn test_audit_log_failure_prevents_signing() {
+        let dir = tempfile::tempdir().unwrap();
+        let signer = Signer::new_with_config(
+            vec![0u8; 32],
+            Network::Bitcoin,
+            credentials::Nobody::default(),
+            SignerConfig {
+                audit: Some(audit::AuditConfig {
+                    max_file_bytes: 0,
+                    ..audit::AuditConfig::new(dir.path())
+                }),
+                ..SignerConfig::default()
+            },
+        )
+        .unwrap();
+        // Replace the log file with a directory, so rotating it before
+        // the next append fails.
+        let log_file = dir.path().join("audit.jsonl");
+        std::fs::remove_file(&log_file).unwrap();
+        std::fs::create_dir(&log_file).unwrap();
+
+        let res = signer
+            .process_request(HsmRequest {
+                request_id: 1,
+                context: None,
+                raw: heartbeat_raw(),
+                signer_state: vec![],
+                requests: vec![],
+            })
+            .await;
+        assert!(res.unwrap_err().to_string().contains("audit log"));
+    }
+
+    #[tokio::test]
+    async fn test_signer_metrics() {
+        let signer = mk_signer(StateSignatureMode::Soft);
+        signer
+            .process_request(HsmRequest {
+                request_id: 0,
+                context: None,
+                raw: heartbeat_raw(),
+                signer_state: vec![],
+                requests: vec![],
+            })
+            .await
+            .unwrap();
+
+        let metrics = signer.metrics();
+        assert!(metrics.contains("# TYPE gl_signer_state_diff_bytes_total counter"));
+        assert!(metrics.contains("\ngl_signer_state_full_bytes_total "));
+    }
+
     #[tokio::test]
     async fn test_soft_mode_rejects_invalid_signature() {
         let signer = mk_signer(StateSignatureMode::Soft);
@@ -2172,6 +2650,36 @@ mod tests {
         assert!(stream.contains("0-gl0&pubkey=000000&method^get"))
     }
 
+    #[test]
+    fn test_readonly_rune_only_allows_get_and_list() {
+        use crate::runes::Restrictor;
+        let signer = Signer::new(
+            vec![0u8; 32],
+            Network::Bitcoin,
+            credentials::Nobody::default(),
+        )
+        .unwrap();
+        let pubkey = signer.node_id();
+        let pubkey_rest = format!("pubkey={}", hex::encode(&pubkey));
+        let readonly = runes::DefRules::ReadOnly.generate().unwrap()[0].encode();
+        let rune = signer
+            .create_rune(None, vec![vec![&pubkey_rest], vec![&readonly]])
+            .unwrap();
+        let request = |uri: &str| pb::PendingRequest {
+            request: vec![],
+            uri: uri.to_string(),
+            signature: vec![],
+            pubkey: pubkey.clone(),
+            timestamp: 0,
+            rune: general_purpose::URL_SAFE.decode(&rune).unwrap(),
+        };
+
+        assert!(signer.verify_rune(request("/cln.Node/Getinfo")).is_ok());
+        assert!(signer.verify_rune(request("/cln.Node/ListFunds")).is_ok());
+        assert!(signer.verify_rune(request("/cln.Node/Pay")).is_err());
+        assert!(signer.verify_rune(request("/cln.Node/Withdraw")).is_err());
+    }
+
     #[test]
     fn test_rune_checks_method() {
         let signer = Signer::new(
@@ -2241,6 +2749,307 @@ mod tests {
         assert!(signer.verify_rune(r).is_err());
     }
 
+    /// Wraps a request in the gRPC framing the plugin forwards.
+    fn grpc_frame(msg: impl prost::Message) -> Vec<u8> {
+        let payload = msg.encode_to_vec();
+        let mut framed = vec![0u8];
+        framed.put_u32(payload.len() as u32);
+        framed.extend(payload);
+        framed
+    }
+
+    /// A pending request signed by a freshly generated device key,
+    /// with a rune for that key.
+    fn signed_request(signer: &Signer, uri: &str, msg: impl prost::Message) -> pb::PendingRequest {
+        use ring::rand::SystemRandom;
+        use ring::signature::{EcdsaKeyPair, KeyPair, ECDSA_P256_SHA256_FIXED_SIGNING};
+
+        let rng = SystemRandom::new();
+        let pkcs8 = EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &rng).unwrap();
+        let key =
+            EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, pkcs8.as_ref()).unwrap();
+        let pubkey = key.public_key().as_ref().to_vec();
+        let rune = signer
+            .create_rune(
+                None,
+                vec![vec![&format!("pubkey={}", hex::encode(&pubkey))]],
+            )
+            .unwrap();
+        let request = grpc_frame(msg);
+        pb::PendingRequest {
+            signature: key.sign(&rng, &request).unwrap().as_ref().to_vec(),
+            request,
+            uri: uri.to_string(),
+            pubkey,
+            timestamp: 0,
+            rune: general_purpose::URL_SAFE.decode(&rune).unwrap(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_policy_violation_only_refuses_its_own_signing() {
+        use lightning_signer::bitcoin::secp256k1::{PublicKey, Secp256k1, SecretKey};
+        let secp = Secp256k1::new();
+        let pubkey = |n: u8| {
+            PublicKey::from_secret_key(&secp, &SecretKey::from_slice(&[n; 32]).unwrap()).serialize()
+        };
+        let (allowed, peer) = (pubkey(1), pubkey(2));
+        let dir = tempfile::tempdir().unwrap();
+        let signer = Signer::new_with_config(
+            vec![0u8; 32],
+            Network::Bitcoin,
+            credentials::Nobody::default(),
+            SignerConfig {
+                audit: Some(audit::AuditConfig::new(dir.path())),
+                spending_policy: Some(policy::SpendingPolicy {
+                    allowed_destinations: Some(vec![hex::encode(allowed)]),
+                    ..Default::default()
+                }),
+                ..SignerConfig::default()
+            },
+        )
+        .unwrap();
+
+        // A keysend to a destination the policy doesn't allow is
+        // pending, next to an allowed one, while the node updates a
+        // channel.
+        let keysend = |destination: [u8; 33]| {
+            signed_request(
+                &signer,
+                "/cln.Node/KeySend",
+                crate::pb::cln::KeysendRequest {
+                    destination: destination.to_vec(),
+                    amount_msat: Some(crate::pb::cln::Amount { msat: 1000 }),
+                    ..Default::default()
+                },
+            )
+        };
+        let requests = vec![keysend(peer), keysend(allowed)];
+        let req = |raw: Vec<u8>, context| HsmRequest {
+            request_id: 1,
+            context,
+            raw,
+            signer_state: vec![],
+            requests: requests.clone(),
+        };
+        let channel = HsmRequestContext {
+            node_id: peer.to_vec(),
+            dbid: 1,
+            capabilities: 0,
+        };
+
+        signer
+            .process_request(req(
+                vls_protocol::msgs::NewChannel {
+                    peer_id: vls_protocol::model::PubKey(peer),
+                    dbid: 1,
+                }
+                .as_vec(),
+                None,
+            ))
+            .await
+            .unwrap();
+        signer
+            .process_request(req(
+                vls_protocol::msgs::GetPerCommitmentPoint {
+                    commitment_number: 0,
+                }
+                .as_vec(),
+                Some(channel),
+            ))
+            .await
+            .unwrap();
+
+        // Only the signing justified by the violating keysend is
+        // refused.
+        let preapprove = |destination| {
+            vls_protocol::msgs::PreapproveKeysend {
+                destination: vls_protocol::model::PubKey(destination),
+                payment_hash: vls_protocol::model::Sha256([0; 32]),
+                amount_msat: 1000,
+            }
+            .as_vec()
+        };
+        let res = signer.process_request(req(preapprove(peer), None)).await;
+        #[cfg(not(feature = "permissive"))]
+        assert!(res.is_err());
+        #[cfg(feature = "permissive")]
+        assert!(res.is_ok());
+        signer
+            .process_request(req(preapprove(allowed), None))
+            .await
+            .unwrap();
+
+        let entries: Vec<_> = audit::read(dir.path())
+            .unwrap()
+            .into_iter()
+            .filter(|e| e.decision != audit::Decision::Pending)
+            .collect();
+        let resolutions: Vec<_> = entries
+            .iter()
+            .map(|e| e.resolution.as_deref().unwrap())
+            .collect();
+        assert_eq!(resolutions[..2], ["no context required"; 2]);
+        assert!(resolutions[2].starts_with("unresolved"));
+        assert_eq!(resolutions[3], "matched context request /cln.Node/KeySend");
+        // The violating keysend is left out of the context.
+        assert!(entries.iter().all(|e| e.context.len() == 1));
+    }
+
+    #[test]
+    fn test_rune_checks_spend_params() {
+        let signer = Signer::new(
+            vec![0u8; 32],
+            Network::Bitcoin,
+            credentials::Nobody::default(),
+        )
+        .unwrap();
+        let pubkey = signer.node_id();
+        let pubkey_rest = format!("pubkey={}", hex::encode(&pubkey));
+        let merchant = hex::encode([2u8; 33]);
+        let dest_rest = format!("destination={}", merchant);
+
+        // At most 1000msat per payment, 1500msat in total, to merchant only.
+        let rune = signer
+            .create_rune(
+                None,
+                vec![
+                    vec![&pubkey_rest],
+                    vec!["amount_msat!", "amount_msat<1001"],
+                    vec!["spent_msat!", "spent_msat<1501"],
+                    vec!["destination!", &dest_rest],
+                ],
+            )
+            .unwrap();
+        let rune = general_purpose::URL_SAFE.decode(&rune).unwrap();
+
+        let keysend = |dest: Vec<u8>, msat: u64, sig: &[u8]| pb::PendingRequest {
+            request: grpc_frame(crate::pb::cln::KeysendRequest {
+                destination: dest,
+                amount_msat: Some(crate::pb::cln::Amount { msat }),
+                ..Default::default()
+            }),
+            uri: "/cln.Node/KeySend".to_string(),
+            signature: sig.to_vec(),
+            pubkey: pubkey.clone(),
+            timestamp: 0,
+            rune: rune.clone(),
+        };
+
+        // Non-spending requests are still allowed.
+        let r = pb::PendingRequest {
+            request: grpc_frame(crate::pb::cln::GetinfoRequest {}),
+            uri: "/cln.Node/Getinfo".to_string(),
+            signature: vec![],
+            pubkey: pubkey.clone(),
+            timestamp: 0,
+            rune: rune.clone(),
+        };
+        assert!(signer.verify_rune(r).is_ok());
+
+        // Wrong destination and too large payments are rejected.
+        assert!(signer
+            .verify_rune(keysend(vec![3u8; 33], 10, b"a"))
+            .is_err());
+        assert!(signer
+            .verify_rune(keysend(vec![2u8; 33], 1001, b"b"))
+            .is_err());
+
+        // Spends only count once the signer request succeeded, and a
+        // retransmit is not counted twice.
+        let accept = |r| {
+            let spend = signer.verify_rune(r).unwrap().unwrap();
+            signer.record_rune_spends(&[spend]).unwrap();
+        };
+        let spend = signer
+            .verify_rune(keysend(vec![2u8; 33], 1000, b"c"))
+            .unwrap();
+        assert_eq!(spend.map(|s| s.amount_msat), Some(1000));
+        accept(keysend(vec![2u8; 33], 1000, b"c"));
+        accept(keysend(vec![2u8; 33], 1000, b"c"));
+        accept(keysend(vec![2u8; 33], 500, b"d"));
+
+        // The total budget is exhausted.
+        assert!(signer.verify_rune(keysend(vec![2u8; 33], 1, b"e")).is_err());
+
+        // The ledger is part of the signer state, and therefore
+        // survives a restart.
+        let restarted = Signer::new(
+            vec![0u8; 32],
+            Network::Bitcoin,
+            credentials::Nobody::default(),
+        )
+        .unwrap();
+        let state = signer.state.lock().unwrap().clone();
+        restarted.state.lock().unwrap().merge(&state).unwrap();
+        assert!(restarted
+            .verify_rune(keysend(vec![2u8; 33], 1, b"e"))
+            .is_err());
+
+        // Spends the rune can not bound are rejected.
+        let onchain = |request: Vec<u8>, uri: &str| pb::PendingRequest {
+            request,
+            uri: uri.to_string(),
+            signature: b"f".to_vec(),
+            pubkey: pubkey.clone(),
+            timestamp: 0,
+            rune: rune.clone(),
+        };
+        let all = crate::pb::cln::AmountOrAll {
+            value: Some(crate::pb::cln::amount_or_all::Value::All(true)),
+        };
+        assert!(restarted
+            .verify_rune(onchain(
+                grpc_frame(crate::pb::cln::WithdrawRequest {
+                    destination: "bc1qaddr".to_string(),
+                    satoshi: Some(all),
+                    ..Default::default()
+                }),
+                "/cln.Node/Withdraw",
+            ))
+            .is_err());
+        assert!(restarted
+            .verify_rune(onchain(
+                grpc_frame(crate::pb::cln::TxsendRequest { txid: vec![0; 32] }),
+                "/cln.Node/TxSend",
+            ))
+            .is_err());
+        // So are frames that can't be decoded, even if the payload is
+        // within the limits of the rune.
+        let mut compressed = keysend(vec![2u8; 33], 1, b"g");
+        compressed.request[0] = 1;
+        assert!(decode_request(compressed.clone()).is_err());
+        assert!(restarted.verify_rune(compressed).is_err());
+        let mut short = keysend(vec![2u8; 33], 1, b"h");
+        short.request.truncate(3);
+        assert!(decode_request(short.clone()).is_err());
+        assert!(restarted.verify_rune(short).is_err());
+
+        // Withdrawals are bound by the amount and destination limits.
+        let fresh = Signer::new(
+            vec![0u8; 32],
+            Network::Bitcoin,
+            credentials::Nobody::default(),
+        )
+        .unwrap();
+        let withdraw = |destination: &str| {
+            onchain(
+                grpc_frame(crate::pb::cln::WithdrawRequest {
+                    destination: destination.to_string(),
+                    satoshi: Some(crate::pb::cln::AmountOrAll {
+                        value: Some(crate::pb::cln::amount_or_all::Value::Amount(
+                            crate::pb::cln::Amount { msat: 1000 },
+                        )),
+                    }),
+                    ..Default::default()
+                }),
+                "/cln.Node/Withdraw",
+            )
+        };
+        assert!(fresh.verify_rune(withdraw("bc1qaddr")).is_err());
+        assert!(fresh.verify_rune(withdraw(&merchant)).is_ok());
+    }
+
     #[test]
     fn test_empty_rune_is_valid() {
         let creds = credentials::Nobody::default();
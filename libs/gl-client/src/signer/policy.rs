@@ -0,0 +1,750 @@
+//! A configurable spending policy enforced by the signer.
+//!
+//! The node is not trusted to enforce spending limits, so the signer
+//! checks every payment it is asked to pre-approve against a
+//! [`SpendingPolicy`]. Request-level checks (destinations, fee caps,
+//! withdraw addresses) run in the [`PolicyAuthorizer`], while amount
+//! limits are enforced by the [`PolicyApprover`] when VLS asks for an
+//! approval. Accepted payments are recorded in a [`SpendLedger`] that
+//! is stored in the signer `State`, and therefore survives restarts
+//! and is covered by the state signatures.
+use crate::persist::State;
+use crate::signer::auth::{spend_params, Authorizer};
+use crate::signer::model::Request;
+use crate::Error;
+use anyhow::{anyhow, Context};
+use lightning_signer::bitcoin::{Address, Network, Transaction, TxOut};
+use lightning_signer::invoice::{Invoice, InvoiceAttributes};
+use lightning_signer::lightning::types::payment::PaymentHash;
+use lightning_signer::prelude::SendSync;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use vls_protocol_signer::approver::{Approval, Approve};
+
+const DAY_SECS: u64 = 24 * 60 * 60;
+
+/// CLN applies a 0.5% fee cap if the caller does not specify one.
+const CLN_DEFAULT_MAX_FEE_PERCENT: f64 = 0.5;
+
+/// The rules the signer enforces on outgoing payments. All rules are
+/// optional, and an empty policy allows everything.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SpendingPolicy {
+    /// Maximum amount sent over lightning in any 24 hour window.
+    pub daily_limit_msat: Option<u64>,
+    /// Maximum number of payments in a sliding time window.
+    pub velocity: Option<VelocityLimit>,
+    /// Node ids (hex) payments may be sent to. Applies to invoice
+    /// payees and keysend destinations.
+    pub allowed_destinations: Option<Vec<String>>,
+    /// Maximum ratio of routing fees to the amount sent, e.g. `0.01`
+    /// for 1%.
+    pub max_fee_ratio: Option<f64>,
+    /// On-chain addresses funds may be withdrawn to.
+    pub withdraw_allowlist: Option<Vec<String>>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VelocityLimit {
+    pub max_payments: u32,
+    pub window_secs: u64,
+}
+
+impl SpendingPolicy {
+    /// Loads a policy from a TOML or JSON file, depending on the
+    /// file extension.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("reading spending policy from {}", path.display()))?;
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => Self::from_toml(&contents),
+            Some("json") => Self::from_json(&contents),
+            _ => Err(anyhow!(
+                "unsupported spending policy file {}, expected a .toml or .json file",
+                path.display()
+            )),
+        }
+    }
+
+    pub fn from_toml(s: &str) -> anyhow::Result<Self> {
+        let policy: Self = toml::from_str(s).context("parsing spending policy")?;
+        policy.validate()?;
+        Ok(policy)
+    }
+
+    pub fn from_json(s: &str) -> anyhow::Result<Self> {
+        let policy: Self = serde_json::from_str(s).context("parsing spending policy")?;
+        policy.validate()?;
+        Ok(policy)
+    }
+
+    /// Checks that the policy is internally consistent.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if let Some(ratio) = self.max_fee_ratio {
+            if !(0.0..=1.0).contains(&ratio) {
+                return Err(anyhow!(
+                    "max_fee_ratio must be between 0 and 1, got {}",
+                    ratio
+                ));
+            }
+        }
+        if let Some(v) = &self.velocity {
+            if v.window_secs == 0 {
+                return Err(anyhow!("velocity window_secs must be positive"));
+            }
+        }
+        for dest in self.allowed_destinations.iter().flatten() {
+            match hex::decode(dest) {
+                Ok(b) if b.len() == 33 => {}
+                _ => return Err(anyhow!("invalid destination node id {}", dest)),
+            }
+        }
+        Ok(())
+    }
+
+    fn is_allowed_destination(&self, node_id: &str) -> bool {
+        match &self.allowed_destinations {
+            Some(allowed) => allowed.iter().any(|d| d.eq_ignore_ascii_case(node_id)),
+            None => true,
+        }
+    }
+
+    /// How long ledger entries are relevant for any of the limits.
+    fn retention_secs(&self) -> u64 {
+        let velocity = self.velocity.as_ref().map(|v| v.window_secs).unwrap_or(0);
+        DAY_SECS.max(velocity)
+    }
+}
+
+/// A payment accepted by the policy.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SpendEntry {
+    /// The payment hash (hex) identifying the payment.
+    pub payment_hash: String,
+    /// Seconds since the epoch when the payment was approved.
+    pub timestamp: u64,
+    pub amount_msat: u64,
+}
+
+/// The record of recent payments used to enforce the rate limits.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SpendLedger {
+    pub entries: Vec<SpendEntry>,
+}
+
+impl SpendLedger {
+    fn contains(&self, payment_hash: &str) -> bool {
+        self.entries.iter().any(|e| e.payment_hash == payment_hash)
+    }
+
+    fn since(&self, since: u64) -> impl Iterator<Item = &SpendEntry> {
+        self.entries.iter().filter(move |e| e.timestamp > since)
+    }
+
+    fn prune(&mut self, before: u64) {
+        self.entries.retain(|e| e.timestamp > before);
+    }
+
+    /// Checks whether a new payment would stay within the limits of
+    /// `policy`.
+    pub fn check(&self, policy: &SpendingPolicy, amount_msat: u64, now: u64) -> Result<(), Error> {
+        if let Some(limit) = policy.daily_limit_msat {
+            let spent: u64 = self
+                .since(now.saturating_sub(DAY_SECS))
+                .map(|e| e.amount_msat)
+                .sum();
+            if spent.saturating_add(amount_msat) > limit {
+                return Err(Error::PolicyViolation(format!(
+                    "daily limit of {}msat exceeded, already spent {}msat",
+                    limit, spent
+                )));
+            }
+        }
+        if let Some(v) = &policy.velocity {
+            let count = self.since(now.saturating_sub(v.window_secs)).count();
+            if count >= v.max_payments as usize {
+                return Err(Error::PolicyViolation(format!(
+                    "at most {} payments per {}s allowed",
+                    v.max_payments, v.window_secs
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Checks the pending requests against the request-level rules of a
+/// [`SpendingPolicy`] before deferring to the `inner` authorizer.
+/// Requests that break the rules are not approved, without affecting
+/// the other requests.
+pub(crate) struct PolicyAuthorizer<A: Authorizer> {
+    inner: A,
+    approver: Arc<PolicyApprover>,
+}
+
+impl<A: Authorizer> PolicyAuthorizer<A> {
+    pub fn new(inner: A, approver: Arc<PolicyApprover>) -> Self {
+        PolicyAuthorizer { inner, approver }
+    }
+
+    /// Whether `request` follows the request-level rules of the policy.
+    pub fn check(&self, request: &Request) -> Result<(), Error> {
+        let policy = &self.approver.policy;
+        match request {
+            Request::Withdraw(req) => {
+                if !self.approver.is_allowed_withdraw_address(&req.destination) {
+                    return Err(Error::PolicyViolation(format!(
+                        "withdraw address {} is not allowlisted",
+                        req.destination
+                    )));
+                }
+                return Ok(());
+            }
+            // On-chain outputs are checked against the withdraw
+            // allowlist when the transaction is signed.
+            Request::FundChannel(_) | Request::TxPrepare(_) => return Ok(()),
+            _ => {}
+        }
+
+        let params = spend_params(request)?;
+        if let Some(dest) = &params.destination {
+            if !policy.is_allowed_destination(dest) {
+                return Err(Error::PolicyViolation(format!(
+                    "destination {} is not allowlisted",
+                    dest
+                )));
+            }
+        }
+
+        if let (Some(max_ratio), Some(amount)) = (policy.max_fee_ratio, params.amount_msat) {
+            if let Some(ratio) = fee_ratio(request, amount) {
+                if ratio > max_ratio {
+                    return Err(Error::PolicyViolation(format!(
+                        "fee ratio {} exceeds the maximum of {}",
+                        ratio, max_ratio
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<A: Authorizer> Authorizer for PolicyAuthorizer<A> {
+    fn authorize(&self, requests: &Vec<Request>) -> Result<Vec<Approval>, Error> {
+        let permitted = requests
+            .iter()
+            .filter(|r| match self.check(r) {
+                Ok(()) => true,
+                Err(e) => {
+                    warn!("Not approving request: {}", e);
+                    false
+                }
+            })
+            .cloned()
+            .collect();
+        self.inner.authorize(&permitted)
+    }
+}
+
+/// The maximum ratio of fees to `amount_msat` the request allows
+/// the node to pay.
+fn fee_ratio(request: &Request, amount_msat: u64) -> Option<f64> {
+    if amount_msat == 0 {
+        return None;
+    }
+    let amount = amount_msat as f64;
+    let from_caps = |maxfee: Option<u64>, percent: Option<f64>| match (maxfee, percent) {
+        (Some(fee), _) => fee as f64 / amount,
+        (None, Some(p)) => p / 100.0,
+        (None, None) => CLN_DEFAULT_MAX_FEE_PERCENT / 100.0,
+    };
+    match request {
+        Request::Pay(r) => Some(from_caps(
+            r.maxfee.as_ref().map(|a| a.msat),
+            r.maxfeepercent,
+        )),
+        Request::KeySend(r) => Some(from_caps(
+            r.maxfee.as_ref().map(|a| a.msat),
+            r.maxfeepercent,
+        )),
+        Request::TrampolinePay(r) => Some(from_caps(
+            None,
+            Some(r.maxfeepercent as f64).filter(|p| *p > 0.0),
+        )),
+        Request::SendPay(r) => {
+            let first = r.route.first()?.amount_msat.as_ref()?.msat;
+            Some(first.saturating_sub(amount_msat) as f64 / amount)
+        }
+        _ => None,
+    }
+}
+
+/// The amounts the pending requests pay, by payment hash (hex). For
+/// amountless invoices this is the only place the amount is known.
+fn request_amounts(requests: &[Request]) -> HashMap<String, u64> {
+    let invoice_hash = |bolt11: &str| {
+        let inv = bolt11.to_lowercase();
+        let inv = inv.strip_prefix("lightning:").unwrap_or(&inv);
+        Invoice::from_str(inv)
+            .ok()
+            .map(|i| hex::encode(i.payment_hash().0))
+    };
+    requests
+        .iter()
+        .filter_map(|r| {
+            let hash = match r {
+                Request::Pay(req) => invoice_hash(&req.bolt11),
+                Request::TrampolinePay(req) => invoice_hash(&req.bolt11),
+                Request::SendPay(req) => Some(hex::encode(&req.payment_hash)),
+                _ => None,
+            }?;
+            let amount = spend_params(r).ok()?.amount_msat?;
+            Some((hash, amount))
+        })
+        .collect()
+}
+
+/// Enforces the amount limits of a [`SpendingPolicy`] and records
+/// approved payments in the [`SpendLedger`] stored in the signer
+/// state.
+///
+/// It is created once per signer, which also checks the withdraw
+/// allowlist against the network, and is attached to the approver
+/// chain of each request with [`PolicyApprover::wrap`].
+pub struct PolicyApprover {
+    policy: Arc<SpendingPolicy>,
+    withdraw_allowlist: Option<Vec<Address>>,
+    network: Network,
+    state: Arc<Mutex<State>>,
+}
+
+impl PolicyApprover {
+    pub fn new(
+        policy: Arc<SpendingPolicy>,
+        network: Network,
+        state: Arc<Mutex<State>>,
+    ) -> anyhow::Result<Self> {
+        policy.validate()?;
+        let withdraw_allowlist = match &policy.withdraw_allowlist {
+            Some(addrs) => Some(
+                addrs
+                    .iter()
+                    .map(|a| {
+                        Address::from_str(a)
+                            .map_err(|e| anyhow!("invalid withdraw address {}: {}", a, e))?
+                            .require_network(network)
+                            .map_err(|e| anyhow!("invalid withdraw address {}: {}", a, e))
+                    })
+                    .collect::<anyhow::Result<Vec<Address>>>()?,
+            ),
+            None => None,
+        };
+        Ok(PolicyApprover {
+            policy,
+            withdraw_allowlist,
+            network,
+            state,
+        })
+    }
+
+    /// Wraps the approver chain of a single request, so that even
+    /// payments that were pre-approved from the pending `requests`
+    /// are subject to the limits.
+    pub fn wrap<A: Approve>(self: &Arc<Self>, inner: A, requests: &[Request]) -> PolicyGuard<A> {
+        PolicyGuard {
+            inner,
+            approver: self.clone(),
+            amounts: request_amounts(requests),
+        }
+    }
+
+    fn is_allowed_withdraw_address(&self, address: &str) -> bool {
+        let allowed = match &self.withdraw_allowlist {
+            Some(allowed) => allowed,
+            None => return true,
+        };
+        Address::from_str(address)
+            .ok()
+            .and_then(|a| a.require_network(self.network).ok())
+            .is_some_and(|a| allowed.contains(&a))
+    }
+
+    /// Checks a payment against the ledger and records it if it is
+    /// within the limits. Payments already in the ledger are not
+    /// counted twice.
+    fn spend(&self, payment_hash: &[u8], amount_msat: u64) -> Result<(), Error> {
+        let payment_hash = hex::encode(payment_hash);
+        let now = now_secs();
+        let mut state = self
+            .state
+            .lock()
+            .map_err(|e| Error::IllegalArgument(format!("state lock poisoned: {:?}", e)))?;
+        let mut ledger = state.spend_ledger();
+        if ledger.contains(&payment_hash) {
+            return Ok(());
+        }
+        ledger.check(&self.policy, amount_msat, now)?;
+        ledger.prune(now.saturating_sub(self.policy.retention_secs()));
+        ledger.entries.push(SpendEntry {
+            payment_hash,
+            timestamp: now,
+            amount_msat,
+        });
+        state
+            .set_spend_ledger(&ledger)
+            .map_err(|e| Error::IllegalArgument(format!("storing spend ledger: {:?}", e)))
+    }
+}
+
+/// A [`PolicyApprover`] attached to the approver chain of a single
+/// request, see [`PolicyApprover::wrap`].
+pub struct PolicyGuard<A: Approve> {
+    inner: A,
+    approver: Arc<PolicyApprover>,
+    /// The amounts of the pending requests, by payment hash (hex).
+    amounts: HashMap<String, u64>,
+}
+
+impl<A: Approve> Approve for PolicyGuard<A> {
+    fn approve_invoice(&self, inv: &Invoice) -> bool {
+        let payee = hex::encode(inv.payee_pub_key().serialize());
+        if !self.approver.policy.is_allowed_destination(&payee) {
+            warn!("Spending policy rejected invoice to {}", payee);
+            return false;
+        }
+        if !self.inner.approve_invoice(inv) {
+            return false;
+        }
+        let hash = inv.payment_hash().0;
+        let amount = match self.amounts.get(&hex::encode(hash)) {
+            Some(a) => *a,
+            None => inv.amount_milli_satoshis(),
+        };
+        if amount == 0 && self.approver.policy.daily_limit_msat.is_some() {
+            warn!("Spending policy rejected invoice without a known amount");
+            return false;
+        }
+        match self.approver.spend(&hash, amount) {
+            Ok(()) => true,
+            Err(e) => {
+                warn!("Spending policy rejected invoice: {}", e);
+                false
+            }
+        }
+    }
+
+    fn approve_keysend(&self, hash: PaymentHash, amount_msat: u64) -> bool {
+        if !self.inner.approve_keysend(hash, amount_msat) {
+            return false;
+        }
+        match self.approver.spend(&hash.0, amount_msat) {
+            Ok(()) => true,
+            Err(e) => {
+                warn!("Spending policy rejected keysend: {}", e);
+                false
+            }
+        }
+    }
+
+    fn approve_onchain(
+        &self,
+        tx: &Transaction,
+        prev_outs: &[TxOut],
+        unknown_indices: &[usize],
+    ) -> bool {
+        if let Some(allowed) = &self.approver.withdraw_allowlist {
+            let disallowed = unknown_indices.iter().any(|i| match tx.output.get(*i) {
+                Some(out) => !allowed
+                    .iter()
+                    .any(|a| a.script_pubkey() == out.script_pubkey),
+                None => true,
+            });
+            if disallowed {
+                warn!("Spending policy rejected on-chain spend to unknown address");
+                return false;
+            }
+        }
+        self.inner.approve_onchain(tx, prev_outs, unknown_indices)
+    }
+}
+
+impl<A: Approve> SendSync for PolicyGuard<A> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signer::auth::GreenlightAuthorizer;
+    use cln_grpc::pb::{Amount, KeysendRequest, PayRequest, WithdrawRequest};
+    use lightning_signer::bitcoin::hashes::{sha256, Hash};
+    use lightning_signer::bitcoin::secp256k1::{Secp256k1, SecretKey};
+    use lightning_signer::bitcoin::ScriptBuf;
+    use lightning_signer::lightning::types::payment::PaymentSecret;
+    use lightning_signer::lightning_invoice::{Currency, InvoiceBuilder};
+    use vls_protocol_signer::approver::PositiveApprover;
+
+    const DEST: &str = "020202020202020202020202020202020202020202020202020202020202020202";
+
+    fn keysend(dest: &str, msat: u64, maxfee: Option<u64>) -> Request {
+        Request::KeySend(KeysendRequest {
+            destination: hex::decode(dest).unwrap(),
+            amount_msat: Some(Amount { msat }),
+            maxfee: maxfee.map(|msat| Amount { msat }),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_policy_from_toml_and_json() {
+        let toml = format!(
+            r#"
+daily_limit_msat = 50000000
+max_fee_ratio = 0.01
+allowed_destinations = ["{}"]
+
+[velocity]
+max_payments = 10
+window_secs = 3600
+"#,
+            DEST
+        );
+        let policy = SpendingPolicy::from_toml(&toml).unwrap();
+        assert_eq!(policy.daily_limit_msat, Some(50_000_000));
+        assert_eq!(
+            policy.velocity,
+            Some(VelocityLimit {
+                max_payments: 10,
+                window_secs: 3600
+            })
+        );
+
+        let json = serde_json::to_string(&policy).unwrap();
+        assert_eq!(SpendingPolicy::from_json(&json).unwrap(), policy);
+
+        assert!(SpendingPolicy::from_json(r#"{"max_fee_ratio": 2.0}"#).is_err());
+        assert!(SpendingPolicy::from_json(r#"{"allowed_destinations": ["02"]}"#).is_err());
+        assert!(SpendingPolicy::from_json(r#"{"unknown_rule": 1}"#).is_err());
+    }
+
+    fn address(n: u8) -> Address {
+        Address::p2wsh(&ScriptBuf::from_bytes(vec![n]), Network::Bitcoin)
+    }
+
+    fn approver(policy: SpendingPolicy, state: Arc<Mutex<State>>) -> Arc<PolicyApprover> {
+        Arc::new(PolicyApprover::new(Arc::new(policy), Network::Bitcoin, state).unwrap())
+    }
+
+    #[test]
+    fn test_policy_authorizer_request_rules() {
+        let policy = SpendingPolicy {
+            allowed_destinations: Some(vec![DEST.to_string()]),
+            max_fee_ratio: Some(0.01),
+            withdraw_allowlist: Some(vec![address(1).to_string()]),
+            ..Default::default()
+        };
+        let auth = PolicyAuthorizer::new(
+            GreenlightAuthorizer {},
+            approver(policy, Arc::new(Mutex::new(State::new()))),
+        );
+
+        assert!(auth.check(&keysend(DEST, 100_000, None)).is_ok());
+        assert!(auth.check(&keysend(DEST, 100_000, Some(1000))).is_ok());
+
+        // Fee cap above 1%.
+        assert!(auth.check(&keysend(DEST, 100_000, Some(1001))).is_err());
+        // Destination not allowlisted.
+        let other = "03".repeat(33);
+        assert!(auth.check(&keysend(&other, 1000, None)).is_err());
+
+        let withdraw = |dest: &str| {
+            Request::Withdraw(WithdrawRequest {
+                destination: dest.to_string(),
+                ..Default::default()
+            })
+        };
+        assert!(auth.check(&withdraw(&address(1).to_string())).is_ok());
+        // Addresses are compared by value, not by their encoding.
+        assert!(auth
+            .check(&withdraw(&address(1).to_string().to_uppercase()))
+            .is_ok());
+        assert!(auth.check(&withdraw(&address(2).to_string())).is_err());
+        assert!(auth.check(&withdraw("notanaddress")).is_err());
+
+        // Invoices that cannot be decoded are rejected.
+        let invalid = Request::Pay(PayRequest {
+            bolt11: "lnbcnotaninvoice".to_string(),
+            ..Default::default()
+        });
+        assert!(auth.check(&invalid).is_err());
+
+        // Requests breaking the rules are not approved, the others
+        // are.
+        let secp = Secp256k1::new();
+        let key = SecretKey::from_slice(&[7; 32]).unwrap();
+        let bolt11 = InvoiceBuilder::new(Currency::Bitcoin)
+            .description(String::new())
+            .payment_hash(sha256::Hash::hash(&[1; 32]))
+            .payment_secret(PaymentSecret([2; 32]))
+            .amount_milli_satoshis(1000)
+            .current_timestamp()
+            .min_final_cltv_expiry_delta(144)
+            .build_signed(|h| secp.sign_ecdsa_recoverable(h, &key))
+            .unwrap()
+            .to_string();
+        let pay = |maxfee| {
+            Request::Pay(PayRequest {
+                bolt11: bolt11.clone(),
+                maxfee: Some(Amount { msat: maxfee }),
+                ..Default::default()
+            })
+        };
+        let auth = PolicyAuthorizer::new(
+            GreenlightAuthorizer {},
+            approver(
+                SpendingPolicy {
+                    max_fee_ratio: Some(0.01),
+                    withdraw_allowlist: Some(vec![address(1).to_string()]),
+                    ..Default::default()
+                },
+                Arc::new(Mutex::new(State::new())),
+            ),
+        );
+        assert_eq!(auth.authorize(&vec![pay(10)]).unwrap().len(), 1);
+        let approvals = auth
+            .authorize(&vec![pay(11), invalid, withdraw("notanaddress")])
+            .unwrap();
+        assert!(approvals.is_empty());
+    }
+
+    #[test]
+    fn test_ledger_limits() {
+        let policy = SpendingPolicy {
+            daily_limit_msat: Some(1000),
+            velocity: Some(VelocityLimit {
+                max_payments: 2,
+                window_secs: 60,
+            }),
+            ..Default::default()
+        };
+        let now = 1_000_000;
+        let mut ledger = SpendLedger::default();
+        assert!(ledger.check(&policy, 1000, now).is_ok());
+        assert!(ledger.check(&policy, 1001, now).is_err());
+
+        ledger.entries.push(SpendEntry {
+            payment_hash: "aa".to_string(),
+            timestamp: now - 10,
+            amount_msat: 600,
+        });
+        assert!(ledger.check(&policy, 400, now).is_ok());
+        assert!(ledger.check(&policy, 401, now).is_err());
+
+        // Spends older than a day no longer count towards the limit.
+        assert!(ledger.check(&policy, 401, now + DAY_SECS).is_ok());
+
+        ledger.entries.push(SpendEntry {
+            payment_hash: "bb".to_string(),
+            timestamp: now - 5,
+            amount_msat: 1,
+        });
+        assert!(ledger.check(&policy, 1, now).is_err());
+        assert!(ledger.check(&policy, 1, now + 60).is_ok());
+
+        ledger.prune(now - 7);
+        assert_eq!(ledger.entries.len(), 1);
+    }
+
+    #[test]
+    fn test_policy_approver_records_keysends() {
+        let state = Arc::new(Mutex::new(State::new()));
+        let policy = SpendingPolicy {
+            daily_limit_msat: Some(1500),
+            ..Default::default()
+        };
+        let approver = approver(policy, state.clone()).wrap(PositiveApprover(), &[]);
+
+        assert!(approver.approve_keysend(PaymentHash([1; 32]), 1000));
+        // Re-approving the same payment does not count it twice.
+        assert!(approver.approve_keysend(PaymentHash([1; 32]), 1000));
+        assert!(!approver.approve_keysend(PaymentHash([2; 32]), 501));
+        assert!(approver.approve_keysend(PaymentHash([2; 32]), 500));
+
+        let ledger = state.lock().unwrap().spend_ledger();
+        assert_eq!(ledger.entries.len(), 2);
+        assert_eq!(ledger.entries[0].payment_hash, hex::encode([1u8; 32]));
+    }
+
+    #[test]
+    fn test_policy_approver_uses_request_amount_for_amountless_invoices() {
+        let secp = Secp256k1::new();
+        let key = SecretKey::from_slice(&[7; 32]).unwrap();
+        let bolt11 = InvoiceBuilder::new(Currency::Bitcoin)
+            .description(String::new())
+            .payment_hash(sha256::Hash::hash(&[1; 32]))
+            .payment_secret(PaymentSecret([2; 32]))
+            .current_timestamp()
+            .min_final_cltv_expiry_delta(144)
+            .build_signed(|h| secp.sign_ecdsa_recoverable(h, &key))
+            .unwrap()
+            .to_string();
+        let invoice = Invoice::from_str(&bolt11).unwrap();
+        let pay = |msat| {
+            Request::Pay(PayRequest {
+                bolt11: bolt11.clone(),
+                amount_msat: Some(Amount { msat }),
+                ..Default::default()
+            })
+        };
+        let policy = SpendingPolicy {
+            daily_limit_msat: Some(1000),
+            ..Default::default()
+        };
+
+        let state = Arc::new(Mutex::new(State::new()));
+        let approver = approver(policy, state.clone());
+        // Without a pending request the amount is unknown.
+        assert!(!approver
+            .wrap(PositiveApprover(), &[])
+            .approve_invoice(&invoice));
+        assert!(!approver
+            .wrap(PositiveApprover(), &[pay(1001)])
+            .approve_invoice(&invoice));
+        assert!(approver
+            .wrap(PositiveApprover(), &[pay(1000)])
+            .approve_invoice(&invoice));
+
+        let ledger = state.lock().unwrap().spend_ledger();
+        assert_eq!(ledger.entries[0].amount_msat, 1000);
+    }
+
+    #[test]
+    fn test_policy_approver_rejects_invalid_withdraw_addresses() {
+        let state = Arc::new(Mutex::new(State::new()));
+        for addr in [
+            "notanaddress".to_string(),
+            // An address for a different network.
+            Address::p2wsh(&ScriptBuf::from_bytes(vec![1]), Network::Testnet).to_string(),
+        ] {
+            let policy = Arc::new(SpendingPolicy {
+                withdraw_allowlist: Some(vec![addr]),
+                ..Default::default()
+            });
+            assert!(PolicyApprover::new(policy, Network::Bitcoin, state.clone()).is_err());
+        }
+    }
+}
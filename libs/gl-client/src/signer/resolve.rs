@@ -1,10 +1,31 @@
 //! Resolver utilities to match incoming requests against the request
 //! context and find a justifications.
 
+use crate::pb::cln::{amount_or_all, amount_or_any, AmountOrAll, AmountOrAny};
+use crate::pb::{cln, LspInvoiceRequest};
 use crate::signer::{model::Request, Error};
-use vls_protocol::msgs::Message;
+use base64::{engine::general_purpose, Engine as _};
+use lightning_signer::bitcoin::bech32::Fe32;
+use lightning_signer::bitcoin::hashes::{sha256, Hash};
+use lightning_signer::bitcoin::{Address, Psbt, ScriptBuf};
+use lightning_signer::lightning_invoice::RawBolt11Invoice;
+use std::str::FromStr;
+use vls_protocol::msgs::{Message, SetupChannel, SignInvoice};
+
+/// The expiry of an invoice without an explicit expiry field, in
+/// seconds.
+const DEFAULT_INVOICE_EXPIRY: u64 = 3600;
 pub struct Resolver {}
 
+/// How a request was justified.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Resolution {
+    /// The request type does not need a justification.
+    NoContextRequired,
+    /// The request matched the context request at this index.
+    Context(usize),
+}
+
 impl Resolver {
     /// Attempt to find a resolution for a given request. We default
     /// to failing, and allowlist individual matches between pending
@@ -12,7 +33,7 @@ impl Resolver {
     /// possible we also verify the contents of the request against
     /// the contents of the context request. TODOs in here may
     /// indicate ways to strengthen the verification.
-    pub fn try_resolve(req: &Message, reqctx: &Vec<Request>) -> Result<(), Error> {
+    pub fn try_resolve(req: &Message, reqctx: &Vec<Request>) -> Result<Resolution, Error> {
         log::trace!("Resolving {:?}", req);
         // Some requests do not need a justification. For example we
         // reconnect automatically, so there may not even be a context
@@ -31,11 +52,17 @@ impl Resolver {
             // Duplicate verification with VLS, we defer to VLS
             Message::GetChannelBasepoints(_) => true,
             Message::ValidateCommitmentTx(_) => true,
-            Message::SignWithdrawal(_) => true,
-            Message::SetupChannel(_) => true,
+            // Channels the peer opens to us. Channels we open are
+            // matched against the `FundChannel` call below, so opens
+            // the node starts on its own, e.g. `fundchannel_start` or
+            // `multifundchannel` from a plugin, are rejected.
+            Message::SetupChannel(m) => !m.is_outbound,
             Message::GetPerCommitmentPoint(_) => true,
             Message::ValidateRevocation(_) => true,
-            Message::NewChannel(_) => true,
+            // Channels the peer opens to us come without a context
+            // request. While a `FundChannel` call is pending the
+            // channel has to be with its peer, see below.
+            Message::NewChannel(_) => !reqctx.iter().any(|r| matches!(r, Request::FundChannel(_))),
             Message::SignCommitmentTx(_) => true,
             Message::SignGossipMessage(_) => true,
             Message::SignMutualCloseTx(_) => true,
@@ -67,10 +94,10 @@ impl Resolver {
                 "Request {:?} resolved with no context request required",
                 req
             );
-            return Ok(());
+            return Ok(Resolution::NoContextRequired);
         }
 
-        for cr in reqctx {
+        for (i, cr) in reqctx.iter().enumerate() {
             let accept = match (req, cr) {
                 (Message::SignMessage(m1), Request::SignMessage(m2)) => {
                     m1.message.0.clone() == m2.message.as_bytes()
@@ -78,20 +105,31 @@ impl Resolver {
                 (Message::NewChannel(m1), Request::FundChannel(m2)) => {
                     // Different node_id? Reject!
                     m1.peer_id.0 == m2.id.as_slice()
-                    // TODO: Add `close_to` to allowlist for the close
-                    // later on
                 }
-                (Message::SignInvoice(_l), Request::LspInvoice(_r)) => {
-		    // TODO: This could also need some
-		    // strengthening. See below.
-		    true
-		}
-                (Message::SignInvoice(_l), Request::Invoice(_r)) => {
-                    // TODO: This could be strengthened by parsing the
-                    // invoice from `l.u5bytes` and verify the
-                    // description, amount and (maybe) payment_hash
-                    true
+                (Message::SetupChannel(l), Request::FundChannel(r)) => setup_channel_matches(l, r),
+                // On-chain spends need a user call that describes the
+                // transaction. Spends the node initiates without one
+                // are rejected.
+                (Message::SignWithdrawal(l), Request::Withdraw(r)) => {
+                    withdraw_matches(&l.psbt.0.psbt.inner, r)
+                }
+                (Message::SignWithdrawal(l), Request::FundChannel(r)) => {
+                    funding_matches(&l.psbt.0.psbt.inner, r)
+                }
+                (Message::SignWithdrawal(l), Request::TxSend(r)) => {
+                    // `txprepare` already showed the user the
+                    // transaction, so it has to be the one they send.
+                    l.psbt.0.psbt.inner.unsigned_tx.compute_txid().to_string()
+                        == hex::encode(&r.txid)
+                }
+                (Message::SignWithdrawal(l), Request::SendPsbt(r)) => {
+                    same_transaction(&l.psbt.0.psbt.inner, &r.psbt)
+                }
+                (Message::SignWithdrawal(l), Request::SignPsbt(r)) => {
+                    same_transaction(&l.psbt.0.psbt.inner, &r.psbt)
                 }
+                (Message::SignInvoice(l), Request::LspInvoice(r)) => lsp_invoice_matches(l, r),
+                (Message::SignInvoice(l), Request::Invoice(r)) => invoice_matches(l, r),
                 (Message::PreapproveInvoice(l), Request::Pay(r)) => {
                     l.invstring.0.eq_ignore_ascii_case(r.bolt11.as_bytes())
                 }
@@ -104,13 +142,20 @@ impl Resolver {
                 (Message::PreapproveInvoice(l), Request::TrampolinePay(r)) => {
                     l.invstring.0.eq_ignore_ascii_case(r.bolt11.as_bytes())
                 }
+                (Message::PreapproveKeysend(l), Request::KeySend(r)) => {
+                    // The destination and amount have to match, the
+                    // payment_hash is derived from a preimage the
+                    // node generates.
+                    l.destination.0[..] == r.destination[..]
+                        && r.amount_msat.as_ref().map(|a| a.msat) == Some(l.amount_msat)
+                }
                 (_, _) => false,
             };
 
             // Did we find a resolution? If yes we can stop here.
             if accept {
                 log::trace!("Request {:?} approved with context request {:?}", req, cr);
-                return Ok(());
+                return Ok(Resolution::Context(i));
             }
         }
 
@@ -118,3 +163,646 @@ impl Resolver {
         Err(Error::Resolver(ser, reqctx.to_vec()))
     }
 }
+
+/// Decode the invoice the node asks us to sign.
+fn decode_invoice(req: &SignInvoice) -> Option<RawBolt11Invoice> {
+    let hrp = std::str::from_utf8(&req.hrp.0).ok()?;
+    let data = req
+        .u5bytes
+        .0
+        .iter()
+        .map(|b| Fe32::try_from(*b).ok())
+        .collect::<Option<Vec<_>>>()?;
+    RawBolt11Invoice::from_raw(hrp, &data).ok()
+}
+
+fn invoice_amount_msat(inv: &RawBolt11Invoice) -> Option<u64> {
+    inv.amount_pico_btc().map(|a| a / 10)
+}
+
+fn invoice_expiry(inv: &RawBolt11Invoice) -> u64 {
+    inv.expiry_time()
+        .map(|e| e.as_seconds())
+        .unwrap_or(DEFAULT_INVOICE_EXPIRY)
+}
+
+/// Check that the invoice commits to `description`, either directly
+/// or by its hash.
+fn invoice_description_matches(inv: &RawBolt11Invoice, description: &str, hashed: bool) -> bool {
+    if hashed {
+        inv.description().is_none()
+            && inv.description_hash().map(|h| h.0)
+                == Some(sha256::Hash::hash(description.as_bytes()))
+    } else {
+        inv.description_hash().is_none()
+            && inv.description().map(|d| d.as_inner().0.as_str()) == Some(description)
+    }
+}
+
+fn invoice_matches(req: &SignInvoice, ctx: &cln::InvoiceRequest) -> bool {
+    let inv = match decode_invoice(req) {
+        Some(inv) => inv,
+        None => return false,
+    };
+
+    let amount_matches = match ctx.amount_msat {
+        Some(AmountOrAny {
+            value: Some(amount_or_any::Value::Amount(ref a)),
+        }) => invoice_amount_msat(&inv) == Some(a.msat),
+        _ => invoice_amount_msat(&inv).is_none(),
+    };
+    let expiry_matches = ctx
+        .expiry
+        .map(|e| invoice_expiry(&inv) == e)
+        .unwrap_or(true);
+    // If the caller picked the preimage we can also check that the
+    // invoice pays to it.
+    let payment_hash_matches = ctx
+        .preimage
+        .as_ref()
+        .map(|p| inv.payment_hash().map(|h| h.0) == Some(sha256::Hash::hash(p)))
+        .unwrap_or(true);
+
+    amount_matches
+        && expiry_matches
+        && payment_hash_matches
+        && invoice_description_matches(&inv, &ctx.description, ctx.deschashonly.unwrap_or(false))
+}
+
+fn lsp_invoice_matches(req: &SignInvoice, ctx: &LspInvoiceRequest) -> bool {
+    let inv = match decode_invoice(req) {
+        Some(inv) => inv,
+        None => return false,
+    };
+    // An `amount_msat` of 0 asks for an any-amount invoice.
+    let amount = Some(ctx.amount_msat).filter(|a| *a > 0);
+    invoice_amount_msat(&inv) == amount
+        && invoice_description_matches(&inv, &ctx.description, false)
+}
+
+fn setup_channel_matches(req: &SetupChannel, ctx: &cln::FundchannelRequest) -> bool {
+    let amount_matches = match ctx.amount {
+        Some(AmountOrAll {
+            value: Some(amount_or_all::Value::Amount(ref a)),
+        }) => req.channel_value.checked_mul(1000) == Some(a.msat),
+        _ => true,
+    };
+    let push_matches = req.push_value == ctx.push_msat.as_ref().map(|a| a.msat).unwrap_or(0);
+    let close_to_matches = match ctx.close_to {
+        Some(ref addr) => match Address::from_str(addr) {
+            Ok(addr) => {
+                req.local_shutdown_script.0 == addr.assume_checked().script_pubkey().as_bytes()
+            }
+            Err(_) => false,
+        },
+        // Without `close_to` the node may only commit to closing to
+        // its own wallet.
+        None => req.local_shutdown_script.0.is_empty() || req.local_shutdown_wallet_index.is_some(),
+    };
+    amount_matches && push_matches && close_to_matches
+}
+
+fn parse_script(addr: &str) -> Option<ScriptBuf> {
+    Address::from_str(addr)
+        .ok()
+        .map(|a| a.assume_checked().script_pubkey())
+}
+
+/// Indices of the outputs the node does not claim as change. Change
+/// outputs carry the wallet keypath, which VLS verifies when signing.
+fn unclaimed_outputs(psbt: &Psbt) -> Vec<usize> {
+    (0..psbt.unsigned_tx.output.len())
+        .filter(|i| {
+            psbt.outputs
+                .get(*i)
+                .map(|o| o.bip32_derivation.is_empty() && o.tap_key_origins.is_empty())
+                .unwrap_or(true)
+        })
+        .collect()
+}
+
+fn withdraw_matches(psbt: &Psbt, ctx: &cln::WithdrawRequest) -> bool {
+    let script = match parse_script(&ctx.destination) {
+        Some(s) => s,
+        None => return false,
+    };
+    let outputs = &psbt.unsigned_tx.output;
+    let unclaimed = unclaimed_outputs(psbt);
+    // Everything that is not change has to go to the destination.
+    if unclaimed.is_empty()
+        || unclaimed
+            .iter()
+            .any(|i| outputs[*i].script_pubkey != script)
+    {
+        return false;
+    }
+    match ctx.satoshi {
+        Some(AmountOrAll {
+            value: Some(amount_or_all::Value::Amount(ref a)),
+        }) => {
+            // An amount that overflows cannot be what the user asked
+            // for.
+            let sent = unclaimed
+                .iter()
+                .try_fold(0u64, |sum, i| sum.checked_add(outputs[*i].value.to_sat()));
+            sent.and_then(|s| s.checked_mul(1000)) == Some(a.msat)
+        }
+        // Sweeping the wallet leaves no change.
+        _ => unclaimed.len() == outputs.len(),
+    }
+}
+
+fn funding_matches(psbt: &Psbt, ctx: &cln::FundchannelRequest) -> bool {
+    // The funding output is the only one that is not change.
+    let unclaimed = unclaimed_outputs(psbt);
+    let funding = match unclaimed.as_slice() {
+        [i] => &psbt.unsigned_tx.output[*i],
+        _ => return false,
+    };
+    match ctx.amount {
+        Some(AmountOrAll {
+            value: Some(amount_or_all::Value::Amount(ref a)),
+        }) => funding.value.to_sat().checked_mul(1000) == Some(a.msat),
+        _ => unclaimed.len() == psbt.unsigned_tx.output.len(),
+    }
+}
+
+/// Check that the PSBT the user passed in spends the same
+/// transaction the node asks us to sign.
+fn same_transaction(psbt: &Psbt, ctx: &str) -> bool {
+    let raw = match general_purpose::STANDARD.decode(ctx) {
+        Ok(raw) => raw,
+        Err(_) => return false,
+    };
+    match Psbt::deserialize(&raw) {
+        Ok(p) => p.unsigned_tx.compute_txid() == psbt.unsigned_tx.compute_txid(),
+        Err(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::pb::cln;
+    use lightning_signer::bitcoin::bip32::{DerivationPath, Fingerprint};
+    use lightning_signer::bitcoin::secp256k1::{PublicKey, Secp256k1, SecretKey};
+    use lightning_signer::bitcoin::{
+        absolute, transaction, Amount, Network, ScriptBuf, Transaction, TxIn, TxOut, Txid,
+    };
+    use lightning_signer::lightning_invoice::{Currency, InvoiceBuilder};
+    use std::time::Duration;
+    use vls_protocol::model::{Basepoints, PubKey, Sha256};
+    use vls_protocol::psbt::{PsbtWrapper, StreamedPSBT};
+    use vls_protocol::serde_bolt::{Array, Octets, WithSize};
+
+    fn address(n: u8) -> Address {
+        Address::p2wsh(&ScriptBuf::from_bytes(vec![n]), Network::Regtest)
+    }
+
+    /// A PSBT paying `outputs`, where outputs flagged `true` are
+    /// change with a wallet keypath.
+    fn psbt(outputs: &[(Address, u64, bool)]) -> Psbt {
+        let tx = Transaction {
+            version: transaction::Version::TWO,
+            lock_time: absolute::LockTime::ZERO,
+            input: vec![TxIn::default()],
+            output: outputs
+                .iter()
+                .map(|(a, v, _)| TxOut {
+                    value: Amount::from_sat(*v),
+                    script_pubkey: a.script_pubkey(),
+                })
+                .collect(),
+        };
+        let mut psbt = Psbt::from_unsigned_tx(tx).unwrap();
+        let key = PublicKey::from_secret_key(
+            &Secp256k1::new(),
+            &SecretKey::from_slice(&[1; 32]).unwrap(),
+        );
+        for (out, (_, _, change)) in psbt.outputs.iter_mut().zip(outputs) {
+            if *change {
+                out.bip32_derivation
+                    .insert(key, (Fingerprint::default(), DerivationPath::master()));
+            }
+        }
+        psbt
+    }
+
+    fn sign_withdrawal(psbt: Psbt) -> Message {
+        Message::SignWithdrawal(vls_protocol::msgs::SignWithdrawal {
+            utxos: Array(vec![]),
+            psbt: WithSize(StreamedPSBT {
+                psbt: PsbtWrapper { inner: psbt },
+                segwit_flags: vec![],
+            }),
+        })
+    }
+
+    fn withdraw_request(destination: &Address, satoshi: Option<u64>) -> Request {
+        let value = match satoshi {
+            Some(sat) => cln::amount_or_all::Value::Amount(cln::Amount { msat: sat * 1000 }),
+            None => cln::amount_or_all::Value::All(true),
+        };
+        Request::Withdraw(cln::WithdrawRequest {
+            destination: destination.to_string(),
+            satoshi: Some(cln::AmountOrAll { value: Some(value) }),
+            ..Default::default()
+        })
+    }
+
+    fn sign_invoice(
+        amount_msat: Option<u64>,
+        description: &str,
+        expiry: Option<u64>,
+        preimage: [u8; 32],
+    ) -> Message {
+        let mut builder = InvoiceBuilder::new(Currency::Regtest)
+            .description(description.to_string())
+            .payment_hash(sha256::Hash::hash(&preimage))
+            .duration_since_epoch(Duration::from_secs(1_700_000_000));
+        if let Some(a) = amount_msat {
+            builder = builder.amount_milli_satoshis(a);
+        }
+        if let Some(e) = expiry {
+            builder = builder.expiry_time(Duration::from_secs(e));
+        }
+        let (hrp, data) = builder.build_raw().unwrap().to_raw();
+        Message::SignInvoice(SignInvoice {
+            u5bytes: Octets(data.iter().map(|f| f.to_u8()).collect()),
+            hrp: Octets(hrp.into_bytes()),
+        })
+    }
+
+    fn invoice_request(amount_msat: u64, description: &str) -> Request {
+        Request::Invoice(cln::InvoiceRequest {
+            amount_msat: Some(cln::AmountOrAny {
+                value: Some(cln::amount_or_any::Value::Amount(cln::Amount {
+                    msat: amount_msat,
+                })),
+            }),
+            description: description.to_string(),
+            ..Default::default()
+        })
+    }
+
+    fn setup_channel(is_outbound: bool, channel_value: u64, shutdown: Vec<u8>) -> Message {
+        let key = || PubKey([2; 33]);
+        Message::SetupChannel(SetupChannel {
+            is_outbound,
+            channel_value,
+            push_value: 0,
+            funding_txid: Txid::all_zeros(),
+            funding_txout: 0,
+            to_self_delay: 144,
+            local_shutdown_script: Octets(shutdown),
+            local_shutdown_wallet_index: None,
+            remote_basepoints: Basepoints {
+                revocation: key(),
+                payment: key(),
+                htlc: key(),
+                delayed_payment: key(),
+            },
+            remote_funding_pubkey: key(),
+            remote_to_self_delay: 144,
+            remote_shutdown_script: Octets(vec![]),
+            channel_type: Octets(vec![]),
+        })
+    }
+
+    fn preapprove_keysend(destination: [u8; 33], amount_msat: u64) -> Message {
+        Message::PreapproveKeysend(vls_protocol::msgs::PreapproveKeysend {
+            destination: PubKey(destination),
+            payment_hash: Sha256([0; 32]),
+            amount_msat,
+        })
+    }
+
+    #[test]
+    fn test_keysend_resolution() {
+        let ctx = vec![Request::KeySend(cln::KeysendRequest {
+            destination: vec![2; 33],
+            amount_msat: Some(cln::Amount { msat: 1000 }),
+            ..Default::default()
+        })];
+
+        assert_eq!(
+            Resolver::try_resolve(&preapprove_keysend([2; 33], 1000), &ctx).unwrap(),
+            Resolution::Context(0)
+        );
+        // Different destination or amount than what was requested.
+        assert!(Resolver::try_resolve(&preapprove_keysend([3; 33], 1000), &ctx).is_err());
+        assert!(Resolver::try_resolve(&preapprove_keysend([2; 33], 2000), &ctx).is_err());
+        // No keysend was requested at all.
+        assert!(Resolver::try_resolve(&preapprove_keysend([2; 33], 1000), &vec![]).is_err());
+    }
+
+    #[test]
+    fn test_invoice_resolution() {
+        let ctx = vec![invoice_request(1000, "coffee")];
+        let inv = |amount, description, expiry| sign_invoice(amount, description, expiry, [1; 32]);
+
+        assert_eq!(
+            Resolver::try_resolve(&inv(Some(1000), "coffee", None), &ctx).unwrap(),
+            Resolution::Context(0)
+        );
+        // Amount, description or expiry differ from the request.
+        assert!(Resolver::try_resolve(&inv(Some(2000), "coffee", None), &ctx).is_err());
+        assert!(Resolver::try_resolve(&inv(None, "coffee", None), &ctx).is_err());
+        assert!(Resolver::try_resolve(&inv(Some(1000), "tea", None), &ctx).is_err());
+        let ctx = vec![Request::Invoice(cln::InvoiceRequest {
+            expiry: Some(60),
+            ..match invoice_request(1000, "coffee") {
+                Request::Invoice(r) => r,
+                _ => unreachable!(),
+            }
+        })];
+        assert!(Resolver::try_resolve(&inv(Some(1000), "coffee", Some(60)), &ctx).is_ok());
+        assert!(Resolver::try_resolve(&inv(Some(1000), "coffee", None), &ctx).is_err());
+
+        // Garbage instead of an invoice.
+        let garbage = Message::SignInvoice(SignInvoice {
+            u5bytes: Octets(vec![0xff; 10]),
+            hrp: Octets(b"lnbcrt".to_vec()),
+        });
+        assert!(Resolver::try_resolve(&garbage, &ctx).is_err());
+    }
+
+    #[test]
+    fn test_invoice_resolution_checks_preimage() {
+        let ctx = vec![Request::Invoice(cln::InvoiceRequest {
+            preimage: Some(vec![1; 32]),
+            ..match invoice_request(1000, "coffee") {
+                Request::Invoice(r) => r,
+                _ => unreachable!(),
+            }
+        })];
+        assert!(
+            Resolver::try_resolve(&sign_invoice(Some(1000), "coffee", None, [1; 32]), &ctx).is_ok()
+        );
+        assert!(
+            Resolver::try_resolve(&sign_invoice(Some(1000), "coffee", None, [2; 32]), &ctx)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_lsp_invoice_resolution() {
+        let ctx = vec![Request::LspInvoice(LspInvoiceRequest {
+            amount_msat: 0,
+            description: "jit".to_string(),
+            ..Default::default()
+        })];
+        assert!(Resolver::try_resolve(&sign_invoice(None, "jit", None, [1; 32]), &ctx).is_ok());
+        assert!(
+            Resolver::try_resolve(&sign_invoice(Some(1000), "jit", None, [1; 32]), &ctx).is_err()
+        );
+        assert!(Resolver::try_resolve(&sign_invoice(None, "other", None, [1; 32]), &ctx).is_err());
+    }
+
+    #[test]
+    fn test_fundchannel_resolution() {
+        let close_to = Address::p2wsh(&ScriptBuf::new(), Network::Regtest);
+        let ctx = vec![Request::FundChannel(cln::FundchannelRequest {
+            id: vec![2; 33],
+            amount: Some(cln::AmountOrAll {
+                value: Some(cln::amount_or_all::Value::Amount(cln::Amount {
+                    msat: 100_000_000,
+                })),
+            }),
+            close_to: Some(close_to.to_string()),
+            ..Default::default()
+        })];
+        let script = close_to.script_pubkey().to_bytes();
+
+        assert_eq!(
+            Resolver::try_resolve(&setup_channel(true, 100_000, script.clone()), &ctx).unwrap(),
+            Resolution::Context(0)
+        );
+        // Different funding amount or close address.
+        assert!(
+            Resolver::try_resolve(&setup_channel(true, 200_000, script.clone()), &ctx).is_err()
+        );
+        assert!(Resolver::try_resolve(&setup_channel(true, 100_000, vec![0x51]), &ctx).is_err());
+        assert!(Resolver::try_resolve(&setup_channel(true, 100_000, vec![]), &ctx).is_err());
+        // A funding amount that overflows in msat matches nothing.
+        assert!(Resolver::try_resolve(
+            &setup_channel(true, u64::MAX / 1000 + 1, script.clone()),
+            &ctx
+        )
+        .is_err());
+        // Outbound channels need a `FundChannel` call, inbound ones
+        // don't.
+        assert!(Resolver::try_resolve(&setup_channel(true, 100_000, script), &vec![]).is_err());
+        assert_eq!(
+            Resolver::try_resolve(&setup_channel(false, 100_000, vec![]), &vec![]).unwrap(),
+            Resolution::NoContextRequired
+        );
+    }
+
+    #[test]
+    fn test_new_channel_resolution() {
+        let new_channel = |peer: u8| {
+            Message::NewChannel(vls_protocol::msgs::NewChannel {
+                peer_id: PubKey([peer; 33]),
+                dbid: 1,
+            })
+        };
+        let ctx = vec![Request::FundChannel(cln::FundchannelRequest {
+            id: vec![2; 33],
+            ..Default::default()
+        })];
+
+        assert_eq!(
+            Resolver::try_resolve(&new_channel(2), &ctx).unwrap(),
+            Resolution::Context(0)
+        );
+        // A channel with a different peer than the one being funded.
+        assert!(Resolver::try_resolve(&new_channel(3), &ctx).is_err());
+        // Without a pending `FundChannel` the peer opened the channel.
+        assert_eq!(
+            Resolver::try_resolve(&new_channel(3), &vec![]).unwrap(),
+            Resolution::NoContextRequired
+        );
+    }
+
+    #[test]
+    fn test_withdraw_resolution() {
+        let dest = address(1);
+        let ctx = vec![withdraw_request(&dest, Some(10_000))];
+        let resolve = |outputs: &[(Address, u64, bool)], ctx: &Vec<Request>| {
+            Resolver::try_resolve(&sign_withdrawal(psbt(outputs)), ctx).ok()
+        };
+
+        assert_eq!(
+            resolve(
+                &[(dest.clone(), 10_000, false), (address(2), 5_000, true)],
+                &ctx
+            ),
+            Some(Resolution::Context(0))
+        );
+        // Wrong amount, an extra output the user never asked for, or
+        // a different destination altogether.
+        assert!(resolve(&[(dest.clone(), 20_000, false)], &ctx).is_none());
+        assert!(resolve(
+            &[(dest.clone(), 10_000, false), (address(2), 5_000, false)],
+            &ctx
+        )
+        .is_none());
+        assert!(resolve(&[(address(2), 10_000, false)], &ctx).is_none());
+        assert!(resolve(&[(dest.clone(), 10_000, false)], &vec![]).is_none());
+        // Outputs whose sum overflows.
+        assert!(resolve(
+            &[
+                (dest.clone(), u64::MAX, false),
+                (dest.clone(), 10_001, false)
+            ],
+            &ctx
+        )
+        .is_none());
+        assert!(resolve(&[(dest.clone(), u64::MAX / 1000 + 1, false)], &ctx).is_none());
+
+        // Sweeps leave no change.
+        let ctx = vec![withdraw_request(&dest, None)];
+        assert!(resolve(&[(dest.clone(), 12_345, false)], &ctx).is_some());
+        assert!(resolve(
+            &[(dest.clone(), 10_000, false), (address(2), 5_000, true)],
+            &ctx
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn test_funding_resolution() {
+        let ctx = vec![Request::FundChannel(cln::FundchannelRequest {
+            id: vec![2; 33],
+            amount: Some(cln::AmountOrAll {
+                value: Some(cln::amount_or_all::Value::Amount(cln::Amount {
+                    msat: 100_000_000,
+                })),
+            }),
+            ..Default::default()
+        })];
+        let tx = |outputs: &[(Address, u64, bool)]| sign_withdrawal(psbt(outputs));
+
+        assert!(Resolver::try_resolve(
+            &tx(&[(address(1), 100_000, false), (address(2), 5_000, true)]),
+            &ctx
+        )
+        .is_ok());
+        assert!(Resolver::try_resolve(&tx(&[(address(1), 90_000, false)]), &ctx).is_err());
+        assert!(
+            Resolver::try_resolve(&tx(&[(address(1), u64::MAX / 1000 + 1, false)]), &ctx).is_err()
+        );
+        assert!(Resolver::try_resolve(
+            &tx(&[(address(1), 100_000, false), (address(2), 5_000, false)]),
+            &ctx
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_fundchannel_flow() {
+        // The messages of a `fundchannel` call all resolve against
+        // the one context request.
+        let ctx = vec![Request::FundChannel(cln::FundchannelRequest {
+            id: vec![2; 33],
+            amount: Some(cln::AmountOrAll {
+                value: Some(cln::amount_or_all::Value::Amount(cln::Amount {
+                    msat: 100_000_000,
+                })),
+            }),
+            ..Default::default()
+        })];
+        let flow = [
+            Message::NewChannel(vls_protocol::msgs::NewChannel {
+                peer_id: PubKey([2; 33]),
+                dbid: 1,
+            }),
+            setup_channel(true, 100_000, vec![]),
+            sign_withdrawal(psbt(&[
+                (address(1), 100_000, false),
+                (address(2), 5_000, true),
+            ])),
+        ];
+        for msg in flow.iter() {
+            assert_eq!(
+                Resolver::try_resolve(msg, &ctx).unwrap(),
+                Resolution::Context(0)
+            );
+        }
+
+        // Opens the node starts on its own, e.g. `fundchannel_start`
+        // or `multifundchannel`, have no context to match.
+        assert!(Resolver::try_resolve(&flow[1], &vec![]).is_err());
+        assert!(Resolver::try_resolve(&flow[2], &vec![]).is_err());
+    }
+
+    #[test]
+    fn test_withdrawal_needs_a_context() {
+        let outputs = [(address(1), 10_000, false), (address(2), 5_000, true)];
+        let tx = psbt(&outputs);
+        let user_psbt = general_purpose::STANDARD.encode(tx.serialize());
+
+        // `withdraw`, `txprepare`/`txsend` and the PSBT calls each
+        // justify signing the transaction.
+        for ctx in [
+            withdraw_request(&address(1), Some(10_000)),
+            Request::TxSend(cln::TxsendRequest {
+                txid: hex::decode(tx.unsigned_tx.compute_txid().to_string()).unwrap(),
+            }),
+            Request::SignPsbt(cln::SignpsbtRequest {
+                psbt: user_psbt.clone(),
+                ..Default::default()
+            }),
+            Request::SendPsbt(cln::SendpsbtRequest {
+                psbt: user_psbt.clone(),
+                ..Default::default()
+            }),
+        ] {
+            assert!(Resolver::try_resolve(&sign_withdrawal(psbt(&outputs)), &vec![ctx]).is_ok());
+        }
+
+        // A withdrawal the node starts without a user call, or while
+        // only unrelated calls are pending, is rejected.
+        assert!(Resolver::try_resolve(&sign_withdrawal(psbt(&outputs)), &vec![]).is_err());
+        let unrelated = vec![Request::ListFunds(Default::default())];
+        assert!(Resolver::try_resolve(&sign_withdrawal(psbt(&outputs)), &unrelated).is_err());
+    }
+
+    #[test]
+    fn test_psbt_resolution() {
+        let outputs = [(address(1), 10_000, false)];
+        let user_psbt = general_purpose::STANDARD.encode(psbt(&outputs).serialize());
+        let other_psbt =
+            general_purpose::STANDARD.encode(psbt(&[(address(2), 10_000, false)]).serialize());
+        let txid = psbt(&outputs).unsigned_tx.compute_txid().to_string();
+
+        for ctx in [
+            Request::SendPsbt(cln::SendpsbtRequest {
+                psbt: user_psbt.clone(),
+                ..Default::default()
+            }),
+            Request::SignPsbt(cln::SignpsbtRequest {
+                psbt: user_psbt.clone(),
+                ..Default::default()
+            }),
+            Request::TxSend(cln::TxsendRequest {
+                txid: hex::decode(&txid).unwrap(),
+            }),
+        ] {
+            let ctx = vec![ctx];
+            assert!(Resolver::try_resolve(&sign_withdrawal(psbt(&outputs)), &ctx).is_ok());
+            assert!(Resolver::try_resolve(
+                &sign_withdrawal(psbt(&[(address(2), 10_000, false)])),
+                &ctx
+            )
+            .is_err());
+        }
+
+        let ctx = vec![Request::SignPsbt(cln::SignpsbtRequest {
+            psbt: other_psbt,
+            ..Default::default()
+        })];
+        assert!(Resolver::try_resolve(&sign_withdrawal(psbt(&outputs)), &ctx).is_err());
+    }
+}
@@ -0,0 +1,209 @@
+//! Backends to persist the signer state outside of the node.
+//!
+//! By default the signer only keeps its state in memory and relies on
+//! the node to send it back with every request. Attaching a
+//! [`StateStore`] with [`Signer::with_state_store`] makes the signer
+//! write its state after each request and merge it back on startup,
+//! so that a node replaying stale state can be detected.
+//!
+//! [`Signer::with_state_store`]: super::Signer::with_state_store
+
+pub use crate::persist::State;
+use async_trait::async_trait;
+use log::debug;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("i/o error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("state corruption: {0}")]
+    CorruptState(#[from] serde_json::Error),
+    #[cfg(feature = "sqlite")]
+    #[error("database error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+    #[error("unhandled error: {0}")]
+    Other(Box<dyn std::error::Error + Send + Sync>),
+}
+
+#[async_trait]
+pub trait StateStore: Send + Sync {
+    async fn write(&self, state: State) -> Result<(), Error>;
+    async fn read(&self) -> Result<State, Error>;
+}
+
+/// A StateStore that keeps the state as JSON in a single file.
+pub struct FileStateStore {
+    path: PathBuf,
+}
+
+impl FileStateStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+#[async_trait]
+impl StateStore for FileStateStore {
+    async fn read(&self) -> Result<State, Error> {
+        match tokio::fs::read(&self.path).await {
+            Ok(raw) => Ok(serde_json::from_slice(&raw)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                debug!("Initializing a new signer state");
+                Ok(State::new())
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn write(&self, state: State) -> Result<(), Error> {
+        let raw = serde_json::to_vec(&state)?;
+        let path = self.path.clone();
+        tokio::task::spawn_blocking(move || write_synced(&path, &raw))
+            .await
+            .map_err(|e| Error::Other(Box::new(e)))?
+    }
+}
+
+/// Write to a temporary file and rename it, so a crash never leaves a
+/// truncated state behind. The file and the directory are synced, so
+/// the new state survives a power loss once this returns.
+fn write_synced(path: &Path, raw: &[u8]) -> Result<(), Error> {
+    use std::io::Write;
+    let tmp = path.with_extension("tmp");
+    let mut file = std::fs::File::create(&tmp)?;
+    file.write_all(raw)?;
+    file.sync_all()?;
+    std::fs::rename(&tmp, path)?;
+    // Windows can not open directories as files, and renames there
+    // are durable once they return.
+    #[cfg(unix)]
+    {
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        std::fs::File::open(dir)?.sync_all()?;
+    }
+    Ok(())
+}
+
+/// A StateStore that keeps the state in a SQLite database.
+#[cfg(feature = "sqlite")]
+pub struct SqliteStateStore {
+    conn: std::sync::Arc<std::sync::Mutex<rusqlite::Connection>>,
+}
+
+#[cfg(feature = "sqlite")]
+impl SqliteStateStore {
+    pub fn new(path: impl AsRef<std::path::Path>) -> Result<Self, Error> {
+        Self::from_connection(rusqlite::Connection::open(path)?)
+    }
+
+    pub fn from_connection(conn: rusqlite::Connection) -> Result<Self, Error> {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS signer_state (
+                id INTEGER PRIMARY KEY CHECK (id = 0),
+                state BLOB NOT NULL
+            )",
+            [],
+        )?;
+        Ok(Self {
+            conn: std::sync::Arc::new(std::sync::Mutex::new(conn)),
+        })
+    }
+
+    /// Run `f` on the connection on the blocking thread pool, so the
+    /// database I/O does not stall the runtime.
+    async fn with_conn<T, F>(&self, f: F) -> Result<T, Error>
+    where
+        T: Send + 'static,
+        F: FnOnce(&rusqlite::Connection) -> Result<T, Error> + Send + 'static,
+    {
+        let conn = self.conn.clone();
+        tokio::task::spawn_blocking(move || {
+            let conn = conn
+                .lock()
+                .map_err(|e| Error::Other(e.to_string().into()))?;
+            f(&conn)
+        })
+        .await
+        .map_err(|e| Error::Other(Box::new(e)))?
+    }
+}
+
+#[cfg(feature = "sqlite")]
+#[async_trait]
+impl StateStore for SqliteStateStore {
+    async fn read(&self) -> Result<State, Error> {
+        use rusqlite::OptionalExtension;
+        let raw: Option<Vec<u8>> = self
+            .with_conn(|conn| {
+                Ok(conn
+                    .query_row("SELECT state FROM signer_state WHERE id = 0", [], |r| {
+                        r.get(0)
+                    })
+                    .optional()?)
+            })
+            .await?;
+        match raw {
+            None => {
+                debug!("Initializing a new signer state");
+                Ok(State::new())
+            }
+            Some(raw) => Ok(serde_json::from_slice(&raw)?),
+        }
+    }
+
+    async fn write(&self, state: State) -> Result<(), Error> {
+        let raw = serde_json::to_vec(&state)?;
+        self.with_conn(move |conn| {
+            conn.execute(
+                "INSERT INTO signer_state (id, state) VALUES (0, ?1)
+                 ON CONFLICT (id) DO UPDATE SET state = excluded.state",
+                [raw],
+            )?;
+            Ok(())
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn state(version: u64) -> State {
+        serde_json::from_value(json!({
+            "values": {"nodes/test": [version, {"v": version}]}
+        }))
+        .unwrap()
+    }
+
+    async fn roundtrip(store: &dyn StateStore) {
+        assert_eq!(store.read().await.unwrap().len(), 0);
+        store.write(state(1)).await.unwrap();
+        store.write(state(2)).await.unwrap();
+        let read = store.read().await.unwrap();
+        assert_eq!(read.len(), 1);
+        assert!(State::new().diff_state(&read).len() == 1);
+        assert_eq!(read.diff_state(&state(2)).len(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_file_state_store_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        roundtrip(&FileStateStore::new(dir.path().join("signer_state.json"))).await;
+        // The temporary file was renamed into place.
+        assert!(!dir.path().join("signer_state.tmp").exists());
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn test_sqlite_state_store_roundtrip() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        roundtrip(&SqliteStateStore::from_connection(conn).unwrap()).await;
+    }
+}
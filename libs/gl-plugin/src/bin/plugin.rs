@@ -1,10 +1,11 @@
 use anyhow::{Context, Error};
 use gl_plugin::config::Config;
 use gl_plugin::{
-    hsm,
+    event_log::EventLog,
+    hsm, metrics,
     node::PluginNodeServer,
-    stager::Stage,
-    storage::{SledStateStore, StateStore},
+    stager::{QuorumMode, RequestTimeouts, Stage},
+    storage::{self, SledStateStore, SqliteStateStore, StateStore},
     Event,
 };
 use log::info;
@@ -19,10 +20,29 @@ async fn main() -> Result<(), Error> {
     let cwd = env::current_dir()?;
     info!("Running in {}", cwd.to_str().unwrap());
     let config = Config::new().context("loading config")?;
-    let stage = Arc::new(Stage::new());
+    let quorum = match env::var("GL_SIGNER_QUORUM") {
+        Ok(q) => QuorumMode::from_str(&q).context("parsing GL_SIGNER_QUORUM")?,
+        Err(_) => QuorumMode::default(),
+    };
+    info!("Using signer quorum mode {:?}", quorum);
+    let timeouts = match env::var("GL_SIGNER_TIMEOUTS") {
+        Ok(t) => RequestTimeouts::from_str(&t).context("parsing GL_SIGNER_TIMEOUTS")?,
+        Err(_) => RequestTimeouts::default(),
+    };
+    let stage = Arc::new(Stage::with_quorum(quorum).with_timeouts(timeouts));
     let (events, _) = tokio::sync::broadcast::channel(16);
     let (notifications, _) = tokio::sync::broadcast::channel(16);
     let state_store = get_signer_store().await?;
+    let event_log = EventLog::new(cwd.join("node_events"))?;
+
+    if let Ok(addr) = env::var("GL_METRICS_BINDING") {
+        let addr: SocketAddr = addr.parse().context("parsing GL_METRICS_BINDING")?;
+        tokio::spawn(async move {
+            if let Err(e) = metrics::serve(addr).await {
+                log::warn!("Metrics endpoint exited: {}", e);
+            }
+        });
+    }
 
     start_hsm_server(config.clone(), stage.clone())?;
     start_node_server(
@@ -31,6 +51,7 @@ async fn main() -> Result<(), Error> {
         events.clone(),
         notifications,
         state_store,
+        event_log,
     )
     .await?;
 
@@ -48,6 +69,7 @@ async fn start_node_server(
     events: tokio::sync::broadcast::Sender<Event>,
     notifications: tokio::sync::broadcast::Sender<cln_rpc::notifications::Notification>,
     signer_state_store: Box<dyn StateStore>,
+    event_log: EventLog,
 ) -> Result<(), Error> {
     let addr: SocketAddr = config
         .node_grpc_binding
@@ -73,6 +95,7 @@ async fn start_node_server(
         events.clone(),
         notifications.clone(),
         signer_state_store,
+        event_log,
     )
     .await?;
 
@@ -100,10 +123,29 @@ async fn start_node_server(
     Ok(())
 }
 
+/// Open the signer state store. `GL_SIGNER_STATE_BACKEND=sqlite`
+/// selects the SQLite store, which is populated from the sled store
+/// on first use.
 async fn get_signer_store() -> Result<Box<dyn StateStore>, Error> {
-    let mut state_dir = env::current_dir()?;
-    state_dir.push("signer_state");
-    Ok(Box::new(SledStateStore::new(state_dir)?))
+    let cwd = env::current_dir()?;
+    let sled_dir = cwd.join("signer_state");
+    let backend = env::var("GL_SIGNER_STATE_BACKEND").unwrap_or_else(|_| "sled".to_string());
+    match backend.as_str() {
+        "sqlite" => {
+            let store = SqliteStateStore::new(cwd.join("signer_state.sqlite3"))?;
+            if store.read().await?.len() == 0 && sled_dir.exists() {
+                let sled = SledStateStore::new(sled_dir)?;
+                let count = storage::migrate(&sled, &store).await?;
+                info!(
+                    "Migrated {} signer state entries from sled to sqlite",
+                    count
+                );
+            }
+            Ok(Box::new(store))
+        }
+        "sled" => Ok(Box::new(SledStateStore::new(sled_dir)?)),
+        other => Err(anyhow::anyhow!("unknown GL_SIGNER_STATE_BACKEND {}", other)),
+    }
 }
 
 fn start_hsm_server(config: Config, stage: Arc<Stage>) -> Result<(), Error> {
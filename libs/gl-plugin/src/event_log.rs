@@ -0,0 +1,213 @@
+//! A persistent log of the `NodeEvent`s streamed to clients.
+//!
+//! Every event is assigned an increasing index and stored, so that a
+//! client that was disconnected for a while (e.g., a backgrounded
+//! mobile app) can resume the stream from the last index it saw. Only
+//! the most recent events are retained.
+use crate::pb;
+use crate::storage::Error;
+use prost::Message;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+
+/// The default number of events retained for replay.
+pub const DEFAULT_CAPACITY: usize = 10_000;
+
+/// Response header of `StreamNodeEvents` with the index the stream
+/// starts after.
+pub const INDEX_HEADER: &str = "gleventindex";
+
+#[derive(thiserror::Error, Debug)]
+pub enum ReplayError {
+    /// Events after the cursor were already dropped from the log, so
+    /// they cannot be replayed.
+    #[error("cursor {since} is too old, the oldest retained event is {oldest}")]
+    CursorTooOld { since: u64, oldest: u64 },
+    #[error(transparent)]
+    Storage(#[from] Error),
+}
+
+#[derive(Clone)]
+pub struct EventLog {
+    tree: sled::Tree,
+    /// The index to assign to the next event. The lock also
+    /// serializes appends, so events are stored and broadcast in
+    /// index order.
+    next_index: Arc<Mutex<u64>>,
+    capacity: usize,
+    live: broadcast::Sender<pb::NodeEvent>,
+}
+
+impl EventLog {
+    pub fn new(path: std::path::PathBuf) -> Result<EventLog, Error> {
+        Self::from_db(sled::open(path)?, DEFAULT_CAPACITY)
+    }
+
+    /// Use an existing database, retaining up to `capacity` events.
+    pub fn from_db(db: sled::Db, capacity: usize) -> Result<EventLog, Error> {
+        let tree = db.open_tree("node_events")?;
+        let next_index = match tree.last()? {
+            Some((k, _)) => decode_index(&k)? + 1,
+            None => 1,
+        };
+        let (live, _) = broadcast::channel(64);
+        Ok(EventLog {
+            tree,
+            next_index: Arc::new(Mutex::new(next_index)),
+            capacity,
+            live,
+        })
+    }
+
+    /// Assign the next index to `event`, store it and notify the
+    /// subscribers. Returns the indexed event.
+    pub fn append(&self, mut event: pb::NodeEvent) -> Result<pb::NodeEvent, Error> {
+        let mut next_index = self.next_index.lock().unwrap();
+        event.index = *next_index;
+        self.tree
+            .insert(event.index.to_be_bytes(), event.encode_to_vec())?;
+        *next_index += 1;
+
+        let oldest = next_index.saturating_sub(self.capacity as u64);
+        while let Some((k, _)) = self.tree.first()? {
+            if decode_index(&k)? >= oldest {
+                break;
+            }
+            self.tree.remove(k)?;
+        }
+
+        // Nobody listening is not an error, the event is in the log.
+        let _ = self.live.send(event.clone());
+        Ok(event)
+    }
+
+    /// The retained events with an index greater than `since`, in
+    /// index order. Fails with [`ReplayError::CursorTooOld`] if some
+    /// of those events were already dropped, unless `since` is 0,
+    /// which replays whatever is retained.
+    pub fn since(&self, since: u64) -> Result<Vec<pb::NodeEvent>, ReplayError> {
+        if since > 0 {
+            if let Some((k, _)) = self.tree.first().map_err(Error::from)? {
+                let oldest = decode_index(&k)?;
+                if oldest > since.saturating_add(1) {
+                    return Err(ReplayError::CursorTooOld { since, oldest });
+                }
+            }
+        }
+        let start = since.saturating_add(1).to_be_bytes();
+        let events = self
+            .tree
+            .range(start..)
+            .map(|r| {
+                let (_, v) = r?;
+                pb::NodeEvent::decode(v.as_ref()).map_err(|e| Error::Other(Box::new(e)))
+            })
+            .collect::<Result<_, Error>>()?;
+        Ok(events)
+    }
+
+    /// The index of the most recently appended event, 0 if there was
+    /// none yet.
+    pub fn last_index(&self) -> u64 {
+        *self.next_index.lock().unwrap() - 1
+    }
+
+    /// Subscribe to the events as they are appended.
+    pub fn subscribe(&self) -> broadcast::Receiver<pb::NodeEvent> {
+        self.live.subscribe()
+    }
+}
+
+fn decode_index(k: &[u8]) -> Result<u64, Error> {
+    let bytes: [u8; 8] = k
+        .try_into()
+        .map_err(|_| Error::Other(format!("malformed event index {:?}", k).into()))?;
+    Ok(u64::from_be_bytes(bytes))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn event(peer_id: u8) -> pb::NodeEvent {
+        pb::NodeEvent {
+            event: Some(pb::node_event::Event::PeerDisconnected(
+                pb::PeerDisconnected {
+                    peer_id: vec![peer_id; 33],
+                },
+            )),
+            index: 0,
+        }
+    }
+
+    fn temporary(capacity: usize) -> (sled::Db, EventLog) {
+        let db = sled::Config::new().temporary(true).open().unwrap();
+        let log = EventLog::from_db(db.clone(), capacity).unwrap();
+        (db, log)
+    }
+
+    #[test]
+    fn test_append_assigns_increasing_indices() {
+        let (_db, log) = temporary(10);
+        assert_eq!(log.last_index(), 0);
+        assert_eq!(log.append(event(1)).unwrap().index, 1);
+        assert_eq!(log.append(event(2)).unwrap().index, 2);
+        assert_eq!(log.last_index(), 2);
+
+        let events = log.since(0).unwrap();
+        assert_eq!(
+            events.iter().map(|e| e.index).collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+        assert_eq!(
+            events[1],
+            pb::NodeEvent {
+                index: 2,
+                ..event(2)
+            }
+        );
+        assert_eq!(log.since(1).unwrap().len(), 1);
+        assert!(log.since(2).unwrap().is_empty());
+        assert!(log.since(u64::MAX).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_indices_survive_reopening() {
+        let (db, log) = temporary(10);
+        log.append(event(1)).unwrap();
+        log.append(event(2)).unwrap();
+        drop(log);
+
+        let log = EventLog::from_db(db, 10).unwrap();
+        assert_eq!(log.append(event(3)).unwrap().index, 3);
+    }
+
+    #[test]
+    fn test_only_recent_events_are_retained() {
+        let (_db, log) = temporary(3);
+        for i in 0..5 {
+            log.append(event(i)).unwrap();
+        }
+        let indices: Vec<u64> = log.since(0).unwrap().iter().map(|e| e.index).collect();
+        assert_eq!(indices, vec![3, 4, 5]);
+
+        // Resuming right before the oldest retained event is fine,
+        // any earlier and events would be silently missed.
+        assert_eq!(log.since(2).unwrap().len(), 3);
+        assert!(matches!(
+            log.since(1),
+            Err(ReplayError::CursorTooOld {
+                since: 1,
+                oldest: 3
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_subscribers_receive_appended_events() {
+        let (_db, log) = temporary(10);
+        let mut rx = log.subscribe();
+        log.append(event(1)).unwrap();
+        assert_eq!(rx.recv().await.unwrap().index, 1);
+    }
+}
@@ -10,9 +10,11 @@
 //!
 //! - **Internal events** (`Event::Internal(I)`) - Type-erased events for
 //!   communication within the plugin process. Not exposed to clients.
-//! - **Public events** (`Event::IncomingPayment`, etc.) - Automatically
-//!   converted to `NodeEvent` protobufs and streamed to gl-sdk clients
-//!   via `StreamNodeEvents`.
+//! - **Public events** (`Event::IncomingPayment`, `Event::PaymentSent`,
+//!   `Event::ChannelStateChanged`, etc.) - Automatically converted to
+//!   `NodeEvent` protobufs and streamed to gl-sdk clients via
+//!   `StreamNodeEvents`. Most of them are sourced from CLN
+//!   notifications the plugin subscribes to.
 //!
 //! The [`EventBus`] uses `tokio::sync::broadcast` so multiple subscribers
 //! can receive the same events. Events are not persisted - if a client
@@ -224,6 +226,28 @@ pub enum Event<I = ()> {
     /// A peer address was learned from CLN's `peer_connected` hook.
     PeerConnected(PeerEntry),
 
+    /// A peer disconnected, from CLN's `disconnect` notification.
+    PeerDisconnected(pb::PeerDisconnected),
+
+    /// An outgoing payment succeeded, from CLN's `sendpay_success`
+    /// notification.
+    PaymentSent(pb::PaymentSent),
+
+    /// An outgoing payment failed permanently, from CLN's
+    /// `sendpay_failure` notification.
+    PaymentFailed(pb::PaymentFailed),
+
+    /// A channel changed state, from CLN's `channel_state_changed`
+    /// notification.
+    ChannelStateChanged(pb::ChannelStateChanged),
+
+    /// An on-chain deposit to the wallet confirmed, from CLN's
+    /// `coin_movement` notification.
+    DepositConfirmed(pb::DepositConfirmed),
+
+    /// An HSM request waited for a signer longer than its timeout.
+    SignerStuck(pb::SignerStuck),
+
     /// Internal events from gl-plugin-internal or other extensions.
     /// This variant is not used when `I = ()`.
     Internal(I),
@@ -241,6 +265,12 @@ impl<I> Event<I> {
             Event::IncomingPayment(p) => Event::IncomingPayment(p),
             Event::CustomMsg(m) => Event::CustomMsg(m),
             Event::PeerConnected(p) => Event::PeerConnected(p),
+            Event::PeerDisconnected(p) => Event::PeerDisconnected(p),
+            Event::PaymentSent(p) => Event::PaymentSent(p),
+            Event::PaymentFailed(p) => Event::PaymentFailed(p),
+            Event::ChannelStateChanged(c) => Event::ChannelStateChanged(c),
+            Event::DepositConfirmed(d) => Event::DepositConfirmed(d),
+            Event::SignerStuck(s) => Event::SignerStuck(s),
             Event::Internal(i) => Event::Internal(f(i)),
         }
     }
@@ -257,6 +287,12 @@ impl<I> Event<I> {
             Event::IncomingPayment(p) => Some(Event::IncomingPayment(p)),
             Event::CustomMsg(m) => Some(Event::CustomMsg(m)),
             Event::PeerConnected(p) => Some(Event::PeerConnected(p)),
+            Event::PeerDisconnected(p) => Some(Event::PeerDisconnected(p)),
+            Event::PaymentSent(p) => Some(Event::PaymentSent(p)),
+            Event::PaymentFailed(p) => Some(Event::PaymentFailed(p)),
+            Event::ChannelStateChanged(c) => Some(Event::ChannelStateChanged(c)),
+            Event::DepositConfirmed(d) => Some(Event::DepositConfirmed(d)),
+            Event::SignerStuck(s) => Some(Event::SignerStuck(s)),
             Event::Internal(i) => f(i).map(Event::Internal),
         }
     }
@@ -346,6 +382,12 @@ impl ErasedEventExt for ErasedEvent {
             Event::IncomingPayment(p) => Some(Event::IncomingPayment(p.clone())),
             Event::CustomMsg(m) => Some(Event::CustomMsg(m.clone())),
             Event::PeerConnected(p) => Some(Event::PeerConnected(p.clone())),
+            Event::PeerDisconnected(p) => Some(Event::PeerDisconnected(p.clone())),
+            Event::PaymentSent(p) => Some(Event::PaymentSent(p.clone())),
+            Event::PaymentFailed(p) => Some(Event::PaymentFailed(p.clone())),
+            Event::ChannelStateChanged(c) => Some(Event::ChannelStateChanged(c.clone())),
+            Event::DepositConfirmed(d) => Some(Event::DepositConfirmed(d.clone())),
+            Event::SignerStuck(s) => Some(Event::SignerStuck(s.clone())),
             Event::Internal(any) => any.downcast_ref::<I>().cloned().map(Event::Internal),
         }
     }
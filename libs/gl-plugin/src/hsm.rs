@@ -64,6 +64,7 @@ impl Hsm for StagingHsmServer {
                 raw: response,
                 signer_state: Vec::new(),
                 error: "".into(),
+                signer_id: "".into(),
             }));
         } else if req.get_type() == 11 {
             debug!("Returning stashed init msg: {:?}", self.node_info.initmsg);
@@ -72,6 +73,7 @@ impl Hsm for StagingHsmServer {
                 raw: self.node_info.initmsg.clone(),
                 signer_state: Vec::new(), // the signerproxy doesn't care about state
                 error: "".into(),
+                signer_id: "".into(),
             }));
         } else if req.get_type() == 33 {
             debug!("Returning stashed dev-memleak response");
@@ -80,6 +82,7 @@ impl Hsm for StagingHsmServer {
                 raw: vec![0, 133, 0],
                 signer_state: Vec::new(), // the signerproxy doesn't care about state
                 error: "".into(),
+                signer_id: "".into(),
             }));
         }
 
@@ -102,6 +105,10 @@ impl Hsm for StagingHsmServer {
             Some(r) => r,
         };
 
+        if !res.error.is_empty() {
+            return Err(Status::aborted(res.error));
+        }
+
         Ok(Response::new(res))
     }
 
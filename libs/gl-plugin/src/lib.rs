@@ -11,10 +11,13 @@ extern crate gl_util;
 
 mod awaitables;
 pub mod config;
+pub mod event_log;
 pub mod events;
 pub mod hsm;
 pub mod messages;
+pub mod metrics;
 pub mod node;
+mod notifications;
 pub mod pb;
 pub mod requests;
 pub mod responses;
@@ -34,6 +37,8 @@ pub use events::{ErasedEvent, ErasedEventExt, ErasedInternal, Event, EventBus};
 pub struct GlPlugin {
     stage: Arc<stager::Stage>,
     events: broadcast::Sender<Event>,
+    seen_payments: Arc<std::sync::Mutex<notifications::SeenPayments>>,
+    failed_payments: Arc<std::sync::Mutex<notifications::SeenPayments>>,
 }
 
 /// A small wrapper around [`cln_plugin::Builder`] that allows us to
@@ -100,13 +105,20 @@ pub async fn init(
     let state = GlPlugin {
         events: events.clone(),
         stage,
+        seen_payments: Default::default(),
+        failed_payments: Default::default(),
     };
 
     let inner = cln_plugin::Builder::new(tokio::io::stdin(), tokio::io::stdout())
         .hook("invoice_payment", on_invoice_payment)
         .hook("peer_connected", on_peer_connected)
         .hook("openchannel", on_openchannel)
-        .hook("custommsg", on_custommsg);
+        .hook("custommsg", on_custommsg)
+        .subscribe("sendpay_success", on_sendpay_success)
+        .subscribe("sendpay_failure", on_sendpay_failure)
+        .subscribe("channel_state_changed", on_channel_state_changed)
+        .subscribe("disconnect", on_disconnect)
+        .subscribe("coin_movement", on_coin_movement);
 
     Ok(Builder {
         state,
@@ -329,4 +341,230 @@ async fn on_invoice_payment(plugin: Plugin, v: serde_json::Value) -> Result<serd
     Ok(json!({"result": "continue"}))
 }
 
+/// Notification handler for successful outgoing payment parts.
+/// Each payment is reported once, with the amounts of all its parts.
+async fn on_sendpay_success(plugin: Plugin, v: serde_json::Value) -> Result<()> {
+    let n: messages::SendpaySuccess =
+        match serde_json::from_value(notifications::params("sendpay_success", v)) {
+            Ok(n) => n,
+            Err(e) => {
+                log::error!("Could not decode the sendpay_success notification: {e}");
+                return Ok(());
+            }
+        };
+
+    let first = match plugin.state().seen_payments.lock() {
+        Ok(mut seen) => seen.insert(&n.payment_hash, n.groupid),
+        Err(_) => true,
+    };
+    if !first {
+        return Ok(());
+    }
+
+    let totals = get_payment_group_totals(plugin.configuration().rpc_file, &n).await;
+    match notifications::payment_sent(&n, totals) {
+        Ok(p) => send_event(&plugin, Event::PaymentSent(p)),
+        Err(e) => warn!("Could not convert the sendpay_success notification: {e}"),
+    }
+    Ok(())
+}
+
+/// Sum the `(amount_msat, amount_sent_msat)` of the parts of the
+/// payment group that did not fail.
+async fn get_payment_group_totals(
+    rpc_file: impl AsRef<std::path::Path>,
+    n: &messages::SendpaySuccess,
+) -> Option<(u64, u64)> {
+    use cln_rpc::model::responses::ListsendpaysPaymentsStatus;
+    use std::str::FromStr;
+
+    let mut rpc = cln_rpc::ClnRpc::new(rpc_file).await.ok()?;
+    let req = cln_rpc::model::requests::ListsendpaysRequest {
+        bolt11: None,
+        index: None,
+        limit: None,
+        payment_hash: Some(cln_rpc::primitives::Sha256::from_str(&n.payment_hash).ok()?),
+        start: None,
+        status: None,
+    };
+    let res = match rpc.call_typed(&req).await {
+        Ok(res) => res,
+        Err(e) => {
+            debug!("Could not list the parts of payment {}: {}", n.payment_hash, e);
+            return None;
+        }
+    };
+
+    let parts = res
+        .payments
+        .iter()
+        .filter(|p| p.groupid == n.groupid && p.status != ListsendpaysPaymentsStatus::FAILED);
+    let totals = parts.fold((0, 0), |(amount, sent), p| {
+        (
+            amount + p.amount_msat.map(|a| a.msat()).unwrap_or_default(),
+            sent + p.amount_sent_msat.msat(),
+        )
+    });
+    Some(totals)
+}
+
+/// How long to wait after a part failed before looking at the
+/// payment, giving `pay` the chance to retry along another route.
+const PAY_SETTLE_DELAY: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// Notification handler for failed outgoing payment parts. Each
+/// payment that failed as a whole is reported once, no matter how
+/// many of its parts failed.
+async fn on_sendpay_failure(plugin: Plugin, v: serde_json::Value) -> Result<()> {
+    let n: messages::SendpayFailure =
+        match serde_json::from_value(notifications::params("sendpay_failure", v)) {
+            Ok(n) => n,
+            Err(e) => {
+                log::error!("Could not decode the sendpay_failure notification: {e}");
+                return Ok(());
+            }
+        };
+
+    // Do not hold up the notification while the payment settles.
+    tokio::spawn(async move {
+        tokio::time::sleep(PAY_SETTLE_DELAY).await;
+        let rpc_file = plugin.configuration().rpc_file;
+        let outcome = match get_pay_outcome(rpc_file, &n.data.payment_hash).await {
+            Some(o) => o,
+            None => return,
+        };
+        let p = match notifications::payment_failed(&n, &outcome) {
+            Ok(Some(p)) => p,
+            Ok(None) => return,
+            Err(e) => {
+                warn!("Could not convert the sendpay_failure notification: {e}");
+                return;
+            }
+        };
+
+        let first = match plugin.state().failed_payments.lock() {
+            Ok(mut seen) => seen.insert(&n.data.payment_hash, outcome.created_at),
+            Err(_) => true,
+        };
+        if first {
+            send_event(&plugin, Event::PaymentFailed(p));
+        }
+    });
+    Ok(())
+}
+
+/// Look up the most recent attempt to pay `payment_hash`. The attempt
+/// only counts as failed if none of the attempts completed, and none
+/// of its parts are still in flight.
+async fn get_pay_outcome(
+    rpc_file: impl AsRef<std::path::Path>,
+    payment_hash: &str,
+) -> Option<notifications::PayOutcome> {
+    use cln_rpc::model::responses::ListpaysPaysStatus;
+    use std::str::FromStr;
+
+    let mut rpc = cln_rpc::ClnRpc::new(rpc_file).await.ok()?;
+    let req = cln_rpc::model::requests::ListpaysRequest {
+        bolt11: None,
+        index: None,
+        limit: None,
+        payment_hash: Some(cln_rpc::primitives::Sha256::from_str(payment_hash).ok()?),
+        start: None,
+        status: None,
+    };
+    let res = match rpc.call_typed(&req).await {
+        Ok(res) => res,
+        Err(e) => {
+            debug!(
+                "Could not list the attempts of payment {}: {}",
+                payment_hash, e
+            );
+            return None;
+        }
+    };
+
+    let pays = res.pays;
+    if pays
+        .iter()
+        .any(|p| p.status == ListpaysPaysStatus::COMPLETE)
+    {
+        return Some(Default::default());
+    }
+    let last = pays.iter().max_by_key(|p| p.created_at)?;
+    Some(notifications::PayOutcome {
+        failed: last.status == ListpaysPaysStatus::FAILED,
+        created_at: last.created_at,
+        amount_msat: last.amount_msat.map(|a| a.msat()),
+        destination: last.destination.map(|d| d.to_string()),
+    })
+}
+
+/// Notification handler for channel state changes.
+async fn on_channel_state_changed(plugin: Plugin, v: serde_json::Value) -> Result<()> {
+    let n: messages::ChannelStateChangedNotification =
+        match serde_json::from_value(notifications::params("channel_state_changed", v)) {
+            Ok(n) => n,
+            Err(e) => {
+                log::error!("Could not decode the channel_state_changed notification: {e}");
+                return Ok(());
+            }
+        };
+
+    match notifications::channel_state_changed(&n) {
+        Ok(c) => send_event(&plugin, Event::ChannelStateChanged(c)),
+        Err(e) => warn!("Could not convert the channel_state_changed notification: {e}"),
+    }
+    Ok(())
+}
+
+/// Notification handler for peer disconnects.
+async fn on_disconnect(plugin: Plugin, v: serde_json::Value) -> Result<()> {
+    let n: messages::DisconnectNotification =
+        match serde_json::from_value(notifications::params("disconnect", v)) {
+            Ok(n) => n,
+            Err(e) => {
+                log::error!("Could not decode the disconnect notification: {e}");
+                return Ok(());
+            }
+        };
+
+    match hex::decode(&n.id) {
+        Ok(peer_id) => send_event(
+            &plugin,
+            Event::PeerDisconnected(pb::PeerDisconnected { peer_id }),
+        ),
+        Err(e) => warn!("Could not decode the disconnected peer id: {e}"),
+    }
+    Ok(())
+}
+
+/// Notification handler for coin movements, reporting confirmed
+/// on-chain deposits to the wallet.
+async fn on_coin_movement(plugin: Plugin, v: serde_json::Value) -> Result<()> {
+    let m: messages::CoinMovement =
+        match serde_json::from_value(notifications::params("coin_movement", v)) {
+            Ok(m) => m,
+            // Channel movements have a different shape, and are not
+            // of interest here.
+            Err(e) => {
+                debug!("Ignoring coin_movement notification that is not an on-chain movement: {e}");
+                return Ok(());
+            }
+        };
+
+    match notifications::deposit_confirmed(&m) {
+        Ok(Some(d)) => send_event(&plugin, Event::DepositConfirmed(d)),
+        Ok(None) => {}
+        Err(e) => warn!("Could not convert the coin_movement notification: {e}"),
+    }
+    Ok(())
+}
+
+/// Publish an event to the listeners. Delivery is best effort.
+fn send_event(plugin: &Plugin, event: Event) {
+    if let Err(e) = plugin.state().events.send(event) {
+        log::debug!("No active listener for event: {}", e);
+    }
+}
+
 pub use cln_grpc as grpc;
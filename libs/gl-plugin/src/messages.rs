@@ -163,6 +163,71 @@ pub struct DisconnectNotification {
     pub id: String,
 }
 
+/// Payload of CLN's `sendpay_success` notification.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SendpaySuccess {
+    pub payment_hash: String,
+    #[serde(default)]
+    pub groupid: u64,
+    pub destination: Option<String>,
+    #[serde(default, deserialize_with = "amt_from_str_or_int")]
+    pub amount_msat: u64,
+    #[serde(deserialize_with = "amt_from_str_or_int")]
+    pub amount_sent_msat: u64,
+    pub payment_preimage: String,
+    pub bolt11: Option<String>,
+}
+
+/// Payload of CLN's `sendpay_failure` notification.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SendpayFailure {
+    pub code: i64,
+    pub message: String,
+    pub data: SendpayFailureData,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SendpayFailureData {
+    pub payment_hash: String,
+    #[serde(default)]
+    pub groupid: u64,
+    pub destination: Option<String>,
+    #[serde(default, deserialize_with = "amt_from_str_or_int")]
+    pub amount_msat: u64,
+}
+
+/// Payload of CLN's `channel_state_changed` notification.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ChannelStateChangedNotification {
+    pub peer_id: String,
+    pub channel_id: String,
+    pub short_channel_id: Option<String>,
+    pub old_state: Option<String>,
+    pub new_state: String,
+    pub cause: String,
+    #[serde(default)]
+    pub message: String,
+}
+
+/// Payload of CLN's `coin_movement` notification. Older versions
+/// carry `tags`, `utxo_txid` and `vout`, newer ones `primary_tag`
+/// and `utxo`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CoinMovement {
+    #[serde(rename = "type")]
+    pub typ: String,
+    pub account_id: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub primary_tag: Option<String>,
+    #[serde(default, deserialize_with = "amt_from_str_or_int")]
+    pub credit_msat: u64,
+    pub utxo_txid: Option<String>,
+    pub vout: Option<u32>,
+    pub utxo: Option<String>,
+    pub blockheight: Option<u32>,
+}
+
 #[derive(Debug)]
 pub enum JsonRpc<N, R> {
     Request(usize, R),
@@ -302,6 +367,60 @@ pub enum Direction {
 mod test {
     use super::*;
 
+    #[test]
+    fn test_sendpay_notifications() {
+        let success: SendpaySuccess = serde_json::from_value(json!({
+            "id": 1,
+            "payment_hash": "5c85bf402b87d4860f4a728e2e58a2418bda92cd7aea0ce494f11670cfbfb206",
+            "groupid": 1,
+            "destination": "035d2b1192dfba134e10e540875d366ebc8bc353d5aa766b80c090b39c3a5d885d",
+            "amount_msat": 100000000,
+            "amount_sent_msat": 100001001,
+            "created_at": 1561390572,
+            "status": "complete",
+            "payment_preimage": "9540d98095fd7f37687ebb7759e733934234d4f934e34433d4998a37de3733ee"
+        }))
+        .unwrap();
+        assert_eq!(success.amount_sent_msat, 100001001);
+        assert_eq!(success.bolt11, None);
+
+        let failure: SendpayFailure = serde_json::from_value(json!({
+            "code": 203,
+            "message": "failed: WIRE_INCORRECT_OR_UNKNOWN_PAYMENT_DETAILS",
+            "data": {
+                "id": 2,
+                "payment_hash": "9036e3bdbd2515f1e653cb9f22f8e4c49b73aa2c36e937c926f43e33b8db8851",
+                "groupid": 1,
+                "destination": "035d2b1192dfba134e10e540875d366ebc8bc353d5aa766b80c090b39c3a5d885d",
+                "amount_msat": "100000msat",
+                "amount_sent_msat": 100001,
+                "status": "failed"
+            }
+        }))
+        .unwrap();
+        assert_eq!(failure.code, 203);
+        assert_eq!(failure.data.amount_msat, 100000);
+    }
+
+    #[test]
+    fn test_coin_movement_notification() {
+        let mvt: CoinMovement = serde_json::from_value(json!({
+            "version": 2,
+            "type": "chain_mvt",
+            "account_id": "wallet",
+            "utxo_txid": "0159693d8f3876b4def468b208712c630309381e9d106a9836fa0a9571a28722",
+            "vout": 1,
+            "credit_msat": 2000000000,
+            "debit_msat": 0,
+            "tags": ["deposit"],
+            "blockheight": 102,
+            "timestamp": 1585948198
+        }))
+        .unwrap();
+        assert_eq!(mvt.credit_msat, 2_000_000_000);
+        assert_eq!(mvt.tags, vec!["deposit".to_string()]);
+    }
+
     #[test]
     fn test_peer_connected_call() {
         let msg = json!({
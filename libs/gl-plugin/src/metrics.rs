@@ -0,0 +1,118 @@
+//! Metrics about the signers and the node, exported in the Prometheus
+//! text exposition format by [`serve`].
+use gl_client::metrics::{Counter, Gauge, Histogram, Registry, LATENCY_BUCKETS};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Response, StatusCode};
+use lazy_static::lazy_static;
+use log::info;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+
+lazy_static! {
+    pub static ref REGISTRY: Registry = Registry::default();
+
+    /// Time between staging a request and a signer responding to it.
+    pub static ref SIGNER_RTT: Histogram = REGISTRY.histogram(
+        "gl_signer_rtt_seconds",
+        "Time until a signer responded to a request, by signer.",
+        LATENCY_BUCKETS,
+    );
+    pub static ref SIGNER_RESPONSES_USED: Counter = REGISTRY.counter(
+        "gl_signer_responses_used_total",
+        "Signer responses returned to hsmd, by signer.",
+    );
+    pub static ref SIGNER_DISAGREEMENTS: Counter = REGISTRY.counter(
+        "gl_signer_disagreements_total",
+        "Responses that differed from another signer's, by signer.",
+    );
+    pub static ref PENDING_REQUESTS: Gauge = REGISTRY.gauge(
+        "gl_pending_hsm_requests",
+        "HSM requests waiting for a signer.",
+    );
+    pub static ref STUCK_REQUESTS: Counter = REGISTRY.counter(
+        "gl_stuck_hsm_requests_total",
+        "HSM requests that timed out waiting for a signer.",
+    );
+    pub static ref STATE_DIFF_BYTES: Counter = REGISTRY.counter(
+        "gl_signer_state_diff_bytes_total",
+        "Bytes of signer state sent to signers.",
+    );
+    pub static ref STATE_FULL_BYTES: Counter = REGISTRY.counter(
+        "gl_signer_state_full_bytes_total",
+        "Bytes the signer state sent to signers would have taken without diffing.",
+    );
+    pub static ref RATE_LIMITED: Counter = REGISTRY.counter(
+        "gl_rpc_rate_limited_total",
+        "RPC calls delayed by the rate limiter.",
+    );
+    pub static ref LSP_INVOICES: Counter = REGISTRY.counter(
+        "gl_lsp_invoices_total",
+        "LspInvoice calls, by outcome.",
+    );
+}
+
+/// Records the outcome of a call once it is dropped. Calls that return
+/// early, e.g., via `?`, are recorded as `failed`.
+pub(crate) struct Outcome<'a> {
+    counter: &'a Counter,
+    outcome: &'static str,
+}
+
+impl<'a> Outcome<'a> {
+    pub(crate) fn new(counter: &'a Counter) -> Self {
+        Outcome {
+            counter,
+            outcome: "failed",
+        }
+    }
+
+    pub(crate) fn set(&mut self, outcome: &'static str) {
+        self.outcome = outcome;
+    }
+}
+
+impl Drop for Outcome<'_> {
+    fn drop(&mut self) {
+        self.counter.inc(&[("outcome", self.outcome)]);
+    }
+}
+
+/// Serve the metrics on `GET /metrics` at `addr`.
+pub async fn serve(addr: SocketAddr) -> Result<(), hyper::Error> {
+    let make_svc = make_service_fn(|_| async {
+        Ok::<_, Infallible>(service_fn(|req| async move {
+            let res = match (req.method(), req.uri().path()) {
+                (&Method::GET, "/metrics") => Response::builder()
+                    .header("Content-Type", "text/plain; version=0.0.4")
+                    .body(Body::from(REGISTRY.render())),
+                _ => Response::builder()
+                    .status(StatusCode::NOT_FOUND)
+                    .body(Body::empty()),
+            };
+            Ok::<_, Infallible>(res.unwrap())
+        }))
+    });
+    info!("Serving metrics on http://{}/metrics", addr);
+    hyper::Server::bind(&addr).serve(make_svc).await
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_outcome_defaults_to_failed() {
+        let registry = Registry::default();
+        let counter = registry.counter("calls_total", "Calls.");
+        {
+            let _outcome = Outcome::new(&counter);
+        }
+        let mut outcome = Outcome::new(&counter);
+        outcome.set("ok");
+        drop(outcome);
+
+        let rendered = registry.render();
+        assert!(rendered.contains("calls_total{outcome=\"failed\"} 1"));
+        assert!(rendered.contains("calls_total{outcome=\"ok\"} 1"));
+    }
+}
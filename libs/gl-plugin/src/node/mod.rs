@@ -1,4 +1,5 @@
 use crate::config::Config;
+use crate::event_log::{EventLog, ReplayError};
 use crate::pb::{self, node_server::Node};
 use crate::storage::StateStore;
 use crate::{messages, Event};
@@ -90,6 +91,7 @@ pub struct PluginNodeServer {
     signer_state_store: Arc<Mutex<Box<dyn StateStore>>>,
     pub ctx: crate::context::Context,
     notifications: tokio::sync::broadcast::Sender<Notification>,
+    event_log: EventLog,
 }
 
 impl PluginNodeServer {
@@ -99,6 +101,7 @@ impl PluginNodeServer {
         events: tokio::sync::broadcast::Sender<super::Event>,
         notifications: tokio::sync::broadcast::Sender<Notification>,
         signer_state_store: Box<dyn StateStore>,
+        event_log: EventLog,
     ) -> Result<Self, Error> {
         let tls = ServerTlsConfig::new()
             .identity(config.identity.id)
@@ -133,8 +136,48 @@ impl PluginNodeServer {
             signer_state_store: Arc::new(Mutex::new(signer_state_store)),
             grpc_binding: config.node_grpc_binding,
             notifications,
+            event_log,
         };
 
+        // Publish the alerts about requests no signer responded to.
+        let mut stuck = s.stage.subscribe_stuck();
+        let tx = s.events.clone();
+        tokio::spawn(async move {
+            loop {
+                match stuck.recv().await {
+                    Ok(e) => {
+                        let _ = tx.send(super::Event::SignerStuck(e));
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("Signer stuck listener skipped {skipped} alerts");
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        });
+
+        // Record the events streamed to clients, so they can be
+        // replayed to clients that reconnect.
+        let event_log = s.event_log.clone();
+        let mut log_events = s.events.subscribe();
+        tokio::spawn(async move {
+            loop {
+                match log_events.recv().await {
+                    Ok(event) => {
+                        for e in node_events_from_event(&event) {
+                            if let Err(e) = event_log.append(e) {
+                                warn!("Failed to append to the node event log: {e}");
+                            }
+                        }
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("Node event log skipped {skipped} events");
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        });
+
         let signer_state = s.signer_state.clone();
         let signer_state_store = s.signer_state_store.clone();
         let mut peer_events = s.events.subscribe();
@@ -205,7 +248,10 @@ impl PluginNodeServer {
             })
             .await;
 
-        limiter.until_ready().await
+        if limiter.check().is_err() {
+            crate::metrics::RATE_LIMITED.inc(&[]);
+            limiter.until_ready().await
+        }
     }
 }
 
@@ -219,6 +265,7 @@ impl Node for PluginNodeServer {
         &self,
         req: Request<pb::LspInvoiceRequest>,
     ) -> Result<Response<pb::LspInvoiceResponse>, Status> {
+        let mut outcome = crate::metrics::Outcome::new(&crate::metrics::LSP_INVOICES);
         let req: pb::LspInvoiceRequest = req.into_inner();
         let rpc_arc = get_rpc(&self.rpc_path).await;
 
@@ -266,6 +313,7 @@ impl Node for PluginNodeServer {
                     .await
                     .map_err(|e| Status::new(Code::Internal, e.to_string()))?;
 
+                outcome.set("regular");
                 return Ok(Response::new(pb::LspInvoiceResponse {
                     bolt11: res.bolt11,
                     created_index: res.created_index.unwrap_or(0) as u32,
@@ -343,6 +391,30 @@ impl Node for PluginNodeServer {
         };
 
         res.opening_fee_msat = opening_fee_msat;
+
+        // Remember the fee, so the payment history can show it once
+        // the invoice is paid and the LSP deducted it.
+        let store_res = rpc
+            .call_typed(&cln_rpc::model::requests::DatastoreRequest {
+                key: vec![
+                    "greenlight".to_string(),
+                    "lsps2_opening_fees".to_string(),
+                    res.payment_hash.clone(),
+                ],
+                string: Some(opening_fee_msat.to_string()),
+                hex: None,
+                mode: Some(cln_rpc::model::requests::DatastoreMode::CREATE_OR_REPLACE),
+                generation: None,
+            })
+            .await;
+        if let Err(e) = store_res {
+            warn!(
+                "Could not store the opening fee of invoice {}: {}",
+                res.payment_hash, e
+            );
+        }
+
+        outcome.set("jit");
         Ok(Response::new(res.into()))
     }
 
@@ -425,17 +497,25 @@ impl Node for PluginNodeServer {
 
     async fn stream_hsm_requests(
         &self,
-        _request: Request<pb::Empty>,
+        request: Request<pb::Empty>,
     ) -> Result<Response<Self::StreamHsmRequestsStream>, Status> {
         let hsm_id = HSM_ID_COUNT.fetch_add(1, Ordering::SeqCst);
         SIGNER_COUNT.fetch_add(1, Ordering::SeqCst);
+        let signer_id = match request
+            .metadata()
+            .get(gl_client::signer::SIGNER_ID_HEADER)
+            .and_then(|v| v.to_str().ok())
+        {
+            Some(id) if !id.is_empty() => id.to_string(),
+            _ => peer_signer_id(&request),
+        };
         info!(
-            "New signer with hsm_id={} attached, streaming requests",
-            hsm_id
+            "New signer with hsm_id={} signer_id={} attached, streaming requests",
+            hsm_id, signer_id
         );
 
         let (tx, rx) = mpsc::channel(10);
-        let mut stream = self.stage.mystream().await;
+        let mut stream = self.stage.mystream(&signer_id).await;
         let signer_state = self.signer_state.clone();
         let ctx = self.ctx.clone();
 
@@ -534,6 +614,8 @@ impl Node for PluginNodeServer {
                     .collect();
 
                 let saved_percent = savings_percent(full_wire_bytes, outgoing_wire_bytes);
+                crate::metrics::STATE_DIFF_BYTES.inc_by(&[], outgoing_wire_bytes as f64);
+                crate::metrics::STATE_FULL_BYTES.inc_by(&[], full_wire_bytes as f64);
                 trace!(
                     "Signer state diff to hsm_id={} request_id={} entries={}, wire_bytes={}, full_wire_bytes={}, saved {}% bandwidth syncing the state",
                     hsm_id,
@@ -584,11 +666,21 @@ impl Node for PluginNodeServer {
         &self,
         request: Request<pb::HsmResponse>,
     ) -> Result<Response<pb::Empty>, Status> {
+        let peer_id = peer_signer_id(&request);
         let req = request.into_inner();
+        let signer_id = match req.signer_id.as_str() {
+            "" => peer_id,
+            id => id.to_string(),
+        };
 
         if req.error != "" {
-            log::error!("Signer reports an error: {}", req.error);
+            log::error!("Signer {} reports an error: {}", signer_id, req.error);
             log::warn!("The above error was returned instead of a response.");
+            // Still let the stage know, so it does not wait for a
+            // response that will never come.
+            if let Err(e) = self.stage.respond(&signer_id, req).await {
+                warn!("Suppressing error: {:?}", e);
+            }
             return Ok(Response::new(pb::Empty::default()));
         }
         eprintln!("WIRE: signer -> plugin: {:?}", req);
@@ -639,12 +731,22 @@ impl Node for PluginNodeServer {
             return Ok(Response::new(pb::Empty::default()));
         }
 
-        if let Err(e) = self.stage.respond(req).await {
+        if let Err(e) = self.stage.respond(&signer_id, req).await {
             warn!("Suppressing error: {:?}", e);
         }
         Ok(Response::new(pb::Empty::default()))
     }
 
+    async fn list_pending_hsm_requests(
+        &self,
+        _request: Request<pb::Empty>,
+    ) -> Result<Response<pb::ListPendingHsmRequestsResponse>, Status> {
+        Ok(Response::new(pb::ListPendingHsmRequestsResponse {
+            requests: self.stage.pending_requests().await,
+            connected_signers: self.stage.connected_signers() as u32,
+        }))
+    }
+
     type StreamIncomingStream = ReceiverStream<Result<pb::IncomingPayment, Status>>;
 
     async fn stream_incoming(
@@ -673,57 +775,71 @@ impl Node for PluginNodeServer {
 
     async fn stream_node_events(
         &self,
-        _req: tonic::Request<pb::NodeEventsRequest>,
+        req: tonic::Request<pb::NodeEventsRequest>,
     ) -> Result<Response<Self::StreamNodeEventsStream>, Status> {
+        let req = req.into_inner();
         let (tx, rx) = mpsc::channel(1);
-        let mut bcast = self.events.subscribe();
+        // Read the head of the log, then subscribe before reading the
+        // backlog, so no event falls between the two. Events that are
+        // in both are skipped by their index.
+        let start = req.since.unwrap_or(self.event_log.last_index());
+        let mut bcast = self.event_log.subscribe();
+        let event_log = self.event_log.clone();
         tokio::spawn(async move {
-            while let Ok(event) = bcast.recv().await {
-                // Convert Event to NodeEvent protobuf, if applicable
-                let node_event = match &event {
-                    super::Event::IncomingPayment(p) => {
-                        // Extract the offchain payment details
-                        if let Some(crate::pb::incoming_payment::Details::Offchain(offchain)) =
-                            &p.details
-                        {
-                            Some(pb::NodeEvent {
-                                event: Some(pb::node_event::Event::InvoicePaid(pb::InvoicePaid {
-                                    payment_hash: offchain.payment_hash.clone(),
-                                    bolt11: offchain.bolt11.clone(),
-                                    preimage: offchain.preimage.clone(),
-                                    label: offchain.label.clone(),
-                                    amount_msat: offchain
-                                        .amount
-                                        .as_ref()
-                                        .and_then(|a| a.unit.as_ref())
-                                        .map(|u| match u {
-                                            pb::amount::Unit::Millisatoshi(m) => *m,
-                                            pb::amount::Unit::Satoshi(s) => s * 1000,
-                                            pb::amount::Unit::Bitcoin(b) => b * 100_000_000_000,
-                                            _ => 0,
-                                        })
-                                        .unwrap_or(0),
-                                    extratlvs: offchain.extratlvs.clone(),
-                                })),
-                            })
-                        } else {
-                            None
-                        }
+            // Without `since` only new events are streamed, but the
+            // stream still tracks where it started, to catch up from
+            // the log if it falls behind.
+            let mut cursor = start;
+            loop {
+                let backlog = match event_log.since(cursor) {
+                    Ok(backlog) => backlog,
+                    Err(e @ ReplayError::CursorTooOld { .. }) => {
+                        let _ = tx.send(Err(Status::out_of_range(e.to_string()))).await;
+                        return;
+                    }
+                    Err(e) => {
+                        let _ = tx
+                            .send(Err(Status::internal(format!(
+                                "error reading the event log: {e}"
+                            ))))
+                            .await;
+                        return;
                     }
-                    // Other event types are not exposed to clients
-                    _ => None,
                 };
-
-                if let Some(event) = node_event {
+                for event in backlog {
+                    cursor = event.index;
                     if tx.send(Ok(event)).await.is_err() {
                         // Client disconnected
-                        break;
+                        return;
+                    }
+                }
+
+                loop {
+                    match bcast.recv().await {
+                        Ok(event) => {
+                            if event.index <= cursor {
+                                continue;
+                            }
+                            cursor = event.index;
+                            if tx.send(Ok(event)).await.is_err() {
+                                // Client disconnected
+                                return;
+                            }
+                        }
+                        // We fell behind, catch up from the log.
+                        Err(broadcast::error::RecvError::Lagged(_)) => break,
+                        Err(broadcast::error::RecvError::Closed) => return,
                     }
                 }
             }
         });
 
-        Ok(Response::new(ReceiverStream::new(rx)))
+        // Tell the client where the stream starts, so it can resume
+        // from there even if it is interrupted before the first event.
+        let mut res = Response::new(ReceiverStream::new(rx));
+        res.metadata_mut()
+            .insert(crate::event_log::INDEX_HEADER, start.into());
+        Ok(res)
     }
 
     async fn configure(
@@ -818,8 +934,19 @@ impl Node for PluginNodeServer {
     ) -> Result<tonic::Response<pb::TrampolinePayResponse>, Status> {
         tramp::trampolinepay(r.into_inner(), self.rpc_path.clone())
             .await
-            .map(cln_rpc::model::responses::PayResponse::into)
-            .map(|res: cln_grpc::pb::PayResponse| {
+            .map(|payment| {
+                let trampoline_node_id = payment
+                    .trampoline_node_id
+                    .map(|id| id.serialize().to_vec())
+                    .unwrap_or_default();
+                let fee_msat = payment.fee_msat.unwrap_or_else(|| {
+                    let response = &payment.response;
+                    response
+                        .amount_sent_msat
+                        .msat()
+                        .saturating_sub(response.amount_msat.msat())
+                });
+                let res: cln_grpc::pb::PayResponse = payment.response.into();
                 tonic::Response::new(pb::TrampolinePayResponse {
                     payment_preimage: res.payment_preimage,
                     payment_hash: res.payment_hash,
@@ -828,6 +955,8 @@ impl Node for PluginNodeServer {
                     amount_msat: res.amount_msat.unwrap_or_default().msat,
                     amount_sent_msat: res.amount_sent_msat.unwrap_or_default().msat,
                     destination: res.destination.unwrap_or_default(),
+                    trampoline_node_id,
+                    fee_msat,
                 })
             })
             .map_err(|err| {
@@ -846,6 +975,19 @@ struct Lsps2Offer {
     params: Vec<crate::responses::OpeningFeeParams>,
 }
 
+/// Identify a signer that did not set `signer_id` in its response by
+/// the certificate it authenticated with.
+fn peer_signer_id<T>(request: &Request<T>) -> String {
+    use bitcoin::hashes::Hash;
+    match request.peer_certs().as_deref().and_then(|c| c.first()) {
+        Some(cert) => {
+            let hash = bitcoin::hashes::sha256::Hash::hash(cert.as_ref());
+            format!("cert-{}", &hash.to_string()[..16])
+        }
+        None => "unknown".to_string(),
+    }
+}
+
 /// Select the LSP and opening fee params to use for an LSPS2 invoice
 /// negotiation.
 ///
@@ -1238,6 +1380,81 @@ where
 mod rpcwait;
 pub use rpcwait::RpcWaitService;
 
+/// Channel states in which a channel's funding is not yet locked in.
+const AWAITING_LOCKIN_STATES: [&str; 2] = ["CHANNELD_AWAITING_LOCKIN", "DUALOPEND_AWAITING_LOCKIN"];
+
+/// Convert an [`Event`] to the `NodeEvent` protobufs streamed to
+/// clients. Events that are not exposed to clients yield nothing, and
+/// channel state changes that open or close a channel yield an
+/// additional `ChannelOpened` or `ChannelClosed` event.
+fn node_events_from_event(event: &Event) -> Vec<pb::NodeEvent> {
+    use pb::node_event::Event as E;
+    let events = match event {
+        Event::IncomingPayment(p) => {
+            // Extract the offchain payment details
+            if let Some(crate::pb::incoming_payment::Details::Offchain(offchain)) = &p.details {
+                vec![E::InvoicePaid(pb::InvoicePaid {
+                    payment_hash: offchain.payment_hash.clone(),
+                    bolt11: offchain.bolt11.clone(),
+                    preimage: offchain.preimage.clone(),
+                    label: offchain.label.clone(),
+                    amount_msat: offchain
+                        .amount
+                        .as_ref()
+                        .and_then(|a| a.unit.as_ref())
+                        .map(|u| match u {
+                            pb::amount::Unit::Millisatoshi(m) => *m,
+                            pb::amount::Unit::Satoshi(s) => s * 1000,
+                            pb::amount::Unit::Bitcoin(b) => b * 100_000_000_000,
+                            _ => 0,
+                        })
+                        .unwrap_or(0),
+                    extratlvs: offchain.extratlvs.clone(),
+                })]
+            } else {
+                vec![]
+            }
+        }
+        Event::PaymentSent(p) => vec![E::PaymentSent(p.clone())],
+        Event::PaymentFailed(p) => vec![E::PaymentFailed(p.clone())],
+        Event::PeerConnected(p) => match hex::decode(&p.peer_id) {
+            Ok(peer_id) => vec![E::PeerConnected(pb::PeerConnected { peer_id })],
+            Err(_) => vec![],
+        },
+        Event::PeerDisconnected(p) => vec![E::PeerDisconnected(p.clone())],
+        Event::ChannelStateChanged(c) => {
+            let mut events = vec![E::ChannelStateChanged(c.clone())];
+            let old_state = c.old_state.as_deref().unwrap_or_default();
+            if c.new_state == "CHANNELD_NORMAL" && AWAITING_LOCKIN_STATES.contains(&old_state) {
+                events.push(E::ChannelOpened(pb::ChannelOpened {
+                    peer_id: c.peer_id.clone(),
+                    channel_id: c.channel_id.clone(),
+                    short_channel_id: c.short_channel_id.clone(),
+                }));
+            } else if c.new_state == "FUNDING_SPEND_SEEN" {
+                events.push(E::ChannelClosed(pb::ChannelClosed {
+                    peer_id: c.peer_id.clone(),
+                    channel_id: c.channel_id.clone(),
+                    short_channel_id: c.short_channel_id.clone(),
+                    cause: c.cause.clone(),
+                }));
+            }
+            events
+        }
+        Event::DepositConfirmed(d) => vec![E::DepositConfirmed(d.clone())],
+        Event::SignerStuck(s) => vec![E::SignerStuck(s.clone())],
+        // Other event types are not exposed to clients
+        _ => vec![],
+    };
+    events
+        .into_iter()
+        .map(|e| pb::NodeEvent {
+            event: Some(e),
+            index: 0,
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -1256,6 +1473,43 @@ mod test {
         }
     }
 
+    fn channel_state_changed(old_state: Option<&str>, new_state: &str) -> Event {
+        Event::ChannelStateChanged(pb::ChannelStateChanged {
+            peer_id: vec![2; 33],
+            channel_id: vec![1; 32],
+            short_channel_id: Some("103x1x0".to_string()),
+            old_state: old_state.map(str::to_string),
+            new_state: new_state.to_string(),
+            cause: "remote".to_string(),
+            message: String::new(),
+        })
+    }
+
+    #[test]
+    fn test_node_events_from_channel_state_changes() {
+        use pb::node_event::Event as E;
+        let kinds = |event: &Event| -> Vec<&'static str> {
+            node_events_from_event(event)
+                .into_iter()
+                .map(|e| match e.event {
+                    Some(E::ChannelStateChanged(_)) => "changed",
+                    Some(E::ChannelOpened(_)) => "opened",
+                    Some(E::ChannelClosed(_)) => "closed",
+                    _ => "other",
+                })
+                .collect()
+        };
+
+        let opened = channel_state_changed(Some("CHANNELD_AWAITING_LOCKIN"), "CHANNELD_NORMAL");
+        assert_eq!(kinds(&opened), vec!["changed", "opened"]);
+        let closed = channel_state_changed(Some("CLOSINGD_COMPLETE"), "FUNDING_SPEND_SEEN");
+        assert_eq!(kinds(&closed), vec!["changed", "closed"]);
+        let created = channel_state_changed(None, "CHANNELD_AWAITING_LOCKIN");
+        assert_eq!(kinds(&created), vec!["changed"]);
+
+        assert!(node_events_from_event(&Event::RpcCall("getinfo".to_string())).is_empty());
+    }
+
     #[test]
     fn test_select_opening_params_empty() {
         // No LSPs at all -> nothing to select.
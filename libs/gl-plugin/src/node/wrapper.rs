@@ -1192,8 +1192,8 @@ impl WrappedNodeServer {
 
 use crate::pb::{
     node_server::Node as GlNode, Custommsg, Empty, HsmRequest, HsmResponse, IncomingPayment,
-    LogEntry, LspInvoiceRequest, LspInvoiceResponse, NodeEvent, NodeEventsRequest,
-    StreamCustommsgRequest, StreamIncomingFilter, StreamLogRequest,
+    ListPendingHsmRequestsResponse, LogEntry, LspInvoiceRequest, LspInvoiceResponse, NodeEvent,
+    NodeEventsRequest, StreamCustommsgRequest, StreamIncomingFilter, StreamLogRequest,
 };
 
 #[tonic::async_trait]
@@ -1225,6 +1225,13 @@ impl GlNode for WrappedNodeServer {
         self.node_server.respond_hsm_request(req).await
     }
 
+    async fn list_pending_hsm_requests(
+        &self,
+        req: Request<Empty>,
+    ) -> Result<Response<ListPendingHsmRequestsResponse>, Status> {
+        self.node_server.list_pending_hsm_requests(req).await
+    }
+
     async fn stream_hsm_requests(
         &self,
         req: Request<Empty>,
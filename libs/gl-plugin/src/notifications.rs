@@ -0,0 +1,225 @@
+//! Conversion of CLN notifications into the public [`Event`]s that
+//! are streamed to clients as `NodeEvent`s.
+//!
+//! [`Event`]: crate::Event
+use crate::messages;
+use crate::pb;
+use anyhow::{anyhow, Result};
+use std::collections::{HashSet, VecDeque};
+
+/// Returns the payload of a notification. CLN wraps the params in an
+/// object keyed by the notification name, while older versions send
+/// them unwrapped.
+pub(crate) fn params(name: &str, v: serde_json::Value) -> serde_json::Value {
+    match v {
+        serde_json::Value::Object(mut m) if m.contains_key(name) => {
+            m.remove(name).unwrap_or_default()
+        }
+        v => v,
+    }
+}
+
+/// Build a [`pb::PaymentSent`] from a `sendpay_success`
+/// notification. `totals` are the `(amount_msat, amount_sent_msat)`
+/// summed over all parts of the payment, if known.
+pub(crate) fn payment_sent(
+    n: &messages::SendpaySuccess,
+    totals: Option<(u64, u64)>,
+) -> Result<pb::PaymentSent> {
+    let (amount_msat, amount_sent_msat) = totals.unwrap_or((n.amount_msat, n.amount_sent_msat));
+    Ok(pb::PaymentSent {
+        payment_hash: hex::decode(&n.payment_hash)?,
+        preimage: hex::decode(&n.payment_preimage)?,
+        destination: n.destination.as_deref().map(hex::decode).transpose()?,
+        amount_msat,
+        amount_sent_msat,
+        bolt11: n.bolt11.clone(),
+    })
+}
+
+/// The outcome of a payment as reported by `listpays`, once all of
+/// its parts have settled.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct PayOutcome {
+    pub(crate) failed: bool,
+    pub(crate) created_at: u64,
+    pub(crate) amount_msat: Option<u64>,
+    pub(crate) destination: Option<String>,
+}
+
+/// Build a [`pb::PaymentFailed`] from a `sendpay_failure`
+/// notification. A failed part is only reported if the payment as a
+/// whole failed, since `pay` retries parts along different routes.
+pub(crate) fn payment_failed(
+    n: &messages::SendpayFailure,
+    outcome: &PayOutcome,
+) -> Result<Option<pb::PaymentFailed>> {
+    if !outcome.failed {
+        return Ok(None);
+    }
+    let destination = outcome
+        .destination
+        .as_deref()
+        .or(n.data.destination.as_deref());
+    Ok(Some(pb::PaymentFailed {
+        payment_hash: hex::decode(&n.data.payment_hash)?,
+        destination: destination.map(hex::decode).transpose()?,
+        amount_msat: outcome.amount_msat.unwrap_or(n.data.amount_msat),
+        message: n.message.clone(),
+    }))
+}
+
+/// Build a [`pb::ChannelStateChanged`] from a `channel_state_changed`
+/// notification.
+pub(crate) fn channel_state_changed(
+    n: &messages::ChannelStateChangedNotification,
+) -> Result<pb::ChannelStateChanged> {
+    Ok(pb::ChannelStateChanged {
+        peer_id: hex::decode(&n.peer_id)?,
+        channel_id: hex::decode(&n.channel_id)?,
+        short_channel_id: n.short_channel_id.clone(),
+        old_state: n.old_state.clone(),
+        new_state: n.new_state.clone(),
+        cause: n.cause.clone(),
+        message: n.message.clone(),
+    })
+}
+
+/// Build a [`pb::DepositConfirmed`] from a `coin_movement`
+/// notification, if it is a confirmed deposit to the wallet.
+pub(crate) fn deposit_confirmed(m: &messages::CoinMovement) -> Result<Option<pb::DepositConfirmed>> {
+    let is_deposit = m.primary_tag.as_deref() == Some("deposit")
+        || m.tags.iter().any(|t| t == "deposit");
+    if m.typ != "chain_mvt" || m.account_id != "wallet" || !is_deposit || m.credit_msat == 0 {
+        return Ok(None);
+    }
+    let blockheight = match m.blockheight {
+        Some(h) if h > 0 => h,
+        _ => return Ok(None),
+    };
+
+    let (txid, outnum) = match (&m.utxo, &m.utxo_txid, m.vout) {
+        (Some(utxo), _, _) => {
+            let (txid, vout) = utxo
+                .split_once(':')
+                .ok_or_else(|| anyhow!("malformed utxo {}", utxo))?;
+            (txid.to_string(), vout.parse()?)
+        }
+        (None, Some(txid), Some(vout)) => (txid.clone(), vout),
+        _ => return Err(anyhow!("coin movement is missing its utxo")),
+    };
+
+    Ok(Some(pb::DepositConfirmed {
+        txid: hex::decode(txid)?,
+        outnum,
+        amount_msat: m.credit_msat,
+        blockheight,
+    }))
+}
+
+/// Remembers the most recent payments that were reported, so that
+/// multi-part payments, which trigger one notification per part, are
+/// only reported once.
+#[derive(Debug, Default)]
+pub(crate) struct SeenPayments {
+    order: VecDeque<(String, u64)>,
+    seen: HashSet<(String, u64)>,
+}
+
+impl SeenPayments {
+    /// Parts of a payment complete within moments of each other, so a
+    /// short history is sufficient.
+    const CAPACITY: usize = 1024;
+
+    /// Returns `true` the first time the payment group is seen.
+    pub(crate) fn insert(&mut self, payment_hash: &str, groupid: u64) -> bool {
+        let key = (payment_hash.to_string(), groupid);
+        if !self.seen.insert(key.clone()) {
+            return false;
+        }
+        self.order.push_back(key);
+        if self.order.len() > Self::CAPACITY {
+            if let Some(old) = self.order.pop_front() {
+                self.seen.remove(&old);
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    const HASH: &str = "5c85bf402b87d4860f4a728e2e58a2418bda92cd7aea0ce494f11670cfbfb206";
+
+    #[test]
+    fn test_params_unwraps_notification() {
+        let wrapped = json!({"disconnect": {"id": "02aa"}});
+        assert_eq!(params("disconnect", wrapped), json!({"id": "02aa"}));
+        let unwrapped = json!({"id": "02aa"});
+        assert_eq!(params("disconnect", unwrapped.clone()), unwrapped);
+    }
+
+    #[test]
+    fn test_payment_failed_only_reports_failed_payments() {
+        let n: messages::SendpayFailure = serde_json::from_value(json!({
+            "code": 204,
+            "message": "failed: WIRE_TEMPORARY_CHANNEL_FAILURE",
+            "data": {"payment_hash": HASH, "amount_msat": 1000}
+        }))
+        .unwrap();
+        let mut outcome = PayOutcome::default();
+        assert!(payment_failed(&n, &outcome).unwrap().is_none());
+
+        outcome.failed = true;
+        let failed = payment_failed(&n, &outcome).unwrap().unwrap();
+        assert_eq!(failed.amount_msat, 1000);
+        assert_eq!(failed.destination, None);
+
+        // The totals of the payment take precedence over the part.
+        outcome.amount_msat = Some(3000);
+        outcome.destination = Some("02aa".to_string());
+        let failed = payment_failed(&n, &outcome).unwrap().unwrap();
+        assert_eq!(failed.amount_msat, 3000);
+        assert_eq!(failed.destination, Some(vec![0x02, 0xaa]));
+    }
+
+    #[test]
+    fn test_deposit_confirmed() {
+        let mut m: messages::CoinMovement = serde_json::from_value(json!({
+            "type": "chain_mvt",
+            "account_id": "wallet",
+            "primary_tag": "deposit",
+            "utxo": format!("{}:1", HASH),
+            "credit_msat": 2000,
+            "blockheight": 102
+        }))
+        .unwrap();
+        let deposit = deposit_confirmed(&m).unwrap().unwrap();
+        assert_eq!(hex::encode(deposit.txid), HASH);
+        assert_eq!(deposit.outnum, 1);
+        assert_eq!(deposit.amount_msat, 2000);
+
+        // Withdrawals and unconfirmed outputs are not deposits.
+        m.blockheight = None;
+        assert!(deposit_confirmed(&m).unwrap().is_none());
+        m.blockheight = Some(102);
+        m.primary_tag = Some("withdrawal".to_string());
+        assert!(deposit_confirmed(&m).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_seen_payments() {
+        let mut seen = SeenPayments::default();
+        assert!(seen.insert(HASH, 1));
+        assert!(!seen.insert(HASH, 1));
+        assert!(seen.insert(HASH, 2));
+
+        for i in 0..SeenPayments::CAPACITY as u64 {
+            seen.insert("other", i);
+        }
+        assert!(seen.insert(HASH, 1));
+    }
+}
@@ -2,21 +2,196 @@
 /// pull to push. Used by `hsmproxy` to stage requests that can then
 /// asynchronously be retrieved and processed by one or more client
 /// devices.
+use crate::metrics;
 use crate::pb;
 use anyhow::{anyhow, Error};
 use log::{debug, trace, warn};
 use std::collections;
+use std::str::FromStr;
 use std::sync::{
     atomic::{AtomicUsize, Ordering},
     Arc,
 };
+use std::time::Duration;
 use tokio::sync::{broadcast, mpsc, Mutex};
 
+/// How long a backup signer's response is held back while waiting for
+/// the primary, unless configured otherwise.
+pub const DEFAULT_FAILOVER: Duration = Duration::from_secs(10);
+
+/// How long channel-critical requests wait for a signer before a
+/// [`pb::SignerStuck`] alert is raised, unless configured otherwise.
+pub const DEFAULT_CHANNEL_CRITICAL_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Request types that block channel progress until a signer responds.
+pub const CHANNEL_CRITICAL_TYPES: &[u16] = &[
+    5,   // WIRE_HSMD_SIGN_COMMITMENT_TX
+    12,  // WIRE_HSMD_SIGN_DELAYED_PAYMENT_TO_US
+    13,  // WIRE_HSMD_SIGN_REMOTE_HTLC_TO_US
+    14,  // WIRE_HSMD_SIGN_PENALTY_TO_US
+    18,  // WIRE_HSMD_GET_PER_COMMITMENT_POINT (onchaind key derivation)
+    20,  // WIRE_HSMD_SIGN_REMOTE_HTLC_TX
+    21,  // WIRE_HSMD_SIGN_MUTUAL_CLOSE_TX
+    28,  // WIRE_HSMD_CHECK_PUBKEY
+    142, // WIRE_HSMD_SIGN_ANY_DELAYED_PAYMENT_TO_US
+    143, // WIRE_HSMD_SIGN_ANY_REMOTE_HTLC_TO_US
+    144, // WIRE_HSMD_SIGN_ANY_PENALTY_TO_US
+    146, // WIRE_HSMD_SIGN_ANY_LOCAL_HTLC_TX
+    147, // WIRE_HSMD_SIGN_ANCHORSPEND
+    149, // WIRE_HSMD_SIGN_HTLC_TX_MINGLE
+];
+
+fn request_type(request: &pb::HsmRequest) -> Option<u16> {
+    match request.raw.len() {
+        0 | 1 => None,
+        _ => Some(request.get_type()),
+    }
+}
+
+/// How long requests may wait for a signer to respond. Once a request
+/// times out a [`pb::SignerStuck`] alert is published. Channel-critical
+/// requests keep waiting, since failing them would not let the channel
+/// progress either, while other requests fail.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RequestTimeouts {
+    /// Timeout of request types without a specific timeout.
+    pub default: Option<Duration>,
+    /// Timeouts by request type.
+    pub per_type: collections::HashMap<u16, Duration>,
+}
+
+impl Default for RequestTimeouts {
+    /// Only channel-critical requests time out, after
+    /// [`DEFAULT_CHANNEL_CRITICAL_TIMEOUT`].
+    fn default() -> Self {
+        RequestTimeouts {
+            default: None,
+            per_type: CHANNEL_CRITICAL_TYPES
+                .iter()
+                .map(|t| (*t, DEFAULT_CHANNEL_CRITICAL_TIMEOUT))
+                .collect(),
+        }
+    }
+}
+
+impl RequestTimeouts {
+    pub fn timeout(&self, request_type: u16) -> Option<Duration> {
+        self.per_type.get(&request_type).copied().or(self.default)
+    }
+}
+
+impl FromStr for RequestTimeouts {
+    type Err = Error;
+
+    /// Parses a comma separated list of `<type>=<secs>` and
+    /// `default=<secs>` entries, applied on top of the defaults.
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let mut timeouts = RequestTimeouts::default();
+        for entry in s.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (typ, secs) = entry
+                .split_once('=')
+                .ok_or_else(|| anyhow!("expected <type>=<secs>, got {:?}", entry))?;
+            let timeout = Duration::from_secs(secs.parse()?);
+            match typ {
+                "default" => timeouts.default = Some(timeout),
+                typ => {
+                    timeouts.per_type.insert(typ.parse()?, timeout);
+                }
+            }
+        }
+        Ok(timeouts)
+    }
+}
+
+/// How the responses of multiple signers attached to the same node
+/// are combined into the response returned to `hsmd`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum QuorumMode {
+    /// The first response is used, later ones are only recorded.
+    #[default]
+    FirstWins,
+    /// Every signer attached when the request was staged has to return
+    /// the same response. If they disagree, or one of them detaches
+    /// before responding, the request fails.
+    RequireAllAgree,
+    /// The response of `primary` is used, including an error. Responses
+    /// of other signers are only used once `primary` did not respond
+    /// within `failover`, or detached.
+    PrimaryBackup { primary: String, failover: Duration },
+}
+
+impl FromStr for QuorumMode {
+    type Err = Error;
+
+    /// Parses `first-wins`, `all-agree` or `primary:<id>[:<secs>]`.
+    fn from_str(s: &str) -> Result<Self, Error> {
+        match s {
+            "first-wins" => return Ok(QuorumMode::FirstWins),
+            "all-agree" => return Ok(QuorumMode::RequireAllAgree),
+            _ => {}
+        }
+        let primary = s
+            .strip_prefix("primary:")
+            .ok_or_else(|| anyhow!("unknown quorum mode {:?}", s))?;
+        let (primary, failover) = match primary.rsplit_once(':') {
+            Some((id, secs)) => (id, Duration::from_secs(secs.parse()?)),
+            None => (primary, DEFAULT_FAILOVER),
+        };
+        if primary.is_empty() {
+            return Err(anyhow!("missing primary signer id in {:?}", s));
+        }
+        Ok(QuorumMode::PrimaryBackup {
+            primary: primary.to_string(),
+            failover,
+        })
+    }
+}
+
+/// Response statistics of a single signer.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SignerStats {
+    /// Number of responses received from the signer.
+    pub responses: u64,
+    /// Number of responses that were returned to `hsmd`.
+    pub used: u64,
+    /// Number of responses that differed from another signer's.
+    pub disagreements: u64,
+    pub last_latency: Duration,
+    pub max_latency: Duration,
+    pub total_latency: Duration,
+}
+
+impl SignerStats {
+    pub fn mean_latency(&self) -> Duration {
+        match self.responses {
+            0 => Duration::ZERO,
+            n => self.total_latency / n as u32,
+        }
+    }
+
+    fn observe(&mut self, latency: Duration) {
+        self.responses += 1;
+        self.last_latency = latency;
+        self.max_latency = self.max_latency.max(latency);
+        self.total_latency += latency;
+    }
+}
+
+type Stats = Arc<std::sync::Mutex<collections::HashMap<String, SignerStats>>>;
+
+/// The number of request streams of each attached signer.
+type Signers = Arc<std::sync::Mutex<collections::HashMap<String, usize>>>;
+
 #[derive(Debug)]
 pub struct Stage {
-    requests: Mutex<collections::HashMap<u32, Request>>,
+    requests: Arc<Mutex<Pending>>,
     notify: broadcast::Sender<Request>,
     hsm_connections: Arc<AtomicUsize>,
+    signers: Signers,
+    quorum: QuorumMode,
+    stats: Stats,
+    timeouts: RequestTimeouts,
+    stuck: broadcast::Sender<pb::SignerStuck>,
 }
 
 #[derive(Clone, Debug)]
@@ -26,21 +201,131 @@ pub struct Request {
     pub start_time: tokio::time::Instant,
 }
 
+/// A request waiting for its response, and the responses that were
+/// held back so far.
+#[derive(Debug)]
+struct Entry {
+    request: Request,
+    /// The signers attached when the request was staged.
+    expected: Vec<String>,
+    responses: Vec<(String, pb::HsmResponse)>,
+}
+
+#[derive(Debug, Default)]
+struct Pending {
+    requests: collections::HashMap<u32, Entry>,
+    /// Start times of recently answered requests, so the latency of
+    /// signers responding late is still recorded.
+    answered: collections::VecDeque<(u32, tokio::time::Instant)>,
+}
+
+impl Pending {
+    /// Requests are answered within seconds, so a short history is
+    /// sufficient.
+    const ANSWERED_CAPACITY: usize = 1024;
+
+    fn pending_request(entry: &Entry, timeouts: &RequestTimeouts) -> pb::PendingHsmRequest {
+        let request_type = request_type(&entry.request.request);
+        pb::PendingHsmRequest {
+            request_id: entry.request.request.request_id,
+            request_type: request_type.unwrap_or_default().into(),
+            age_ms: entry.request.start_time.elapsed().as_millis() as u64,
+            channel_critical: request_type
+                .map(|t| CHANNEL_CRITICAL_TYPES.contains(&t))
+                .unwrap_or(false),
+            timeout_ms: request_type
+                .and_then(|t| timeouts.timeout(t))
+                .map(|t| t.as_millis() as u64),
+        }
+    }
+
+    fn start_time(&self, request_id: u32) -> Option<tokio::time::Instant> {
+        match self.requests.get(&request_id) {
+            Some(e) => Some(e.request.start_time),
+            None => self
+                .answered
+                .iter()
+                .find(|(id, _)| *id == request_id)
+                .map(|(_, t)| *t),
+        }
+    }
+
+    /// Remove the request and return `response` to its requester.
+    async fn deliver(
+        &mut self,
+        stats: &Stats,
+        signer_id: Option<&str>,
+        response: pb::HsmResponse,
+    ) -> Result<(), Error> {
+        let entry = match self.requests.remove(&response.request_id) {
+            Some(e) => e,
+            None => return Ok(()),
+        };
+        metrics::PENDING_REQUESTS.set(&[], self.requests.len() as f64);
+        self.answered
+            .push_back((response.request_id, entry.request.start_time));
+        if self.answered.len() > Self::ANSWERED_CAPACITY {
+            self.answered.pop_front();
+        }
+        if let Some(signer_id) = signer_id {
+            stats
+                .lock()
+                .unwrap()
+                .entry(signer_id.to_string())
+                .or_default()
+                .used += 1;
+            metrics::SIGNER_RESPONSES_USED.inc(&[("signer", signer_id)]);
+        }
+        debug!(
+            "Response for request_id={}, signer={:?}, signer_rtt={}s, outstanding requests count={}",
+            response.request_id,
+            signer_id,
+            entry.request.start_time.elapsed().as_secs_f64(),
+            self.requests.len()
+        );
+        if let Err(e) = entry.request.response.send(response).await {
+            Err(anyhow!("Error sending request to requester: {:?}", e))
+        } else {
+            Ok(())
+        }
+    }
+}
+
 impl Stage {
     pub fn new() -> Self {
+        Self::with_quorum(QuorumMode::default())
+    }
+
+    pub fn with_quorum(quorum: QuorumMode) -> Self {
         let (notify, _) = broadcast::channel(1000);
         Stage {
-            requests: Mutex::new(collections::HashMap::new()),
+            requests: Arc::new(Mutex::new(Pending::default())),
             notify: notify,
             hsm_connections: Arc::new(AtomicUsize::new(0)),
+            signers: Arc::default(),
+            quorum,
+            stats: Arc::default(),
+            timeouts: RequestTimeouts::default(),
+            stuck: broadcast::channel(16).0,
         }
     }
 
+    pub fn with_timeouts(mut self, timeouts: RequestTimeouts) -> Self {
+        self.timeouts = timeouts;
+        self
+    }
+
+    /// Subscribe to the alerts about requests that timed out waiting
+    /// for a signer.
+    pub fn subscribe_stuck(&self) -> broadcast::Receiver<pb::SignerStuck> {
+        self.stuck.subscribe()
+    }
+
     pub async fn send(
         &self,
         request: pb::HsmRequest,
     ) -> Result<mpsc::Receiver<pb::HsmResponse>, Error> {
-        let mut requests = self.requests.lock().await;
+        let mut pending = self.requests.lock().await;
         let (response, receiver): (
             mpsc::Sender<pb::HsmResponse>,
             mpsc::Receiver<pb::HsmResponse>,
@@ -52,7 +337,20 @@ impl Stage {
             start_time: tokio::time::Instant::now(),
         };
 
-        requests.insert(r.request.request_id, r.clone());
+        let expected = self.signers.lock().unwrap().keys().cloned().collect();
+        pending.requests.insert(
+            r.request.request_id,
+            Entry {
+                request: r.clone(),
+                expected,
+                responses: vec![],
+            },
+        );
+        metrics::PENDING_REQUESTS.set(&[], pending.requests.len() as f64);
+
+        if let Some(timeout) = request_type(&r.request).and_then(|t| self.timeouts.timeout(t)) {
+            self.watch(r.request.request_id, r.start_time, timeout);
+        }
 
         if let Err(_) = self.notify.send(r) {
             warn!("Error notifying hsmd request stream, likely lost connection.");
@@ -61,75 +359,266 @@ impl Stage {
         Ok(receiver)
     }
 
-    pub async fn mystream(&self) -> StageStream {
-        let requests = self.requests.lock().await;
+    /// Raise an alert if the request is still pending after `timeout`,
+    /// and fail it unless it is channel-critical.
+    fn watch(&self, request_id: u32, start_time: tokio::time::Instant, timeout: Duration) {
+        let requests = self.requests.clone();
+        let stats = self.stats.clone();
+        let timeouts = self.timeouts.clone();
+        let stuck = self.stuck.clone();
+        let hsm_connections = self.hsm_connections.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep_until(start_time + timeout).await;
+            let mut pending = requests.lock().await;
+            let request = match pending.requests.get(&request_id) {
+                // The request id may have been reused since.
+                Some(e) if e.request.start_time == start_time => {
+                    Pending::pending_request(e, &timeouts)
+                }
+                _ => return,
+            };
+            let connected_signers = hsm_connections.load(Ordering::Relaxed) as u32;
+            warn!(
+                "No signer responded to request {} of type {} within {:?}, {} signers connected",
+                request_id, request.request_type, timeout, connected_signers
+            );
+            let critical_label = request.channel_critical.to_string();
+            metrics::STUCK_REQUESTS.inc(&[("channel_critical", &critical_label)]);
+            let channel_critical = request.channel_critical;
+            // Nobody listening is not an error, the request is still
+            // logged above.
+            let _ = stuck.send(pb::SignerStuck {
+                request: Some(request),
+                connected_signers,
+            });
+            if channel_critical {
+                return;
+            }
+            let response = pb::HsmResponse {
+                request_id,
+                error: format!("no signer responded within {:?}", timeout),
+                ..Default::default()
+            };
+            if let Err(e) = pending.deliver(&stats, None, response).await {
+                warn!("Error failing timed out request: {}", e);
+            }
+        });
+    }
+
+    /// The requests waiting for a signer to respond, oldest first.
+    pub async fn pending_requests(&self) -> Vec<pb::PendingHsmRequest> {
+        let pending = self.requests.lock().await;
+        let mut requests: Vec<pb::PendingHsmRequest> = pending
+            .requests
+            .values()
+            .map(|e| Pending::pending_request(e, &self.timeouts))
+            .collect();
+        requests.sort_by_key(|r| std::cmp::Reverse(r.age_ms));
+        requests
+    }
+
+    /// The number of signers currently streaming requests.
+    pub fn connected_signers(&self) -> usize {
+        self.hsm_connections.load(Ordering::Relaxed)
+    }
+
+    /// Whether a signer with this id is streaming requests.
+    fn is_attached(&self, signer_id: &str) -> bool {
+        self.signers.lock().unwrap().contains_key(signer_id)
+    }
+
+    /// Attach the signer identified by `signer_id` and stream the
+    /// requests to it, starting with the ones already pending.
+    /// Requests staged while no signer was attached expect a response
+    /// from it.
+    pub async fn mystream(&self, signer_id: &str) -> StageStream {
+        let mut pending = self.requests.lock().await;
         self.hsm_connections.fetch_add(1, Ordering::Relaxed);
+        *self
+            .signers
+            .lock()
+            .unwrap()
+            .entry(signer_id.to_string())
+            .or_default() += 1;
+        for entry in pending.requests.values_mut() {
+            if entry.expected.is_empty() {
+                entry.expected.push(signer_id.to_string());
+            }
+        }
         StageStream {
-            backlog: requests.values().map(|e| e.clone()).collect(),
+            backlog: pending
+                .requests
+                .values()
+                .map(|e| e.request.clone())
+                .collect(),
             bcast: self.notify.subscribe(),
             hsm_connections: self.hsm_connections.clone(),
+            detach: Some(Detach {
+                signer_id: signer_id.to_string(),
+                signers: self.signers.clone(),
+                requests: self.requests.clone(),
+                stats: self.stats.clone(),
+                quorum: self.quorum.clone(),
+            }),
         }
     }
 
-    pub async fn respond(&self, response: pb::HsmResponse) -> Result<(), Error> {
-        let mut requests = self.requests.lock().await;
-        match requests.remove(&response.request_id) {
-            Some(req) => {
-                debug!(
-                    "Response for request_id={}, signer_rtt={}s, outstanding requests count={}",
-                    response.request_id,
-                    req.start_time.elapsed().as_secs_f64(),
-                    requests.len()
-                );
-                if let Err(e) = req.response.send(response).await {
-                    Err(anyhow!("Error sending request to requester: {:?}", e))
-                } else {
-                    Ok(())
-                }
-            }
+    /// Handle the response of the signer identified by `signer_id`.
+    /// Depending on the [`QuorumMode`] the response is returned to
+    /// the requester right away, held back, or only recorded.
+    pub async fn respond(&self, signer_id: &str, response: pb::HsmResponse) -> Result<(), Error> {
+        let mut pending = self.requests.lock().await;
+        if let Some(start_time) = pending.start_time(response.request_id) {
+            metrics::SIGNER_RTT
+                .observe(&[("signer", signer_id)], start_time.elapsed().as_secs_f64());
+            self.stats
+                .lock()
+                .unwrap()
+                .entry(signer_id.to_string())
+                .or_default()
+                .observe(start_time.elapsed());
+        }
+
+        let entry = match pending.requests.get_mut(&response.request_id) {
+            Some(e) => e,
             None => {
                 trace!(
                     "Request {} not found, is this a duplicate result?",
                     response.request_id
                 );
+                return Ok(());
+            }
+        };
+
+        // Only a signer that failed to produce a response reports an
+        // error. Unless all signers have to agree, or it is the
+        // primary's final answer, somebody else may still respond.
+        if !response.error.is_empty() {
+            let is_final = match &self.quorum {
+                QuorumMode::FirstWins => false,
+                QuorumMode::RequireAllAgree => true,
+                QuorumMode::PrimaryBackup { primary, .. } => signer_id == primary,
+            };
+            if !is_final {
+                return Ok(());
+            }
+        }
+
+        match &self.quorum {
+            QuorumMode::FirstWins => {
+                pending
+                    .deliver(&self.stats, Some(signer_id), response)
+                    .await
+            }
+            QuorumMode::RequireAllAgree => {
+                entry.responses.retain(|(id, _)| id != signer_id);
+                entry.responses.push((signer_id.to_string(), response));
+                let waiting = entry
+                    .expected
+                    .iter()
+                    .any(|id| !entry.responses.iter().any(|(r, _)| r == id));
+                if waiting {
+                    return Ok(());
+                }
+                let responses = std::mem::take(&mut entry.responses);
+                let (first_id, first) = &responses[0];
+                let disagreeing: Vec<&str> = responses
+                    .iter()
+                    .filter(|(_, r)| r.raw != first.raw)
+                    .map(|(id, _)| id.as_str())
+                    .collect();
+                if disagreeing.is_empty() {
+                    let (id, response) = responses.into_iter().next().unwrap();
+                    return pending.deliver(&self.stats, Some(&id), response).await;
+                }
+
+                warn!(
+                    "Signers {:?} disagree with {} on request {}",
+                    disagreeing, first_id, first.request_id
+                );
+                {
+                    let mut stats = self.stats.lock().unwrap();
+                    for (id, _) in responses.iter() {
+                        stats.entry(id.clone()).or_default().disagreements += 1;
+                        metrics::SIGNER_DISAGREEMENTS.inc(&[("signer", id)]);
+                    }
+                }
+                let response = pb::HsmResponse {
+                    request_id: first.request_id,
+                    error: format!(
+                        "signers {:?} disagree with {} on the response",
+                        disagreeing, first_id
+                    ),
+                    ..Default::default()
+                };
+                pending.deliver(&self.stats, None, response).await
+            }
+            QuorumMode::PrimaryBackup { primary, failover } => {
+                let elapsed = entry.request.start_time.elapsed();
+                if signer_id == primary {
+                    return pending
+                        .deliver(&self.stats, Some(signer_id), response)
+                        .await;
+                } else if elapsed >= *failover
+                    || (entry.expected.contains(primary) && !self.is_attached(primary))
+                {
+                    warn!(
+                        "Primary signer {} did not respond to request {} within {:?} or detached, using response of {}",
+                        primary, response.request_id, failover, signer_id
+                    );
+                    return pending
+                        .deliver(&self.stats, Some(signer_id), response)
+                        .await;
+                }
+
+                // Hold the response back, and use it if the primary
+                // does not respond in time.
+                let request_id = response.request_id;
+                let first_held = entry.responses.is_empty();
+                entry.responses.push((signer_id.to_string(), response));
+                if first_held {
+                    let requests = self.requests.clone();
+                    let stats = self.stats.clone();
+                    let primary = primary.clone();
+                    let failover = *failover;
+                    tokio::spawn(async move {
+                        tokio::time::sleep(failover - elapsed).await;
+                        let mut pending = requests.lock().await;
+                        let held = match pending.requests.get_mut(&request_id) {
+                            Some(e) if !e.responses.is_empty() => e.responses.remove(0),
+                            _ => return,
+                        };
+                        warn!(
+                            "Primary signer {} did not respond to request {} within {:?}, using response of {}",
+                            primary, request_id, failover, held.0
+                        );
+                        if let Err(e) = pending.deliver(&stats, Some(&held.0), held.1).await {
+                            warn!("Error delivering backup response: {}", e);
+                        }
+                    });
+                }
                 Ok(())
             }
         }
     }
 
+    /// Response statistics of the signers seen so far, keyed by their
+    /// signer id.
+    pub fn signer_stats(&self) -> collections::HashMap<String, SignerStats> {
+        self.stats.lock().unwrap().clone()
+    }
+
     /// Returns the HSM request types currently queued that block node progress.
     /// An empty vec means the node is not stuck.
     pub async fn stuck_request_types(&self) -> Vec<u16> {
-        let sticky_types: Vec<u16> = vec![
-            5,   // WIRE_HSMD_SIGN_COMMITMENT_TX
-            12,  // WIRE_HSMD_SIGN_DELAYED_PAYMENT_TO_US
-            13,  // WIRE_HSMD_SIGN_REMOTE_HTLC_TO_US
-            14,  // WIRE_HSMD_SIGN_PENALTY_TO_US
-            18,  // WIRE_HSMD_GET_PER_COMMITMENT_POINT (onchaind key derivation)
-            20,  // WIRE_HSMD_SIGN_REMOTE_HTLC_TX
-            21,  // WIRE_HSMD_SIGN_MUTUAL_CLOSE_TX
-            28,  // WIRE_HSMD_CHECK_PUBKEY
-            142, // WIRE_HSMD_SIGN_ANY_DELAYED_PAYMENT_TO_US
-            143, // WIRE_HSMD_SIGN_ANY_REMOTE_HTLC_TO_US
-            144, // WIRE_HSMD_SIGN_ANY_PENALTY_TO_US
-            146, // WIRE_HSMD_SIGN_ANY_LOCAL_HTLC_TX
-            147, // WIRE_HSMD_SIGN_ANCHORSPEND
-            149, // WIRE_HSMD_SIGN_HTLC_TX_MINGLE
-        ];
         let types: Vec<u16> = self
             .requests
             .lock()
             .await
+            .requests
             .values()
-            .filter_map(|r| {
-                let head: [u16; 2] = [r.request.raw[0].into(), r.request.raw[1].into()];
-                let typ = head[0] << 8 | head[1];
-                if sticky_types.contains(&typ) {
-                    Some(typ)
-                } else {
-                    None
-                }
-            })
+            .filter_map(|e| request_type(&e.request.request))
+            .filter(|t| CHANNEL_CRITICAL_TYPES.contains(t))
             .collect();
 
         trace!("Found stuck request types: {:?}", types);
@@ -145,6 +634,74 @@ pub struct StageStream {
     backlog: Vec<Request>,
     bcast: broadcast::Receiver<Request>,
     hsm_connections: Arc<AtomicUsize>,
+    detach: Option<Detach>,
+}
+
+/// Updates the pending requests once the last stream of a signer is
+/// dropped.
+struct Detach {
+    signer_id: String,
+    signers: Signers,
+    requests: Arc<Mutex<Pending>>,
+    stats: Stats,
+    quorum: QuorumMode,
+}
+
+impl Detach {
+    async fn run(self) {
+        let mut pending = self.requests.lock().await;
+        // The signer may have attached again in the meantime.
+        if self.signers.lock().unwrap().contains_key(&self.signer_id) {
+            return;
+        }
+        let affected: Vec<u32> = pending
+            .requests
+            .iter()
+            .filter(|(_, e)| match &self.quorum {
+                QuorumMode::FirstWins => false,
+                QuorumMode::RequireAllAgree => {
+                    e.expected.contains(&self.signer_id)
+                        && !e.responses.iter().any(|(id, _)| *id == self.signer_id)
+                }
+                QuorumMode::PrimaryBackup { primary, .. } => {
+                    *primary == self.signer_id && !e.responses.is_empty()
+                }
+            })
+            .map(|(id, _)| *id)
+            .collect();
+
+        for request_id in affected {
+            let (signer_id, response) = match &self.quorum {
+                QuorumMode::PrimaryBackup { .. } => {
+                    let entry = pending.requests.get_mut(&request_id).unwrap();
+                    let (id, response) = entry.responses.remove(0);
+                    warn!(
+                        "Primary signer {} detached, using response of {} for request {}",
+                        self.signer_id, id, request_id
+                    );
+                    (Some(id), response)
+                }
+                _ => {
+                    warn!(
+                        "Signer {} detached before responding to request {}",
+                        self.signer_id, request_id
+                    );
+                    let response = pb::HsmResponse {
+                        request_id,
+                        error: format!("signer {} detached before responding", self.signer_id),
+                        ..Default::default()
+                    };
+                    (None, response)
+                }
+            };
+            if let Err(e) = pending
+                .deliver(&self.stats, signer_id.as_deref(), response)
+                .await
+            {
+                warn!("Error delivering response after signer detached: {}", e);
+            }
+        }
+    }
 }
 
 impl StageStream {
@@ -165,6 +722,27 @@ impl StageStream {
 impl Drop for StageStream {
     fn drop(&mut self) {
         self.hsm_connections.fetch_sub(1, Ordering::Relaxed);
+        let detach = match self.detach.take() {
+            Some(d) => d,
+            None => return,
+        };
+        {
+            let mut signers = detach.signers.lock().unwrap();
+            match signers.get_mut(&detach.signer_id) {
+                Some(n) if *n > 1 => {
+                    *n -= 1;
+                    return;
+                }
+                _ => {
+                    signers.remove(&detach.signer_id);
+                }
+            }
+        }
+        // Without a runtime there is nobody left waiting for a
+        // response either.
+        if let Ok(handle) = tokio::runtime::Handle::try_current() {
+            handle.spawn(detach.run());
+        }
     }
 }
 
@@ -195,8 +773,8 @@ mod test {
             );
         }
 
-        let mut s1 = stage.mystream().await;
-        let mut s2 = stage.mystream().await;
+        let mut s1 = stage.mystream("1").await;
+        let mut s2 = stage.mystream("2").await;
         let f1 = tokio::spawn(async move {
             while let Ok(r) = s1.next().await {
                 eprintln!("hsmd {} is handling request {}", 1, r.request.request_id);
@@ -207,6 +785,7 @@ mod test {
                         raw: vec![],
                         signer_state: vec![],
                         error: "".into(),
+                        signer_id: "".into(),
                     })
                     .await
                 {
@@ -226,6 +805,7 @@ mod test {
                         raw: vec![],
                         signer_state: vec![],
                         error: "".into(),
+                        signer_id: "".into(),
                     })
                     .await
                 {
@@ -260,4 +840,240 @@ mod test {
         f1.await.unwrap();
         f2.await.unwrap();
     }
+
+    fn request(request_id: u32) -> pb::HsmRequest {
+        pb::HsmRequest {
+            request_id,
+            ..Default::default()
+        }
+    }
+
+    fn response(request_id: u32, raw: &[u8]) -> pb::HsmResponse {
+        pb::HsmResponse {
+            request_id,
+            raw: raw.to_vec(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_quorum_mode() {
+        assert_eq!(
+            "first-wins".parse::<QuorumMode>().unwrap(),
+            QuorumMode::FirstWins
+        );
+        assert_eq!(
+            "all-agree".parse::<QuorumMode>().unwrap(),
+            QuorumMode::RequireAllAgree
+        );
+        assert_eq!(
+            "primary:phone".parse::<QuorumMode>().unwrap(),
+            QuorumMode::PrimaryBackup {
+                primary: "phone".to_string(),
+                failover: DEFAULT_FAILOVER,
+            }
+        );
+        assert_eq!(
+            "primary:phone:3".parse::<QuorumMode>().unwrap(),
+            QuorumMode::PrimaryBackup {
+                primary: "phone".to_string(),
+                failover: Duration::from_secs(3),
+            }
+        );
+        assert!("primary:".parse::<QuorumMode>().is_err());
+        assert!("primary:phone:soon".parse::<QuorumMode>().is_err());
+        assert!("majority".parse::<QuorumMode>().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_first_wins_records_all_signers() {
+        let stage = Stage::new();
+        let mut rx = stage.send(request(1)).await.unwrap();
+        stage.respond("a", response(1, &[1])).await.unwrap();
+        stage.respond("b", response(1, &[2])).await.unwrap();
+        assert_eq!(rx.recv().await.unwrap().raw, vec![1]);
+
+        let stats = stage.signer_stats();
+        assert_eq!(stats["a"].responses, 1);
+        assert_eq!(stats["a"].used, 1);
+        assert_eq!(stats["b"].responses, 1);
+        assert_eq!(stats["b"].used, 0);
+    }
+
+    #[tokio::test]
+    async fn test_require_all_agree() {
+        let stage = Stage::with_quorum(QuorumMode::RequireAllAgree);
+        let _s1 = stage.mystream("a").await;
+        let s2 = stage.mystream("b").await;
+
+        let mut rx = stage.send(request(1)).await.unwrap();
+        stage.respond("a", response(1, &[1])).await.unwrap();
+        assert!(rx.try_recv().is_err());
+        stage.respond("b", response(1, &[1])).await.unwrap();
+        assert_eq!(rx.recv().await.unwrap().raw, vec![1]);
+
+        let mut rx = stage.send(request(2)).await.unwrap();
+        stage.respond("a", response(2, &[1])).await.unwrap();
+        stage.respond("b", response(2, &[2])).await.unwrap();
+        let res = rx.recv().await.unwrap();
+        assert!(res.raw.is_empty());
+        assert!(res.error.contains("disagree"));
+        assert_eq!(stage.signer_stats()["b"].disagreements, 1);
+
+        // A signer attaching later is not waited for.
+        let mut rx = stage.send(request(3)).await.unwrap();
+        let _s3 = stage.mystream("c").await;
+        stage.respond("a", response(3, &[1])).await.unwrap();
+        stage.respond("b", response(3, &[1])).await.unwrap();
+        assert_eq!(rx.recv().await.unwrap().raw, vec![1]);
+
+        // A signer detaching before responding fails the request.
+        let mut rx = stage.send(request(4)).await.unwrap();
+        stage.respond("a", response(4, &[1])).await.unwrap();
+        stage.respond("c", response(4, &[1])).await.unwrap();
+        drop(s2);
+        let res = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(res.error.contains("signer b detached"));
+        assert_eq!(stage.connected_signers(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_primary_backup_failover() {
+        let stage = Stage::with_quorum(QuorumMode::PrimaryBackup {
+            primary: "primary".to_string(),
+            failover: Duration::from_millis(50),
+        });
+
+        // The primary's response is used even if the backup is faster.
+        let mut rx = stage.send(request(1)).await.unwrap();
+        stage.respond("backup", response(1, &[2])).await.unwrap();
+        stage.respond("primary", response(1, &[1])).await.unwrap();
+        assert_eq!(rx.recv().await.unwrap().raw, vec![1]);
+
+        // Without the primary the backup's response is used after
+        // the failover timeout.
+        let mut rx = stage.send(request(2)).await.unwrap();
+        stage.respond("backup", response(2, &[2])).await.unwrap();
+        assert!(rx.try_recv().is_err());
+        let res = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(res.raw, vec![2]);
+        assert_eq!(stage.signer_stats()["backup"].used, 1);
+    }
+
+    #[tokio::test]
+    async fn test_primary_backup_errors() {
+        let stage = Stage::with_quorum(QuorumMode::PrimaryBackup {
+            primary: "primary".to_string(),
+            failover: Duration::from_secs(60),
+        });
+        let failed = |request_id| pb::HsmResponse {
+            request_id,
+            error: "policy violation".to_string(),
+            ..Default::default()
+        };
+
+        // The primary's error is final.
+        let mut rx = stage.send(request(1)).await.unwrap();
+        stage.respond("backup", response(1, &[2])).await.unwrap();
+        stage.respond("primary", failed(1)).await.unwrap();
+        assert_eq!(rx.recv().await.unwrap().error, "policy violation");
+
+        // A backup's error is ignored.
+        let mut rx = stage.send(request(2)).await.unwrap();
+        stage.respond("backup", failed(2)).await.unwrap();
+        assert!(rx.try_recv().is_err());
+        stage.respond("primary", response(2, &[1])).await.unwrap();
+        assert_eq!(rx.recv().await.unwrap().raw, vec![1]);
+    }
+
+    #[tokio::test]
+    async fn test_primary_detach_fails_over() {
+        let stage = Stage::with_quorum(QuorumMode::PrimaryBackup {
+            primary: "primary".to_string(),
+            failover: Duration::from_secs(60),
+        });
+        let primary = stage.mystream("primary").await;
+        let _backup = stage.mystream("backup").await;
+
+        // The held response is used once the primary detaches.
+        let mut rx = stage.send(request(1)).await.unwrap();
+        stage.respond("backup", response(1, &[2])).await.unwrap();
+        assert!(rx.try_recv().is_err());
+        drop(primary);
+        let res = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(res.raw, vec![2]);
+
+        // A primary that was not attached may still attach and
+        // respond before the failover timeout.
+        let mut rx = stage.send(request(2)).await.unwrap();
+        stage.respond("backup", response(2, &[3])).await.unwrap();
+        assert!(rx.try_recv().is_err());
+    }
+
+    fn typed_request(request_id: u32, request_type: u16) -> pb::HsmRequest {
+        pb::HsmRequest {
+            request_id,
+            raw: request_type.to_be_bytes().to_vec(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_request_timeouts() {
+        let timeouts: RequestTimeouts = "default=30, 5=120,2=1".parse().unwrap();
+        assert_eq!(timeouts.timeout(5), Some(Duration::from_secs(120)));
+        assert_eq!(timeouts.timeout(2), Some(Duration::from_secs(1)));
+        assert_eq!(timeouts.timeout(21), Some(DEFAULT_CHANNEL_CRITICAL_TIMEOUT));
+        assert_eq!(timeouts.timeout(10), Some(Duration::from_secs(30)));
+
+        assert_eq!(RequestTimeouts::default().timeout(10), None);
+        assert!("5".parse::<RequestTimeouts>().is_err());
+        assert!("commitment=5".parse::<RequestTimeouts>().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_timed_out_requests() {
+        let stage = Stage::new().with_timeouts(RequestTimeouts {
+            default: Some(Duration::from_millis(20)),
+            per_type: collections::HashMap::new(),
+        });
+        let mut stuck = stage.subscribe_stuck();
+
+        // Channel-critical requests keep waiting for a signer.
+        let mut critical = stage.send(typed_request(1, 5)).await.unwrap();
+        let alert = stuck.recv().await.unwrap();
+        let request = alert.request.unwrap();
+        assert_eq!(request.request_id, 1);
+        assert_eq!(request.request_type, 5);
+        assert!(request.channel_critical);
+        assert_eq!(request.timeout_ms, Some(20));
+        assert_eq!(alert.connected_signers, 0);
+        assert!(critical.try_recv().is_err());
+        assert_eq!(stage.stuck_request_types().await, vec![5]);
+
+        // Other requests fail.
+        let mut rx = stage.send(typed_request(2, 10)).await.unwrap();
+        let alert = stuck.recv().await.unwrap();
+        assert!(!alert.request.unwrap().channel_critical);
+        let res = rx.recv().await.unwrap();
+        assert!(res.error.contains("no signer responded"));
+
+        let pending = stage.pending_requests().await;
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].request_id, 1);
+        assert!(pending[0].age_ms >= 20);
+
+        stage.respond("a", response(1, &[1])).await.unwrap();
+        assert_eq!(critical.recv().await.unwrap().raw, vec![1]);
+        assert!(stage.pending_requests().await.is_empty());
+    }
 }
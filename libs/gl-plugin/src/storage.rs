@@ -10,6 +10,8 @@ pub enum Error {
     /// underlying database error
     #[error("database error: {0}")]
     Sled(#[from] ::sled::Error),
+    #[error("database error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
     #[error("state corruption: {0}")]
     CorruptState(#[from] serde_json::Error),
     #[error("unhandled error: {0}")]
@@ -66,3 +68,216 @@ impl StateStore for SledStateStore {
             .map_err(|e| e.into())
     }
 }
+
+/// A StateStore that uses SQLite as its storage backend.
+///
+/// Each state entry is stored in its own row, and a write only
+/// touches the entries that changed since the last read or write, in
+/// a single transaction. This keeps writes small for nodes with many
+/// channels, where the serialized state grows to several megabytes.
+pub struct SqliteStateStore {
+    inner: std::sync::Arc<std::sync::Mutex<SqliteInner>>,
+}
+
+struct SqliteInner {
+    conn: rusqlite::Connection,
+    /// The state as it is stored in the database.
+    stored: State,
+}
+
+impl SqliteStateStore {
+    pub fn new(path: impl AsRef<std::path::Path>) -> Result<SqliteStateStore, Error> {
+        Self::from_connection(rusqlite::Connection::open(path)?)
+    }
+
+    pub fn from_connection(conn: rusqlite::Connection) -> Result<SqliteStateStore, Error> {
+        // Versions are stored as the bit pattern of the `u64` in a
+        // signed `INTEGER`, tombstones (`u64::MAX`) end up as -1.
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS signer_state (
+                key TEXT PRIMARY KEY NOT NULL,
+                version INTEGER NOT NULL,
+                value BLOB NOT NULL,
+                signature BLOB NOT NULL
+            )",
+            [],
+        )?;
+        let stored = Self::load(&conn)?;
+        Ok(Self {
+            inner: std::sync::Arc::new(std::sync::Mutex::new(SqliteInner { conn, stored })),
+        })
+    }
+
+    fn load(conn: &rusqlite::Connection) -> Result<State, Error> {
+        let mut stmt = conn.prepare("SELECT key, version, value, signature FROM signer_state")?;
+        let entries = stmt
+            .query_map([], |row| {
+                Ok(gl_client::pb::SignerStateEntry {
+                    key: row.get(0)?,
+                    version: row.get::<_, i64>(1)? as u64,
+                    value: row.get(2)?,
+                    signature: row.get(3)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        State::try_from(entries.as_slice()).map_err(|e| Error::Other(e.into()))
+    }
+
+    /// Run `f` on the database on the blocking thread pool, so the
+    /// database I/O does not stall the runtime.
+    async fn with_inner<T, F>(&self, f: F) -> Result<T, Error>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut SqliteInner) -> Result<T, Error> + Send + 'static,
+    {
+        let inner = self.inner.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut inner = inner
+                .lock()
+                .map_err(|e| Error::Other(e.to_string().into()))?;
+            f(&mut inner)
+        })
+        .await
+        .map_err(|e| Error::Other(Box::new(e)))?
+    }
+}
+
+#[async_trait]
+impl StateStore for SqliteStateStore {
+    async fn read(&self) -> Result<State, Error> {
+        self.with_inner(|inner| {
+            inner.stored = Self::load(&inner.conn)?;
+            if inner.stored.len() == 0 {
+                debug!("Initializing a new signer state");
+            }
+            Ok(inner.stored.clone())
+        })
+        .await
+    }
+
+    async fn write(&self, state: State) -> Result<(), Error> {
+        self.with_inner(move |inner| {
+            let SqliteInner { conn, stored } = inner;
+            let diff = stored.diff_state(&state);
+            if diff.len() == 0 {
+                return Ok(());
+            }
+
+            let entries: Vec<gl_client::pb::SignerStateEntry> = diff.clone().into();
+            let tx = conn.transaction()?;
+            for e in entries.iter() {
+                tx.execute(
+                    "INSERT INTO signer_state (key, version, value, signature)
+                     VALUES (?1, ?2, ?3, ?4)
+                     ON CONFLICT (key) DO UPDATE SET
+                       version = excluded.version,
+                       value = excluded.value,
+                       signature = excluded.signature",
+                    rusqlite::params![e.key, e.version as i64, e.value, e.signature],
+                )?;
+            }
+            tx.commit()?;
+            debug!("Stored {} changed signer state entries", entries.len());
+
+            stored.merge(&diff).map_err(|e| Error::Other(e.into()))?;
+            Ok(())
+        })
+        .await
+    }
+}
+
+/// Copy the state from one store into another, e.g., from a
+/// [`SledStateStore`] into a [`SqliteStateStore`]. Returns the number
+/// of entries copied.
+pub async fn migrate(from: &dyn StateStore, to: &dyn StateStore) -> Result<usize, Error> {
+    let state = from.read().await?;
+    let count = state.len();
+    to.write(state).await?;
+    Ok(count)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    fn state(entries: &[(&str, u64)]) -> State {
+        let values: serde_json::Map<String, serde_json::Value> = entries
+            .iter()
+            .map(|(k, v)| (k.to_string(), json!([v, {"v": v}])))
+            .collect();
+        serde_json::from_value(json!({ "values": values })).unwrap()
+    }
+
+    fn sqlite() -> SqliteStateStore {
+        SqliteStateStore::from_connection(rusqlite::Connection::open_in_memory().unwrap()).unwrap()
+    }
+
+    fn total_changes(store: &SqliteStateStore) -> i64 {
+        let inner = store.inner.lock().unwrap();
+        inner
+            .conn
+            .query_row("SELECT total_changes()", [], |r| r.get(0))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_roundtrip() {
+        let store = sqlite();
+        assert_eq!(store.read().await.unwrap().len(), 0);
+
+        let s = state(&[("nodes/a", 1), ("channels/b", u64::MAX)]);
+        store.write(s.clone()).await.unwrap();
+
+        let inner = std::sync::Arc::into_inner(store.inner)
+            .unwrap()
+            .into_inner()
+            .unwrap();
+        let reopened = SqliteStateStore::from_connection(inner.conn).unwrap();
+        let read = reopened.read().await.unwrap();
+        assert_eq!(read.len(), 2);
+        assert_eq!(read.diff_state(&s).len(), 0);
+        assert_eq!(s.diff_state(&read).len(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_writes_only_changed_entries() {
+        let store = sqlite();
+        store
+            .write(state(&[("nodes/a", 1), ("nodes/b", 1), ("nodes/c", 1)]))
+            .await
+            .unwrap();
+        let before = total_changes(&store);
+
+        // Writing the same state again is a no-op.
+        store
+            .write(state(&[("nodes/a", 1), ("nodes/b", 1), ("nodes/c", 1)]))
+            .await
+            .unwrap();
+        assert_eq!(total_changes(&store), before);
+
+        store
+            .write(state(&[("nodes/a", 1), ("nodes/b", 2), ("nodes/c", 1)]))
+            .await
+            .unwrap();
+        assert_eq!(total_changes(&store), before + 1);
+
+        let read = store.read().await.unwrap();
+        assert_eq!(read.diff_state(&state(&[("nodes/b", 2)])).len(), 0);
+        assert_eq!(read.diff_state(&state(&[("nodes/b", 3)])).len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_migrate_from_sled() {
+        let dir = tempfile::tempdir().unwrap();
+        let sled = SledStateStore::new(dir.path().join("signer_state")).unwrap();
+        let s = state(&[("nodes/a", 3), ("channels/b", 7)]);
+        sled.write(s.clone()).await.unwrap();
+
+        let store = sqlite();
+        assert_eq!(migrate(&sled, &store).await.unwrap(), 2);
+        let read = store.read().await.unwrap();
+        assert_eq!(read.diff_state(&s).len(), 0);
+        assert_eq!(s.diff_state(&read).len(), 0);
+    }
+}
@@ -4,7 +4,11 @@ use cln_rpc::{
     primitives::{Amount, PublicKey, ShortChannelId},
     ClnRpc, RpcError,
 };
-use futures::{future::join_all, FutureExt};
+use futures::{
+    future::{join_all, BoxFuture},
+    stream::{FuturesUnordered, StreamExt},
+    FutureExt,
+};
 use gl_util::error::{ClnRpcError, Error, ErrorCode, ErrorStatusConversionExt, RpcErrConversion};
 use log::{debug, warn};
 use serde::{Deserialize, Serialize};
@@ -19,13 +23,27 @@ const TRAMPOLINE_FEATURE_BIT: usize = 427;
 // BOLT#4 default value:
 // https://github.com/lightning/bolts/blob/master/04-onion-routing.md#max-htlc-cltv-selection
 const MAX_DELAY_DEFAULT: u32 = 2016;
-// The amount we overpay to allow the trampoline node to spend some fees.
+// BOLT#11 default `min_final_cltv_expiry_delta`, for invoices that
+// don't set one.
+const MIN_FINAL_CLTV_EXPIRY_DEFAULT: u32 = 18;
+// The most we overpay to allow the trampoline node to spend some fees,
+// unless `maxfeepercent` is set.
 const DEFAULT_OVERPAY_PERCENT: f32 = 0.5;
+// Failure code of a trampoline node that needs a higher fee to route
+// the payment (NODE|51). The failure data holds the fee it asks for.
+const TRAMPOLINE_FEE_INSUFFICIENT: u16 = 0x2000 | 51;
+// How long we start at the fee a trampoline node asked for last.
+const FEE_CACHE_TTL_SEC: u64 = 7 * 24 * 60 * 60;
 // Type used to address bolt11 in the onion payload.
 const TLV_BOLT11: u64 = 33001;
 // Type used to address the amount in msat in the onion payload, in case
 // that the bolt11 does not have an amount set.
 const TLV_AMT_MSAT: u64 = 33003;
+// Type used to tell a trampoline the part of the amount in msat it
+// forwards, when the payment is split across several trampolines. It
+// forwards its share once the parts it received add up to the share
+// and its fee, whatever the total of the parts says.
+const TLV_PARTIAL_MSAT: u64 = 33005;
 // Error Message that CLN returns on an unknown onion error. This is the
 // case when the trampoline server rejected with a custom error type.
 const PAY_UNPARSEABLE_ONION_MSG: &str = "Malformed error reply";
@@ -60,6 +78,7 @@ pub enum TrampolineErrorCode {
     PeerConnectionFailure,
     MissingPreimage,
     Internal,
+    FeeInsufficient,
     RpcError(ClnRpcError),
 }
 
@@ -72,7 +91,7 @@ impl core::fmt::Display for TrampolineErrorCode {
 /// Maps trampoline error codes to numeric values and back.
 ///
 /// Error code allocation:
-/// - 42701-42714: Reserved for trampoline-specific errors
+/// - 42701-42715: Reserved for trampoline-specific errors
 /// - Other ranges: CLN RPC error codes (see comments in from_code)
 impl ErrorCode for TrampolineErrorCode {
     fn code(&self) -> i32 {
@@ -91,6 +110,7 @@ impl ErrorCode for TrampolineErrorCode {
             Self::PeerConnectionFailure => 42712,
             Self::MissingPreimage => 42713,
             Self::Internal => 42714,
+            Self::FeeInsufficient => 42715,
             Self::RpcError(cln_err) => cln_err.code(),
         }
     }
@@ -111,6 +131,7 @@ impl ErrorCode for TrampolineErrorCode {
             42712 => Some(Self::PeerConnectionFailure),
             42713 => Some(Self::MissingPreimage),
             42714 => Some(Self::Internal),
+            42715 => Some(Self::FeeInsufficient),
             // Possible sendpay failure codes:
             // -1: Catchall nonspecific error.
             // 201: Already paid with this hash using different amount or destination.
@@ -150,7 +171,8 @@ impl ErrorStatusConversionExt for TrampolineErrorCode {
             | TrampolineErrorCode::MissingChannel
             | TrampolineErrorCode::InsufficientFunds
             | TrampolineErrorCode::PeerConnectionFailure
-            | TrampolineErrorCode::MissingPreimage => tonic::Code::FailedPrecondition,
+            | TrampolineErrorCode::MissingPreimage
+            | TrampolineErrorCode::FeeInsufficient => tonic::Code::FailedPrecondition,
 
             // Invalid arguments: Client specified an invalid argument
             TrampolineErrorCode::InvalidNodeId
@@ -203,22 +225,367 @@ where
     }
 }
 
+/// The result of a trampoline payment.
+#[derive(Clone, Debug)]
+pub struct TrampolinePayment {
+    pub response: cln_rpc::model::responses::PayResponse,
+    /// The trampoline node the payment went through, the one with the
+    /// biggest share if it was split, if known.
+    pub trampoline_node_id: Option<PublicKey>,
+    /// The fee granted to the trampoline nodes, if known.
+    pub fee_msat: Option<u64>,
+}
+
+/// A fee offered to a trampoline node.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct TrampolineFee {
+    base_msat: u64,
+    proportional_millionths: u64,
+    // The CLTV delta the trampoline node asked for, 0 if it didn't.
+    #[serde(default)]
+    cltv_expiry_delta: u16,
+}
+
+impl TrampolineFee {
+    const fn new(base_msat: u64, proportional_millionths: u64) -> Self {
+        TrampolineFee {
+            base_msat,
+            proportional_millionths,
+            cltv_expiry_delta: 0,
+        }
+    }
+
+    const fn with_cltv_expiry_delta(self, cltv_expiry_delta: u16) -> Self {
+        TrampolineFee {
+            cltv_expiry_delta,
+            ..self
+        }
+    }
+
+    fn from_percent(percent: f32) -> Self {
+        TrampolineFee::new(0, (percent as f64 * 10_000.) as u64)
+    }
+
+    fn fee_msat(&self, amount_msat: u64) -> u64 {
+        let proportional = amount_msat as u128 * self.proportional_millionths as u128 / 1_000_000;
+        self.base_msat + proportional as u64
+    }
+}
+
+/// The fee a trampoline node asked for, stored in the datastore.
+#[derive(Debug, Deserialize, Serialize)]
+struct CachedFee {
+    fee: TrampolineFee,
+    updated_at: u64,
+}
+
+/// A payment to send through trampolines, the same for each
+/// trampoline we try.
+struct Payment {
+    bolt11: String,
+    label: String,
+    description: Option<String>,
+    payment_hash: cln_rpc::primitives::Sha256,
+    payment_secret: cln_rpc::primitives::Secret,
+    amount_msat: u64,
+    min_final_cltv_expiry: u32,
+    max_delay: Option<u32>,
+}
+
+impl Payment {
+    /// The onion payload telling a trampoline what to pay: the invoice,
+    /// its amount and, if the trampoline only forwards part of it,
+    /// that `share_msat`.
+    fn payload_hex(&self, share_msat: u64) -> String {
+        use crate::tlv::{SerializedTlvStream, ToBytes};
+        let mut payload: SerializedTlvStream = SerializedTlvStream::new();
+        payload.set_bytes(TLV_BOLT11, self.bolt11.as_bytes());
+        payload.set_tu64(TLV_AMT_MSAT, self.amount_msat);
+        if share_msat < self.amount_msat {
+            payload.set_tu64(TLV_PARTIAL_MSAT, share_msat);
+        }
+        hex::encode(SerializedTlvStream::to_bytes(payload))
+    }
+
+    /// The CLTV delay to give the trampoline along with `fee`: the
+    /// delta it asked for on top of the invoice's final delta, or
+    /// `max_delay` if it didn't ask. `None` if it asked for more than
+    /// `max_delay`.
+    fn delay(&self, fee: &TrampolineFee) -> Option<u32> {
+        let max_delay = self.max_delay.unwrap_or(MAX_DELAY_DEFAULT);
+        if fee.cltv_expiry_delta == 0 {
+            return Some(max_delay);
+        }
+        let delay = self.min_final_cltv_expiry + fee.cltv_expiry_delta as u32;
+        (delay <= max_delay).then_some(delay)
+    }
+}
+
+/// A trampoline we can pay through.
+struct Trampoline {
+    node_id: PublicKey,
+    // Our usable channels to the trampoline, less what is in flight.
+    channels: Vec<Channel>,
+    // The fee to offer the trampoline.
+    fee: TrampolineFee,
+    // The fee the trampoline asked for last.
+    cached: Option<TrampolineFee>,
+}
+
+impl Trampoline {
+    /// The most the trampoline can forward, with its fee on top.
+    fn max_share_msat(&self) -> u64 {
+        let spendable = self.channels.iter().map(|c| c.spendable_msat).sum();
+        max_share(spendable, &self.fee)
+    }
+}
+
+/// The part of a payment sent through one trampoline.
+struct Share {
+    node_id: PublicKey,
+    share_msat: u64,
+    fee: TrampolineFee,
+    delay: u32,
+    // The amount sent over each channel to the trampoline.
+    parts: Vec<(ShortChannelId, u64)>,
+}
+
+impl Share {
+    fn fee_msat(&self) -> u64 {
+        self.fee.fee_msat(self.share_msat)
+    }
+
+    fn sent_msat(&self) -> u64 {
+        self.share_msat + self.fee_msat()
+    }
+}
+
+/// Splits a payment across the trampolines we can pay through, and
+/// keeps track of their fees and of what is in flight through them.
+struct Split<'a> {
+    rpc: ClnRpc,
+    rpc_path: &'a Path,
+    // The candidates we haven't looked at yet.
+    candidates: std::vec::IntoIter<PublicKey>,
+    trampolines: Vec<Trampoline>,
+    max_fee: TrampolineFee,
+    last_err: Option<TrampolineError>,
+}
+
+impl Split<'_> {
+    /// Adds the next candidate we can pay through. `false` if there
+    /// is none left.
+    async fn add_trampoline(&mut self, amount_msat: u64) -> Result<bool> {
+        for node_id in self.candidates.by_ref() {
+            match trampoline_channels(node_id, self.rpc_path).await {
+                Ok(channels) => {
+                    // Start at the fee the trampoline asked for last.
+                    let cached = cached_fee(&mut self.rpc, &node_id).await;
+                    self.trampolines.push(Trampoline {
+                        node_id,
+                        channels,
+                        fee: initial_fee(amount_msat, self.max_fee, cached),
+                        cached,
+                    });
+                    return Ok(true);
+                }
+                Err(e) if is_trampoline_failure(&e) => {
+                    warn!("Can't pay through trampoline {}: {}", node_id, e);
+                    self.last_err = Some(e);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(false)
+    }
+
+    /// Splits `amount_msat` across the trampolines, in order, adding
+    /// candidates until they can forward it all. `None` if all of them
+    /// together can't.
+    async fn split(&mut self, amount_msat: u64) -> Result<Option<Vec<(PublicKey, u64)>>> {
+        loop {
+            let max_shares: Vec<u64> = self
+                .trampolines
+                .iter()
+                .map(Trampoline::max_share_msat)
+                .collect();
+            if let Some(shares) = split_amount(amount_msat, &max_shares) {
+                return Ok(Some(
+                    self.trampolines
+                        .iter()
+                        .zip(shares)
+                        .filter(|(_, share)| *share > 0)
+                        .map(|(t, share)| (t.node_id, share))
+                        .collect(),
+                ));
+            }
+            if !self.add_trampoline(amount_msat).await? {
+                return Ok(None);
+            }
+        }
+    }
+
+    /// Allocates `share_msat` and the fee across our channels to
+    /// `node_id`, taking it off their spendable amount.
+    fn reserve(&mut self, payment: &Payment, node_id: PublicKey, share_msat: u64) -> Result<Share> {
+        let t = self
+            .trampolines
+            .iter_mut()
+            .find(|t| t.node_id == node_id)
+            .ok_or_else(|| {
+                error!(
+                    TrampolineErrorCode::Internal,
+                    "Unknown trampoline {}", node_id
+                )
+            })?;
+        // Each share pays at most `max_fee` of itself, so the fees
+        // stay within `max_fee` of the whole amount.
+        let fee = initial_fee(share_msat, self.max_fee, Some(t.fee));
+        let delay = payment.delay(&fee).ok_or_else(|| {
+            error!(
+                TrampolineErrorCode::FeeInsufficient,
+                "Trampoline {} needs a CLTV delta of {}, above the maximum delay",
+                node_id,
+                fee.cltv_expiry_delta
+            )
+        })?;
+        let sent_msat = share_msat + fee.fee_msat(share_msat);
+        let parts = allocate(&mut t.channels, sent_msat).ok_or_else(|| {
+            error!(
+                TrampolineErrorCode::InsufficientFunds,
+                "Insufficient funds, {}msat are required, current maximal available {}msat",
+                sent_msat,
+                t.channels.iter().map(|ch| ch.spendable_msat).sum::<u64>()
+            )
+        })?;
+        for (scid, amount) in parts.iter() {
+            if let Some(ch) = t.channels.iter_mut().find(|c| c.short_channel_id == *scid) {
+                ch.spendable_msat -= amount;
+            }
+        }
+        Ok(Share {
+            node_id,
+            share_msat,
+            fee,
+            delay,
+            parts,
+        })
+    }
+
+    /// Gives the channel balance of a failed `share` back.
+    fn release(&mut self, share: &Share) {
+        let t = match self
+            .trampolines
+            .iter_mut()
+            .find(|t| t.node_id == share.node_id)
+        {
+            Some(t) => t,
+            None => return,
+        };
+        for (scid, amount) in share.parts.iter() {
+            if let Some(ch) = t.channels.iter_mut().find(|c| c.short_channel_id == *scid) {
+                ch.spendable_msat += amount;
+            }
+        }
+    }
+
+    /// Stops paying through `node_id`.
+    fn fail(&mut self, node_id: PublicKey, err: TrampolineError) {
+        warn!("Trampoline payment via {} failed: {}", node_id, err);
+        self.trampolines.retain(|t| t.node_id != node_id);
+        self.last_err = Some(err);
+    }
+
+    /// Handles the failure of `share`: retries the trampoline at the
+    /// fee it asks for, or stops paying through it. Failures that are
+    /// not specific to the trampoline are returned.
+    async fn handle_failure(&mut self, share: &Share, err: TrampolineError) -> Result<()> {
+        self.release(share);
+        if err.code == TrampolineErrorCode::FeeInsufficient {
+            debug!(
+                "Trampoline {} rejected a fee of {}msat",
+                share.node_id,
+                share.fee_msat()
+            );
+            let requested: Option<TrampolineFee> = err
+                .context
+                .as_deref()
+                .and_then(|c| serde_json::from_str(c).ok());
+            if let Some(t) = self
+                .trampolines
+                .iter_mut()
+                .find(|t| t.node_id == share.node_id)
+            {
+                if let Some(requested) = requested.filter(|r| Some(*r) != t.cached) {
+                    store_fee(&mut self.rpc, &t.node_id, requested).await;
+                    t.cached = Some(requested);
+                }
+                if let Some(fee) = retry_fee(share.share_msat, share.fee, requested, self.max_fee) {
+                    t.fee = fee;
+                    self.last_err = Some(err);
+                    return Ok(());
+                }
+            }
+        }
+        if !is_trampoline_failure(&err) {
+            return Err(err);
+        }
+        self.fail(share.node_id, err);
+        Ok(())
+    }
+
+    /// Remembers the fees the trampolines of the `paid` shares took.
+    async fn store_fees(&mut self, paid: &[Share]) {
+        for share in paid {
+            let cached = self
+                .trampolines
+                .iter()
+                .find(|t| t.node_id == share.node_id)
+                .and_then(|t| t.cached);
+            if cached != Some(share.fee) {
+                store_fee(&mut self.rpc, &share.node_id, share.fee).await;
+            }
+        }
+    }
+}
+
+/// The most a trampoline can forward through channels with
+/// `spendable_msat`, with `fee` on top.
+fn max_share(spendable_msat: u64, fee: &TrampolineFee) -> u64 {
+    let available = spendable_msat.saturating_sub(fee.base_msat) as u128;
+    (available * 1_000_000 / (1_000_000 + fee.proportional_millionths as u128)) as u64
+}
+
+/// Splits `amount_msat` across trampolines that can forward at most
+/// `max_shares` each, filling them in order. `None` if they can't
+/// forward all of it.
+fn split_amount(amount_msat: u64, max_shares: &[u64]) -> Option<Vec<u64>> {
+    let mut remaining = amount_msat;
+    let shares = max_shares
+        .iter()
+        .map(|max| {
+            let share = remaining.min(*max);
+            remaining -= share;
+            share
+        })
+        .collect();
+    (remaining == 0).then_some(shares)
+}
+
+/// Pays `req.bolt11` through the trampolines of `req`.
+///
+/// The payment is split across our channels to as many trampolines as
+/// it takes, in order. A share that fails is sent again through the
+/// trampolines left while the other shares are held at the
+/// destination, and at the fee the trampoline asks for if it rejected
+/// ours. If all shares fail, the payment is tried again as a new
+/// group, until a failure is not specific to a trampoline or none is
+/// left.
 pub async fn trampolinepay(
     req: pb::TrampolinePayRequest,
     rpc_path: impl AsRef<Path>,
-) -> Result<cln_rpc::model::responses::PayResponse> {
-    let node_id = cln_rpc::primitives::PublicKey::from_slice(&req.trampoline_node_id[..]).map_err(
-        |source| {
-            error!(
-                TrampolineErrorCode::InvalidNodeId,
-                "Got an invalid node id: {}",
-                source.to_string()
-            )
-            .with_hint("A node id must be exactly 33 bytes (66 hex characters)")
-            .with_source(source)
-        },
-    )?;
-    let hex_node_id = hex::encode(node_id.serialize());
+) -> Result<TrampolinePayment> {
+    let trampolines = trampoline_candidates(&req)?;
 
     let mut rpc = ClnRpc::new(&rpc_path).await.map_err(|source| {
         error!(
@@ -275,24 +642,29 @@ pub async fn trampolinepay(
                 ))
             }
         };
-        return Ok(cln_rpc::model::responses::PayResponse {
-            amount_msat: resp.amount_msat.unwrap_or(Amount::from_msat(0)),
-            amount_sent_msat: resp.amount_sent_msat,
-            created_at: 0.,
-            destination: resp.destination,
-            parts: match resp.partid {
-                Some(0) => 1,
-                Some(partid) => partid as u32,
-                None => 1,
-            },
-            payment_hash: resp.payment_hash,
-            payment_preimage: preimage,
-            status: match resp.status {
-                cln_rpc::model::responses::WaitsendpayStatus::COMPLETE => {
-                    cln_rpc::model::responses::PayStatus::COMPLETE
-                }
+        return Ok(TrampolinePayment {
+            // The parts are sent to the trampoline.
+            trampoline_node_id: resp.destination,
+            fee_msat: None,
+            response: cln_rpc::model::responses::PayResponse {
+                amount_msat: resp.amount_msat.unwrap_or(Amount::from_msat(0)),
+                amount_sent_msat: resp.amount_sent_msat,
+                created_at: 0.,
+                destination: resp.destination,
+                parts: match resp.partid {
+                    Some(0) => 1,
+                    Some(partid) => partid as u32,
+                    None => 1,
+                },
+                payment_hash: resp.payment_hash,
+                payment_preimage: preimage,
+                status: match resp.status {
+                    cln_rpc::model::responses::WaitsendpayStatus::COMPLETE => {
+                        cln_rpc::model::responses::PayStatus::COMPLETE
+                    }
+                },
+                warning_partial_completion: None,
             },
-            warning_partial_completion: None,
         });
     }
 
@@ -303,14 +675,397 @@ pub async fn trampolinepay(
         .max()
         .unwrap_or(0);
     log::debug!(
-        "New trampoline payment via {}: {} ",
-        hex_node_id,
+        "New trampoline payment via {:?}: {} ",
+        trampolines,
         req.bolt11.clone()
     );
 
+    let amount_msat = match (as_option(req.amount_msat), decoded.amount_msat) {
+        (None, None) => {
+            return Err(error!(
+                TrampolineErrorCode::MissingAmount,
+                "Missing amount")
+            .with_hint(
+                "If the invoice does not have a fixed amount you need to set the amount parameter"
+            ));
+        }
+        (None, Some(amt)) => amt.msat(),
+        (Some(amt), None) => amt,
+        (Some(set_amt), Some(bolt11_amt)) => {
+            if set_amt != bolt11_amt.msat() {
+                return Err(error!(
+                    TrampolineErrorCode::AmbigousAmount,
+                    "Invoice amount and the given amount don't match"
+                )
+                .with_hint(
+                    "If the invoice has the amount set you don't need to set it as a parameter",
+                ));
+            }
+            bolt11_amt.msat()
+        }
+    };
+
+    // We need to add some sats to the htlcs to allow the trampoline node
+    // to pay fees on routing, up to `maxfeepercent`.
+    let max_fee = TrampolineFee::from_percent(
+        as_option(req.maxfeepercent).unwrap_or(DEFAULT_OVERPAY_PERCENT),
+    );
+    debug!("max_overpay={}", max_fee.fee_msat(amount_msat));
+
+    let payment_secret = decoded
+        .payment_secret
+        .map(|e| e.to_vec())
+        .ok_or(error!(
+            TrampolineErrorCode::InvalidInvoice,
+            "The invoice is invalid, missing payment secret"
+        ))?
+        .try_into()
+        .map_err(|e: anyhow::Error| {
+            error!(
+                TrampolineErrorCode::InvalidInvoice,
+                "The invoice is invalid, {}",
+                e.to_string(),
+            )
+        })?;
+
+    let payment = Payment {
+        bolt11: req.bolt11.clone(),
+        label: req.label.clone(),
+        description: decoded.description.clone(),
+        payment_hash,
+        payment_secret,
+        amount_msat,
+        min_final_cltv_expiry: decoded
+            .min_final_cltv_expiry
+            .unwrap_or(MIN_FINAL_CLTV_EXPIRY_DEFAULT),
+        max_delay: as_option(req.maxdelay),
+    };
+
+    let mut split = Split {
+        rpc,
+        rpc_path: rpc_path.as_ref(),
+        candidates: trampolines.into_iter(),
+        trampolines: vec![],
+        max_fee,
+        last_err: None,
+    };
+    let mut group_id = max_group_id;
+    let mut preapproved = false;
+    loop {
+        let shares = match split.split(amount_msat).await? {
+            Some(shares) => shares,
+            None => {
+                return Err(split.last_err.take().unwrap_or_else(|| {
+                    error!(
+                        TrampolineErrorCode::InsufficientFunds,
+                        "Insufficient funds, {}msat are required", amount_msat
+                    )
+                    .with_hint("The channels to the trampoline nodes can't carry the payment")
+                }))
+            }
+        };
+        debug!(
+            "Splitting {}msat across trampolines {:?}",
+            amount_msat, shares
+        );
+
+        let mut reserved = vec![];
+        let mut complete = true;
+        for (node_id, share_msat) in shares {
+            match split.reserve(&payment, node_id, share_msat) {
+                Ok(share) => reserved.push(share),
+                Err(e) => {
+                    split.fail(node_id, e);
+                    complete = false;
+                }
+            }
+        }
+        if !complete {
+            // Split again without the trampolines that failed.
+            for share in reserved.iter() {
+                split.release(share);
+            }
+            continue;
+        }
+
+        if !preapproved {
+            split
+                .rpc
+                .call_typed(&cln_rpc::model::requests::PreapproveinvoiceRequest {
+                    bolt11: payment.bolt11.clone(),
+                })
+                .await?;
+            preapproved = true;
+        }
+
+        // Core Lightning only allows the parts of one group in flight,
+        // all with the same total, so the parts through all
+        // trampolines share a group. Failed shares are sent again in
+        // the same group while the other parts are held at the
+        // destination. The total leaves room for their fees to
+        // differ, each trampoline learns its share from the payload.
+        group_id += 1;
+        let total_msat = match reserved.as_slice() {
+            [share] => share.sent_msat(),
+            _ => amount_msat + max_fee.fee_msat(amount_msat),
+        };
+        // A single part is sent without a part id.
+        let mut part_id = match reserved.as_slice() {
+            [share] if share.parts.len() == 1 => 0,
+            _ => 1,
+        };
+        let mut in_flight = FuturesUnordered::new();
+        for share in reserved {
+            debug!(
+                "Sending {}msat with a fee of {}msat via {}, delay={}",
+                share.share_msat,
+                share.fee_msat(),
+                share.node_id,
+                share.delay
+            );
+            let first_part_id = part_id;
+            part_id += share.parts.len() as u64;
+            in_flight.push(send_share(
+                share,
+                &payment,
+                group_id,
+                total_msat,
+                first_part_id,
+                rpc_path.as_ref(),
+            ));
+        }
+
+        let mut paid: Vec<Share> = vec![];
+        let mut payment_preimage = None;
+        let mut fatal = None;
+        while let Some((share, result)) = in_flight.next().await {
+            let err = match result {
+                Ok(preimage) => {
+                    payment_preimage = Some(preimage);
+                    paid.push(share);
+                    continue;
+                }
+                Err(e) => e,
+            };
+            if let Err(e) = split.handle_failure(&share, err).await {
+                fatal.get_or_insert(e);
+            }
+            if payment_preimage.is_some() || fatal.is_some() || in_flight.is_empty() {
+                continue;
+            }
+            // The other parts are still held at the destination, send
+            // this share again through the trampolines left.
+            let shares = match split.split(share.share_msat).await {
+                Ok(Some(shares)) => shares,
+                Ok(None) => {
+                    debug!(
+                        "No trampoline left to send {}msat through",
+                        share.share_msat
+                    );
+                    continue;
+                }
+                Err(e) => {
+                    fatal.get_or_insert(e);
+                    continue;
+                }
+            };
+            for (node_id, share_msat) in shares {
+                match split.reserve(&payment, node_id, share_msat) {
+                    Ok(share) => {
+                        debug!(
+                            "Retrying {}msat with a fee of {}msat via {}",
+                            share.share_msat,
+                            share.fee_msat(),
+                            share.node_id
+                        );
+                        let first_part_id = part_id;
+                        part_id += share.parts.len() as u64;
+                        in_flight.push(send_share(
+                            share,
+                            &payment,
+                            group_id,
+                            total_msat,
+                            first_part_id,
+                            rpc_path.as_ref(),
+                        ));
+                    }
+                    Err(e) => split.fail(node_id, e),
+                }
+            }
+        }
+
+        if let Some(payment_preimage) = payment_preimage {
+            split.store_fees(&paid).await;
+            let fee_msat: u64 = paid.iter().map(Share::fee_msat).sum();
+            let amount_sent_msat = amount_msat + fee_msat;
+            return Ok(TrampolinePayment {
+                trampoline_node_id: paid.iter().max_by_key(|s| s.share_msat).map(|s| s.node_id),
+                fee_msat: Some(fee_msat),
+                response: cln_rpc::model::responses::PayResponse {
+                    destination: decoded.payee,
+                    warning_partial_completion: None,
+                    status: cln_rpc::model::responses::PayStatus::COMPLETE,
+                    amount_msat: cln_rpc::primitives::Amount::from_msat(amount_msat),
+                    amount_sent_msat: cln_rpc::primitives::Amount::from_msat(amount_sent_msat),
+                    created_at: 0.,
+                    parts: paid.iter().map(|s| s.parts.len() as u32).sum(),
+                    payment_hash: payment.payment_hash,
+                    payment_preimage,
+                },
+            });
+        }
+        if let Some(e) = fatal {
+            return Err(e);
+        }
+        // All parts failed, try again in a new group.
+    }
+}
+
+/// The fee to offer a trampoline node for `amount_msat` first: the
+/// `cached` fee it asked for before, or no fee at all, capped at
+/// `max_fee`.
+fn initial_fee(
+    amount_msat: u64,
+    max_fee: TrampolineFee,
+    cached: Option<TrampolineFee>,
+) -> TrampolineFee {
+    match cached {
+        Some(fee) if fee.fee_msat(amount_msat) <= max_fee.fee_msat(amount_msat) => fee,
+        Some(fee) => max_fee.with_cltv_expiry_delta(fee.cltv_expiry_delta),
+        None => TrampolineFee::new(0, 0),
+    }
+}
+
+/// The fee to offer a trampoline node that rejected `fee` for
+/// `amount_msat`: the `requested` fee and CLTV delta, or `max_fee` if
+/// the node didn't ask for more. `None` if the node needs more than
+/// `max_fee`.
+fn retry_fee(
+    amount_msat: u64,
+    fee: TrampolineFee,
+    requested: Option<TrampolineFee>,
+    max_fee: TrampolineFee,
+) -> Option<TrampolineFee> {
+    let fee_msat = fee.fee_msat(amount_msat);
+    let max_fee_msat = max_fee.fee_msat(amount_msat);
+    match requested {
+        Some(r)
+            if r.fee_msat(amount_msat) > fee_msat
+                || r.cltv_expiry_delta > fee.cltv_expiry_delta =>
+        {
+            (r.fee_msat(amount_msat) <= max_fee_msat).then_some(r)
+        }
+        _ => (fee_msat < max_fee_msat)
+            .then_some(max_fee.with_cltv_expiry_delta(fee.cltv_expiry_delta)),
+    }
+}
+
+fn fee_cache_key(node_id: &PublicKey) -> Vec<String> {
+    vec![
+        "greenlight".to_string(),
+        "trampoline_fees".to_string(),
+        hex::encode(node_id.serialize()),
+    ]
+}
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// The fee `node_id` asked for last, unless that was too long ago.
+async fn cached_fee(rpc: &mut ClnRpc, node_id: &PublicKey) -> Option<TrampolineFee> {
+    let res = rpc
+        .call_typed(&cln_rpc::model::requests::ListdatastoreRequest {
+            key: Some(fee_cache_key(node_id)),
+        })
+        .await
+        .map_err(|e| debug!("Could not read the fee of trampoline {}: {}", node_id, e))
+        .ok()?;
+    let cached: CachedFee = serde_json::from_str(res.datastore.first()?.string.as_ref()?).ok()?;
+    (now_secs().saturating_sub(cached.updated_at) < FEE_CACHE_TTL_SEC).then_some(cached.fee)
+}
+
+/// Remembers that `node_id` asked for `fee`.
+async fn store_fee(rpc: &mut ClnRpc, node_id: &PublicKey, fee: TrampolineFee) {
+    let cached = CachedFee {
+        fee,
+        updated_at: now_secs(),
+    };
+    let res = rpc
+        .call_typed(&cln_rpc::model::requests::DatastoreRequest {
+            key: fee_cache_key(node_id),
+            string: Some(json!(cached).to_string()),
+            hex: None,
+            mode: Some(cln_rpc::model::requests::DatastoreMode::CREATE_OR_REPLACE),
+            generation: None,
+        })
+        .await;
+    if let Err(e) = res {
+        debug!("Could not store the fee of trampoline {}: {}", node_id, e);
+    }
+}
+
+/// The trampolines to try, in order: `trampoline_node_id` followed by
+/// `trampoline_node_ids`, without duplicates.
+fn trampoline_candidates(req: &pb::TrampolinePayRequest) -> Result<Vec<PublicKey>> {
+    let first = (!req.trampoline_node_id.is_empty() || req.trampoline_node_ids.is_empty())
+        .then_some(&req.trampoline_node_id);
+    let mut candidates: Vec<PublicKey> = vec![];
+    for id in first.into_iter().chain(&req.trampoline_node_ids) {
+        let node_id = PublicKey::from_slice(id).map_err(|source| {
+            error!(
+                TrampolineErrorCode::InvalidNodeId,
+                "Got an invalid node id: {}",
+                source.to_string()
+            )
+            .with_hint("A node id must be exactly 33 bytes (66 hex characters)")
+            .with_source(source)
+        })?;
+        if !candidates.contains(&node_id) {
+            candidates.push(node_id);
+        }
+    }
+    Ok(candidates)
+}
+
+/// Whether a payment that failed with `err` may succeed through a
+/// different trampoline.
+fn is_trampoline_failure(err: &TrampolineError) -> bool {
+    matches!(
+        err.code,
+        TrampolineErrorCode::FeatureNotSupported
+            | TrampolineErrorCode::UnknownPeer
+            | TrampolineErrorCode::PeerConnectionFailure
+            | TrampolineErrorCode::MissingChannel
+            | TrampolineErrorCode::InsufficientFunds
+            | TrampolineErrorCode::PeerNodeFailure
+            | TrampolineErrorCode::PaymentFailure
+            | TrampolineErrorCode::FeeInsufficient
+            // Permanent failure at the trampoline, or failure on the
+            // way to it.
+            | TrampolineErrorCode::RpcError(203 | 204)
+    )
+}
+
+/// Checks that we can pay through the trampoline `node_id` and returns
+/// our usable channels to it.
+async fn trampoline_channels(node_id: PublicKey, rpc_path: &Path) -> Result<Vec<Channel>> {
+    let hex_node_id = hex::encode(node_id.serialize());
+
+    let mut rpc = ClnRpc::new(rpc_path).await.map_err(|source| {
+        error!(
+            TrampolineErrorCode::NetworkError,
+            "Couldn't connect to core-lightning node: {}",
+            source.to_string()
+        )
+    })?;
+
     // Wait for the peer connection to re-establish.
     log::debug!("Await peer connection to {}", hex_node_id);
-    AwaitablePeer::new(node_id, rpc_path.as_ref().to_path_buf())
+    AwaitablePeer::new(node_id, rpc_path.to_path_buf())
         .wait()
         .await
         .map_err(|e| match e {
@@ -360,40 +1115,6 @@ pub async fn trampolinepay(
 
     feature_guard(features, TRAMPOLINE_FEATURE_BIT)?;
 
-    let amount_msat = match (as_option(req.amount_msat), decoded.amount_msat) {
-        (None, None) => {
-            return Err(error!(
-                TrampolineErrorCode::MissingAmount,
-                "Missing amount")
-            .with_hint(
-                "If the invoice does not have a fixed amount you need to set the amount parameter"
-            ));
-        }
-        (None, Some(amt)) => amt.msat(),
-        (Some(amt), None) => amt,
-        (Some(set_amt), Some(bolt11_amt)) => {
-            if set_amt != bolt11_amt.msat() {
-                return Err(error!(
-                    TrampolineErrorCode::AmbigousAmount,
-                    "Invoice amount and the given amount don't match"
-                )
-                .with_hint(
-                    "If the invoice has the amount set you don't need to set it as a parameter",
-                ));
-            }
-            bolt11_amt.msat()
-        }
-    };
-
-    // We need to add some sats to the htlcs to allow the trampoline node
-    // to pay fees on routing.
-    let tlv_amount_msat = amount_msat;
-    let overpay = amount_msat as f64
-        * (as_option(req.maxfeepercent).unwrap_or(DEFAULT_OVERPAY_PERCENT) as f64 / 100 as f64);
-    let amount_msat = amount_msat + overpay as u64;
-
-    debug!("overpay={}, total_amt={}", overpay as u64, amount_msat);
-
     let channels: Vec<Channel> = rpc
         .call_typed(&cln_rpc::model::requests::ListpeerchannelsRequest { id: Some(node_id) })
         .await?
@@ -448,145 +1169,130 @@ pub async fn trampolinepay(
 
     // Await and filter out re-established channels.
     let deadline = Instant::now() + Duration::from_secs(AWAIT_CHANNELS_TIMEOUT_SEC);
-    let mut channels =
-        reestablished_channels(channels, node_id, rpc_path.as_ref().to_path_buf(), deadline)
-            .await?;
+    reestablished_channels(channels, node_id, rpc_path.to_path_buf(), deadline).await
+}
+
+/// Allocates `amount_msat` across `channels`. Tries the most drained
+/// channels first, then the channels with the biggest local balance
+/// first.
+fn allocate(channels: &mut [Channel], amount_msat: u64) -> Option<Vec<(ShortChannelId, u64)>> {
+    fn owned(alloc: Vec<ChannelContribution>) -> Vec<(ShortChannelId, u64)> {
+        alloc
+            .into_iter()
+            .map(|c| (c.channel.short_channel_id, c.contrib_msat))
+            .collect()
+    }
 
-    // Try different allocation strategies in sequence. First try in ascending
-    // order of spendable_msat, giving us most drained channels first. Then
-    // try in descending order of spendable_msat giving us the channels with the
-    // biggest local balance first.
     debug!(
         "Trying to allocate {}msat accross {} channels in ascending order",
         amount_msat,
         channels.len()
     );
-    let alloc = match find_allocation_ascending_order(&mut channels, amount_msat)
-        .filter(|alloc| !alloc.is_empty())
+    if let Some(alloc) =
+        find_allocation_ascending_order(channels, amount_msat).filter(|alloc| !alloc.is_empty())
     {
-        Some(alloc) => alloc,
-        None => {
-            debug!("Failed to allocate {}msat in ascending channel order {:?}, trying in descending order",amount_msat, &channels);
-            match find_allocation_descending_order(&mut channels, amount_msat)
-                .filter(|alloc| !alloc.is_empty())
-            {
-                Some(alloc) => alloc,
-                None => {
-                    return Err(error!(
-                        TrampolineErrorCode::InsufficientFunds,
-                        "Insufficient funds, {}msat are required, current maximal available {}msat",
-                        amount_msat,
-                        channels.iter().map(|ch| ch.spendable_msat).sum::<u64>()
-                    ));
-                }
-            }
-        }
-    };
-
-    // All set we can preapprove the invoice
-    let _ = rpc
-        .call_typed(&cln_rpc::model::requests::PreapproveinvoiceRequest {
-            bolt11: req.bolt11.clone(),
-        })
-        .await?;
+        return Some(owned(alloc));
+    }
+    debug!(
+        "Failed to allocate {}msat in ascending channel order {:?}, trying in descending order",
+        amount_msat, &channels
+    );
+    find_allocation_descending_order(channels, amount_msat)
+        .filter(|alloc| !alloc.is_empty())
+        .map(owned)
+}
 
-    // Create TLV payload.
-    use crate::tlv::{SerializedTlvStream, ToBytes};
-    let mut payload: SerializedTlvStream = SerializedTlvStream::new();
-    payload.set_bytes(TLV_BOLT11, req.bolt11.as_bytes());
-    payload.set_tu64(TLV_AMT_MSAT, tlv_amount_msat);
-    let payload_hex = hex::encode(SerializedTlvStream::to_bytes(payload));
-
-    let mut part_id = if alloc.len() == 1 { 0 } else { 1 };
-    let group_id = max_group_id + 1;
-    let mut handles: Vec<
-        tokio::task::JoinHandle<Result<cln_rpc::model::responses::WaitsendpayResponse>>,
-    > = vec![];
-    for ch in &alloc {
-        let bolt11 = req.bolt11.clone();
-        let label = req.label.clone();
-        let part_amt = ch.contrib_msat.clone();
-        let scid = ch.channel.short_channel_id.clone();
-        let description = decoded.description.clone();
-        let payload_hex = payload_hex.clone();
-        let mut rpc = ClnRpc::new(&rpc_path).await.map_err(|e| {
-            error!(
-                TrampolineErrorCode::NetworkError,
-                "Couldn't connect to core-lightning node: {}",
-                e.to_string()
-            )
-        })?;
-        let handle = tokio::spawn(async move {
-            let payment_secret = decoded
-                .payment_secret
-                .map(|e| e.to_vec())
-                .ok_or(error!(
-                    TrampolineErrorCode::InvalidInvoice,
-                    "The invoice is invalid, missing payment secret"
-                ))?
-                .try_into()
-                .map_err(|e: anyhow::Error| {
+/// Sends `share` through its trampoline, one part per channel, numbered
+/// from `first_part_id`. Resolves to the share and the preimage once
+/// all parts settled.
+fn send_share(
+    share: Share,
+    payment: &Payment,
+    group_id: u64,
+    total_msat: u64,
+    first_part_id: u64,
+    rpc_path: &Path,
+) -> BoxFuture<'static, (Share, Result<cln_rpc::primitives::Secret>)> {
+    let payload_hex = payment.payload_hex(share.share_msat);
+    let handles: Vec<_> = share
+        .parts
+        .iter()
+        .zip(first_part_id..)
+        .map(|((scid, part_amt), part_id)| {
+            let rpc_path = rpc_path.to_path_buf();
+            let node_id = share.node_id;
+            let bolt11 = payment.bolt11.clone();
+            let label = payment.label.clone();
+            let description = payment.description.clone();
+            let payment_hash = payment.payment_hash;
+            let payment_secret = payment.payment_secret;
+            let payload_hex = payload_hex.clone();
+            let (scid, part_amt, delay) = (*scid, *part_amt, share.delay);
+            tokio::spawn(async move {
+                let mut rpc = ClnRpc::new(&rpc_path).await.map_err(|e| {
                     error!(
-                        TrampolineErrorCode::InvalidInvoice,
-                        "The invoice is invalid, {}",
-                        e.to_string(),
+                        TrampolineErrorCode::NetworkError,
+                        "Couldn't connect to core-lightning node: {}",
+                        e.to_string()
                     )
                 })?;
-            do_pay(
-                &mut rpc,
-                node_id,
-                bolt11,
-                label,
-                description,
-                part_amt,
-                scid,
-                part_id,
-                group_id,
-                payment_hash.clone(),
-                cln_rpc::primitives::Amount::from_msat(amount_msat),
-                payment_secret,
-                payload_hex,
-                as_option(req.maxdelay),
-            )
-            .await
-        });
-        part_id += 1;
-        handles.push(handle);
-    }
+                do_pay(
+                    &mut rpc,
+                    node_id,
+                    bolt11,
+                    label,
+                    description,
+                    part_amt,
+                    scid,
+                    part_id,
+                    group_id,
+                    payment_hash,
+                    cln_rpc::primitives::Amount::from_msat(total_msat),
+                    payment_secret,
+                    payload_hex,
+                    delay,
+                )
+                .await
+            })
+        })
+        .collect();
 
-    let results = join_all(handles).await;
-    let mut payment_preimage = None;
-    for result in results {
-        let response = result.map_err(|e| {
-            error!(
-                TrampolineErrorCode::Internal,
-                "Failed to wait for all tasks to complete"
-            )
-            .with_source(e)
-        })??;
-        if let Some(preimage) = response.payment_preimage {
-            payment_preimage = Some(preimage);
+    async move {
+        // A part may fail while the others succeed, one preimage is
+        // enough.
+        let mut payment_preimage = None;
+        let mut first_err = None;
+        for result in join_all(handles).await {
+            let response = match result {
+                Ok(response) => response,
+                Err(e) => Err(error!(
+                    TrampolineErrorCode::Internal,
+                    "Failed to wait for all tasks to complete"
+                )
+                .with_source(e)),
+            };
+            match response {
+                Ok(response) => {
+                    if let Some(preimage) = response.payment_preimage {
+                        payment_preimage = Some(preimage);
+                    }
+                }
+                Err(e) => {
+                    first_err.get_or_insert(e);
+                }
+            }
         }
+        let result = match (payment_preimage, first_err) {
+            (Some(payment_preimage), _) => Ok(payment_preimage),
+            (None, Some(e)) => Err(e),
+            (None, None) => Err(error!(
+                TrampolineErrorCode::PaymentFailure,
+                "Payment failed, missing payment preimage"
+            )),
+        };
+        (share, result)
     }
-
-    if let Some(payment_preimage) = payment_preimage {
-        Ok(cln_rpc::model::responses::PayResponse {
-            destination: decoded.payee,
-            warning_partial_completion: None,
-            status: cln_rpc::model::responses::PayStatus::COMPLETE,
-            amount_msat: cln_rpc::primitives::Amount::from_msat(amount_msat),
-            amount_sent_msat: cln_rpc::primitives::Amount::from_msat(amount_msat),
-            created_at: 0.,
-            parts: alloc.len() as u32,
-            payment_hash: payment_hash,
-            payment_preimage,
-        })
-    } else {
-        Err(error!(
-            TrampolineErrorCode::PaymentFailure,
-            "Payment failed, missing payment preimage"
-        ))
-    }
+    .boxed()
 }
 
 async fn do_pay(
@@ -603,12 +1309,12 @@ async fn do_pay(
     total_amount: Amount,
     payment_secret: cln_rpc::primitives::Secret,
     payment_metadata: String,
-    max_delay: Option<u32>,
+    delay: u32,
 ) -> Result<cln_rpc::model::responses::WaitsendpayResponse> {
     let route = cln_rpc::model::requests::SendpayRoute {
         amount_msat: cln_rpc::primitives::Amount::from_msat(part_amt),
         id: node_id.clone(),
-        delay: max_delay.unwrap_or(MAX_DELAY_DEFAULT),
+        delay,
         channel: scid,
     };
 
@@ -647,6 +1353,16 @@ async fn do_pay(
     {
         Ok(v) => Ok(v),
         Err(e) => {
+            if failcode(&e) == Some(TRAMPOLINE_FEE_INSUFFICIENT as u64) {
+                let err = error!(
+                    TrampolineErrorCode::FeeInsufficient,
+                    "Trampoline node requires a higher fee"
+                );
+                return Err(match requested_fee(&e) {
+                    Some(fee) => err.with_context(json!(fee).to_string()),
+                    None => err,
+                });
+            }
             if let Some(code) = e.code {
                 if code == PAY_UNPARSEABLE_ONION_CODE {
                     return Err(error!(
@@ -665,6 +1381,30 @@ async fn do_pay(
     }
 }
 
+/// The onion failure code of a failed payment part, if any.
+fn failcode(err: &RpcError) -> Option<u64> {
+    err.data.as_ref()?.get("failcode")?.as_u64()
+}
+
+/// The fee a trampoline node asks for in a `trampoline_fee_insufficient`
+/// failure: the failure message is the failure code followed by
+/// `fee_base_msat` (u32), `fee_proportional_millionths` (u32) and
+/// `cltv_expiry_delta` (u16).
+fn requested_fee(err: &RpcError) -> Option<TrampolineFee> {
+    let raw_message = err.data.as_ref()?.get("raw_message")?.as_str()?;
+    let msg = hex::decode(raw_message).ok()?;
+    if msg.get(..2)? != TRAMPOLINE_FEE_INSUFFICIENT.to_be_bytes() {
+        return None;
+    }
+    let base_msat = u32::from_be_bytes(msg.get(2..6)?.try_into().ok()?);
+    let proportional_millionths = u32::from_be_bytes(msg.get(6..10)?.try_into().ok()?);
+    let cltv_expiry_delta = u16::from_be_bytes(msg.get(10..12)?.try_into().ok()?);
+    Some(
+        TrampolineFee::new(base_msat as u64, proportional_millionths as u64)
+            .with_cltv_expiry_delta(cltv_expiry_delta),
+    )
+}
+
 // FIXME: Once the `assert_send` is removed we can return a `Vec<Channel>` or an
 // `Option<Vec<Channel>>` instead of a result.
 async fn reestablished_channels(
@@ -929,6 +1669,290 @@ pub struct SendpayRequest {
     pub route: Vec<cln_rpc::model::requests::SendpayRoute>,
 }
 
+#[cfg(test)]
+mod candidate_tests {
+    use super::*;
+
+    // The public keys of the secret keys 1, 2 and 3.
+    fn node_id(n: u8) -> PublicKey {
+        PublicKey::from_str(match n {
+            1 => "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+            2 => "02c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5",
+            _ => "02f9308a019258c31049344f85f89d5229b531c845836f99b08601f113bce036f9",
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn candidates_start_with_trampoline_node_id() {
+        let req = pb::TrampolinePayRequest {
+            trampoline_node_id: node_id(1).serialize().to_vec(),
+            trampoline_node_ids: vec![
+                node_id(2).serialize().to_vec(),
+                node_id(1).serialize().to_vec(),
+                node_id(3).serialize().to_vec(),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(
+            trampoline_candidates(&req).unwrap(),
+            vec![node_id(1), node_id(2), node_id(3)]
+        );
+    }
+
+    #[test]
+    fn candidates_may_omit_trampoline_node_id() {
+        let req = pb::TrampolinePayRequest {
+            trampoline_node_ids: vec![node_id(2).serialize().to_vec()],
+            ..Default::default()
+        };
+        assert_eq!(trampoline_candidates(&req).unwrap(), vec![node_id(2)]);
+    }
+
+    #[test]
+    fn candidates_reject_invalid_node_ids() {
+        let err = trampoline_candidates(&pb::TrampolinePayRequest::default()).unwrap_err();
+        assert_eq!(err.code, TrampolineErrorCode::InvalidNodeId);
+        let req = pb::TrampolinePayRequest {
+            trampoline_node_id: node_id(1).serialize().to_vec(),
+            trampoline_node_ids: vec![vec![2; 3]],
+            ..Default::default()
+        };
+        let err = trampoline_candidates(&req).unwrap_err();
+        assert_eq!(err.code, TrampolineErrorCode::InvalidNodeId);
+    }
+
+    #[test]
+    fn only_trampoline_failures_try_the_next_trampoline() {
+        let err = |code| error!(code, "failed");
+        assert!(is_trampoline_failure(&err(
+            TrampolineErrorCode::UnknownPeer
+        )));
+        assert!(is_trampoline_failure(&err(
+            TrampolineErrorCode::InsufficientFunds
+        )));
+        assert!(is_trampoline_failure(&err(TrampolineErrorCode::RpcError(
+            204
+        ))));
+        assert!(!is_trampoline_failure(&err(TrampolineErrorCode::RpcError(
+            201
+        ))));
+        assert!(!is_trampoline_failure(&err(
+            TrampolineErrorCode::InvalidInvoice
+        )));
+    }
+}
+
+#[cfg(test)]
+mod fee_tests {
+    use super::*;
+
+    #[test]
+    fn fee_msat_adds_base_and_proportional_fee() {
+        let fee = TrampolineFee::new(1_000, 2_000);
+        assert_eq!(fee.fee_msat(0), 1_000);
+        assert_eq!(fee.fee_msat(1_000_000), 3_000);
+        assert_eq!(TrampolineFee::from_percent(0.5).fee_msat(1_000_000), 5_000);
+        // No overflow on huge amounts.
+        assert_eq!(
+            TrampolineFee::new(0, 1_000_000).fee_msat(u64::MAX / 2),
+            u64::MAX / 2
+        );
+    }
+
+    #[test]
+    fn initial_fee_starts_at_the_cached_fee() {
+        let max_fee = TrampolineFee::from_percent(0.5);
+        assert_eq!(
+            initial_fee(10_000_000, max_fee, None),
+            TrampolineFee::new(0, 0)
+        );
+        let cached = TrampolineFee::new(1_500, 2_500);
+        assert_eq!(initial_fee(10_000_000, max_fee, Some(cached)), cached);
+
+        // A cached fee above the maximum is capped, its CLTV delta is
+        // kept.
+        let cached = TrampolineFee::new(1_000_000, 0).with_cltv_expiry_delta(144);
+        assert_eq!(
+            initial_fee(10_000_000, max_fee, Some(cached)),
+            max_fee.with_cltv_expiry_delta(144)
+        );
+    }
+
+    #[test]
+    fn retry_fee_offers_the_requested_fee() {
+        let max_fee = TrampolineFee::from_percent(0.5);
+        let fee = TrampolineFee::new(0, 0);
+        let requested = TrampolineFee::new(1_000, 1_000);
+        assert_eq!(
+            retry_fee(10_000_000, fee, Some(requested), max_fee),
+            Some(requested)
+        );
+
+        // More than the maximum, give up on the trampoline.
+        let requested = TrampolineFee::new(100_000, 0);
+        assert_eq!(retry_fee(10_000_000, fee, Some(requested), max_fee), None);
+
+        // Unknown or no higher fee requested, try the maximum once.
+        assert_eq!(retry_fee(10_000_000, fee, None, max_fee), Some(max_fee));
+        assert_eq!(retry_fee(10_000_000, requested, Some(fee), max_fee), None);
+        assert_eq!(retry_fee(10_000_000, max_fee, None, max_fee), None);
+
+        // The same fee with a higher CLTV delta is retried, and the
+        // delta is kept when trying the maximum.
+        let requested = fee.with_cltv_expiry_delta(144);
+        assert_eq!(
+            retry_fee(10_000_000, fee, Some(requested), max_fee),
+            Some(requested)
+        );
+        assert_eq!(
+            retry_fee(10_000_000, requested, None, max_fee),
+            Some(max_fee.with_cltv_expiry_delta(144))
+        );
+    }
+
+    #[test]
+    fn payment_delay_applies_the_requested_cltv_delta() {
+        let payment = |max_delay| Payment {
+            bolt11: String::new(),
+            label: String::new(),
+            description: None,
+            payment_hash: cln_rpc::primitives::Sha256::from_str(&"00".repeat(32)).unwrap(),
+            payment_secret: cln_rpc::primitives::Secret::try_from(vec![0; 32]).unwrap(),
+            amount_msat: 100_000,
+            min_final_cltv_expiry: 18,
+            max_delay,
+        };
+        let fee = TrampolineFee::new(1_000, 1_000);
+        assert_eq!(payment(None).delay(&fee), Some(MAX_DELAY_DEFAULT));
+        assert_eq!(payment(Some(500)).delay(&fee), Some(500));
+
+        let fee = fee.with_cltv_expiry_delta(144);
+        assert_eq!(payment(None).delay(&fee), Some(162));
+        // More than the caller allows.
+        assert_eq!(payment(Some(100)).delay(&fee), None);
+    }
+
+    #[test]
+    fn requested_fee_reads_the_failure_message() {
+        let err = |raw_message: &str| RpcError {
+            code: Some(204),
+            message: "failed".to_string(),
+            data: Some(json!({"failcode": 8243, "raw_message": raw_message})),
+        };
+        let requested = TrampolineFee::new(1_000, 2_000).with_cltv_expiry_delta(144);
+        assert_eq!(
+            requested_fee(&err("2033000003e8000007d00090")),
+            Some(requested)
+        );
+        // Trailing data is ignored.
+        assert_eq!(
+            requested_fee(&err("2033000003e8000007d00090ff")),
+            Some(requested)
+        );
+        // Wrong failure code or truncated message.
+        assert_eq!(requested_fee(&err("2032000003e8000007d00090")), None);
+        assert_eq!(requested_fee(&err("2033000003e8")), None);
+        assert_eq!(requested_fee(&err("zz")), None);
+    }
+
+    #[test]
+    fn failcode_reads_the_onion_failure_code() {
+        let err = |data| RpcError {
+            code: Some(204),
+            message: "failed".to_string(),
+            data,
+        };
+        assert_eq!(
+            failcode(&err(Some(json!({"failcode": 8243})))),
+            Some(TRAMPOLINE_FEE_INSUFFICIENT as u64)
+        );
+        assert_eq!(failcode(&err(Some(json!({})))), None);
+        assert_eq!(failcode(&err(None)), None);
+    }
+}
+
+#[cfg(test)]
+mod split_tests {
+    use super::*;
+    use crate::tlv::{FromBytes, SerializedTlvStream};
+
+    #[test]
+    fn max_share_leaves_room_for_the_fee() {
+        let fee = TrampolineFee::new(1_000, 10_000);
+        let share = max_share(1_000_000, &fee);
+        assert_eq!(share, 989_108);
+        assert!(share + fee.fee_msat(share) <= 1_000_000);
+        assert_eq!(max_share(1_000, &TrampolineFee::new(0, 0)), 1_000);
+        // Not even the base fee fits.
+        assert_eq!(max_share(500, &fee), 0);
+    }
+
+    #[test]
+    fn split_amount_fills_trampolines_in_order() {
+        assert_eq!(split_amount(100, &[150, 50]), Some(vec![100, 0]));
+        assert_eq!(split_amount(100, &[60, 0, 50]), Some(vec![60, 0, 40]));
+        // Not enough between them.
+        assert_eq!(split_amount(100, &[60, 30]), None);
+        assert_eq!(split_amount(100, &[]), None);
+    }
+
+    #[test]
+    fn allocate_covers_the_amount() {
+        let scid = |s| ShortChannelId::from_str(s).unwrap();
+        let mut channels = vec![
+            Channel {
+                short_channel_id: scid("1x1x1"),
+                spendable_msat: 60_000,
+                min_htlc_out_msat: 1,
+            },
+            Channel {
+                short_channel_id: scid("2x2x2"),
+                spendable_msat: 50_000,
+                min_htlc_out_msat: 1,
+            },
+        ];
+        let parts = allocate(&mut channels, 100_000).unwrap();
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts.iter().map(|(_, amount)| amount).sum::<u64>(), 100_000);
+        assert_eq!(allocate(&mut channels, 120_000), None);
+    }
+
+    #[test]
+    fn payload_names_the_share_of_split_payments() {
+        let payment = Payment {
+            bolt11: "lnbcrt1".to_string(),
+            label: String::new(),
+            description: None,
+            payment_hash: cln_rpc::primitives::Sha256::from_str(&"00".repeat(32)).unwrap(),
+            payment_secret: cln_rpc::primitives::Secret::try_from(vec![0; 32]).unwrap(),
+            amount_msat: 100_000,
+            min_final_cltv_expiry: 18,
+            max_delay: None,
+        };
+        let payload = |share| {
+            SerializedTlvStream::from_bytes(hex::decode(payment.payload_hex(share)).unwrap())
+                .unwrap()
+        };
+
+        let full = payload(100_000);
+        assert_eq!(full.get(TLV_BOLT11).unwrap().value, b"lnbcrt1".to_vec());
+        assert_eq!(
+            full.get(TLV_AMT_MSAT).unwrap().value,
+            vec![0x01, 0x86, 0xa0]
+        );
+        assert!(full.get(TLV_PARTIAL_MSAT).is_none());
+
+        // The amount stays the invoice's, the share is on its own.
+        let part = payload(40_000);
+        assert_eq!(
+            part.get(TLV_AMT_MSAT).unwrap().value,
+            vec![0x01, 0x86, 0xa0]
+        );
+        assert_eq!(part.get(TLV_PARTIAL_MSAT).unwrap().value, vec![0x9c, 0x40]);
+    }
+}
+
 #[cfg(test)]
 mod channel_allocation_tests {
     use super::*;
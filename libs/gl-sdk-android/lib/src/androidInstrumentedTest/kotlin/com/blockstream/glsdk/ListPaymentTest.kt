@@ -55,6 +55,13 @@ class ListPaymentTest {
         assertNotNull(PaymentStatus.FAILED)
     }
 
+    @Test
+    fun payment_kind_enum_values() {
+        assertNotNull(PaymentKind.LIGHTNING)
+        assertNotNull(PaymentKind.ONCHAIN)
+        assertNotNull(PaymentKind.LSP_FEE)
+    }
+
     // ============================================================
     // Invoice appears in list_invoices and list_payments
     // ============================================================
@@ -117,4 +124,17 @@ class ListPaymentTest {
             }
         }
     }
+
+    @Test
+    fun list_payments_since_returns_a_cursor() {
+        val config = Config()
+        NodeBuilder(config).registerOrRecover(testMnemonic, null).use { node ->
+            val first = node.listPaymentsSince()
+            assertTrue(first.cursor.isNotEmpty())
+
+            // Nothing changed in between.
+            val second = node.listPaymentsSince(first.cursor)
+            assertTrue(second.payments.isEmpty())
+        }
+    }
 }
@@ -33,4 +33,19 @@ public fun Node.listPayments(
     includeFailures: Boolean = false,
     offset: UInt? = null,
     limit: UInt? = null,
-): List<Payment> = listPayments(ListPaymentsRequest(filters, fromTimestamp, toTimestamp, includeFailures, offset, limit))
+    statuses: List<PaymentStatus>? = null,
+    kinds: List<PaymentKind>? = null,
+    minAmountMsat: ULong? = null,
+    maxAmountMsat: ULong? = null,
+    cursor: String? = null,
+): List<Payment> = listPayments(
+    ListPaymentsRequest(
+        filters, fromTimestamp, toTimestamp, includeFailures, offset, limit,
+        statuses, kinds, minAmountMsat, maxAmountMsat, cursor,
+    ),
+)
+
+@Throws(Exception::class)
+public fun Node.listPaymentsSince(
+    cursor: String? = null,
+): ListPaymentsSinceResponse = listPaymentsSince(cursor)
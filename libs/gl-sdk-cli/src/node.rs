@@ -1,7 +1,7 @@
 use crate::error::{Error, Result};
 use crate::output::{self, *};
 use crate::util::{self, DataDir};
-use clap::Subcommand;
+use clap::{Args, Subcommand};
 use std::sync::{
     atomic::{AtomicBool, Ordering},
     Arc,
@@ -34,6 +34,28 @@ pub enum Command {
         /// Amount in millisatoshis (for amount-less invoices)
         #[arg(long)]
         amount_msat: Option<u64>,
+        #[command(flatten)]
+        options: SendOptionsArgs,
+    },
+    /// Estimate the fee of paying a lightning invoice, without paying
+    PrepareSend {
+        /// BOLT11 invoice to pay
+        invoice: String,
+        /// Amount in millisatoshis (for amount-less invoices)
+        #[arg(long)]
+        amount_msat: Option<u64>,
+        #[command(flatten)]
+        options: SendOptionsArgs,
+    },
+    /// Send a spontaneous (keysend) payment to a node
+    Keysend {
+        /// Node ID of the recipient (hex)
+        node_id: String,
+        /// Amount in millisatoshis
+        amount_msat: u64,
+        /// Custom TLV record as TYPE=HEXVALUE (repeatable)
+        #[arg(long = "tlv", value_parser = parse_tlv)]
+        tlvs: Vec<glsdk::TlvEntry>,
     },
     /// Get a new on-chain address
     OnchainReceive,
@@ -50,6 +72,52 @@ pub enum Command {
     Stop,
 }
 
+#[derive(Args, Debug)]
+pub struct SendOptionsArgs {
+    /// Maximum fee in millisatoshis
+    #[arg(long, conflicts_with_all = ["max_fee_percent", "exempt_fee_msat"])]
+    max_fee_msat: Option<u64>,
+    /// Maximum fee as a percentage of the amount
+    #[arg(long)]
+    max_fee_percent: Option<f64>,
+    /// Fees below this many millisatoshis are always accepted
+    #[arg(long)]
+    exempt_fee_msat: Option<u64>,
+    /// Give up retrying after this many seconds
+    #[arg(long)]
+    timeout: Option<u32>,
+    /// Node ID or short_channel_id/direction to avoid (repeatable)
+    #[arg(long)]
+    exclude: Vec<String>,
+    /// Label for the payment
+    #[arg(long)]
+    label: Option<String>,
+    /// Only pay through a trampoline peer
+    #[arg(long, conflicts_with = "no_trampoline")]
+    trampoline: bool,
+    /// Never pay through a trampoline peer
+    #[arg(long)]
+    no_trampoline: bool,
+}
+
+impl From<SendOptionsArgs> for glsdk::SendOptions {
+    fn from(a: SendOptionsArgs) -> Self {
+        Self {
+            max_fee_msat: a.max_fee_msat,
+            max_fee_percent: a.max_fee_percent,
+            exempt_fee_msat: a.exempt_fee_msat,
+            timeout_secs: a.timeout,
+            exclude: a.exclude,
+            label: a.label,
+            trampoline: match (a.trampoline, a.no_trampoline) {
+                (true, _) => Some(true),
+                (_, true) => Some(false),
+                _ => None,
+            },
+        }
+    }
+}
+
 pub fn handle(cmd: Command, data_dir: &DataDir) -> Result<()> {
     let creds = util::read_credentials(data_dir)?;
     // CLI wraps an externally-running signer (the gl-client signer
@@ -69,7 +137,18 @@ pub fn handle(cmd: Command, data_dir: &DataDir) -> Result<()> {
         Command::Send {
             invoice,
             amount_msat,
-        } => send(&node, invoice, amount_msat),
+            options,
+        } => send(&node, invoice, amount_msat, options.into()),
+        Command::PrepareSend {
+            invoice,
+            amount_msat,
+            options,
+        } => prepare_send(&node, invoice, amount_msat, options.into()),
+        Command::Keysend {
+            node_id,
+            amount_msat,
+            tlvs,
+        } => keysend(&node, node_id, amount_msat, tlvs),
         Command::OnchainReceive => onchain_receive(&node),
         Command::OnchainSend {
             destination,
@@ -115,12 +194,52 @@ fn receive(
     Ok(())
 }
 
-fn send(node: &glsdk::Node, invoice: String, amount_msat: Option<u64>) -> Result<()> {
-    let res = node.send(invoice, amount_msat)?;
+fn send(
+    node: &glsdk::Node,
+    invoice: String,
+    amount_msat: Option<u64>,
+    options: glsdk::SendOptions,
+) -> Result<()> {
+    let res = node.send(invoice, amount_msat, Some(options))?;
+    output::print_json(&SendOutput::from(res));
+    Ok(())
+}
+
+fn prepare_send(
+    node: &glsdk::Node,
+    invoice: String,
+    amount_msat: Option<u64>,
+    options: glsdk::SendOptions,
+) -> Result<()> {
+    let res = node.prepare_send(invoice, amount_msat, Some(options))?;
+    output::print_json(&PrepareSendOutput::from(res));
+    Ok(())
+}
+
+fn keysend(
+    node: &glsdk::Node,
+    node_id: String,
+    amount_msat: u64,
+    tlvs: Vec<glsdk::TlvEntry>,
+) -> Result<()> {
+    let tlvs = if tlvs.is_empty() { None } else { Some(tlvs) };
+    let res = node.send_spontaneous(node_id, amount_msat, tlvs)?;
     output::print_json(&SendOutput::from(res));
     Ok(())
 }
 
+fn parse_tlv(s: &str) -> std::result::Result<glsdk::TlvEntry, String> {
+    let (typ, value) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected TYPE=HEXVALUE, got {s}"))?;
+    Ok(glsdk::TlvEntry {
+        tlv_type: typ
+            .parse()
+            .map_err(|e| format!("invalid TLV type {typ}: {e}"))?,
+        value: hex::decode(value).map_err(|e| format!("invalid TLV value {value}: {e}"))?,
+    })
+}
+
 fn onchain_receive(node: &glsdk::Node) -> Result<()> {
     let res = node.onchain_receive()?;
     output::print_json(&OnchainReceiveOutput::from(res));
@@ -254,6 +254,8 @@ pub struct SendOutput {
     pub amount_msat: u64,
     pub amount_sent_msat: u64,
     pub parts: u32,
+    pub fee_msat: u64,
+    pub trampoline_node_id: Option<String>,
 }
 
 fn pay_status_str(s: &glsdk::PayStatus) -> &'static str {
@@ -272,6 +274,32 @@ impl From<glsdk::SendResponse> for SendOutput {
             amount_msat: r.amount_msat,
             amount_sent_msat: r.amount_sent_msat,
             parts: r.parts,
+            fee_msat: r.fee_msat,
+            trampoline_node_id: match r.route {
+                glsdk::SendRoute::Regular => None,
+                glsdk::SendRoute::Trampoline { node_id } => Some(node_id),
+            },
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct PrepareSendOutput {
+    pub amount_msat: u64,
+    pub estimated_fee_msat: u64,
+    pub total_msat: u64,
+    pub fee_limit_msat: u64,
+    pub hops: u32,
+}
+
+impl From<glsdk::PreparedSend> for PrepareSendOutput {
+    fn from(r: glsdk::PreparedSend) -> Self {
+        Self {
+            amount_msat: r.amount_msat,
+            estimated_fee_msat: r.estimated_fee_msat,
+            total_msat: r.total_msat,
+            fee_limit_msat: r.fee_limit_msat,
+            hops: r.hops,
         }
     }
 }
@@ -323,6 +351,56 @@ pub enum NodeEventOutput {
         label: String,
         amount_msat: u64,
     },
+    #[serde(rename = "payment_sent")]
+    PaymentSent {
+        payment_hash: String,
+        preimage: String,
+        destination: Option<String>,
+        amount_msat: u64,
+        amount_sent_msat: u64,
+        bolt11: Option<String>,
+    },
+    #[serde(rename = "payment_failed")]
+    PaymentFailed {
+        payment_hash: String,
+        destination: Option<String>,
+        amount_msat: u64,
+        message: String,
+    },
+    #[serde(rename = "channel_opened")]
+    ChannelOpened {
+        peer_id: String,
+        channel_id: String,
+        short_channel_id: Option<String>,
+    },
+    #[serde(rename = "channel_closed")]
+    ChannelClosed {
+        peer_id: String,
+        channel_id: String,
+        short_channel_id: Option<String>,
+        cause: String,
+    },
+    #[serde(rename = "channel_state_changed")]
+    ChannelStateChanged {
+        peer_id: String,
+        channel_id: String,
+        short_channel_id: Option<String>,
+        old_state: Option<String>,
+        new_state: String,
+        cause: String,
+        message: String,
+    },
+    #[serde(rename = "peer_connected")]
+    PeerConnected { peer_id: String },
+    #[serde(rename = "peer_disconnected")]
+    PeerDisconnected { peer_id: String },
+    #[serde(rename = "deposit_confirmed")]
+    DepositConfirmed {
+        txid: String,
+        outnum: u32,
+        amount_msat: u64,
+        blockheight: u32,
+    },
 }
 
 impl From<glsdk::NodeEvent> for NodeEventOutput {
@@ -335,6 +413,54 @@ impl From<glsdk::NodeEvent> for NodeEventOutput {
                 label: details.label,
                 amount_msat: details.amount_msat,
             },
+            glsdk::NodeEvent::PaymentSent { details } => NodeEventOutput::PaymentSent {
+                payment_hash: details.payment_hash,
+                preimage: details.preimage,
+                destination: details.destination,
+                amount_msat: details.amount_msat,
+                amount_sent_msat: details.amount_sent_msat,
+                bolt11: details.bolt11,
+            },
+            glsdk::NodeEvent::PaymentFailed { details } => NodeEventOutput::PaymentFailed {
+                payment_hash: details.payment_hash,
+                destination: details.destination,
+                amount_msat: details.amount_msat,
+                message: details.message,
+            },
+            glsdk::NodeEvent::ChannelOpened { details } => NodeEventOutput::ChannelOpened {
+                peer_id: details.peer_id,
+                channel_id: details.channel_id,
+                short_channel_id: details.short_channel_id,
+            },
+            glsdk::NodeEvent::ChannelClosed { details } => NodeEventOutput::ChannelClosed {
+                peer_id: details.channel.peer_id,
+                channel_id: details.channel.channel_id,
+                short_channel_id: details.channel.short_channel_id,
+                cause: details.cause,
+            },
+            glsdk::NodeEvent::ChannelStateChanged { details } => {
+                NodeEventOutput::ChannelStateChanged {
+                    peer_id: details.channel.peer_id,
+                    channel_id: details.channel.channel_id,
+                    short_channel_id: details.channel.short_channel_id,
+                    old_state: details.old_state,
+                    new_state: details.new_state,
+                    cause: details.cause,
+                    message: details.message,
+                }
+            }
+            glsdk::NodeEvent::PeerConnected { peer_id } => {
+                NodeEventOutput::PeerConnected { peer_id }
+            }
+            glsdk::NodeEvent::PeerDisconnected { peer_id } => {
+                NodeEventOutput::PeerDisconnected { peer_id }
+            }
+            glsdk::NodeEvent::DepositConfirmed { details } => NodeEventOutput::DepositConfirmed {
+                txid: details.txid,
+                outnum: details.outnum,
+                amount_msat: details.amount_msat,
+                blockheight: details.blockheight,
+            },
         }
     }
 }
@@ -1,23 +1,35 @@
 #![deny(clippy::all)]
 
 use napi::bindgen_prelude::*;
+use napi::threadsafe_function::{ErrorStrategy, ThreadsafeFunction, ThreadsafeFunctionCallMode};
 use napi_derive::napi;
 
 // Import from glsdk crate (gl-sdk library)
 use glsdk::{
     // Enum types for conversion
     ChannelState as GlChannelState,
+    Config as GlConfig,
     Credentials as GlCredentials,
     DeveloperCert as GlDeveloperCert,
     Handle as GlHandle,
+    ListIndex as GlListIndex,
+    LogLevel as GlLogLevel,
     ParsedInput as GlParsedInput,
     ResolvedInput as GlResolvedInput,
     Network as GlNetwork,
     Node as GlNode,
+    NodeBuilder as GlNodeBuilder,
     NodeEvent as GlNodeEvent,
+    NodeEventListener as GlNodeEventListener,
     NodeEventStream as GlNodeEventStream,
     OutputStatus as GlOutputStatus,
+    PayStatus as GlPayStatus,
+    PaymentKind as GlPaymentKind,
+    PaymentStatus as GlPaymentStatus,
+    PaymentType as GlPaymentType,
+    PaymentTypeFilter as GlPaymentTypeFilter,
     ParsedInvoice as GlParsedInvoice,
+    ParsedOffer as GlParsedOffer,
     Scheduler as GlScheduler,
     Signer as GlSigner,
 };
@@ -29,6 +41,9 @@ use glsdk::{
 #[napi(object)]
 pub struct ReceiveResponse {
     pub bolt11: String,
+    /// Fee charged by the LSP for opening a JIT channel in
+    /// millisatoshis (as i64 for JS compatibility), 0 if none was needed
+    pub opening_fee_msat: i64,
 }
 
 #[napi(object)]
@@ -45,6 +60,58 @@ pub struct SendResponse {
     /// Amount sent in millisatoshis (as i64 for JS compatibility)
     pub amount_sent_msat: i64,
     pub parts: u32,
+    /// "regular" | "trampoline"
+    pub route: String,
+    /// Trampoline node pubkey as lowercase hex (66 chars). Present
+    /// when route == "trampoline"
+    pub trampoline_node_id: Option<String>,
+    /// Fee paid on top of `amountMsat` in millisatoshis (as i64 for JS compatibility)
+    pub fee_msat: i64,
+}
+
+/// Controls for `Node.send`. Unset fields use the node's defaults.
+#[napi(object)]
+pub struct SendOptions {
+    /// Maximum total fee in millisatoshis (as i64 for JS compatibility).
+    /// Cannot be combined with `maxFeePercent` or `exemptFeeMsat`.
+    pub max_fee_msat: Option<i64>,
+    /// Maximum fee as a percentage of the amount (node default 0.5)
+    pub max_fee_percent: Option<f64>,
+    /// Fees up to this amount in millisatoshis are accepted regardless
+    /// of `maxFeePercent` (node default 5000)
+    pub exempt_fee_msat: Option<i64>,
+    /// Stop retrying along other routes after this many seconds (node default 60).
+    /// Does not apply to trampoline payments
+    pub timeout_secs: Option<u32>,
+    /// Node IDs, or short channel IDs with a direction (e.g.
+    /// `103x1x0/1`), that must not be used to route the payment
+    pub exclude: Option<Vec<String>>,
+    pub label: Option<String>,
+    /// Unset uses a trampoline peer if possible, `false` never uses
+    /// one and `true` fails rather than falling back
+    pub trampoline: Option<bool>,
+}
+
+#[napi(object)]
+pub struct PreparedSend {
+    /// Amount the recipient receives in millisatoshis (as i64 for JS compatibility)
+    pub amount_msat: i64,
+    /// Routing fee of the cheapest route found in millisatoshis (as i64 for JS compatibility)
+    pub estimated_fee_msat: i64,
+    /// `amountMsat` plus `estimatedFeeMsat` (as i64 for JS compatibility)
+    pub total_msat: i64,
+    /// Highest fee `send` accepts with the same options in
+    /// millisatoshis (as i64 for JS compatibility)
+    pub fee_limit_msat: i64,
+    pub hops: u32,
+}
+
+/// A custom TLV record attached to a spontaneous payment.
+#[napi(object)]
+pub struct TlvEntry {
+    /// Custom types must be odd and at least 65536 (as i64 for JS compatibility)
+    pub tlv_type: i64,
+    pub value: Buffer,
 }
 
 #[napi(object)]
@@ -61,6 +128,227 @@ pub struct OnchainReceiveResponse {
     pub p2tr: String,
 }
 
+#[napi(object)]
+pub struct Outpoint {
+    /// Transaction id as lowercase hex (64 chars)
+    pub txid: String,
+    pub vout: u32,
+}
+
+/// Preview of an on-chain send. Amounts are in satoshis.
+#[napi(object)]
+pub struct PreparedOnchainSend {
+    pub utxos: Vec<Outpoint>,
+    /// Sum of the input values in satoshis (as i64 for JS compatibility)
+    pub total_input_sat: i64,
+    /// Fee in satoshis (as i64 for JS compatibility)
+    pub fee_sat: i64,
+    /// Amount the recipient receives in satoshis (as i64 for JS compatibility)
+    pub recipient_sat: i64,
+    /// Effective fee rate; pass it back to `onchainSend` with `utxos`
+    pub sat_per_vbyte: u32,
+}
+
+/// Fee rates in sats per virtual byte (as i64 for JS compatibility).
+#[napi(object)]
+pub struct OnchainFeeRates {
+    pub next_block_sat_per_vbyte: i64,
+    pub half_hour_sat_per_vbyte: i64,
+    pub hour_sat_per_vbyte: i64,
+    pub day_sat_per_vbyte: i64,
+    pub minimum_relay_sat_per_vbyte: i64,
+}
+
+/// Classification of the on-chain wallet. Discriminated by `type`.
+/// Amounts are in satoshis (as i64 for JS compatibility).
+#[napi(object)]
+pub struct OnchainBalanceState {
+    /// "unavailable" | "available" | "reserve_only" |
+    /// "pending_confirmation" | "immature"
+    pub r#type: String,
+    /// Present when type == "available"
+    pub withdrawable_sat: Option<i64>,
+    /// Present when type == "available" or "reserve_only"
+    pub emergency_reserve_sat: Option<i64>,
+    /// Present when type == "available" or "pending_confirmation"
+    pub unconfirmed_sat: Option<i64>,
+    /// Present when type == "immature"
+    pub immature_sat: Option<i64>,
+}
+
+// ============================================================================
+// BOLT12 Offer Types
+// ============================================================================
+
+#[napi(object)]
+pub struct Offer {
+    /// Offer id as lowercase hex (64 chars)
+    pub offer_id: String,
+    pub bolt12: String,
+    pub active: bool,
+    pub single_use: bool,
+    pub used: bool,
+    pub label: Option<String>,
+}
+
+#[napi(object)]
+pub struct ListOffersResponse {
+    pub offers: Vec<Offer>,
+}
+
+// ============================================================================
+// Payment History Types
+// ============================================================================
+
+/// Filters for `Node.listInvoices`. All fields are optional.
+#[derive(Default)]
+#[napi(object)]
+pub struct ListInvoicesRequest {
+    pub label: Option<String>,
+    pub invstring: Option<String>,
+    pub payment_hash: Option<Buffer>,
+    /// Offer id as lowercase hex (64 chars)
+    pub offer_id: Option<String>,
+    /// "created" | "updated"
+    pub index: Option<String>,
+    pub start: Option<i64>,
+    pub limit: Option<u32>,
+}
+
+#[napi(object)]
+pub struct Invoice {
+    pub label: String,
+    pub description: String,
+    /// Payment hash as lowercase hex (64 chars)
+    pub payment_hash: String,
+    /// "unpaid" | "paid" | "expired"
+    pub status: String,
+    /// Amount in millisatoshis (as i64 for JS compatibility)
+    pub amount_msat: Option<i64>,
+    /// Amount received in millisatoshis (as i64 for JS compatibility)
+    pub amount_received_msat: Option<i64>,
+    pub bolt11: Option<String>,
+    pub bolt12: Option<String>,
+    pub paid_at: Option<i64>,
+    pub expires_at: i64,
+    /// Preimage as lowercase hex (64 chars), if paid
+    pub payment_preimage: Option<String>,
+    /// Recipient node pubkey as lowercase hex (66 chars)
+    pub destination_pubkey: Option<String>,
+}
+
+#[napi(object)]
+pub struct ListInvoicesResponse {
+    pub invoices: Vec<Invoice>,
+}
+
+/// Filters for `Node.listPays`. All fields are optional.
+#[derive(Default)]
+#[napi(object)]
+pub struct ListPaysRequest {
+    pub bolt11: Option<String>,
+    pub payment_hash: Option<Buffer>,
+    /// "pending" | "complete" | "failed"
+    pub status: Option<String>,
+    /// "created" | "updated"
+    pub index: Option<String>,
+    pub start: Option<i64>,
+    pub limit: Option<u32>,
+}
+
+#[napi(object)]
+pub struct Pay {
+    /// Payment hash as lowercase hex (64 chars)
+    pub payment_hash: String,
+    /// "pending" | "complete" | "failed"
+    pub status: String,
+    /// Recipient node pubkey as lowercase hex (66 chars), if known
+    pub destination_pubkey: Option<String>,
+    /// Amount in millisatoshis (as i64 for JS compatibility)
+    pub amount_msat: Option<i64>,
+    /// Amount sent in millisatoshis (as i64 for JS compatibility)
+    pub amount_sent_msat: Option<i64>,
+    pub label: Option<String>,
+    pub bolt11: Option<String>,
+    pub description: Option<String>,
+    pub bolt12: Option<String>,
+    /// Preimage as lowercase hex (64 chars), if completed
+    pub preimage: Option<String>,
+    pub created_at: i64,
+    pub completed_at: Option<i64>,
+    pub number_of_parts: Option<i64>,
+}
+
+#[napi(object)]
+pub struct ListPaysResponse {
+    pub pays: Vec<Pay>,
+}
+
+/// Filters for `Node.listPayments`. All fields are optional.
+#[derive(Default)]
+#[napi(object)]
+pub struct ListPaymentsRequest {
+    /// "sent" | "received". Unset or empty returns both
+    pub filters: Option<Vec<String>>,
+    /// Unix timestamp (seconds)
+    pub from_timestamp: Option<i64>,
+    /// Unix timestamp (seconds)
+    pub to_timestamp: Option<i64>,
+    pub include_failures: Option<bool>,
+    /// Applied after `cursor`
+    pub offset: Option<u32>,
+    pub limit: Option<u32>,
+    /// "pending" | "complete" | "failed". Unset or empty returns all but
+    /// failed payments, unless `includeFailures` is set
+    pub statuses: Option<Vec<String>>,
+    /// "lightning" | "onchain" | "lsp_fee". Unset or empty returns
+    /// Lightning payments only
+    pub kinds: Option<Vec<String>>,
+    /// Minimum amount in millisatoshis (as i64 for JS compatibility)
+    pub min_amount_msat: Option<i64>,
+    /// Maximum amount in millisatoshis (as i64 for JS compatibility)
+    pub max_amount_msat: Option<i64>,
+    /// `cursor` of the payment to continue after, e.g. the last one of
+    /// the previous page
+    pub cursor: Option<String>,
+}
+
+#[napi(object)]
+pub struct Payment {
+    pub id: String,
+    /// "sent" | "received"
+    pub payment_type: String,
+    /// Unix timestamp (seconds)
+    pub payment_time: i64,
+    /// Amount in millisatoshis (as i64 for JS compatibility)
+    pub amount_msat: i64,
+    /// Fee in millisatoshis (as i64 for JS compatibility)
+    pub fee_msat: i64,
+    /// "pending" | "complete" | "failed"
+    pub status: String,
+    pub description: Option<String>,
+    pub bolt11: Option<String>,
+    /// Preimage as lowercase hex (64 chars), when known
+    pub preimage: Option<String>,
+    /// Recipient node pubkey as lowercase hex (66 chars), for sent payments
+    pub destination: Option<String>,
+    /// "lightning" | "onchain" | "lsp_fee"
+    pub kind: String,
+    /// Transaction id as lowercase hex, for on-chain payments
+    pub txid: Option<String>,
+    /// Pass as `ListPaymentsRequest.cursor` to list the payments after
+    /// this one. Unset for payments returned by `listPaymentsSince`
+    pub cursor: Option<String>,
+}
+
+#[napi(object)]
+pub struct ListPaymentsSinceResponse {
+    /// Payments created or changed since the cursor, newest first
+    pub payments: Vec<Payment>,
+    /// Pass to the next `listPaymentsSince` call
+    pub cursor: String,
+}
+
 // ============================================================================
 // Event Streaming Response Types
 // ============================================================================
@@ -77,12 +365,118 @@ pub struct InvoicePaidEvent {
     pub amount_msat: i64,
 }
 
+#[napi(object)]
+pub struct PaymentSentEvent {
+    /// Payment hash as lowercase hex (64 chars)
+    pub payment_hash: String,
+    /// Preimage as lowercase hex (64 chars)
+    pub preimage: String,
+    /// Recipient node pubkey as lowercase hex (66 chars), if known
+    pub destination: Option<String>,
+    /// Amount delivered in millisatoshis (as i64 for JS compatibility)
+    pub amount_msat: i64,
+    /// Amount sent including fees in millisatoshis (as i64 for JS compatibility)
+    pub amount_sent_msat: i64,
+    pub bolt11: Option<String>,
+}
+
+#[napi(object)]
+pub struct PaymentFailedEvent {
+    /// Payment hash as lowercase hex (64 chars)
+    pub payment_hash: String,
+    /// Recipient node pubkey as lowercase hex (66 chars), if known
+    pub destination: Option<String>,
+    /// Attempted amount in millisatoshis (as i64 for JS compatibility)
+    pub amount_msat: i64,
+    pub message: String,
+}
+
+#[napi(object)]
+pub struct ChannelEvent {
+    /// Peer node pubkey as lowercase hex (66 chars)
+    pub peer_id: String,
+    /// Channel id as lowercase hex (64 chars)
+    pub channel_id: String,
+    pub short_channel_id: Option<String>,
+}
+
+#[napi(object)]
+pub struct ChannelClosedEvent {
+    pub channel: ChannelEvent,
+    /// "user" | "local" | "remote" | "protocol" | "onchain" | "unknown"
+    pub cause: String,
+}
+
+#[napi(object)]
+pub struct ChannelStateChangedEvent {
+    pub channel: ChannelEvent,
+    pub old_state: Option<String>,
+    pub new_state: String,
+    /// "user" | "local" | "remote" | "protocol" | "onchain" | "unknown"
+    pub cause: String,
+    pub message: String,
+}
+
+#[napi(object)]
+pub struct DepositConfirmedEvent {
+    /// Transaction id as lowercase hex (64 chars)
+    pub txid: String,
+    pub outnum: u32,
+    /// Amount in millisatoshis (as i64 for JS compatibility)
+    pub amount_msat: i64,
+    pub blockheight: u32,
+}
+
 #[napi(object)]
 pub struct NodeEvent {
-    /// Discriminant: "invoice_paid" | "unknown"
+    /// Discriminant: "invoice_paid" | "payment_sent" | "payment_failed" |
+    /// "channel_opened" | "channel_closed" | "channel_state_changed" |
+    /// "peer_connected" | "peer_disconnected" | "deposit_confirmed" | "unknown"
     pub event_type: String,
     /// Present when event_type == "invoice_paid"
     pub invoice_paid: Option<InvoicePaidEvent>,
+    /// Present when event_type == "payment_sent"
+    pub payment_sent: Option<PaymentSentEvent>,
+    /// Present when event_type == "payment_failed"
+    pub payment_failed: Option<PaymentFailedEvent>,
+    /// Present when event_type == "channel_opened"
+    pub channel_opened: Option<ChannelEvent>,
+    /// Present when event_type == "channel_closed"
+    pub channel_closed: Option<ChannelClosedEvent>,
+    /// Present when event_type == "channel_state_changed"
+    pub channel_state_changed: Option<ChannelStateChangedEvent>,
+    /// Peer node pubkey as lowercase hex (66 chars). Present when
+    /// event_type == "peer_connected" or "peer_disconnected"
+    pub peer_id: Option<String>,
+    /// Present when event_type == "deposit_confirmed"
+    pub deposit_confirmed: Option<DepositConfirmedEvent>,
+}
+
+/// Callbacks receiving node events pushed by the SDK, see
+/// `NodeBuilder.withEventListener`. The callbacks run on the JS
+/// event loop and do not keep the process alive on their own.
+#[napi(object, object_to_js = false)]
+pub struct NodeEventListener {
+    #[napi(ts_type = "(event: NodeEvent) => void")]
+    pub on_event: ThreadsafeFunction<NodeEvent, ErrorStrategy::Fatal>,
+    /// Called when the event stream was (re-)established
+    #[napi(ts_type = "() => void")]
+    pub on_connected: Option<ThreadsafeFunction<(), ErrorStrategy::Fatal>>,
+    /// Called with the error when the event stream was interrupted.
+    /// The SDK keeps reconnecting until the node is disconnected
+    #[napi(ts_type = "(reason: string) => void")]
+    pub on_disconnected: Option<ThreadsafeFunction<String, ErrorStrategy::Fatal>>,
+}
+
+#[napi(object)]
+pub struct LogEntry {
+    /// "error" | "warn" | "info" | "debug" | "trace"
+    pub level: String,
+    pub message: String,
+    /// Module that produced the log (e.g. "gl_client::scheduler")
+    pub target: String,
+    pub file: Option<String>,
+    pub line: Option<u32>,
 }
 
 // ============================================================================
@@ -267,15 +661,33 @@ pub struct ParsedInvoice {
     pub timestamp: i64,
 }
 
+#[napi(object)]
+pub struct ParsedOffer {
+    pub bolt12: String,
+    /// Offer id as lowercase hex (64 chars)
+    pub offer_id: String,
+    pub description: Option<String>,
+    pub issuer: Option<String>,
+    /// Amount in millisatoshis (i64 for JS), `None` for any-amount or
+    /// fiat-denominated offers.
+    pub amount_msat: Option<i64>,
+    /// Unix timestamp (seconds) after which the offer expires.
+    pub absolute_expiry: Option<i64>,
+    /// Issuer signing pubkey as lowercase hex (66 chars)
+    pub issuer_pubkey: Option<String>,
+}
+
 /// Result of `parseInput` — offline classification, no HTTP.
 /// Discriminated by `type`. LNURL bech32 strings come back as their
 /// decoded URL; Lightning Addresses as the unparsed `user@host` form.
 #[napi(object)]
 pub struct ParsedInput {
-    /// "bolt11" | "node_id" | "lnurl" | "lnurl_address"
+    /// "bolt11" | "bolt12_offer" | "node_id" | "lnurl" | "lnurl_address"
     pub r#type: String,
     /// Present when type == "bolt11"
     pub bolt11: Option<ParsedInvoice>,
+    /// Present when type == "bolt12_offer"
+    pub bolt12_offer: Option<ParsedOffer>,
     /// Present when type == "node_id"
     pub node_id: Option<String>,
     /// Present when type == "lnurl" — the decoded URL of the LNURL.
@@ -286,19 +698,24 @@ pub struct ParsedInput {
 
 /// Result of `resolveInput` — fully-resolved, may have performed HTTP.
 /// Discriminated by `type`. Exactly one of the variant fields
-/// (`bolt11`, `node_id`, `lnurl_pay`, `lnurl_withdraw`) is populated.
+/// (`bolt11`, `bolt12_offer`, `node_id`, `lnurl_pay`, `lnurl_withdraw`,
+/// `lnurl_auth`) is populated.
 #[napi(object)]
 pub struct ResolvedInput {
-    /// "bolt11" | "node_id" | "lnurl_pay" | "lnurl_withdraw"
+    /// "bolt11" | "bolt12_offer" | "node_id" | "lnurl_pay" | "lnurl_withdraw" | "lnurl_auth"
     pub r#type: String,
     /// Present when type == "bolt11"
     pub bolt11: Option<ParsedInvoice>,
+    /// Present when type == "bolt12_offer"
+    pub bolt12_offer: Option<ParsedOffer>,
     /// Present when type == "node_id"
     pub node_id: Option<String>,
     /// Present when type == "lnurl_pay"
     pub lnurl_pay: Option<LnUrlPayRequestData>,
     /// Present when type == "lnurl_withdraw"
     pub lnurl_withdraw: Option<LnUrlWithdrawRequestData>,
+    /// Present when type == "lnurl_auth"
+    pub lnurl_auth: Option<LnUrlAuthRequestData>,
 }
 
 // ============================================================================
@@ -330,6 +747,15 @@ pub struct LnUrlWithdrawRequestData {
     pub lnurl: String,
 }
 
+#[napi(object)]
+pub struct LnUrlAuthRequestData {
+    pub k1: String,
+    /// "register", "login", "link" or "auth", if announced
+    pub action: Option<String>,
+    pub domain: String,
+    pub url: String,
+}
+
 #[napi(object)]
 pub struct LnUrlPayRequest {
     pub data: LnUrlPayRequestData,
@@ -395,6 +821,15 @@ pub struct LnUrlWithdrawResult {
     pub error: Option<LnUrlErrorData>,
 }
 
+/// Result of an LNURL-auth login. Discriminated by `type` field.
+#[napi(object)]
+pub struct LnUrlCallbackStatus {
+    /// "ok" or "error"
+    pub r#type: String,
+    /// Present when type == "error"
+    pub error: Option<LnUrlErrorData>,
+}
+
 /// Processed success action. Discriminated by `type` field.
 #[napi(object)]
 pub struct SuccessActionProcessed {
@@ -429,6 +864,16 @@ pub struct Scheduler {
     inner: GlScheduler,
 }
 
+#[napi]
+pub struct Config {
+    inner: std::sync::Arc<GlConfig>,
+}
+
+#[napi]
+pub struct NodeBuilder {
+    inner: std::sync::Arc<GlNodeBuilder>,
+}
+
 #[napi]
 pub struct Signer {
     inner: GlSigner,
@@ -495,109 +940,315 @@ impl Credentials {
 
         Ok(Buffer::from(bytes))
     }
+
+    /// Load credentials saved with `saveEncrypted`
+    ///
+    /// # Arguments
+    /// * `raw` - Encrypted credentials bytes
+    /// * `passphrase` - Passphrase used when saving
+    #[napi(factory)]
+    pub async fn load_encrypted(raw: Buffer, passphrase: String) -> Result<Credentials> {
+        let bytes = raw.to_vec();
+        let inner = tokio::task::spawn_blocking(move || {
+            GlCredentials::load_encrypted(bytes, passphrase)
+                .map_err(|e| Error::from_reason(e.to_string()))
+        })
+        .await
+        .map_err(|e| Error::from_reason(e.to_string()))??;
+
+        Ok(Self { inner })
+    }
+
+    /// Save credentials to bytes encrypted with `passphrase`
+    #[napi]
+    pub async fn save_encrypted(&self, passphrase: String) -> Result<Buffer> {
+        let inner = self.inner.clone();
+        let bytes = tokio::task::spawn_blocking(move || {
+            inner
+                .save_encrypted(passphrase)
+                .map_err(|e| Error::from_reason(e.to_string()))
+        })
+        .await
+        .map_err(|e| Error::from_reason(e.to_string()))??;
+
+        Ok(Buffer::from(bytes))
+    }
+
+    /// Get the node ID these credentials belong to
+    /// (stays sync — pure in-memory computation, no I/O)
+    #[napi]
+    pub fn node_id(&self) -> Result<Buffer> {
+        self.inner
+            .node_id()
+            .map(Buffer::from)
+            .map_err(|e| Error::from_reason(e.to_string()))
+    }
 }
 
 #[napi]
-impl Scheduler {
-    /// Create a new scheduler client
+impl Config {
+    /// Create a config for the "bitcoin" network without a developer
+    /// certificate
+    #[napi(constructor)]
+    pub fn new() -> Self {
+        Self {
+            inner: std::sync::Arc::new(GlConfig::new()),
+        }
+    }
+
+    /// Return a new Config with the given developer certificate
+    #[napi]
+    pub fn with_developer_cert(&self, cert: &DeveloperCert) -> Config {
+        Config {
+            inner: self.inner.with_developer_cert(&cert.inner),
+        }
+    }
+
+    /// Return a new Config with the given network
     ///
     /// # Arguments
     /// * `network` - Network name ("bitcoin" or "regtest")
+    #[napi]
+    pub fn with_network(&self, network: String) -> Result<Config> {
+        Ok(Config {
+            inner: self.inner.with_network(gl_network_from_str(&network)?),
+        })
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[napi]
+impl NodeBuilder {
+    /// Create a builder for Nodes with `config`. No I/O happens until
+    /// `connect` / `register` / `recover` / `registerOrRecover`.
     #[napi(constructor)]
-    pub fn new(network: String) -> Result<Self> {
-        // Constructor stays sync — it's just parsing a string and initialising a struct
-        let gl_network = match network.to_lowercase().as_str() {
-            "bitcoin" => GlNetwork::BITCOIN,
-            "regtest" => GlNetwork::REGTEST,
-            _ => {
-                return Err(Error::from_reason(format!(
-                    "Invalid network: {}. Must be 'bitcoin' or 'regtest'",
-                    network
-                )))
-            }
-        };
+    pub fn new(config: &Config) -> Self {
+        Self {
+            inner: GlNodeBuilder::new(&config.inner),
+        }
+    }
 
-        let inner = GlScheduler::new(gl_network).map_err(|e| Error::from_reason(e.to_string()))?;
+    /// Return a new builder that delivers node events to `listener`
+    /// from the moment the node is built.
+    #[napi]
+    pub fn with_event_listener(
+        &self,
+        env: Env,
+        mut listener: NodeEventListener,
+    ) -> Result<NodeBuilder> {
+        listener.on_event.unref(&env)?;
+        if let Some(f) = listener.on_connected.as_mut() {
+            f.unref(&env)?;
+        }
+        if let Some(f) = listener.on_disconnected.as_mut() {
+            f.unref(&env)?;
+        }
+        Ok(NodeBuilder {
+            inner: self.inner.clone().with_event_listener(Box::new(listener)),
+        })
+    }
 
-        Ok(Self { inner })
+    /// Return a new builder that replays the events retained after
+    /// index `since` to the event listener before new ones. Pass the
+    /// `Node.lastEventIndex()` persisted in a previous session.
+    #[napi]
+    pub fn with_event_cursor(&self, since: i64) -> Result<NodeBuilder> {
+        let since = u64_from_napi("since", since)?;
+        Ok(NodeBuilder {
+            inner: self.inner.clone().with_event_cursor(since),
+        })
     }
 
-    /// Configure a developer certificate obtained from the Greenlight
-    /// Developer Console. Nodes registered through this scheduler
-    /// will be associated with the developer's account.
-    ///
-    /// Returns a new Scheduler instance with the developer certificate
-    /// configured.
-    ///
-    /// # Arguments
-    /// * `cert` - Developer certificate from the Greenlight Developer Console
+    /// Return a new builder whose Nodes reject calls that spend,
+    /// receive or otherwise change the node
     #[napi]
-    pub fn with_developer_cert(&self, cert: &DeveloperCert) -> Scheduler {
-        let inner = self.inner.with_developer_cert(&cert.inner);
-        Scheduler { inner }
+    pub fn with_read_only(&self) -> NodeBuilder {
+        NodeBuilder {
+            inner: self.inner.clone().with_read_only(),
+        }
     }
 
-    /// Register a new node with the scheduler
+    /// Register a new node and return it connected, with the SDK
+    /// signer running
     ///
     /// # Arguments
-    /// * `signer` - The signer instance
-    /// * `code` - Optional invite code
+    /// * `mnemonic` - BIP39 mnemonic phrase
+    /// * `invite_code` - Optional invite code
     #[napi]
-    pub async fn register(&self, signer: &Signer, code: Option<String>) -> Result<Credentials> {
-        let inner_scheduler = self.inner.clone();
-        let inner_signer = signer.inner.clone();
+    pub async fn register(&self, mnemonic: String, invite_code: Option<String>) -> Result<Node> {
+        let builder = self.inner.clone();
         let inner = tokio::task::spawn_blocking(move || {
-            inner_scheduler
-                .register(&inner_signer, code)
+            builder
+                .register(mnemonic, invite_code)
                 .map_err(|e| Error::from_reason(e.to_string()))
         })
         .await
         .map_err(|e| Error::from_reason(e.to_string()))??;
 
-        Ok(Credentials { inner })
+        Ok(Node { inner })
     }
 
-    /// Recover node credentials
+    /// Recover an existing node and return it connected, with the SDK
+    /// signer running
     ///
     /// # Arguments
-    /// * `signer` - The signer instance
+    /// * `mnemonic` - BIP39 mnemonic phrase
     #[napi]
-    pub async fn recover(&self, signer: &Signer) -> Result<Credentials> {
-        let inner_scheduler = self.inner.clone();
-        let inner_signer = signer.inner.clone();
+    pub async fn recover(&self, mnemonic: String) -> Result<Node> {
+        let builder = self.inner.clone();
         let inner = tokio::task::spawn_blocking(move || {
-            inner_scheduler
-                .recover(&inner_signer)
+            builder
+                .recover(mnemonic)
                 .map_err(|e| Error::from_reason(e.to_string()))
         })
         .await
         .map_err(|e| Error::from_reason(e.to_string()))??;
 
-        Ok(Credentials { inner })
+        Ok(Node { inner })
     }
-}
 
-#[napi]
-impl Signer {
-    /// Create a new signer from a BIP39 mnemonic phrase
+    /// Connect to an existing node with saved credentials
     ///
     /// # Arguments
-    /// * `phrase` - BIP39 mnemonic phrase (12 or 24 words)
-    #[napi(constructor)]
-    pub fn new(phrase: String) -> Result<Self> {
-        // Constructor stays sync — pure key derivation, no I/O
-        let inner = GlSigner::new(phrase).map_err(|e| Error::from_reason(e.to_string()))?;
+    /// * `credentials` - Bytes from `Node.credentials()` or `Credentials.save()`
+    /// * `mnemonic` - If set, the SDK runs a signer for the node;
+    ///   otherwise signing happens elsewhere
+    #[napi]
+    pub async fn connect(&self, credentials: Buffer, mnemonic: Option<String>) -> Result<Node> {
+        let builder = self.inner.clone();
+        let credentials = credentials.to_vec();
+        let inner = tokio::task::spawn_blocking(move || {
+            builder
+                .connect(credentials, mnemonic)
+                .map_err(|e| Error::from_reason(e.to_string()))
+        })
+        .await
+        .map_err(|e| Error::from_reason(e.to_string()))??;
 
-        Ok(Self { inner })
+        Ok(Node { inner })
     }
 
-    /// Authenticate the signer with credentials
+    /// Recover the node, or register it if it does not exist yet
     ///
     /// # Arguments
-    /// * `credentials` - Device credentials from registration
+    /// * `mnemonic` - BIP39 mnemonic phrase
+    /// * `invite_code` - Optional invite code, used when registering
     #[napi]
-    pub async fn authenticate(&self, credentials: &Credentials) -> Result<Signer> {
-        let inner_signer = self.inner.clone();
+    pub async fn register_or_recover(
+        &self,
+        mnemonic: String,
+        invite_code: Option<String>,
+    ) -> Result<Node> {
+        let builder = self.inner.clone();
+        let inner = tokio::task::spawn_blocking(move || {
+            builder
+                .register_or_recover(mnemonic, invite_code)
+                .map_err(|e| Error::from_reason(e.to_string()))
+        })
+        .await
+        .map_err(|e| Error::from_reason(e.to_string()))??;
+
+        Ok(Node { inner })
+    }
+}
+
+#[napi]
+impl Scheduler {
+    /// Create a new scheduler client
+    ///
+    /// # Arguments
+    /// * `network` - Network name ("bitcoin" or "regtest")
+    #[napi(constructor)]
+    pub fn new(network: String) -> Result<Self> {
+        // Constructor stays sync — it's just parsing a string and initialising a struct
+        let gl_network = gl_network_from_str(&network)?;
+
+        let inner = GlScheduler::new(gl_network).map_err(|e| Error::from_reason(e.to_string()))?;
+
+        Ok(Self { inner })
+    }
+
+    /// Configure a developer certificate obtained from the Greenlight
+    /// Developer Console. Nodes registered through this scheduler
+    /// will be associated with the developer's account.
+    ///
+    /// Returns a new Scheduler instance with the developer certificate
+    /// configured.
+    ///
+    /// # Arguments
+    /// * `cert` - Developer certificate from the Greenlight Developer Console
+    #[napi]
+    pub fn with_developer_cert(&self, cert: &DeveloperCert) -> Scheduler {
+        let inner = self.inner.with_developer_cert(&cert.inner);
+        Scheduler { inner }
+    }
+
+    /// Register a new node with the scheduler
+    ///
+    /// # Arguments
+    /// * `signer` - The signer instance
+    /// * `code` - Optional invite code
+    #[napi]
+    pub async fn register(&self, signer: &Signer, code: Option<String>) -> Result<Credentials> {
+        let inner_scheduler = self.inner.clone();
+        let inner_signer = signer.inner.clone();
+        let inner = tokio::task::spawn_blocking(move || {
+            inner_scheduler
+                .register(&inner_signer, code)
+                .map_err(|e| Error::from_reason(e.to_string()))
+        })
+        .await
+        .map_err(|e| Error::from_reason(e.to_string()))??;
+
+        Ok(Credentials { inner })
+    }
+
+    /// Recover node credentials
+    ///
+    /// # Arguments
+    /// * `signer` - The signer instance
+    #[napi]
+    pub async fn recover(&self, signer: &Signer) -> Result<Credentials> {
+        let inner_scheduler = self.inner.clone();
+        let inner_signer = signer.inner.clone();
+        let inner = tokio::task::spawn_blocking(move || {
+            inner_scheduler
+                .recover(&inner_signer)
+                .map_err(|e| Error::from_reason(e.to_string()))
+        })
+        .await
+        .map_err(|e| Error::from_reason(e.to_string()))??;
+
+        Ok(Credentials { inner })
+    }
+}
+
+#[napi]
+impl Signer {
+    /// Create a new signer from a BIP39 mnemonic phrase
+    ///
+    /// # Arguments
+    /// * `phrase` - BIP39 mnemonic phrase (12 or 24 words)
+    #[napi(constructor)]
+    pub fn new(phrase: String) -> Result<Self> {
+        // Constructor stays sync — pure key derivation, no I/O
+        let inner = GlSigner::new(phrase).map_err(|e| Error::from_reason(e.to_string()))?;
+
+        Ok(Self { inner })
+    }
+
+    /// Authenticate the signer with credentials
+    ///
+    /// # Arguments
+    /// * `credentials` - Device credentials from registration
+    #[napi]
+    pub async fn authenticate(&self, credentials: &Credentials) -> Result<Signer> {
+        let inner_signer = self.inner.clone();
         let inner_creds = credentials.inner.clone();
         let inner = tokio::task::spawn_blocking(move || {
             inner_signer
@@ -663,6 +1314,14 @@ impl NodeEventStream {
         .await
         .map_err(|e| Error::from_reason(e.to_string()))?
     }
+
+    /// Index of the last event received from the node (as i64 for JS
+    /// compatibility). Pass it to `Node.streamNodeEventsSince()` to
+    /// resume the stream after a disconnect.
+    #[napi]
+    pub fn last_index(&self) -> Option<i64> {
+        self.inner.last_index().map(|i| i as i64)
+    }
 }
 
 #[napi]
@@ -698,6 +1357,52 @@ impl Node {
         .map_err(|e| Error::from_reason(e.to_string()))?
     }
 
+    /// Serialized credentials of this node. Persist them and pass
+    /// them to `NodeBuilder.connect()` on the next launch.
+    #[napi]
+    pub fn credentials(&self) -> Result<Buffer> {
+        self.inner
+            .credentials()
+            .map(Buffer::from)
+            .map_err(|e| Error::from_reason(e.to_string()))
+    }
+
+    /// Create credentials for a device that can only read from this
+    /// node. Connect with them via `NodeBuilder.withReadOnly()`.
+    ///
+    /// # Arguments
+    /// * `device_name` - Name of the new device
+    #[napi]
+    pub async fn derive_readonly_credentials(&self, device_name: String) -> Result<Credentials> {
+        let inner = self.inner.clone();
+        let creds = tokio::task::spawn_blocking(move || {
+            inner
+                .derive_readonly_credentials(device_name)
+                .map_err(|e| Error::from_reason(e.to_string()))
+        })
+        .await
+        .map_err(|e| Error::from_reason(e.to_string()))??;
+
+        Ok(Credentials {
+            inner: (*creds).clone(),
+        })
+    }
+
+    /// Whether the node was built with `NodeBuilder.withReadOnly()`
+    #[napi]
+    pub fn is_read_only(&self) -> bool {
+        self.inner.is_read_only()
+    }
+
+    /// Disconnect from the node, stop the SDK signer if running and
+    /// release the event listener. Safe to call multiple times.
+    #[napi]
+    pub fn disconnect(&self) -> Result<()> {
+        self.inner
+            .disconnect()
+            .map_err(|e| Error::from_reason(e.to_string()))
+    }
+
     /// Receive a payment (generate invoice with JIT channel support)
     ///
     /// # Arguments
@@ -712,7 +1417,9 @@ impl Node {
         amount_msat: Option<i64>,
     ) -> Result<ReceiveResponse> {
         let inner = self.inner.clone();
-        let amount = amount_msat.map(|a| a as u64);
+        let amount = amount_msat
+            .map(|a| u64_from_napi("amount_msat", a))
+            .transpose()?;
         let response = tokio::task::spawn_blocking(move || {
             inner
                 .receive(label, description, amount)
@@ -723,6 +1430,7 @@ impl Node {
 
         Ok(ReceiveResponse {
             bolt11: response.bolt11,
+            opening_fee_msat: response.opening_fee_msat as i64,
         })
     }
 
@@ -731,44 +1439,132 @@ impl Node {
     /// # Arguments
     /// * `invoice` - BOLT11 invoice string
     /// * `amount_msat` - Optional amount for zero-amount invoices
+    /// * `options` - Optional fee limits, timeout, exclusions and trampoline use
     #[napi]
-    pub async fn send(&self, invoice: String, amount_msat: Option<i64>) -> Result<SendResponse> {
+    pub async fn send(
+        &self,
+        invoice: String,
+        amount_msat: Option<i64>,
+        options: Option<SendOptions>,
+    ) -> Result<SendResponse> {
         let inner = self.inner.clone();
-        let amount = amount_msat.map(|a| a as u64);
+        let amount = amount_msat
+            .map(|a| u64_from_napi("amount_msat", a))
+            .transpose()?;
+        let options = options.map(gl_send_options_from_napi).transpose()?;
         let response = tokio::task::spawn_blocking(move || {
             inner
-                .send(invoice, amount)
+                .send(invoice, amount, options)
                 .map_err(|e| Error::from_reason(e.to_string()))
         })
         .await
         .map_err(|e| Error::from_reason(e.to_string()))??;
 
-        Ok(SendResponse {
-            status: response.status as u32,
-            preimage: response.preimage,
-            payment_hash: response.payment_hash,
-            destination_pubkey: response.destination_pubkey,
+        Ok(napi_send_response_from_gl(response))
+    }
+
+    /// Estimate the fee of paying a BOLT11 invoice, without paying it
+    ///
+    /// Takes the same arguments as `send`.
+    #[napi]
+    pub async fn prepare_send(
+        &self,
+        invoice: String,
+        amount_msat: Option<i64>,
+        options: Option<SendOptions>,
+    ) -> Result<PreparedSend> {
+        let inner = self.inner.clone();
+        let amount = amount_msat
+            .map(|a| u64_from_napi("amount_msat", a))
+            .transpose()?;
+        let options = options.map(gl_send_options_from_napi).transpose()?;
+        let response = tokio::task::spawn_blocking(move || {
+            inner
+                .prepare_send(invoice, amount, options)
+                .map_err(|e| Error::from_reason(e.to_string()))
+        })
+        .await
+        .map_err(|e| Error::from_reason(e.to_string()))??;
+
+        Ok(PreparedSend {
             amount_msat: response.amount_msat as i64,
-            amount_sent_msat: response.amount_sent_msat as i64,
-            parts: response.parts,
+            estimated_fee_msat: response.estimated_fee_msat as i64,
+            total_msat: response.total_msat as i64,
+            fee_limit_msat: response.fee_limit_msat as i64,
+            hops: response.hops,
         })
     }
 
+    /// Send a spontaneous (keysend) payment directly to a node
+    ///
+    /// # Arguments
+    /// * `node_id` - Recipient node pubkey as hex (66 chars)
+    /// * `amount_msat` - Amount in millisatoshis
+    /// * `extra_tlvs` - Optional custom TLV records, e.g. for podcasting boosts
+    #[napi]
+    pub async fn send_spontaneous(
+        &self,
+        node_id: String,
+        amount_msat: i64,
+        extra_tlvs: Option<Vec<TlvEntry>>,
+    ) -> Result<SendResponse> {
+        let inner = self.inner.clone();
+        let amount_msat = u64_from_napi("amount_msat", amount_msat)?;
+        let extra_tlvs = extra_tlvs
+            .map(|tlvs| {
+                tlvs.into_iter()
+                    .map(|t| {
+                        let entry = glsdk::TlvEntry {
+                            tlv_type: u64_from_napi("tlv_type", t.tlv_type)?,
+                            value: t.value.to_vec(),
+                        };
+                        entry
+                            .validate()
+                            .map_err(|e| Error::from_reason(e.to_string()))?;
+                        Ok(entry)
+                    })
+                    .collect::<Result<Vec<_>>>()
+            })
+            .transpose()?;
+        let response = tokio::task::spawn_blocking(move || {
+            inner
+                .send_spontaneous(node_id, amount_msat, extra_tlvs)
+                .map_err(|e| Error::from_reason(e.to_string()))
+        })
+        .await
+        .map_err(|e| Error::from_reason(e.to_string()))??;
+
+        Ok(napi_send_response_from_gl(response))
+    }
+
     /// Send an on-chain transaction
     ///
     /// # Arguments
     /// * `destination` - Bitcoin address
     /// * `amount_or_all` - Amount (e.g., "10000sat", "1000msat") or "all"
+    /// * `sat_per_vbyte` - Optional fee rate, e.g. from `prepareOnchainSend`
+    /// * `utxos` - Optional inputs to spend, e.g. from `prepareOnchainSend`
     #[napi]
     pub async fn onchain_send(
         &self,
         destination: String,
         amount_or_all: String,
+        sat_per_vbyte: Option<u32>,
+        utxos: Option<Vec<Outpoint>>,
     ) -> Result<OnchainSendResponse> {
         let inner = self.inner.clone();
+        let utxos = utxos.map(|utxos| {
+            utxos
+                .into_iter()
+                .map(|u| glsdk::Outpoint {
+                    txid: u.txid,
+                    vout: u.vout,
+                })
+                .collect()
+        });
         let response = tokio::task::spawn_blocking(move || {
             inner
-                .onchain_send(destination, amount_or_all, None, None)
+                .onchain_send(destination, amount_or_all, sat_per_vbyte, utxos)
                 .map_err(|e| Error::from_reason(e.to_string()))
         })
         .await
@@ -781,6 +1577,78 @@ impl Node {
         })
     }
 
+    /// Preview an on-chain send without broadcasting or reserving UTXOs
+    ///
+    /// Pass the returned `utxos` and `satPerVbyte` to `onchainSend` to
+    /// broadcast with the previewed inputs and fee.
+    #[napi]
+    pub async fn prepare_onchain_send(
+        &self,
+        destination: String,
+        amount_or_all: String,
+        sat_per_vbyte: Option<u32>,
+    ) -> Result<PreparedOnchainSend> {
+        let inner = self.inner.clone();
+        let response = tokio::task::spawn_blocking(move || {
+            inner
+                .prepare_onchain_send(destination, amount_or_all, sat_per_vbyte)
+                .map_err(|e| Error::from_reason(e.to_string()))
+        })
+        .await
+        .map_err(|e| Error::from_reason(e.to_string()))??;
+
+        Ok(PreparedOnchainSend {
+            utxos: response
+                .utxos
+                .into_iter()
+                .map(|u| Outpoint {
+                    txid: u.txid,
+                    vout: u.vout,
+                })
+                .collect(),
+            total_input_sat: response.total_input_sat as i64,
+            fee_sat: response.fee_sat as i64,
+            recipient_sat: response.recipient_sat as i64,
+            sat_per_vbyte: response.sat_per_vbyte,
+        })
+    }
+
+    /// Classify the on-chain wallet for a withdraw entry point
+    #[napi]
+    pub async fn onchain_balance_state(&self) -> Result<OnchainBalanceState> {
+        let inner = self.inner.clone();
+        let response = tokio::task::spawn_blocking(move || {
+            inner
+                .onchain_balance_state()
+                .map_err(|e| Error::from_reason(e.to_string()))
+        })
+        .await
+        .map_err(|e| Error::from_reason(e.to_string()))??;
+
+        Ok(napi_onchain_balance_state_from_gl(response))
+    }
+
+    /// On-chain fee rates at several confirmation targets
+    #[napi]
+    pub async fn onchain_fee_rates(&self) -> Result<OnchainFeeRates> {
+        let inner = self.inner.clone();
+        let response = tokio::task::spawn_blocking(move || {
+            inner
+                .onchain_fee_rates()
+                .map_err(|e| Error::from_reason(e.to_string()))
+        })
+        .await
+        .map_err(|e| Error::from_reason(e.to_string()))??;
+
+        Ok(OnchainFeeRates {
+            next_block_sat_per_vbyte: response.next_block_sat_per_vbyte as i64,
+            half_hour_sat_per_vbyte: response.half_hour_sat_per_vbyte as i64,
+            hour_sat_per_vbyte: response.hour_sat_per_vbyte as i64,
+            day_sat_per_vbyte: response.day_sat_per_vbyte as i64,
+            minimum_relay_sat_per_vbyte: response.minimum_relay_sat_per_vbyte as i64,
+        })
+    }
+
     /// Generate a new on-chain address
     #[napi]
     pub async fn onchain_receive(&self) -> Result<OnchainReceiveResponse> {
@@ -822,6 +1690,35 @@ impl Node {
         Ok(NodeEventStream { inner: gl_stream })
     }
 
+    /// Stream node events, starting with the events the node retained
+    /// after the event with index `since`.
+    ///
+    /// Pass the `lastIndex()` of a previous stream to receive the
+    /// events emitted while disconnected, or `0` to replay all
+    /// retained events.
+    #[napi]
+    pub async fn stream_node_events_since(&self, since: i64) -> Result<NodeEventStream> {
+        let since = u64_from_napi("since", since)?;
+        let inner = self.inner.clone();
+        let gl_stream = tokio::task::spawn_blocking(move || {
+            inner
+                .stream_node_events_since(since)
+                .map_err(|e| Error::from_reason(e.to_string()))
+        })
+        .await
+        .map_err(|e| Error::from_reason(e.to_string()))??;
+
+        Ok(NodeEventStream { inner: gl_stream })
+    }
+
+    /// Index of the last event delivered to the event listener (as
+    /// i64 for JS compatibility). Persist it and pass it to
+    /// `NodeBuilder.withEventCursor()` in a later session.
+    #[napi]
+    pub fn last_event_index(&self) -> Option<i64> {
+        self.inner.last_event_index().map(|i| i as i64)
+    }
+
     /// Get information about the node
     ///
     /// Returns basic information about the node including its ID,
@@ -1017,6 +1914,287 @@ impl Node {
         })
     }
 
+    /// List invoices (received payment requests)
+    ///
+    /// All filters are optional; pass nothing to fetch all.
+    #[napi]
+    pub async fn list_invoices(
+        &self,
+        request: Option<ListInvoicesRequest>,
+    ) -> Result<ListInvoicesResponse> {
+        let inner = self.inner.clone();
+        let req = request.unwrap_or_default();
+        let index = req
+            .index
+            .as_deref()
+            .map(gl_list_index_from_str)
+            .transpose()?;
+        let start = req.start.map(|v| u64_from_napi("start", v)).transpose()?;
+        let response = tokio::task::spawn_blocking(move || {
+            inner
+                .list_invoices(
+                    req.label,
+                    req.invstring,
+                    req.payment_hash.map(|h| h.to_vec()),
+                    req.offer_id,
+                    index,
+                    start,
+                    req.limit,
+                )
+                .map_err(|e| Error::from_reason(e.to_string()))
+        })
+        .await
+        .map_err(|e| Error::from_reason(e.to_string()))??;
+
+        Ok(ListInvoicesResponse {
+            invoices: response
+                .invoices
+                .into_iter()
+                .map(|i| Invoice {
+                    label: i.label,
+                    description: i.description,
+                    payment_hash: i.payment_hash,
+                    status: invoice_status_to_string(&i.status),
+                    amount_msat: i.amount_msat.map(|v| v as i64),
+                    amount_received_msat: i.amount_received_msat.map(|v| v as i64),
+                    bolt11: i.bolt11,
+                    bolt12: i.bolt12,
+                    paid_at: i.paid_at.map(|v| v as i64),
+                    expires_at: i.expires_at as i64,
+                    payment_preimage: i.payment_preimage,
+                    destination_pubkey: i.destination_pubkey,
+                })
+                .collect(),
+        })
+    }
+
+    /// List outgoing payments
+    ///
+    /// All filters are optional; pass nothing to fetch all.
+    #[napi]
+    pub async fn list_pays(&self, request: Option<ListPaysRequest>) -> Result<ListPaysResponse> {
+        let inner = self.inner.clone();
+        let req = request.unwrap_or_default();
+        let status = req
+            .status
+            .as_deref()
+            .map(gl_pay_status_from_str)
+            .transpose()?;
+        let index = req
+            .index
+            .as_deref()
+            .map(gl_list_index_from_str)
+            .transpose()?;
+        let start = req.start.map(|v| u64_from_napi("start", v)).transpose()?;
+        let response = tokio::task::spawn_blocking(move || {
+            inner
+                .list_pays(
+                    req.bolt11,
+                    req.payment_hash.map(|h| h.to_vec()),
+                    status,
+                    index,
+                    start,
+                    req.limit,
+                )
+                .map_err(|e| Error::from_reason(e.to_string()))
+        })
+        .await
+        .map_err(|e| Error::from_reason(e.to_string()))??;
+
+        Ok(ListPaysResponse {
+            pays: response
+                .pays
+                .into_iter()
+                .map(|p| Pay {
+                    payment_hash: p.payment_hash,
+                    status: pay_status_to_string(&p.status),
+                    destination_pubkey: p.destination_pubkey,
+                    amount_msat: p.amount_msat.map(|v| v as i64),
+                    amount_sent_msat: p.amount_sent_msat.map(|v| v as i64),
+                    label: p.label,
+                    bolt11: p.bolt11,
+                    description: p.description,
+                    bolt12: p.bolt12,
+                    preimage: p.preimage,
+                    created_at: p.created_at as i64,
+                    completed_at: p.completed_at.map(|v| v as i64),
+                    number_of_parts: p.number_of_parts.map(|v| v as i64),
+                })
+                .collect(),
+        })
+    }
+
+    /// List sent and received payments in a single timeline, newest first
+    #[napi]
+    pub async fn list_payments(
+        &self,
+        request: Option<ListPaymentsRequest>,
+    ) -> Result<Vec<Payment>> {
+        let inner = self.inner.clone();
+        let req = gl_list_payments_request_from_napi(request.unwrap_or_default())?;
+        let payments = tokio::task::spawn_blocking(move || {
+            inner
+                .list_payments(req)
+                .map_err(|e| Error::from_reason(e.to_string()))
+        })
+        .await
+        .map_err(|e| Error::from_reason(e.to_string()))??;
+
+        Ok(payments.into_iter().map(napi_payment_from_gl).collect())
+    }
+
+    /// List the payments created or changed since `cursor`, to keep a
+    /// local copy of the payment history up to date. Pass no cursor
+    /// to list all payments
+    #[napi]
+    pub async fn list_payments_since(
+        &self,
+        cursor: Option<String>,
+    ) -> Result<ListPaymentsSinceResponse> {
+        let inner = self.inner.clone();
+        let res = tokio::task::spawn_blocking(move || {
+            inner
+                .list_payments_since(cursor)
+                .map_err(|e| Error::from_reason(e.to_string()))
+        })
+        .await
+        .map_err(|e| Error::from_reason(e.to_string()))??;
+
+        Ok(ListPaymentsSinceResponse {
+            payments: res.payments.into_iter().map(napi_payment_from_gl).collect(),
+            cursor: res.cursor,
+        })
+    }
+
+    /// Collect a diagnostic snapshot of the node and SDK state as a
+    /// pretty-printed JSON string, e.g. for support tickets. Payment
+    /// history is excluded.
+    #[napi]
+    pub async fn generate_diagnostic_data(&self) -> Result<String> {
+        let inner = self.inner.clone();
+        tokio::task::spawn_blocking(move || {
+            inner
+                .generate_diagnostic_data()
+                .map_err(|e| Error::from_reason(e.to_string()))
+        })
+        .await
+        .map_err(|e| Error::from_reason(e.to_string()))?
+    }
+
+    // ── BOLT12 offer methods ────────────────────────────────────
+
+    /// Create a BOLT12 offer, a reusable payment code
+    ///
+    /// # Arguments
+    /// * `amount_msat` - Amount per payment, or unset for "any amount"
+    /// * `description` - Shown to the payer, required with an amount
+    /// * `label` - Internal label, not shown to payers
+    /// * `single_use` - Disable the offer after the first payment
+    #[napi]
+    pub async fn create_offer(
+        &self,
+        amount_msat: Option<i64>,
+        description: Option<String>,
+        label: Option<String>,
+        single_use: Option<bool>,
+    ) -> Result<Offer> {
+        let inner = self.inner.clone();
+        let amount = amount_msat
+            .map(|a| u64_from_napi("amount_msat", a))
+            .transpose()?;
+        let offer = tokio::task::spawn_blocking(move || {
+            inner
+                .create_offer(amount, description, label, single_use)
+                .map_err(|e| Error::from_reason(e.to_string()))
+        })
+        .await
+        .map_err(|e| Error::from_reason(e.to_string()))??;
+
+        Ok(napi_offer_from_gl(offer))
+    }
+
+    /// List the BOLT12 offers created by this node
+    ///
+    /// # Arguments
+    /// * `active_only` - Skip disabled and used single-use offers
+    #[napi]
+    pub async fn list_offers(&self, active_only: Option<bool>) -> Result<ListOffersResponse> {
+        let inner = self.inner.clone();
+        let response = tokio::task::spawn_blocking(move || {
+            inner
+                .list_offers(active_only)
+                .map_err(|e| Error::from_reason(e.to_string()))
+        })
+        .await
+        .map_err(|e| Error::from_reason(e.to_string()))??;
+
+        Ok(ListOffersResponse {
+            offers: response
+                .offers
+                .into_iter()
+                .map(napi_offer_from_gl)
+                .collect(),
+        })
+    }
+
+    /// Disable an offer so that no further invoices are issued for it
+    #[napi]
+    pub async fn disable_offer(&self, offer_id: String) -> Result<Offer> {
+        let inner = self.inner.clone();
+        let offer = tokio::task::spawn_blocking(move || {
+            inner
+                .disable_offer(offer_id)
+                .map_err(|e| Error::from_reason(e.to_string()))
+        })
+        .await
+        .map_err(|e| Error::from_reason(e.to_string()))??;
+
+        Ok(napi_offer_from_gl(offer))
+    }
+
+    /// Re-enable a previously disabled offer
+    #[napi]
+    pub async fn enable_offer(&self, offer_id: String) -> Result<Offer> {
+        let inner = self.inner.clone();
+        let offer = tokio::task::spawn_blocking(move || {
+            inner
+                .enable_offer(offer_id)
+                .map_err(|e| Error::from_reason(e.to_string()))
+        })
+        .await
+        .map_err(|e| Error::from_reason(e.to_string()))??;
+
+        Ok(napi_offer_from_gl(offer))
+    }
+
+    /// Pay a BOLT12 offer
+    ///
+    /// # Arguments
+    /// * `offer` - The offer string (`lno1...`)
+    /// * `amount_msat` - Required for "any amount" offers
+    /// * `payer_note` - Optional message delivered to the recipient
+    #[napi]
+    pub async fn pay_offer(
+        &self,
+        offer: String,
+        amount_msat: Option<i64>,
+        payer_note: Option<String>,
+    ) -> Result<SendResponse> {
+        let inner = self.inner.clone();
+        let amount = amount_msat
+            .map(|a| u64_from_napi("amount_msat", a))
+            .transpose()?;
+        let response = tokio::task::spawn_blocking(move || {
+            inner
+                .pay_offer(offer, amount, payer_note)
+                .map_err(|e| Error::from_reason(e.to_string()))
+        })
+        .await
+        .map_err(|e| Error::from_reason(e.to_string()))??;
+
+        Ok(napi_send_response_from_gl(response))
+    }
+
     // ── LNURL methods ───────────────────────────────────────────
 
     /// Execute an LNURL-pay flow.
@@ -1056,6 +2234,25 @@ impl Node {
 
         Ok(napi_lnurl_withdraw_result_from_gl(result))
     }
+
+    /// Log in to an LNURL-auth service (LUD-04).
+    ///
+    /// Build the request from the `lnurl_auth` data returned by
+    /// `resolveInput`. Requires a node connected with its mnemonic.
+    #[napi]
+    pub async fn lnurl_auth(&self, data: LnUrlAuthRequestData) -> Result<LnUrlCallbackStatus> {
+        let inner = self.inner.clone();
+        let gl_data = gl_auth_request_data_from_napi(data);
+        let result = tokio::task::spawn_blocking(move || {
+            inner
+                .lnurl_auth(gl_data)
+                .map_err(|e| Error::from_reason(e.to_string()))
+        })
+        .await
+        .map_err(|e| Error::from_reason(e.to_string()))??;
+
+        Ok(napi_lnurl_callback_status_from_gl(result))
+    }
 }
 
 // ============================================================================
@@ -1064,17 +2261,127 @@ impl Node {
 
 /// Convert a gl-sdk `NodeEvent` to the NAPI flat discriminated-union object.
 fn napi_node_event_from_gl(event: GlNodeEvent) -> NodeEvent {
+    let mut out = NodeEvent {
+        event_type: String::new(),
+        invoice_paid: None,
+        payment_sent: None,
+        payment_failed: None,
+        channel_opened: None,
+        channel_closed: None,
+        channel_state_changed: None,
+        peer_id: None,
+        deposit_confirmed: None,
+    };
     match event {
-        GlNodeEvent::InvoicePaid { details } => NodeEvent {
-            event_type: "invoice_paid".to_string(),
-            invoice_paid: Some(InvoicePaidEvent {
+        GlNodeEvent::InvoicePaid { details } => {
+            out.event_type = "invoice_paid".to_string();
+            out.invoice_paid = Some(InvoicePaidEvent {
                 payment_hash: details.payment_hash,
                 bolt11: details.bolt11,
                 preimage: details.preimage,
                 label: details.label,
                 amount_msat: details.amount_msat as i64,
-            }),
-        },
+            });
+        }
+        GlNodeEvent::PaymentSent { details } => {
+            out.event_type = "payment_sent".to_string();
+            out.payment_sent = Some(PaymentSentEvent {
+                payment_hash: details.payment_hash,
+                preimage: details.preimage,
+                destination: details.destination,
+                amount_msat: details.amount_msat as i64,
+                amount_sent_msat: details.amount_sent_msat as i64,
+                bolt11: details.bolt11,
+            });
+        }
+        GlNodeEvent::PaymentFailed { details } => {
+            out.event_type = "payment_failed".to_string();
+            out.payment_failed = Some(PaymentFailedEvent {
+                payment_hash: details.payment_hash,
+                destination: details.destination,
+                amount_msat: details.amount_msat as i64,
+                message: details.message,
+            });
+        }
+        GlNodeEvent::ChannelOpened { details } => {
+            out.event_type = "channel_opened".to_string();
+            out.channel_opened = Some(napi_channel_event_from_gl(details));
+        }
+        GlNodeEvent::ChannelClosed { details } => {
+            out.event_type = "channel_closed".to_string();
+            out.channel_closed = Some(ChannelClosedEvent {
+                channel: napi_channel_event_from_gl(details.channel),
+                cause: details.cause,
+            });
+        }
+        GlNodeEvent::ChannelStateChanged { details } => {
+            out.event_type = "channel_state_changed".to_string();
+            out.channel_state_changed = Some(ChannelStateChangedEvent {
+                channel: napi_channel_event_from_gl(details.channel),
+                old_state: details.old_state,
+                new_state: details.new_state,
+                cause: details.cause,
+                message: details.message,
+            });
+        }
+        GlNodeEvent::PeerConnected { peer_id } => {
+            out.event_type = "peer_connected".to_string();
+            out.peer_id = Some(peer_id);
+        }
+        GlNodeEvent::PeerDisconnected { peer_id } => {
+            out.event_type = "peer_disconnected".to_string();
+            out.peer_id = Some(peer_id);
+        }
+        GlNodeEvent::DepositConfirmed { details } => {
+            out.event_type = "deposit_confirmed".to_string();
+            out.deposit_confirmed = Some(DepositConfirmedEvent {
+                txid: details.txid,
+                outnum: details.outnum,
+                amount_msat: details.amount_msat as i64,
+                blockheight: details.blockheight,
+            });
+        }
+    }
+    out
+}
+
+impl GlNodeEventListener for NodeEventListener {
+    fn on_event(&self, event: GlNodeEvent) {
+        self.on_event.call(
+            napi_node_event_from_gl(event),
+            ThreadsafeFunctionCallMode::NonBlocking,
+        );
+    }
+
+    fn on_connected(&self) {
+        if let Some(f) = &self.on_connected {
+            f.call((), ThreadsafeFunctionCallMode::NonBlocking);
+        }
+    }
+
+    fn on_disconnected(&self, reason: String) {
+        if let Some(f) = &self.on_disconnected {
+            f.call(reason, ThreadsafeFunctionCallMode::NonBlocking);
+        }
+    }
+}
+
+fn napi_channel_event_from_gl(channel: glsdk::ChannelEvent) -> ChannelEvent {
+    ChannelEvent {
+        peer_id: channel.peer_id,
+        channel_id: channel.channel_id,
+        short_channel_id: channel.short_channel_id,
+    }
+}
+
+fn gl_network_from_str(network: &str) -> Result<GlNetwork> {
+    match network.to_lowercase().as_str() {
+        "bitcoin" => Ok(GlNetwork::BITCOIN),
+        "regtest" => Ok(GlNetwork::REGTEST),
+        _ => Err(Error::from_reason(format!(
+            "Invalid network: {}. Must be 'bitcoin' or 'regtest'",
+            network
+        ))),
     }
 }
 
@@ -1106,6 +2413,257 @@ fn output_status_to_string(status: &GlOutputStatus) -> String {
     }
 }
 
+// ============================================================================
+// Payment Conversion Helpers
+// ============================================================================
+
+/// Convert a JS integer argument that must not be negative.
+fn u64_from_napi(name: &str, value: i64) -> Result<u64> {
+    u64::try_from(value).map_err(|_| Error::from_reason(format!("{name} must not be negative")))
+}
+
+fn gl_send_options_from_napi(options: SendOptions) -> Result<glsdk::SendOptions> {
+    Ok(glsdk::SendOptions {
+        max_fee_msat: options
+            .max_fee_msat
+            .map(|v| u64_from_napi("max_fee_msat", v))
+            .transpose()?,
+        max_fee_percent: options.max_fee_percent,
+        exempt_fee_msat: options
+            .exempt_fee_msat
+            .map(|v| u64_from_napi("exempt_fee_msat", v))
+            .transpose()?,
+        timeout_secs: options.timeout_secs,
+        exclude: options.exclude.unwrap_or_default(),
+        label: options.label,
+        trampoline: options.trampoline,
+    })
+}
+
+fn napi_send_response_from_gl(response: glsdk::SendResponse) -> SendResponse {
+    let (route, trampoline_node_id) = match response.route {
+        glsdk::SendRoute::Regular => ("regular".to_string(), None),
+        glsdk::SendRoute::Trampoline { node_id } => ("trampoline".to_string(), Some(node_id)),
+    };
+    SendResponse {
+        status: response.status as u32,
+        preimage: response.preimage,
+        payment_hash: response.payment_hash,
+        destination_pubkey: response.destination_pubkey,
+        amount_msat: response.amount_msat as i64,
+        amount_sent_msat: response.amount_sent_msat as i64,
+        parts: response.parts,
+        route,
+        trampoline_node_id,
+        fee_msat: response.fee_msat as i64,
+    }
+}
+
+fn napi_onchain_balance_state_from_gl(state: glsdk::OnchainBalanceState) -> OnchainBalanceState {
+    let mut out = OnchainBalanceState {
+        r#type: String::new(),
+        withdrawable_sat: None,
+        emergency_reserve_sat: None,
+        unconfirmed_sat: None,
+        immature_sat: None,
+    };
+    match state {
+        glsdk::OnchainBalanceState::Unavailable => {
+            out.r#type = "unavailable".to_string();
+        }
+        glsdk::OnchainBalanceState::Available {
+            withdrawable_sat,
+            emergency_reserve_sat,
+            unconfirmed_sat,
+        } => {
+            out.r#type = "available".to_string();
+            out.withdrawable_sat = Some(withdrawable_sat as i64);
+            out.emergency_reserve_sat = Some(emergency_reserve_sat as i64);
+            out.unconfirmed_sat = Some(unconfirmed_sat as i64);
+        }
+        glsdk::OnchainBalanceState::ReserveOnly { reserve_sat } => {
+            out.r#type = "reserve_only".to_string();
+            out.emergency_reserve_sat = Some(reserve_sat as i64);
+        }
+        glsdk::OnchainBalanceState::PendingConfirmation { unconfirmed_sat } => {
+            out.r#type = "pending_confirmation".to_string();
+            out.unconfirmed_sat = Some(unconfirmed_sat as i64);
+        }
+        glsdk::OnchainBalanceState::Immature { immature_sat } => {
+            out.r#type = "immature".to_string();
+            out.immature_sat = Some(immature_sat as i64);
+        }
+    }
+    out
+}
+
+fn napi_offer_from_gl(offer: glsdk::Offer) -> Offer {
+    Offer {
+        offer_id: offer.offer_id,
+        bolt12: offer.bolt12,
+        active: offer.active,
+        single_use: offer.single_use,
+        used: offer.used,
+        label: offer.label,
+    }
+}
+
+fn gl_list_index_from_str(index: &str) -> Result<GlListIndex> {
+    match index.to_lowercase().as_str() {
+        "created" => Ok(GlListIndex::CREATED),
+        "updated" => Ok(GlListIndex::UPDATED),
+        _ => Err(Error::from_reason(format!(
+            "Invalid index: {}. Must be 'created' or 'updated'",
+            index
+        ))),
+    }
+}
+
+fn gl_pay_status_from_str(status: &str) -> Result<GlPayStatus> {
+    match status.to_lowercase().as_str() {
+        "pending" => Ok(GlPayStatus::PENDING),
+        "complete" => Ok(GlPayStatus::COMPLETE),
+        "failed" => Ok(GlPayStatus::FAILED),
+        _ => Err(Error::from_reason(format!(
+            "Invalid status: {}. Must be 'pending', 'complete' or 'failed'",
+            status
+        ))),
+    }
+}
+
+fn pay_status_to_string(status: &GlPayStatus) -> String {
+    match status {
+        GlPayStatus::PENDING => "pending".to_string(),
+        GlPayStatus::COMPLETE => "complete".to_string(),
+        GlPayStatus::FAILED => "failed".to_string(),
+    }
+}
+
+fn invoice_status_to_string(status: &glsdk::InvoiceStatus) -> String {
+    match status {
+        glsdk::InvoiceStatus::UNPAID => "unpaid".to_string(),
+        glsdk::InvoiceStatus::PAID => "paid".to_string(),
+        glsdk::InvoiceStatus::EXPIRED => "expired".to_string(),
+    }
+}
+
+fn gl_list_payments_request_from_napi(
+    req: ListPaymentsRequest,
+) -> Result<glsdk::ListPaymentsRequest> {
+    let filters = req
+        .filters
+        .map(|filters| {
+            filters
+                .iter()
+                .map(|f| match f.to_lowercase().as_str() {
+                    "sent" => Ok(GlPaymentTypeFilter::Sent),
+                    "received" => Ok(GlPaymentTypeFilter::Received),
+                    _ => Err(Error::from_reason(format!(
+                        "Invalid filter: {}. Must be 'sent' or 'received'",
+                        f
+                    ))),
+                })
+                .collect::<Result<Vec<_>>>()
+        })
+        .transpose()?;
+    let statuses = req
+        .statuses
+        .map(|statuses| {
+            statuses
+                .iter()
+                .map(|s| gl_payment_status_from_str(s))
+                .collect::<Result<Vec<_>>>()
+        })
+        .transpose()?;
+    let kinds = req
+        .kinds
+        .map(|kinds| {
+            kinds
+                .iter()
+                .map(|k| gl_payment_kind_from_str(k))
+                .collect::<Result<Vec<_>>>()
+        })
+        .transpose()?;
+    Ok(glsdk::ListPaymentsRequest {
+        filters,
+        from_timestamp: req
+            .from_timestamp
+            .map(|v| u64_from_napi("from_timestamp", v))
+            .transpose()?,
+        to_timestamp: req
+            .to_timestamp
+            .map(|v| u64_from_napi("to_timestamp", v))
+            .transpose()?,
+        include_failures: req.include_failures,
+        offset: req.offset,
+        limit: req.limit,
+        statuses,
+        kinds,
+        min_amount_msat: req
+            .min_amount_msat
+            .map(|v| u64_from_napi("min_amount_msat", v))
+            .transpose()?,
+        max_amount_msat: req
+            .max_amount_msat
+            .map(|v| u64_from_napi("max_amount_msat", v))
+            .transpose()?,
+        cursor: req.cursor,
+    })
+}
+
+fn gl_payment_status_from_str(status: &str) -> Result<GlPaymentStatus> {
+    match status.to_lowercase().as_str() {
+        "pending" => Ok(GlPaymentStatus::Pending),
+        "complete" => Ok(GlPaymentStatus::Complete),
+        "failed" => Ok(GlPaymentStatus::Failed),
+        _ => Err(Error::from_reason(format!(
+            "Invalid status: {}. Must be 'pending', 'complete' or 'failed'",
+            status
+        ))),
+    }
+}
+
+fn gl_payment_kind_from_str(kind: &str) -> Result<GlPaymentKind> {
+    match kind.to_lowercase().as_str() {
+        "lightning" => Ok(GlPaymentKind::Lightning),
+        "onchain" => Ok(GlPaymentKind::Onchain),
+        "lsp_fee" => Ok(GlPaymentKind::LspFee),
+        _ => Err(Error::from_reason(format!(
+            "Invalid kind: {}. Must be 'lightning', 'onchain' or 'lsp_fee'",
+            kind
+        ))),
+    }
+}
+
+fn napi_payment_from_gl(payment: glsdk::Payment) -> Payment {
+    Payment {
+        id: payment.id,
+        payment_type: match payment.payment_type {
+            GlPaymentType::Sent => "sent".to_string(),
+            GlPaymentType::Received => "received".to_string(),
+        },
+        payment_time: payment.payment_time as i64,
+        amount_msat: payment.amount_msat as i64,
+        fee_msat: payment.fee_msat as i64,
+        status: match payment.status {
+            GlPaymentStatus::Pending => "pending".to_string(),
+            GlPaymentStatus::Complete => "complete".to_string(),
+            GlPaymentStatus::Failed => "failed".to_string(),
+        },
+        description: payment.description,
+        bolt11: payment.bolt11,
+        preimage: payment.preimage,
+        destination: payment.destination,
+        kind: match payment.kind {
+            GlPaymentKind::Lightning => "lightning".to_string(),
+            GlPaymentKind::Onchain => "onchain".to_string(),
+            GlPaymentKind::LspFee => "lsp_fee".to_string(),
+        },
+        txid: payment.txid,
+        cursor: payment.cursor,
+    }
+}
+
 // ============================================================================
 // Input Parsing Conversion Helpers
 // ============================================================================
@@ -1122,6 +2680,18 @@ fn napi_parsed_invoice_from_gl(invoice: GlParsedInvoice) -> ParsedInvoice {
     }
 }
 
+fn napi_parsed_offer_from_gl(offer: GlParsedOffer) -> ParsedOffer {
+    ParsedOffer {
+        bolt12: offer.bolt12,
+        offer_id: offer.offer_id,
+        description: offer.description,
+        issuer: offer.issuer,
+        amount_msat: offer.amount_msat.map(|v| v as i64),
+        absolute_expiry: offer.absolute_expiry.map(|v| v as i64),
+        issuer_pubkey: offer.issuer_pubkey,
+    }
+}
+
 fn napi_pay_request_data_from_gl(data: glsdk::LnUrlPayRequestData) -> LnUrlPayRequestData {
     LnUrlPayRequestData {
         callback: data.callback,
@@ -1147,11 +2717,29 @@ fn napi_withdraw_request_data_from_gl(
     }
 }
 
+fn napi_auth_request_data_from_gl(data: glsdk::LnUrlAuthRequestData) -> LnUrlAuthRequestData {
+    LnUrlAuthRequestData {
+        k1: data.k1,
+        action: data.action,
+        domain: data.domain,
+        url: data.url,
+    }
+}
+
 fn napi_parsed_input_from_gl(input: GlParsedInput) -> ParsedInput {
     match input {
         GlParsedInput::Bolt11 { invoice } => ParsedInput {
             r#type: "bolt11".to_string(),
             bolt11: Some(napi_parsed_invoice_from_gl(invoice)),
+            bolt12_offer: None,
+            node_id: None,
+            lnurl: None,
+            lnurl_address: None,
+        },
+        GlParsedInput::Bolt12Offer { offer } => ParsedInput {
+            r#type: "bolt12_offer".to_string(),
+            bolt11: None,
+            bolt12_offer: Some(napi_parsed_offer_from_gl(offer)),
             node_id: None,
             lnurl: None,
             lnurl_address: None,
@@ -1159,6 +2747,7 @@ fn napi_parsed_input_from_gl(input: GlParsedInput) -> ParsedInput {
         GlParsedInput::NodeId { node_id } => ParsedInput {
             r#type: "node_id".to_string(),
             bolt11: None,
+            bolt12_offer: None,
             node_id: Some(node_id),
             lnurl: None,
             lnurl_address: None,
@@ -1166,6 +2755,7 @@ fn napi_parsed_input_from_gl(input: GlParsedInput) -> ParsedInput {
         GlParsedInput::LnUrl { url } => ParsedInput {
             r#type: "lnurl".to_string(),
             bolt11: None,
+            bolt12_offer: None,
             node_id: None,
             lnurl: Some(url),
             lnurl_address: None,
@@ -1173,6 +2763,7 @@ fn napi_parsed_input_from_gl(input: GlParsedInput) -> ParsedInput {
         GlParsedInput::LnUrlAddress { address } => ParsedInput {
             r#type: "lnurl_address".to_string(),
             bolt11: None,
+            bolt12_offer: None,
             node_id: None,
             lnurl: None,
             lnurl_address: Some(address),
@@ -1185,38 +2776,64 @@ fn napi_resolved_input_from_gl(input: GlResolvedInput) -> ResolvedInput {
         GlResolvedInput::Bolt11 { invoice } => ResolvedInput {
             r#type: "bolt11".to_string(),
             bolt11: Some(napi_parsed_invoice_from_gl(invoice)),
+            bolt12_offer: None,
+            node_id: None,
+            lnurl_pay: None,
+            lnurl_withdraw: None,
+            lnurl_auth: None,
+        },
+        GlResolvedInput::Bolt12Offer { offer } => ResolvedInput {
+            r#type: "bolt12_offer".to_string(),
+            bolt11: None,
+            bolt12_offer: Some(napi_parsed_offer_from_gl(offer)),
             node_id: None,
             lnurl_pay: None,
             lnurl_withdraw: None,
+            lnurl_auth: None,
         },
         GlResolvedInput::NodeId { node_id } => ResolvedInput {
             r#type: "node_id".to_string(),
             bolt11: None,
+            bolt12_offer: None,
             node_id: Some(node_id),
             lnurl_pay: None,
             lnurl_withdraw: None,
+            lnurl_auth: None,
         },
         GlResolvedInput::LnUrlPay { data } => ResolvedInput {
             r#type: "lnurl_pay".to_string(),
             bolt11: None,
+            bolt12_offer: None,
             node_id: None,
             lnurl_pay: Some(napi_pay_request_data_from_gl(data)),
             lnurl_withdraw: None,
+            lnurl_auth: None,
         },
         GlResolvedInput::LnUrlWithdraw { data } => ResolvedInput {
             r#type: "lnurl_withdraw".to_string(),
             bolt11: None,
+            bolt12_offer: None,
             node_id: None,
             lnurl_pay: None,
             lnurl_withdraw: Some(napi_withdraw_request_data_from_gl(data)),
+            lnurl_auth: None,
+        },
+        GlResolvedInput::LnUrlAuth { data } => ResolvedInput {
+            r#type: "lnurl_auth".to_string(),
+            bolt11: None,
+            bolt12_offer: None,
+            node_id: None,
+            lnurl_pay: None,
+            lnurl_withdraw: None,
+            lnurl_auth: Some(napi_auth_request_data_from_gl(data)),
         },
     }
 }
 
 /// Synchronously classify the input. **No HTTP, no I/O.**
 ///
-/// Recognises BOLT11 invoices, node IDs, LNURL bech32 strings, and
-/// Lightning Addresses. Strips `lightning:` / `LIGHTNING:` prefixes
+/// Recognises BOLT11 invoices, BOLT12 offers, node IDs, LNURL bech32
+/// strings, and Lightning Addresses. Strips `lightning:` / `LIGHTNING:` prefixes
 /// automatically. LNURL inputs are decoded to their underlying URL
 /// but **not fetched** — call `resolveInput` for that.
 #[napi]
@@ -1242,6 +2859,78 @@ pub async fn resolve_input(input: String) -> Result<ResolvedInput> {
     Ok(napi_resolved_input_from_gl(resolved))
 }
 
+struct NapiLogListener {
+    listener: ThreadsafeFunction<LogEntry, ErrorStrategy::Fatal>,
+}
+
+impl glsdk::LogListener for NapiLogListener {
+    fn on_log(&self, entry: glsdk::LogEntry) {
+        self.listener.call(
+            LogEntry {
+                level: log_level_to_string(entry.level),
+                message: entry.message,
+                target: entry.target,
+                file: entry.file,
+                line: entry.line,
+            },
+            ThreadsafeFunctionCallMode::NonBlocking,
+        );
+    }
+}
+
+fn gl_log_level_from_str(level: &str) -> Result<GlLogLevel> {
+    match level.to_lowercase().as_str() {
+        "error" => Ok(GlLogLevel::Error),
+        "warn" => Ok(GlLogLevel::Warn),
+        "info" => Ok(GlLogLevel::Info),
+        "debug" => Ok(GlLogLevel::Debug),
+        "trace" => Ok(GlLogLevel::Trace),
+        _ => Err(Error::from_reason(format!(
+            "Invalid log level: {}. Must be 'error', 'warn', 'info', 'debug' or 'trace'",
+            level
+        ))),
+    }
+}
+
+fn log_level_to_string(level: GlLogLevel) -> String {
+    match level {
+        GlLogLevel::Error => "error".to_string(),
+        GlLogLevel::Warn => "warn".to_string(),
+        GlLogLevel::Info => "info".to_string(),
+        GlLogLevel::Debug => "debug".to_string(),
+        GlLogLevel::Trace => "trace".to_string(),
+    }
+}
+
+/// Install a listener for the SDK's log messages. Call once, as early
+/// as possible; fails if a logger is already installed. The listener
+/// does not keep the process alive on its own.
+///
+/// # Arguments
+/// * `level` - "error" | "warn" | "info" | "debug" | "trace"
+/// * `listener` - Called with every log entry at or above `level`
+#[napi]
+pub fn set_logger(
+    env: Env,
+    level: String,
+    #[napi(ts_arg_type = "(entry: LogEntry) => void")] mut listener: ThreadsafeFunction<
+        LogEntry,
+        ErrorStrategy::Fatal,
+    >,
+) -> Result<()> {
+    let level = gl_log_level_from_str(&level)?;
+    listener.unref(&env)?;
+    glsdk::set_logger(level, Box::new(NapiLogListener { listener }))
+        .map_err(|e| Error::from_reason(e.to_string()))
+}
+
+/// Change the log filter without reinstalling the listener.
+#[napi]
+pub fn set_log_level(level: String) -> Result<()> {
+    glsdk::set_log_level(gl_log_level_from_str(&level)?);
+    Ok(())
+}
+
 // ============================================================================
 // LNURL Conversion Helpers
 // ============================================================================
@@ -1288,6 +2977,15 @@ fn gl_lnurl_withdraw_request_from_napi(req: LnUrlWithdrawRequest) -> glsdk::LnUr
     }
 }
 
+fn gl_auth_request_data_from_napi(data: LnUrlAuthRequestData) -> glsdk::LnUrlAuthRequestData {
+    glsdk::LnUrlAuthRequestData {
+        k1: data.k1,
+        action: data.action,
+        domain: data.domain,
+        url: data.url,
+    }
+}
+
 fn napi_success_action_from_gl(action: glsdk::SuccessActionProcessed) -> SuccessActionProcessed {
     match action {
         glsdk::SuccessActionProcessed::Message { message } => SuccessActionProcessed {
@@ -1366,3 +3064,18 @@ fn napi_lnurl_withdraw_result_from_gl(result: glsdk::LnUrlWithdrawResult) -> LnU
         },
     }
 }
+
+fn napi_lnurl_callback_status_from_gl(status: glsdk::LnUrlCallbackStatus) -> LnUrlCallbackStatus {
+    match status {
+        glsdk::LnUrlCallbackStatus::Ok => LnUrlCallbackStatus {
+            r#type: "ok".to_string(),
+            error: None,
+        },
+        glsdk::LnUrlCallbackStatus::ErrorStatus { data } => LnUrlCallbackStatus {
+            r#type: "error".to_string(),
+            error: Some(LnUrlErrorData {
+                reason: data.reason,
+            }),
+        },
+    }
+}
@@ -1,6 +1,6 @@
 import * as crypto from 'crypto';
 import * as bip39 from 'bip39';
-import { Credentials, Scheduler, Signer, Node, NodeEventStream, NodeEvent, InvoicePaidEvent, Handle } from '../index.js';
+import { Config, Credentials, NodeBuilder, Scheduler, Signer, Node, NodeEventStream, NodeEvent, InvoicePaidEvent, PaymentSentEvent, ChannelClosedEvent, Handle } from '../index.js';
 import { fundWallet, getGLNode } from './test.helper.js';
 
 describe('NodeEvent (type contract)', () => {
@@ -21,6 +21,55 @@ describe('NodeEvent (type contract)', () => {
     // the constructed value is truthy (i.e. the module loaded correctly).
     expect(event).toBeDefined();
   });
+
+  it('PaymentSentEvent and ChannelClosedEvent are assignable from NAPI-generated types', () => {
+    const sent: PaymentSentEvent = {
+      paymentHash: '00'.repeat(32),
+      preimage: '11'.repeat(32),
+      destination: '02'.repeat(33),
+      amountMsat: 1_000,
+      amountSentMsat: 1_001,
+    };
+    const closed: ChannelClosedEvent = {
+      channel: { peerId: '02'.repeat(33), channelId: '22'.repeat(32), shortChannelId: '103x1x0' },
+      cause: 'remote',
+    };
+    const events: NodeEvent[] = [
+      { eventType: 'payment_sent', paymentSent: sent },
+      { eventType: 'channel_closed', channelClosed: closed },
+      { eventType: 'peer_disconnected', peerId: '02'.repeat(33) },
+    ];
+
+    expect(events).toHaveLength(3);
+  });
+});
+
+// ============================================================================
+// NodeEventListener integration tests — require a live regtest scheduler
+// ============================================================================
+
+describe('NodeEventListener (integration)', () => {
+  it('is notified once the event stream is established', async () => {
+    const mnemonic = bip39.entropyToMnemonic(crypto.randomBytes(16).toString('hex'));
+    const events: NodeEvent[] = [];
+    let onConnected!: () => void;
+    const connected = new Promise<void>((resolve) => { onConnected = resolve; });
+
+    const node = await new NodeBuilder(new Config().withNetwork('regtest'))
+      .withEventListener({
+        onEvent: (event: NodeEvent) => { events.push(event); },
+        onConnected,
+      })
+      .register(mnemonic);
+
+    await expect(Promise.race([
+      connected.then(() => true),
+      new Promise<boolean>(resolve => setTimeout(() => resolve(false), 10_000)),
+    ])).resolves.toBe(true);
+    expect(events.every((e) => typeof e.eventType === 'string')).toBe(true);
+    expect(node.lastEventIndex() ?? 0).toBeGreaterThanOrEqual(0);
+    node.disconnect();
+  });
 });
 
 // ============================================================================
@@ -68,6 +117,14 @@ describe('NodeEventStream (integration)', () => {
     expect(typeof stream.next).toBe('function');
   });
 
+  it('streamNodeEventsSince resumes from a cursor', async () => {
+    const stream: NodeEventStream = await node.streamNodeEventsSince(0);
+    expect(typeof stream.next).toBe('function');
+    const index = stream.lastIndex();
+    expect(index === null || index === undefined || typeof index === 'number').toBe(true);
+    await expect(node.streamNodeEventsSince(-1)).rejects.toThrow();
+  });
+
   it('next resolves to null or a well-formed NodeEvent within 2 seconds', async () => {
     const stream: NodeEventStream = await node.streamNodeEvents();
 
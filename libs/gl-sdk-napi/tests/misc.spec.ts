@@ -1,10 +1,18 @@
 import * as crypto from 'crypto';
 import * as bip39 from 'bip39';
-import { Credentials, Scheduler, Signer, Node } from '../index.js';
+import { Config, Credentials, NodeBuilder, Scheduler, Signer, Node } from '../index.js';
+
+// A protobuf encoded credentials blob with a placeholder certificate,
+// key and rune.
+function credentialsBlob(): Buffer {
+  const field = (tag: number, value: string) =>
+    Buffer.concat([Buffer.from([tag, value.length]), Buffer.from(value)]);
+  return Buffer.concat([Buffer.from([0x08, 0x01]), field(0x12, 'cert'), field(0x1a, 'key'), field(0x2a, 'rune')]);
+}
 
 describe('Credentials', () => {
   it('can save and load raw credentials', async () => {
-    const original = await Credentials.load(Buffer.from('test'));
+    const original = await Credentials.load(credentialsBlob());
     const raw = await original.save();
 
     expect(Buffer.isBuffer(raw)).toBe(true);
@@ -14,6 +22,45 @@ describe('Credentials', () => {
 
     expect(raw2.equals(raw)).toBe(true);
   });
+
+  it('rejects corrupt credentials', async () => {
+    await expect(Credentials.load(Buffer.from('test'))).rejects.toThrow();
+    await expect(Credentials.load(Buffer.alloc(0))).rejects.toThrow();
+  });
+});
+
+describe('Credentials (encrypted)', () => {
+  it('round-trips through saveEncrypted and loadEncrypted', async () => {
+    const original = await Credentials.load(credentialsBlob());
+    const encrypted = await original.saveEncrypted('correct horse');
+    const raw = await original.save();
+
+    expect(encrypted.equals(raw)).toBe(false);
+
+    const restored = await Credentials.loadEncrypted(encrypted, 'correct horse');
+    expect((await restored.save()).equals(raw)).toBe(true);
+  });
+
+  it('rejects the wrong passphrase', async () => {
+    const original = await Credentials.load(credentialsBlob());
+    const encrypted = await original.saveEncrypted('correct horse');
+
+    await expect(Credentials.loadEncrypted(encrypted, 'battery staple')).rejects.toThrow();
+  });
+});
+
+describe('Config and NodeBuilder', () => {
+  it('can be constructed and configured', () => {
+    const config = new Config().withNetwork('regtest');
+    const builder = new NodeBuilder(config)
+      .withReadOnly()
+      .withEventCursor(0)
+      .withEventListener({ onEvent: () => {} });
+
+    expect(builder).toBeInstanceOf(NodeBuilder);
+    expect(() => config.withNetwork('testnet')).toThrow();
+    expect(() => builder.withEventCursor(-1)).toThrow();
+  });
 });
 
 describe('Signer', () => {
@@ -103,3 +150,20 @@ describe('Integration: scheduler and signer', () => {
     }
   });
 });
+
+describe('Integration: NodeBuilder', () => {
+  it('registers, reconnects read-only and rejects writes', async () => {
+    const mnemonic = bip39.entropyToMnemonic(crypto.randomBytes(16).toString('hex'));
+    const builder = new NodeBuilder(new Config().withNetwork('regtest'));
+    const node = await builder.register(mnemonic);
+    const credentials = node.credentials();
+    expect(node.isReadOnly()).toBe(false);
+    node.disconnect();
+
+    const readOnly = await builder.withReadOnly().connect(credentials, mnemonic);
+    expect(readOnly.isReadOnly()).toBe(true);
+    expect((await readOnly.getInfo()).id.length).toBeGreaterThan(0);
+    await expect(readOnly.receive(`test-${Date.now()}`, 'read-only', 1000)).rejects.toThrow(/read-only/);
+    readOnly.disconnect();
+  });
+});
@@ -142,6 +142,92 @@ describe('Node', () => {
     });
   });
 
+  describe('calls onchainFeeRates', () => {
+    it('returns rates no lower than the relay floor', async () => {
+      const rates = await node.onchainFeeRates();
+
+      expect(rates.minimumRelaySatPerVbyte).toBeGreaterThan(0);
+      expect(rates.nextBlockSatPerVbyte).toBeGreaterThanOrEqual(rates.minimumRelaySatPerVbyte);
+      expect(rates.daySatPerVbyte).toBeGreaterThanOrEqual(rates.minimumRelaySatPerVbyte);
+    });
+  });
+
+  describe('calls prepareOnchainSend', () => {
+    it('previews a send that onchainSend can reproduce', async () => {
+      await fundWallet(node, 500_000_000);
+      const destAddress = (await node.onchainReceive()).bech32;
+      const prepared = await node.prepareOnchainSend(destAddress, '10000sat');
+
+      expect(prepared.recipientSat).toBe(10_000);
+      expect(prepared.utxos.length).toBeGreaterThan(0);
+      expect(prepared.feeSat).toBeGreaterThan(0);
+
+      const response = await node.onchainSend(destAddress, '10000sat', prepared.satPerVbyte, prepared.utxos);
+      expect(response.txid).toHaveLength(64);
+    });
+  });
+
+  describe('calls listInvoices, listPays and listPayments', () => {
+    it('finds a created invoice by label', async () => {
+      const extraGLNode = await getGLNode(scheduler, true) as { node: Node; handle: Handle };
+      glNodes.push(extraGLNode);
+      const label = `test-${Date.now()}`;
+      const { bolt11 } = await extraGLNode.node.receive(label, 'Test payment', 100000);
+
+      const all = await extraGLNode.node.listInvoices();
+      expect(all.invoices.some((i) => i.label === label)).toBe(true);
+
+      const filtered = await extraGLNode.node.listInvoices({ label });
+      expect(filtered.invoices).toHaveLength(1);
+      expect(filtered.invoices[0].bolt11).toBe(bolt11);
+      expect(filtered.invoices[0].status).toBe('unpaid');
+      expect(filtered.invoices[0].amountMsat).toBe(100000);
+    });
+
+    it('returns empty histories for a fresh node', async () => {
+      const pays = await node.listPays({ status: 'complete' });
+      expect(pays.pays).toEqual([]);
+
+      const payments = await node.listPayments({ filters: ['sent'], includeFailures: true });
+      expect(payments).toEqual([]);
+
+      await expect(node.listPays({ status: 'bogus' })).rejects.toThrow();
+      await expect(node.listPayments({ kinds: ['bogus'] })).rejects.toThrow();
+      await expect(node.listPayments({ cursor: 'bogus' })).rejects.toThrow();
+    });
+
+    it('pages through the payment history with cursors', async () => {
+      const all = await node.listPayments({ includeFailures: true });
+      const firstPage = await node.listPayments({ includeFailures: true, limit: 1 });
+      expect(firstPage).toHaveLength(Math.min(all.length, 1));
+
+      if (firstPage.length > 0) {
+        const rest = await node.listPayments({ includeFailures: true, cursor: firstPage[0].cursor });
+        expect([...firstPage, ...rest].map((p) => p.id)).toEqual(all.map((p) => p.id));
+      }
+    });
+
+    it('lists the payments changed since a cursor', async () => {
+      const first = await node.listPaymentsSince();
+      expect(typeof first.cursor).toBe('string');
+      first.payments.forEach((p) => expect(p.cursor).toBeUndefined());
+
+      const second = await node.listPaymentsSince(first.cursor);
+      expect(second.payments).toEqual([]);
+      await expect(node.listPaymentsSince('bogus')).rejects.toThrow();
+    });
+  });
+
+  describe('calls generateDiagnosticData', () => {
+    it('returns a JSON snapshot of the node', async () => {
+      const data = JSON.parse(await node.generateDiagnosticData());
+
+      expect(typeof data.timestamp).toBe('number');
+      expect(data.node).toHaveProperty('getinfo');
+      expect(data.sdk).toHaveProperty('version');
+    });
+  });
+
   describe('calls send', () => {
     it.skip('can attempt to send payment to valid invoice', async () => {
       await fundWallet(node, 500_000_000);
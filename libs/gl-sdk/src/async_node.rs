@@ -0,0 +1,245 @@
+// Non-blocking variants of the `Node` API.
+//
+// Every `Node` method blocks the calling thread until the node
+// responds. `AsyncNode` exposes the same calls as `async` functions,
+// which the bindings turn into native `suspend` (Kotlin), `async`
+// (Swift) and `asyncio` (Python) functions. The calls run on the
+// SDK's runtime, so they can be awaited from any executor, and
+// cancelling the foreign call cancels the request.
+//
+//     let node = NodeBuilder::new(&config).connect(credentials, None)?;
+//     let async_node = AsyncNode::new(node);
+//     let invoice = async_node.receive(label, description, None).await?;
+
+use std::sync::Arc;
+
+use crate::{
+    Credentials, Error,
+    node::{
+        GetInfoResponse, ListFundsResponse, ListIndex, ListInvoicesResponse, ListPaymentsRequest,
+        ListPaymentsSinceResponse, ListPaysResponse, ListPeerChannelsResponse, ListPeersResponse,
+        Node, NodeState, OnchainBalanceState, OnchainFeeRates, OnchainReceiveResponse,
+        OnchainSendResponse, Outpoint, PayStatus, Payment, PreparedOnchainSend, PreparedSend,
+        ReceiveResponse, SendOptions, SendResponse, TlvEntry,
+    },
+    util::spawn,
+};
+
+/// Async counterpart of [`Node`]. Wraps a `Node` and shares its
+/// connection and signer, so both can be used side by side.
+#[derive(uniffi::Object)]
+pub struct AsyncNode {
+    node: Arc<Node>,
+}
+
+#[uniffi::export]
+impl AsyncNode {
+    #[uniffi::constructor]
+    pub fn new(node: Arc<Node>) -> Arc<Self> {
+        Arc::new(Self { node })
+    }
+
+    /// The blocking `Node` this wraps, for the calls that have no
+    /// async variant.
+    pub fn node(&self) -> Arc<Node> {
+        Arc::clone(&self.node)
+    }
+
+    /// See [`Node::receive`].
+    pub async fn receive(
+        &self,
+        label: String,
+        description: String,
+        amount_msat: Option<u64>,
+    ) -> Result<ReceiveResponse, Error> {
+        let node = Arc::clone(&self.node);
+        spawn(async move { node.receive_async(label, description, amount_msat).await }).await
+    }
+
+    /// See [`Node::send`].
+    #[uniffi::method(default(options = None))]
+    pub async fn send(
+        &self,
+        invoice: String,
+        amount_msat: Option<u64>,
+        options: Option<SendOptions>,
+    ) -> Result<SendResponse, Error> {
+        let node = Arc::clone(&self.node);
+        spawn(async move { node.send_async(invoice, amount_msat, options).await }).await
+    }
+
+    /// See [`Node::prepare_send`].
+    #[uniffi::method(default(options = None))]
+    pub async fn prepare_send(
+        &self,
+        invoice: String,
+        amount_msat: Option<u64>,
+        options: Option<SendOptions>,
+    ) -> Result<PreparedSend, Error> {
+        let node = Arc::clone(&self.node);
+        spawn(async move { node.prepare_send_async(invoice, amount_msat, options).await }).await
+    }
+
+    /// See [`Node::send_spontaneous`].
+    pub async fn send_spontaneous(
+        &self,
+        node_id: String,
+        amount_msat: u64,
+        extra_tlvs: Option<Vec<TlvEntry>>,
+    ) -> Result<SendResponse, Error> {
+        let node = Arc::clone(&self.node);
+        spawn(async move {
+            node.send_spontaneous_async(node_id, amount_msat, extra_tlvs)
+                .await
+        })
+        .await
+    }
+
+    /// See [`Node::onchain_send`].
+    pub async fn onchain_send(
+        &self,
+        destination: String,
+        amount_or_all: String,
+        sat_per_vbyte: Option<u32>,
+        utxos: Option<Vec<Outpoint>>,
+    ) -> Result<OnchainSendResponse, Error> {
+        let node = Arc::clone(&self.node);
+        spawn(async move {
+            node.onchain_send_async(destination, amount_or_all, sat_per_vbyte, utxos)
+                .await
+        })
+        .await
+    }
+
+    /// See [`Node::prepare_onchain_send`].
+    pub async fn prepare_onchain_send(
+        &self,
+        destination: String,
+        amount_or_all: String,
+        sat_per_vbyte: Option<u32>,
+    ) -> Result<PreparedOnchainSend, Error> {
+        let node = Arc::clone(&self.node);
+        spawn(async move {
+            node.prepare_onchain_send_async(destination, amount_or_all, sat_per_vbyte)
+                .await
+        })
+        .await
+    }
+
+    /// See [`Node::onchain_balance_state`].
+    pub async fn onchain_balance_state(&self) -> Result<OnchainBalanceState, Error> {
+        let node = Arc::clone(&self.node);
+        spawn(async move { node.onchain_balance_state_async().await }).await
+    }
+
+    /// See [`Node::onchain_fee_rates`].
+    pub async fn onchain_fee_rates(&self) -> Result<OnchainFeeRates, Error> {
+        let node = Arc::clone(&self.node);
+        spawn(async move { node.onchain_fee_rates_async().await }).await
+    }
+
+    /// See [`Node::onchain_receive`].
+    pub async fn onchain_receive(&self) -> Result<OnchainReceiveResponse, Error> {
+        let node = Arc::clone(&self.node);
+        spawn(async move { node.onchain_receive_async().await }).await
+    }
+
+    /// See [`Node::get_info`].
+    pub async fn get_info(&self) -> Result<GetInfoResponse, Error> {
+        let node = Arc::clone(&self.node);
+        spawn(async move { node.get_info_async().await }).await
+    }
+
+    /// See [`Node::list_peers`].
+    pub async fn list_peers(&self) -> Result<ListPeersResponse, Error> {
+        let node = Arc::clone(&self.node);
+        spawn(async move { node.list_peers_async().await }).await
+    }
+
+    /// See [`Node::list_peer_channels`].
+    pub async fn list_peer_channels(&self) -> Result<ListPeerChannelsResponse, Error> {
+        let node = Arc::clone(&self.node);
+        spawn(async move { node.list_peer_channels_async().await }).await
+    }
+
+    /// See [`Node::list_funds`].
+    pub async fn list_funds(&self) -> Result<ListFundsResponse, Error> {
+        let node = Arc::clone(&self.node);
+        spawn(async move { node.list_funds_async().await }).await
+    }
+
+    /// See [`Node::node_state`].
+    pub async fn node_state(&self) -> Result<NodeState, Error> {
+        let node = Arc::clone(&self.node);
+        spawn(async move { node.node_state_async().await }).await
+    }
+
+    /// See [`Node::list_invoices`].
+    pub async fn list_invoices(
+        &self,
+        label: Option<String>,
+        invstring: Option<String>,
+        payment_hash: Option<Vec<u8>>,
+        offer_id: Option<String>,
+        index: Option<ListIndex>,
+        start: Option<u64>,
+        limit: Option<u32>,
+    ) -> Result<ListInvoicesResponse, Error> {
+        let node = Arc::clone(&self.node);
+        spawn(async move {
+            node.list_invoices_async(
+                label,
+                invstring,
+                payment_hash,
+                offer_id,
+                index,
+                start,
+                limit,
+            )
+            .await
+        })
+        .await
+    }
+
+    /// See [`Node::list_pays`].
+    pub async fn list_pays(
+        &self,
+        bolt11: Option<String>,
+        payment_hash: Option<Vec<u8>>,
+        status: Option<PayStatus>,
+        index: Option<ListIndex>,
+        start: Option<u64>,
+        limit: Option<u32>,
+    ) -> Result<ListPaysResponse, Error> {
+        let node = Arc::clone(&self.node);
+        spawn(async move {
+            node.list_pays_async(bolt11, payment_hash, status, index, start, limit)
+                .await
+        })
+        .await
+    }
+
+    /// See [`Node::list_payments`].
+    pub async fn list_payments(&self, req: ListPaymentsRequest) -> Result<Vec<Payment>, Error> {
+        let node = Arc::clone(&self.node);
+        spawn(async move { node.list_payments_async(req).await }).await
+    }
+
+    /// See [`Node::list_payments_since`].
+    pub async fn list_payments_since(
+        &self,
+        cursor: Option<String>,
+    ) -> Result<ListPaymentsSinceResponse, Error> {
+        let node = Arc::clone(&self.node);
+        spawn(async move { node.list_payments_since_async(cursor).await }).await
+    }
+
+    /// See [`Node::derive_readonly_credentials`].
+    pub async fn derive_readonly_credentials(
+        &self,
+        device_name: String,
+    ) -> Result<Arc<Credentials>, Error> {
+        let node = Arc::clone(&self.node);
+        spawn(async move { node.derive_readonly_credentials_async(device_name).await }).await
+    }
+}
@@ -1,5 +1,5 @@
 use crate::Error;
-use gl_client::credentials::{Device as DeviceCredentials, NodeIdProvider};
+use gl_client::credentials::{Device as DeviceCredentials, NodeIdProvider, Secret};
 
 /// A developer certificate obtained from the Greenlight Developer
 /// Console (GDC). When provided to a `Scheduler` via
@@ -40,15 +40,37 @@ pub struct Credentials {
 impl Credentials {
     #[uniffi::constructor()]
     pub fn load(raw: Vec<u8>) -> Result<Credentials, Error> {
-        Ok(Self {
-            inner: DeviceCredentials::from_bytes(raw),
-        })
+        if DeviceCredentials::is_encrypted(&raw) {
+            return Err(Error::other(
+                "the credentials are encrypted, use load_encrypted",
+            ));
+        }
+        let inner = DeviceCredentials::from_bytes(raw).map_err(|_| Error::unparseable_creds())?;
+        Ok(Self { inner })
+    }
+
+    /// Load credentials saved with `save_encrypted`. Fails if the
+    /// passphrase is wrong or the data was modified.
+    #[uniffi::constructor()]
+    pub fn load_encrypted(raw: Vec<u8>, passphrase: String) -> Result<Credentials, Error> {
+        let inner = DeviceCredentials::from_encrypted_bytes(raw, Secret::Passphrase(&passphrase))
+            .map_err(|e| Error::other(e.to_string()))?;
+        Ok(Self { inner })
     }
 
     pub fn save(&self) -> Result<Vec<u8>, Error> {
         Ok(self.inner.to_bytes())
     }
 
+    /// Like `save`, but encrypts the credentials with a key derived
+    /// from `passphrase`, so that a copy of the stored blob, e.g. in a
+    /// device backup, can not be used without it.
+    pub fn save_encrypted(&self, passphrase: String) -> Result<Vec<u8>, Error> {
+        self.inner
+            .to_encrypted_bytes(Secret::Passphrase(&passphrase))
+            .map_err(|e| Error::other(e.to_string()))
+    }
+
     pub fn node_id(&self) -> Result<Vec<u8>, Error> {
         self.inner.node_id().map_err(|e| Error::other(e.to_string()))
     }
@@ -1,5 +1,5 @@
-// Input parsing for BOLT11 invoices, Lightning node IDs, LNURL
-// strings, and Lightning Addresses.
+// Input parsing for BOLT11 invoices, BOLT12 offers, Lightning node
+// IDs, LNURL strings, and Lightning Addresses.
 //
 // Two entry points with explicit cost contracts:
 //
@@ -12,15 +12,15 @@
 //   * `resolve_input(input)` — asynchronous, network-touching. Calls
 //     `parse_input` internally, then for the LNURL / Lightning
 //     Address branches fetches the endpoint to produce typed pay or
-//     withdraw request data. BOLT11 and node IDs pass through
-//     without I/O.
+//     withdraw request data. BOLT11, BOLT12 offers and node IDs pass
+//     through without I/O.
 //
 // Wallets that want offline classification (clipboard checks,
 // invoice sanity-checks on the send screen) call `parse_input`.
 // Wallets handling a QR scan that should proceed straight to the
 // pay/withdraw screen call `resolve_input`.
 
-use crate::lnurl::{LnUrlPayRequestData, LnUrlWithdrawRequestData};
+use crate::lnurl::{LnUrlAuthRequestData, LnUrlPayRequestData, LnUrlWithdrawRequestData};
 use crate::Error;
 
 /// Parsed BOLT11 invoice with extracted fields.
@@ -42,6 +42,33 @@ pub struct ParsedInvoice {
     pub timestamp: u64,
 }
 
+/// Parsed BOLT12 offer with extracted fields.
+///
+/// An offer is a reusable payment code: unlike a BOLT11 invoice it
+/// can be paid many times, and each payment fetches a fresh invoice
+/// from the issuer over onion messages (see `Node::pay_offer`).
+#[derive(Clone, uniffi::Record)]
+pub struct ParsedOffer {
+    /// The original offer string (`lno1...`).
+    pub bolt12: String,
+    /// Offer id as lowercase hex (64 chars).
+    pub offer_id: String,
+    /// Offer description, if set by the issuer.
+    pub description: Option<String>,
+    /// Human-readable issuer name, if set.
+    pub issuer: Option<String>,
+    /// Requested amount in millisatoshis. None for "any amount"
+    /// offers, and for offers denominated in a fiat currency (the
+    /// amount is then only known once the invoice is fetched).
+    pub amount_msat: Option<u64>,
+    /// Unix timestamp (seconds) after which the offer is no longer
+    /// valid, if any.
+    pub absolute_expiry: Option<u64>,
+    /// Issuer signing public key as lowercase hex (66 chars). None
+    /// when the offer only uses blinded paths.
+    pub issuer_pubkey: Option<String>,
+}
+
 /// The result of `parse_input`: an offline classification of the
 /// input. No HTTP, no I/O. LNURL bech32 strings are returned as their
 /// decoded URL; Lightning Addresses as the unparsed `user@host` form.
@@ -49,6 +76,8 @@ pub struct ParsedInvoice {
 pub enum ParsedInput {
     /// A BOLT11 Lightning invoice.
     Bolt11 { invoice: ParsedInvoice },
+    /// A BOLT12 offer (`lno1...`).
+    Bolt12Offer { offer: ParsedOffer },
     /// A Lightning node public key.
     NodeId { node_id: String },
     /// An LNURL bech32 string (LUD-01) decoded to its underlying URL.
@@ -64,24 +93,32 @@ pub enum ParsedInput {
 /// The result of `resolve_input`: a fully-resolved input ready for
 /// the caller's next action. LNURL bech32 strings and Lightning
 /// Addresses are resolved over HTTP into typed pay or withdraw
-/// request data; BOLT11 and node IDs pass through unchanged.
+/// request data; LNURL-auth login URLs are classified from their
+/// query string. BOLT11, BOLT12 offers and node IDs pass through
+/// unchanged.
 #[derive(Clone, uniffi::Enum)]
 pub enum ResolvedInput {
     /// A BOLT11 Lightning invoice. No HTTP was performed.
     Bolt11 { invoice: ParsedInvoice },
+    /// A BOLT12 offer. No HTTP was performed; the invoice is fetched
+    /// over onion messages when paying via `Node::pay_offer`.
+    Bolt12Offer { offer: ParsedOffer },
     /// A Lightning node public key. No HTTP was performed.
     NodeId { node_id: String },
     /// An LNURL-pay endpoint with the service's parameters fetched.
     LnUrlPay { data: LnUrlPayRequestData },
     /// An LNURL-withdraw endpoint with the service's parameters fetched.
     LnUrlWithdraw { data: LnUrlWithdrawRequestData },
+    /// An LNURL-auth login request (LUD-04). No HTTP was performed;
+    /// call `Node::lnurl_auth` to log in.
+    LnUrlAuth { data: LnUrlAuthRequestData },
 }
 
 /// Synchronously classify the input. **No HTTP, no I/O.**
 ///
-/// Recognises BOLT11 invoices, node IDs, LNURL bech32 strings, and
-/// Lightning Addresses. Strips `lightning:` / `LIGHTNING:` prefixes
-/// automatically.
+/// Recognises BOLT11 invoices, BOLT12 offers, node IDs, LNURL bech32
+/// strings, and Lightning Addresses. Strips `lightning:` /
+/// `LIGHTNING:` prefixes automatically.
 ///
 /// LNURL inputs are decoded to their underlying URL but **not
 /// fetched** — the caller chooses whether to resolve further (via
@@ -111,6 +148,11 @@ pub fn parse_input(input: String) -> Result<ParsedInput, Error> {
         return result;
     }
 
+    // Try BOLT12 offer
+    if let Some(result) = try_parse_bolt12_offer(stripped) {
+        return result;
+    }
+
     // Try Lightning Address (user@domain)
     if let Some(result) = try_parse_lightning_address(stripped) {
         return Ok(result);
@@ -126,10 +168,12 @@ pub fn parse_input(input: String) -> Result<ParsedInput, Error> {
 
 /// Asynchronously classify and resolve the input.
 ///
-/// Internally calls `parse_input`. For BOLT11 and node IDs returns
+/// Internally calls `parse_input`. For BOLT11, BOLT12 offers and node
+/// IDs returns
 /// immediately without I/O. For LNURL bech32 strings and Lightning
 /// Addresses, performs the HTTP GET and returns the typed pay or
-/// withdraw request data.
+/// withdraw request data. LNURL-auth login URLs are recognised from
+/// their query string and returned without I/O.
 pub async fn resolve_input(input: String) -> Result<ResolvedInput, Error> {
     use gl_client::lnurl::models::LnUrlHttpClearnetClient;
     use gl_client::lnurl::{LnUrlResponse, LNURL};
@@ -143,6 +187,7 @@ pub async fn resolve_input(input: String) -> Result<ResolvedInput, Error> {
     // — the only branch-specific bit is how the URL is derived.
     let url = match parse_input(input)? {
         ParsedInput::Bolt11 { invoice } => return Ok(ResolvedInput::Bolt11 { invoice }),
+        ParsedInput::Bolt12Offer { offer } => return Ok(ResolvedInput::Bolt12Offer { offer }),
         ParsedInput::NodeId { node_id } => return Ok(ResolvedInput::NodeId { node_id }),
         ParsedInput::LnUrl { url } => url,
         ParsedInput::LnUrlAddress { address } => {
@@ -168,6 +213,7 @@ pub async fn resolve_input(input: String) -> Result<ResolvedInput, Error> {
             data.lnurl = original;
             ResolvedInput::LnUrlWithdraw { data }
         }
+        LnUrlResponse::Auth(d) => ResolvedInput::LnUrlAuth { data: d.into() },
     })
 }
 
@@ -259,6 +305,40 @@ fn try_parse_bolt11(input: &str) -> Option<Result<ParsedInput, Error>> {
     }))
 }
 
+/// Try parsing as a BOLT12 offer. Returns None if the input doesn't
+/// look like an offer, or Some(Result) if it does (even if malformed).
+fn try_parse_bolt12_offer(input: &str) -> Option<Result<ParsedInput, Error>> {
+    use gl_client::lightning::offers::offer::{Amount, Offer};
+
+    if !input.to_lowercase().starts_with("lno1") {
+        return None;
+    }
+
+    let parsed: Offer = match input.parse() {
+        Ok(o) => o,
+        Err(e) => return Some(Err(Error::other(format!("Invalid BOLT12 offer: {e:?}")))),
+    };
+
+    let amount_msat = match parsed.amount() {
+        Some(Amount::Bitcoin { amount_msats }) => Some(amount_msats),
+        Some(Amount::Currency { .. }) | None => None,
+    };
+
+    Some(Ok(ParsedInput::Bolt12Offer {
+        offer: ParsedOffer {
+            bolt12: input.to_string(),
+            offer_id: hex::encode(parsed.id().0),
+            description: parsed.description().map(|d| d.to_string()),
+            issuer: parsed.issuer().map(|i| i.to_string()),
+            amount_msat,
+            absolute_expiry: parsed.absolute_expiry().map(|d| d.as_secs()),
+            issuer_pubkey: parsed
+                .issuer_signing_pubkey()
+                .map(|p| hex::encode(p.serialize())),
+        },
+    }))
+}
+
 /// Try parsing as a node ID (66-char hex → 33-byte compressed pubkey).
 fn try_parse_node_id(input: &str) -> Option<ParsedInput> {
     if input.len() != 66 {
@@ -284,6 +364,7 @@ mod tests {
     fn parsed_variant_name(t: &ParsedInput) -> &'static str {
         match t {
             ParsedInput::Bolt11 { .. } => "Bolt11",
+            ParsedInput::Bolt12Offer { .. } => "Bolt12Offer",
             ParsedInput::NodeId { .. } => "NodeId",
             ParsedInput::LnUrl { .. } => "LnUrl",
             ParsedInput::LnUrlAddress { .. } => "LnUrlAddress",
@@ -293,9 +374,11 @@ mod tests {
     fn resolved_variant_name(t: &ResolvedInput) -> &'static str {
         match t {
             ResolvedInput::Bolt11 { .. } => "Bolt11",
+            ResolvedInput::Bolt12Offer { .. } => "Bolt12Offer",
             ResolvedInput::NodeId { .. } => "NodeId",
             ResolvedInput::LnUrlPay { .. } => "LnUrlPay",
             ResolvedInput::LnUrlWithdraw { .. } => "LnUrlWithdraw",
+            ResolvedInput::LnUrlAuth { .. } => "LnUrlAuth",
         }
     }
 
@@ -317,6 +400,52 @@ mod tests {
         assert!(matches!(result, ParsedInput::Bolt11 { .. }));
     }
 
+    fn test_offer(amount_msat: Option<u64>) -> String {
+        use gl_client::bitcoin::secp256k1::{PublicKey, Secp256k1, SecretKey};
+        use gl_client::lightning::offers::offer::OfferBuilder;
+
+        let secp = Secp256k1::new();
+        let key = SecretKey::from_slice(&[0x42; 32]).unwrap();
+        let builder = OfferBuilder::new(PublicKey::from_secret_key(&secp, &key))
+            .description("coffee".to_string())
+            .issuer("Satoshi's Cafe".to_string());
+        let builder = match amount_msat {
+            Some(a) => builder.amount_msats(a),
+            None => builder,
+        };
+        builder.build().unwrap().to_string()
+    }
+
+    #[test]
+    fn test_parse_input_bolt12_offer() {
+        let offer = test_offer(Some(21_000));
+        match parse_input(offer.clone()).unwrap() {
+            ParsedInput::Bolt12Offer { offer: parsed } => {
+                assert_eq!(parsed.bolt12, offer);
+                assert_eq!(parsed.amount_msat, Some(21_000));
+                assert_eq!(parsed.description.as_deref(), Some("coffee"));
+                assert_eq!(parsed.issuer.as_deref(), Some("Satoshi's Cafe"));
+                assert_eq!(parsed.offer_id.len(), 64);
+                assert_eq!(parsed.issuer_pubkey.map(|p| p.len()), Some(66));
+            }
+            other => panic!("Expected Bolt12Offer, got {}", parsed_variant_name(&other)),
+        }
+    }
+
+    #[test]
+    fn test_parse_input_bolt12_offer_any_amount_with_prefix() {
+        let offer = test_offer(None);
+        match parse_input(format!("lightning:{}", offer.to_uppercase())).unwrap() {
+            ParsedInput::Bolt12Offer { offer: parsed } => assert_eq!(parsed.amount_msat, None),
+            other => panic!("Expected Bolt12Offer, got {}", parsed_variant_name(&other)),
+        }
+    }
+
+    #[test]
+    fn test_parse_input_invalid_bolt12_offer_errors() {
+        assert!(parse_input("lno1notanoffer".to_string()).is_err());
+    }
+
     #[test]
     fn test_parse_input_node_id() {
         let node_id = "02eec7245d6b7d2ccb30380bfbe2a3648cd7a942653f5aa340edcea1f283686619";
@@ -395,6 +524,17 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_resolve_input_bolt12_offer_passes_through() {
+        let offer = test_offer(Some(1_000));
+        match crate::util::exec(resolve_input(offer)).unwrap() {
+            ResolvedInput::Bolt12Offer { offer: parsed } => {
+                assert_eq!(parsed.amount_msat, Some(1_000))
+            }
+            other => panic!("Expected Bolt12Offer, got {}", resolved_variant_name(&other)),
+        }
+    }
+
     #[test]
     fn test_resolve_input_node_id_passes_through() {
         let node_id = "02eec7245d6b7d2ccb30380bfbe2a3648cd7a942653f5aa340edcea1f283686619";
@@ -408,4 +548,20 @@ mod tests {
     fn test_resolve_input_invalid_lnurl_errors_before_http() {
         assert!(crate::util::exec(resolve_input("LNURL1INVALIDDATA".to_string())).is_err());
     }
+
+    #[test]
+    fn test_resolve_input_lnurl_auth_without_http() {
+        let k1 = "e2af6254a8df433264fa23f67eb8188635d15ce883e8fc020989d5f82ae6f11e";
+        let url = format!("https://site.com/auth?tag=login&k1={}&action=login", k1);
+        let lnurl = gl_client::lnurl::utils::lnurl_encode(&url).unwrap();
+        match crate::util::exec(resolve_input(lnurl)).unwrap() {
+            ResolvedInput::LnUrlAuth { data } => {
+                assert_eq!(data.k1, k1);
+                assert_eq!(data.action.as_deref(), Some("login"));
+                assert_eq!(data.domain, "site.com");
+                assert_eq!(data.url, url);
+            }
+            other => panic!("Expected LnUrlAuth, got {}", resolved_variant_name(&other)),
+        }
+    }
 }
@@ -39,6 +39,13 @@ pub enum Error {
         values: HashMap<String, String>,
     },
 
+    #[error("{msg}")]
+    CursorTooOld {
+        code: i32,
+        msg: String,
+        values: HashMap<String, String>,
+    },
+
     #[error("{msg}")]
     Argument {
         code: i32,
@@ -46,6 +53,13 @@ pub enum Error {
         values: HashMap<String, String>,
     },
 
+    #[error("{msg}")]
+    ReadOnly {
+        code: i32,
+        msg: String,
+        values: HashMap<String, String>,
+    },
+
     #[error("{msg}")]
     Other {
         code: i32,
@@ -102,6 +116,15 @@ impl Error {
         }
     }
 
+    pub fn cursor_too_old(detail: impl Into<String>) -> Self {
+        let detail = detail.into();
+        Error::CursorTooOld {
+            code: 2100,
+            msg: format!("The node no longer retains the events after the cursor: {detail}"),
+            values: HashMap::from([("detail".into(), detail)]),
+        }
+    }
+
     pub fn argument(arg_name: impl Into<String>, arg_value: impl Into<String>) -> Self {
         let arg_name = arg_name.into();
         let arg_value = arg_value.into();
@@ -115,6 +138,15 @@ impl Error {
         }
     }
 
+    pub fn read_only(method: impl Into<String>) -> Self {
+        let method = method.into();
+        Error::ReadOnly {
+            code: 4000,
+            msg: format!("The node is read-only, {method} is not allowed"),
+            values: HashMap::from([("method".into(), method)]),
+        }
+    }
+
     pub fn other(detail: impl Into<String>) -> Self {
         let detail = detail.into();
         Error::Other {
@@ -125,6 +157,7 @@ impl Error {
     }
 }
 
+mod async_node;
 mod config;
 mod credentials;
 mod input;
@@ -132,26 +165,31 @@ mod lnurl;
 mod logging;
 mod node;
 mod node_builder;
+mod payments;
 mod scheduler;
 mod signer;
 mod util;
 
 pub use crate::{
+    async_node::AsyncNode,
     config::Config,
     credentials::{Credentials, DeveloperCert},
     node::{
-        ChannelState, FundChannel, FundOutput, GetInfoResponse, Invoice,
+        ChannelClosedEvent, ChannelEvent, ChannelState, ChannelStateChangedEvent,
+        DepositConfirmedEvent, FundChannel, FundOutput, GetInfoResponse, Invoice,
         InvoicePaidEvent, InvoiceStatus, ListFundsResponse, ListIndex, ListInvoicesResponse,
-        ListPaymentsRequest, ListPeerChannelsResponse, ListPaysResponse, ListPeersResponse,
-        Node, NodeEvent, NodeEventListener, NodeEventStream, NodeState, OnchainBalanceState,
-        OnchainFeeRates, OnchainReceiveResponse, OnchainSendResponse, Outpoint, OutputStatus,
-        Pay, PayStatus, Payment, PaymentStatus, PaymentType, PaymentTypeFilter, Peer,
-        PeerChannel, PreparedOnchainSend, ReceiveResponse, SendResponse,
+        ListOffersResponse, ListPaymentsRequest, ListPaymentsSinceResponse,
+        ListPeerChannelsResponse, ListPaysResponse,
+        ListPeersResponse, Node, NodeEvent, NodeEventListener, NodeEventStream, NodeState,
+        Offer, OnchainBalanceState, OnchainFeeRates, OnchainReceiveResponse,
+        OnchainSendResponse, Outpoint, OutputStatus, Pay, PayStatus, Payment, PaymentFailedEvent, PaymentKind, PaymentSentEvent, PaymentStatus, PaymentType, PaymentTypeFilter, Peer,
+        PeerChannel, PreparedOnchainSend, PreparedSend, ReceiveResponse, SendOptions, SendResponse,
+        SendRoute, TlvEntry,
     },
-    input::{ParsedInput, ParsedInvoice, ResolvedInput},
+    input::{ParsedInput, ParsedInvoice, ParsedOffer, ResolvedInput},
     logging::{LogEntry, LogLevel, LogListener},
     lnurl::{
-        LnUrlErrorData, LnUrlPayRequest, LnUrlPayRequestData, LnUrlPayResult,
+        LnUrlAuthRequestData, LnUrlCallbackStatus, LnUrlErrorData, LnUrlPayRequest, LnUrlPayRequestData, LnUrlPayResult,
         LnUrlPaySuccessData, LnUrlWithdrawRequest, LnUrlWithdrawRequestData,
         LnUrlWithdrawResult, LnUrlWithdrawSuccessData, SuccessActionProcessed,
     },
@@ -213,8 +251,7 @@ fn schedule_node(
         gl_client::signer::Signer::new(seed, network, credentials.inner.clone())
             .map_err(|e| Error::other(e.to_string()))?;
 
-    let handle = signer::Handle::spawn(authenticated_signer);
-    let node = node::Node::with_signer(credentials, handle, network)?;
+    let node = node::Node::with_signer(credentials, authenticated_signer, network)?;
     Ok(Arc::new(node))
 }
 
@@ -276,8 +313,7 @@ pub(crate) fn connect_internal(
         gl_client::signer::Signer::new(seed, network, creds.inner.clone())
             .map_err(|e| Error::other(e.to_string()))?;
 
-    let handle = signer::Handle::spawn(authenticated_signer);
-    let node = node::Node::with_signer(creds, handle, network)?;
+    let node = node::Node::with_signer(creds, authenticated_signer, network)?;
     Ok(Arc::new(node))
 }
 
@@ -337,9 +373,9 @@ pub(crate) fn connect_signerless_internal(
 
 /// Synchronously classify the input. **No HTTP, no I/O.**
 ///
-/// Recognises BOLT11 invoices, node IDs, LNURL bech32 strings, and
-/// Lightning Addresses. Strips `lightning:` / `LIGHTNING:` prefixes
-/// automatically. LNURL inputs are decoded to their underlying URL
+/// Recognises BOLT11 invoices, BOLT12 offers, node IDs, LNURL bech32
+/// strings, and Lightning Addresses. Strips `lightning:` /
+/// `LIGHTNING:` prefixes automatically. LNURL inputs are decoded to their underlying URL
 /// but **not fetched** — the caller chooses whether to resolve
 /// further (via `resolve_input`) or to surface the URL to the user
 /// as-is.
@@ -357,8 +393,8 @@ pub fn parse_input(input: String) -> Result<input::ParsedInput, Error> {
 /// Internally calls `parse_input` for offline classification, then
 /// for LNURL bech32 strings and Lightning Addresses performs the
 /// HTTP GET to the LNURL endpoint and returns typed pay or withdraw
-/// request data. For BOLT11 invoices and node IDs it returns
-/// immediately without I/O.
+/// request data. For BOLT11 invoices, BOLT12 offers and node IDs it
+/// returns immediately without I/O.
 ///
 /// Strips `lightning:` / `LIGHTNING:` prefixes automatically.
 ///
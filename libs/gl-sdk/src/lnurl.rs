@@ -53,6 +53,25 @@ pub struct LnUrlWithdrawRequestData {
     pub lnurl: String,
 }
 
+/// A login request from an LNURL-auth endpoint (LUD-04).
+///
+/// Returned inside `ResolvedInput::LnUrlAuth`. Resolving a login
+/// URL needs no HTTP request, since everything is carried in its
+/// query string. Pass to `Node::lnurl_auth` to log in.
+#[derive(Clone, uniffi::Record)]
+pub struct LnUrlAuthRequestData {
+    /// Hex-encoded 32 byte challenge the wallet signs.
+    pub k1: String,
+    /// Action announced by the service: `register`, `login`, `link`
+    /// or `auth`. None if the service did not specify one.
+    pub action: Option<String>,
+    /// Domain of the service. The linking key is derived from it, so
+    /// wallets should show it to the user before logging in.
+    pub domain: String,
+    /// The decoded login URL, which is also the callback URL.
+    pub url: String,
+}
+
 // ── User request types ──────────────────────────────────────────────
 
 /// Request to execute an LNURL-pay flow.
@@ -137,6 +156,15 @@ pub struct LnUrlWithdrawSuccessData {
     pub invoice: String,
 }
 
+/// Result of an LNURL-auth login.
+#[derive(Clone, uniffi::Enum)]
+pub enum LnUrlCallbackStatus {
+    /// The service accepted the signed challenge.
+    Ok,
+    /// The LNURL service returned an error.
+    ErrorStatus { data: LnUrlErrorData },
+}
+
 /// Error returned by an LNURL service endpoint.
 #[derive(Clone, uniffi::Record)]
 pub struct LnUrlErrorData {
@@ -188,6 +216,28 @@ impl From<wire::WithdrawRequestResponse> for LnUrlWithdrawRequestData {
     }
 }
 
+impl From<wire::LnUrlAuthRequestData> for LnUrlAuthRequestData {
+    fn from(r: wire::LnUrlAuthRequestData) -> Self {
+        Self {
+            k1: r.k1,
+            action: r.action,
+            domain: r.domain,
+            url: r.url,
+        }
+    }
+}
+
+impl From<LnUrlAuthRequestData> for wire::LnUrlAuthRequestData {
+    fn from(r: LnUrlAuthRequestData) -> Self {
+        Self {
+            k1: r.k1,
+            action: r.action,
+            domain: r.domain,
+            url: r.url,
+        }
+    }
+}
+
 impl From<wire::ProcessedSuccessAction> for SuccessActionProcessed {
     fn from(a: wire::ProcessedSuccessAction) -> Self {
         match a {
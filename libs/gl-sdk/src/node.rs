@@ -1,6 +1,8 @@
+use crate::payments::{self, FeedCursor, SyncCursor};
 use crate::{credentials::Credentials, signer::Handle, util::exec, Error};
+use std::collections::HashMap;
 use std::str::FromStr;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use gl_client::credentials::NodeIdProvider;
 use gl_client::lnurl::models::LnUrlHttpClient as _;
 use gl_client::node::{Client as GlClient, ClnClient, Node as ClientNode};
@@ -19,11 +21,21 @@ pub struct Node {
     gl_client: OnceCell<GlClient>,
     stored_credentials: Option<Credentials>,
     signer_handle: Option<Handle>,
+    /// The signer running alongside this node, if any. Used for
+    /// signatures the node cannot produce, such as LNURL-auth.
+    signer: Option<gl_client::signer::Signer>,
     disconnected: AtomicBool,
+    /// Set by `NodeBuilder::with_read_only`. Calls that spend, receive
+    /// or otherwise change the node fail client-side instead of being
+    /// sent to the node.
+    read_only: AtomicBool,
     /// Background task that tails the gRPC event stream and dispatches
     /// events to the installed listener. A single listener per node;
     /// installing a new one aborts the previous task. Aborted on Drop.
     event_task: Mutex<Option<tokio::task::JoinHandle<()>>>,
+    /// Index of the last event delivered to the listener, or 0 if
+    /// none was delivered yet. Used to resume the event stream.
+    event_cursor: Arc<AtomicU64>,
     network: gl_client::bitcoin::Network,
 }
 
@@ -63,8 +75,11 @@ impl Node {
             gl_client,
             stored_credentials: Some(credentials),
             signer_handle: None,
+            signer: None,
             disconnected: AtomicBool::new(false),
+            read_only: AtomicBool::new(false),
             event_task: Mutex::new(None),
+            event_cursor: Arc::new(AtomicU64::new(0)),
             network: gl_client::bitcoin::Network::Bitcoin,
         })
     }
@@ -76,6 +91,7 @@ impl Node {
     /// Stop the node if it is currently running.
     pub fn stop(&self) -> Result<(), Error> {
         self.check_connected()?;
+        self.check_writable("stop")?;
         let mut cln_client = exec(self.get_cln_client())?.clone();
 
         let req = clnpb::StopRequest {};
@@ -98,11 +114,41 @@ impl Node {
         }
     }
 
+    /// Create credentials for a new device that can only read from
+    /// this node, e.g., for an accountant or a dashboard.
+    ///
+    /// Runs the device pairing flow with this node approving the
+    /// pairing: the new device gets a fresh certificate and a rune
+    /// restricted to `Get*` and `List*` calls, bound to the device's
+    /// key. A signer for this node must be running, since it issues
+    /// the rune.
+    ///
+    /// The rune is only checked by the signer, for requests it has to
+    /// sign. Read-only is not enforced for calls the signer never
+    /// sees, e.g. `stop()`, `disable_offer()` or `onchain_receive()`:
+    /// those are only rejected client-side (`check_writable`), by a
+    /// Node built with `NodeBuilder::with_read_only()`. Anyone holding
+    /// the returned credentials can make them with a different client.
+    pub fn derive_readonly_credentials(
+        &self,
+        device_name: String,
+    ) -> Result<Arc<Credentials>, Error> {
+        exec(self.derive_readonly_credentials_async(device_name))
+    }
+
+    /// Whether the node was built with `NodeBuilder::with_read_only()`.
+    pub fn is_read_only(&self) -> bool {
+        self.read_only.load(Ordering::Relaxed)
+    }
+
     /// Disconnects from the node and stops the signer if running.
     /// After disconnect, all RPC methods will return an error.
     /// Safe to call multiple times.
     pub fn disconnect(&self) -> Result<(), Error> {
         self.disconnected.store(true, Ordering::Relaxed);
+        if let Some(task) = self.event_task.lock().ok().and_then(|mut t| t.take()) {
+            task.abort();
+        }
         if let Some(ref handle) = self.signer_handle {
             handle.try_stop();
         }
@@ -125,50 +171,59 @@ impl Node {
         description: String,
         amount_msat: Option<u64>,
     ) -> Result<ReceiveResponse, Error> {
-        self.check_connected()?;
-        let mut gl_client = exec(self.get_gl_client())?.clone();
+        exec(self.receive_async(label, description, amount_msat))
+    }
 
-        let req = gl_client::pb::LspInvoiceRequest {
-            amount_msat: amount_msat.unwrap_or_default(),
-            description: description,
-            label: label,
-            lsp_id: "".to_owned(),
-            token: "".to_owned(),
-        };
-        let res = exec(gl_client.lsp_invoice(req))
-            .map_err(|s| Error::rpc(s.to_string()))?
-            .into_inner();
-        Ok(ReceiveResponse {
-            bolt11: res.bolt11,
-            opening_fee_msat: res.opening_fee_msat,
-        })
+    /// Pay a BOLT11 invoice.
+    ///
+    /// `amount_msat` is required for invoices without an amount, and
+    /// must be omitted otherwise. `options` caps the fee, bounds the
+    /// time spent retrying and excludes nodes or channels from the
+    /// routes; without options the node's defaults apply.
+    #[uniffi::method(default(options = None))]
+    pub fn send(
+        &self,
+        invoice: String,
+        amount_msat: Option<u64>,
+        options: Option<SendOptions>,
+    ) -> Result<SendResponse, Error> {
+        exec(self.send_async(invoice, amount_msat, options))
     }
 
-    pub fn send(&self, invoice: String, amount_msat: Option<u64>) -> Result<SendResponse, Error> {
-        self.check_connected()?;
-        let mut cln_client = exec(self.get_cln_client())?.clone();
-        let req = clnpb::PayRequest {
-            amount_msat: match amount_msat {
-                Some(a) => Some(clnpb::Amount { msat: a }),
-                None => None,
-            },
+    /// Estimate the fee of paying a BOLT11 invoice, without paying it.
+    ///
+    /// Finds a route to the recipient, including through the route
+    /// hints in the invoice, and returns the fee it would cost, along
+    /// with the maximum fee `send` would accept with the same
+    /// `options`. Show both to the user before confirming, then pass
+    /// the same arguments to `send`. The actual fee may differ if the
+    /// network changes in between.
+    #[uniffi::method(default(options = None))]
+    pub fn prepare_send(
+        &self,
+        invoice: String,
+        amount_msat: Option<u64>,
+        options: Option<SendOptions>,
+    ) -> Result<PreparedSend, Error> {
+        exec(self.prepare_send_async(invoice, amount_msat, options))
+    }
 
-            bolt11: invoice,
-            description: None,
-            exclude: vec![],
-            exemptfee: None,
-            label: None,
-            localinvreqid: None,
-            maxdelay: None,
-            maxfee: None,
-            maxfeepercent: None,
-            partial_msat: None,
-            retry_for: None,
-            riskfactor: None,
-        };
-        exec(cln_client.pay(req))
-            .map_err(|e| Error::rpc(e.to_string()))
-            .map(|r| r.into_inner().into())
+    /// Send a spontaneous (keysend) payment directly to a node.
+    ///
+    /// No invoice is needed: the preimage is generated locally and
+    /// delivered to the recipient in the onion. Use this to tip a
+    /// `ParsedInput::NodeId`, or for podcasting 2.0 boosts, which
+    /// carry their metadata in `extra_tlvs` (e.g. type 7629169).
+    /// Custom TLV types must be odd and at least 65536.
+    ///
+    /// * `node_id` — The recipient's node ID as hex (66 chars).
+    pub fn send_spontaneous(
+        &self,
+        node_id: String,
+        amount_msat: u64,
+        extra_tlvs: Option<Vec<TlvEntry>>,
+    ) -> Result<SendResponse, Error> {
+        exec(self.send_spontaneous_async(node_id, amount_msat, extra_tlvs))
     }
 
     /// Send bitcoin on-chain to a destination address.
@@ -197,26 +252,7 @@ impl Node {
         sat_per_vbyte: Option<u32>,
         utxos: Option<Vec<Outpoint>>,
     ) -> Result<OnchainSendResponse, Error> {
-        self.check_connected()?;
-        let mut cln_client = exec(self.get_cln_client())?.clone();
-
-        let satoshi = parse_amount_or_all(&amount_or_all)?;
-
-        let req = clnpb::WithdrawRequest {
-            destination,
-            minconf: None,
-            feerate: sat_per_vbyte.map(feerate_perkw_from_sat_per_vbyte),
-            satoshi: Some(satoshi),
-            utxos: utxos
-                .unwrap_or_default()
-                .into_iter()
-                .map(outpoint_to_pb)
-                .collect::<Result<Vec<_>, _>>()?,
-        };
-
-        exec(cln_client.withdraw(req))
-            .map_err(|e| Error::rpc(e.to_string()))
-            .map(|r| r.into_inner().into())
+        exec(self.onchain_send_async(destination, amount_or_all, sat_per_vbyte, utxos))
     }
 
     /// Preview an on-chain send without broadcasting or reserving UTXOs.
@@ -255,163 +291,7 @@ impl Node {
         amount_or_all: String,
         sat_per_vbyte: Option<u32>,
     ) -> Result<PreparedOnchainSend, Error> {
-        self.check_connected()?;
-        let cln_client = exec(self.get_cln_client())?.clone();
-
-        let satoshi = parse_amount_or_all(&amount_or_all)?;
-        let is_sweep = matches!(satoshi.value, Some(clnpb::amount_or_all::Value::All(true)));
-
-        // `startweight` must cover everything CLN does NOT add itself
-        // during fundpsbt: the base tx overhead and the destination
-        // output. CLN accumulates per-input spend weights and the
-        // change output weight on top of this. See
-        // lightning/plugins/spender/multiwithdraw.c:339 for the
-        // canonical formula CLN uses for its own withdraw plugin.
-        let startweight = BASE_TX_CORE_WEIGHT + output_weight_for_address(&destination);
-
-        let feerate = match sat_per_vbyte {
-            Some(rate) => feerate_perkw_from_sat_per_vbyte(rate),
-            None => clnpb::Feerate {
-                style: Some(clnpb::feerate::Style::Normal(true)),
-            },
-        };
-
-        let req = clnpb::FundpsbtRequest {
-            satoshi: Some(satoshi),
-            feerate: Some(feerate),
-            startweight,
-            // `reserve = 0` is the whole point: CLN runs coin selection
-            // and returns the would-be inputs but does not lock them.
-            reserve: Some(0),
-            minconf: None,
-            locktime: None,
-            min_witness_weight: None,
-            // For non-sweep sends any leftover after the requested
-            // amount + fee becomes change. For sweeps there is no
-            // requested amount so the leftover is the recipient amount
-            // and CLN reports it via `excess_msat`.
-            excess_as_change: Some(!is_sweep),
-            nonwrapped: None,
-            opening_anchor_channel: None,
-        };
-
-        // Run fund_psbt and feerates concurrently. The latter is used
-        // only to validate the requested rate against the network's
-        // relay floor — without this check, a too-low `sat_per_vbyte`
-        // produces a confusing post-broadcast `min relay fee not met`
-        // failure instead of a clean pre-confirmation error.
-        let (fund_res, feerates_res) = exec(async {
-            let mut c_fund = cln_client.clone();
-            let mut c_rates = cln_client.clone();
-            tokio::join!(
-                c_fund.fund_psbt(req),
-                c_rates.feerates(clnpb::FeeratesRequest {
-                    style: clnpb::feerates_request::FeeratesStyle::Perkw as i32,
-                }),
-            )
-        });
-
-        // Reject below-relay rates up front when the caller specified
-        // one. If `feerates` itself failed, skip the check — a stale
-        // bitcoind connection shouldn't block a prepare.
-        if let (Some(rate), Ok(rates)) = (sat_per_vbyte, feerates_res.as_ref())
-            && let Some(perkw) = rates.get_ref().perkw.as_ref()
-        {
-            let min_sat_per_vbyte =
-                sat_per_vbyte_from_perkw(perkw.min_acceptable).max(1);
-            if (rate as u64) < min_sat_per_vbyte {
-                return Err(Error::argument(
-                    "sat_per_vbyte",
-                    format!(
-                        "{} sat/vbyte is below the network minimum of {} sat/vbyte",
-                        rate, min_sat_per_vbyte
-                    ),
-                ));
-            }
-        }
-
-        let res = fund_res
-            .map_err(|e| Error::rpc(e.to_string()))?
-            .into_inner();
-
-        // CLN only emits the `reservations` array when `reserve > 0`
-        // (see lightning/wallet/reservation.c:421 — `if (reserve)`).
-        // We deliberately pass `reserve=0` to avoid locking UTXOs, so
-        // we extract the chosen inputs from the returned PSBT instead.
-        let psbt = bitcoin::Psbt::from_str(&res.psbt)
-            .map_err(|e| Error::rpc(format!("invalid psbt from fund_psbt: {}", e)))?;
-        let utxos: Vec<Outpoint> = psbt
-            .unsigned_tx
-            .input
-            .iter()
-            .map(|tx_in| Outpoint {
-                txid: tx_in.previous_output.txid.to_string(),
-                vout: tx_in.previous_output.vout,
-            })
-            .collect();
-
-        // BIP-141: feerate_per_kw is sats per 1000 weight units, so
-        // fee_sat = weight_wu × feerate_per_kw / 1000. The proto-level
-        // `estimated_final_weight` already includes the destination
-        // output we declared via `startweight`, plus any change output.
-        let fee_sat: u64 =
-            (res.estimated_final_weight as u64 * res.feerate_per_kw as u64) / 1000;
-
-        // Sum input values directly from the PSBT. Each PSBT input
-        // carries its prevout amount in `witness_utxo` (segwit) or
-        // `non_witness_utxo` (legacy). This is the one source of truth
-        // and works for sweeps that include an emergency-reserve
-        // change output (anchor-channel wallets) — see
-        // lightning/wallet/reservation.c:443 `change_for_emergency`,
-        // which carves out `emergency_sat` even from `satoshi=All`.
-        let mut total_input_sat: u64 = 0;
-        for (i, input) in psbt.inputs.iter().enumerate() {
-            let value = if let Some(ref txout) = input.witness_utxo {
-                txout.value
-            } else if let Some(ref tx) = input.non_witness_utxo {
-                let vout = psbt.unsigned_tx.input[i].previous_output.vout as usize;
-                tx.output
-                    .get(vout)
-                    .map(|o| o.value)
-                    .ok_or_else(|| {
-                        Error::rpc("psbt non_witness_utxo missing vout")
-                    })?
-            } else {
-                return Err(Error::rpc(format!(
-                    "psbt input {} has no witness_utxo or non_witness_utxo",
-                    i
-                )));
-            };
-            total_input_sat = total_input_sat.saturating_add(value.to_sat());
-        }
-
-        let recipient_sat: u64 = if is_sweep {
-            // For `satoshi=All` CLN reports the post-fee, post-emergency
-            // leftover via `excess_msat`; that's what the recipient
-            // receives. Any difference between `total_input_sat` and
-            // `recipient_sat + fee_sat` is the emergency-reserve change
-            // CLN keeps in the wallet for anchor channels.
-            res.excess_msat.as_ref().map(|a| a.msat).unwrap_or(0) / 1000
-        } else {
-            match parse_amount_or_all(&amount_or_all)?.value {
-                Some(clnpb::amount_or_all::Value::Amount(a)) => a.msat / 1000,
-                _ => 0,
-            }
-        };
-
-        // Round up so passing this back to `onchain_send` produces a
-        // feerate at least as high as the previewed one; that way the
-        // broadcast fee is never below what the user agreed to.
-        let effective_sat_per_vbyte: u32 =
-            (res.feerate_per_kw as u64).div_ceil(250) as u32;
-
-        Ok(PreparedOnchainSend {
-            utxos,
-            total_input_sat,
-            fee_sat,
-            recipient_sat,
-            sat_per_vbyte: effective_sat_per_vbyte,
-        })
+        exec(self.prepare_onchain_send_async(destination, amount_or_all, sat_per_vbyte))
     }
 
     /// Classify the on-chain wallet for the withdraw entry-point UI.
@@ -437,124 +317,8 @@ impl Node {
     /// `prepare_onchain_send`; the `withdrawable_sat` returned here
     /// is a pre-fee, reserve-aware figure for the entry-point label.
     pub fn onchain_balance_state(&self) -> Result<OnchainBalanceState, Error> {
-        self.check_connected()?;
-        let cln_client = exec(self.get_cln_client())?.clone();
-
-        // Run the three RPCs concurrently. The probe is allowed to
-        // fail (e.g. empty wallet, insufficient funds for any spend);
-        // we treat that as "no reserve applicable" and let the rest
-        // of the classification proceed.
-        let (funds_res, channels_res, probe_res) = exec(async {
-            let mut c_funds = cln_client.clone();
-            let mut c_channels = cln_client.clone();
-            let mut c_probe = cln_client.clone();
-            let probe_req = clnpb::FundpsbtRequest {
-                satoshi: Some(clnpb::AmountOrAll {
-                    value: Some(clnpb::amount_or_all::Value::All(true)),
-                }),
-                feerate: Some(clnpb::Feerate {
-                    style: Some(clnpb::feerate::Style::Normal(true)),
-                }),
-                // Assume a P2WPKH destination for the probe — we don't
-                // have a real address here. The output type only
-                // affects fee estimation by a handful of weight units;
-                // it does not affect the carved emergency reserve.
-                startweight: BASE_TX_CORE_WEIGHT + 124,
-                reserve: Some(0),
-                minconf: None,
-                locktime: None,
-                min_witness_weight: None,
-                excess_as_change: Some(false),
-                nonwrapped: None,
-                opening_anchor_channel: None,
-            };
-            tokio::join!(
-                c_funds.list_funds(clnpb::ListfundsRequest { spent: None }),
-                c_channels.list_peer_channels(clnpb::ListpeerchannelsRequest { id: None }),
-                c_probe.fund_psbt(probe_req),
-            )
-        });
-
-        let funds: ListFundsResponse = funds_res
-            .map_err(|e| Error::rpc(e.to_string()))?
-            .into_inner()
-            .into();
-        let channels: ListPeerChannelsResponse = channels_res
-            .map_err(|e| Error::rpc(e.to_string()))?
-            .into_inner()
-            .into();
-
-        let mut confirmed_sat: u64 = 0;
-        let mut unconfirmed_sat: u64 = 0;
-        let mut immature_sat: u64 = 0;
-        for output in &funds.outputs {
-            if output.reserved {
-                continue;
-            }
-            let value_sat = output.amount_msat / 1000;
-            match output.status {
-                OutputStatus::Confirmed => confirmed_sat += value_sat,
-                OutputStatus::Unconfirmed => unconfirmed_sat += value_sat,
-                OutputStatus::Immature => immature_sat += value_sat,
-                OutputStatus::Spent => {}
-            }
-        }
-
-        let mut pending_close_sat: u64 = 0;
-        for ch in &channels.channels {
-            if channel_payout_still_pending(ch) {
-                pending_close_sat += ch.to_us_msat.unwrap_or(0) / 1000;
-            }
-        }
-
-        // Derive the actual emergency reserve from the probe. CLN's
-        // `change_for_emergency` runs server-side in the same
-        // `fund_psbt` call we just made; the difference between the
-        // input total and (recipient + fee) is exactly what would be
-        // carved as change on a real sweep.
-        let reserve_sat = match probe_res {
-            Ok(resp) => {
-                let resp = resp.into_inner();
-                // CLN-managed UTXOs are always segwit, so each PSBT
-                // input carries `witness_utxo` with the prevout
-                // amount. Sum to get total input value.
-                let total_input_sat = bitcoin::Psbt::from_str(&resp.psbt)
-                    .ok()
-                    .map(|p| {
-                        p.inputs
-                            .iter()
-                            .filter_map(|i| {
-                                i.witness_utxo.as_ref().map(|t| t.value.to_sat())
-                            })
-                            .sum::<u64>()
-                    })
-                    .unwrap_or(0);
-                let excess_sat = resp
-                    .excess_msat
-                    .as_ref()
-                    .map(|a| a.msat / 1000)
-                    .unwrap_or(0);
-                let fee_sat = (resp.estimated_final_weight as u64
-                    * resp.feerate_per_kw as u64)
-                    / 1000;
-                total_input_sat
-                    .saturating_sub(excess_sat)
-                    .saturating_sub(fee_sat)
-            }
-            // `fund_psbt` errors are expected on empty wallets or when
-            // every UTXO is dust-uneconomic at the chosen feerate;
-            // treat as "no reserve applicable."
-            Err(_) => 0,
-        };
-
-        Ok(classify_onchain_balance(
-            confirmed_sat,
-            reserve_sat,
-            unconfirmed_sat,
-            immature_sat,
-            pending_close_sat,
-        ))
-    }
+        exec(self.onchain_balance_state_async())
+    }
 
     /// On-chain fee rates, in sats per virtual byte, at several
     /// confirmation targets.
@@ -564,16 +328,7 @@ impl Node {
     /// `minimum_relay_sat_per_vbyte` is the relay floor enforced at
     /// broadcast time and should be the lower bound of any slider.
     pub fn onchain_fee_rates(&self) -> Result<OnchainFeeRates, Error> {
-        self.check_connected()?;
-        let mut cln_client = exec(self.get_cln_client())?.clone();
-
-        let req = clnpb::FeeratesRequest {
-            style: clnpb::feerates_request::FeeratesStyle::Perkw as i32,
-        };
-        let res = exec(cln_client.feerates(req))
-            .map_err(|e| Error::rpc(e.to_string()))?
-            .into_inner();
-        Ok(compute_fee_rates(res.perkw.as_ref()))
+        exec(self.onchain_fee_rates_async())
     }
 
     /// Generate a fresh on-chain Bitcoin address for receiving funds.
@@ -582,17 +337,7 @@ impl Node {
     /// Either can be shared with a sender. Deposited funds will appear
     /// in `node_state().onchain_balance_msat` once confirmed.
     pub fn onchain_receive(&self) -> Result<OnchainReceiveResponse, Error> {
-        self.check_connected()?;
-        let mut cln_client = exec(self.get_cln_client())?.clone();
-
-        let req = clnpb::NewaddrRequest {
-            addresstype: Some(clnpb::newaddr_request::NewaddrAddresstype::All.into()),
-        };
-
-        let res = exec(cln_client.new_addr(req))
-            .map_err(|e| Error::rpc(e.to_string()))?
-            .into_inner();
-        Ok(res.into())
+        exec(self.onchain_receive_async())
     }
 
     /// Get information about the node.
@@ -600,15 +345,7 @@ impl Node {
     /// Returns basic information about the node including its ID,
     /// alias, network, and channel counts.
     pub fn get_info(&self) -> Result<GetInfoResponse, Error> {
-        self.check_connected()?;
-        let mut cln_client = exec(self.get_cln_client())?.clone();
-
-        let req = clnpb::GetinfoRequest {};
-
-        let res = exec(cln_client.getinfo(req))
-            .map_err(|e| Error::rpc(e.to_string()))?
-            .into_inner();
-        Ok(res.into())
+        exec(self.get_info_async())
     }
 
     /// List all peers connected to this node.
@@ -616,18 +353,7 @@ impl Node {
     /// Returns information about all peers including their connection
     /// status.
     pub fn list_peers(&self) -> Result<ListPeersResponse, Error> {
-        self.check_connected()?;
-        let mut cln_client = exec(self.get_cln_client())?.clone();
-
-        let req = clnpb::ListpeersRequest {
-            id: None,
-            level: None,
-        };
-
-        let res = exec(cln_client.list_peers(req))
-            .map_err(|e| Error::rpc(e.to_string()))?
-            .into_inner();
-        Ok(res.into())
+        exec(self.list_peers_async())
     }
 
     /// List all channels with peers.
@@ -635,15 +361,7 @@ impl Node {
     /// Returns detailed information about all channels including their
     /// state, capacity, and balances.
     pub fn list_peer_channels(&self) -> Result<ListPeerChannelsResponse, Error> {
-        self.check_connected()?;
-        let mut cln_client = exec(self.get_cln_client())?.clone();
-
-        let req = clnpb::ListpeerchannelsRequest { id: None };
-
-        let res = exec(cln_client.list_peer_channels(req))
-            .map_err(|e| Error::rpc(e.to_string()))?
-            .into_inner();
-        Ok(res.into())
+        exec(self.list_peer_channels_async())
     }
 
     /// List all funds available to the node.
@@ -651,15 +369,7 @@ impl Node {
     /// Returns information about on-chain outputs and channel funds
     /// that are available or pending.
     pub fn list_funds(&self) -> Result<ListFundsResponse, Error> {
-        self.check_connected()?;
-        let mut cln_client = exec(self.get_cln_client())?.clone();
-
-        let req = clnpb::ListfundsRequest { spent: None };
-
-        let res = exec(cln_client.list_funds(req))
-            .map_err(|e| Error::rpc(e.to_string()))?
-            .into_inner();
-        Ok(res.into())
+        exec(self.list_funds_async())
     }
 
     /// Get a snapshot of the node's balances, capacity, and connectivity.
@@ -667,125 +377,7 @@ impl Node {
     /// Aggregates data from multiple RPCs into a single `NodeState`.
     /// Queries the node live on each call — not cached.
     pub fn node_state(&self) -> Result<NodeState, Error> {
-        self.check_connected()?;
-        let cln_client = exec(self.get_cln_client())?.clone();
-
-        let (info_res, channels_res, funds_res) = exec(async {
-            let mut c_info = cln_client.clone();
-            let mut c_channels = cln_client.clone();
-            let mut c_funds = cln_client.clone();
-            tokio::join!(
-                c_info.getinfo(clnpb::GetinfoRequest {}),
-                c_channels.list_peer_channels(clnpb::ListpeerchannelsRequest { id: None }),
-                c_funds.list_funds(clnpb::ListfundsRequest { spent: None }),
-            )
-        });
-
-        let info: GetInfoResponse = info_res
-            .map_err(|e| Error::rpc(e.to_string()))?
-            .into_inner()
-            .into();
-        let channels: ListPeerChannelsResponse = channels_res
-            .map_err(|e| Error::rpc(e.to_string()))?
-            .into_inner()
-            .into();
-        let funds: ListFundsResponse = funds_res
-            .map_err(|e| Error::rpc(e.to_string()))?
-            .into_inner()
-            .into();
-
-        let mut channels_balance_msat: u64 = 0;
-        let mut max_payable_msat: u64 = 0;
-        let mut total_channel_capacity_msat: u64 = 0;
-        let mut max_receivable_single_payment_msat: u64 = 0;
-        let mut total_inbound_liquidity_msat: u64 = 0;
-        let mut pending_onchain_balance_msat: u64 = 0;
-        let mut connected_channel_peer_set: std::collections::HashSet<String> =
-            std::collections::HashSet::new();
-
-        for ch in &channels.channels {
-            if ch.state.is_open() {
-                channels_balance_msat += ch.to_us_msat.unwrap_or(0);
-                max_payable_msat += ch.spendable_msat.unwrap_or(0);
-                total_channel_capacity_msat += ch.total_msat.unwrap_or(0);
-                let receivable = ch.receivable_msat.unwrap_or(0);
-                if receivable > max_receivable_single_payment_msat {
-                    max_receivable_single_payment_msat = receivable;
-                }
-                total_inbound_liquidity_msat += receivable;
-            }
-            if channel_payout_still_pending(ch) {
-                pending_onchain_balance_msat += ch.to_us_msat.unwrap_or(0);
-            }
-            if ch.peer_connected {
-                connected_channel_peer_set.insert(ch.peer_id.clone());
-            }
-        }
-
-        let connected_channel_peers: Vec<String> =
-            connected_channel_peer_set.into_iter().collect();
-
-        let max_chan_reserve_msat =
-            channels_balance_msat.saturating_sub(max_payable_msat);
-
-        let mut onchain_balance_msat: u64 = 0;
-        let mut unconfirmed_onchain_balance_msat: u64 = 0;
-        let mut immature_onchain_balance_msat: u64 = 0;
-        let mut utxos: Vec<FundOutput> = Vec::with_capacity(funds.outputs.len());
-        for output in &funds.outputs {
-            if !matches!(output.status, OutputStatus::Spent) {
-                utxos.push(output.clone());
-            }
-            if output.reserved {
-                continue;
-            }
-            match output.status {
-                OutputStatus::Confirmed => onchain_balance_msat += output.amount_msat,
-                OutputStatus::Unconfirmed => {
-                    unconfirmed_onchain_balance_msat += output.amount_msat
-                }
-                OutputStatus::Immature => {
-                    immature_onchain_balance_msat += output.amount_msat
-                }
-                OutputStatus::Spent => {}
-            }
-        }
-
-        let total_onchain_msat = onchain_balance_msat
-            .saturating_add(unconfirmed_onchain_balance_msat)
-            .saturating_add(immature_onchain_balance_msat);
-        let total_balance_msat = channels_balance_msat
-            .saturating_add(total_onchain_msat)
-            .saturating_add(pending_onchain_balance_msat);
-        let spendable_balance_msat = max_payable_msat.saturating_add(onchain_balance_msat);
-
-
-        Ok(NodeState {
-            id: info.id,
-            block_height: info.blockheight,
-            network: info.network,
-            version: info.version,
-            alias: info.alias,
-            color: info.color,
-            num_active_channels: info.num_active_channels,
-            num_pending_channels: info.num_pending_channels,
-            num_inactive_channels: info.num_inactive_channels,
-            channels_balance_msat,
-            max_payable_msat,
-            total_channel_capacity_msat,
-            max_chan_reserve_msat,
-            onchain_balance_msat,
-            unconfirmed_onchain_balance_msat,
-            immature_onchain_balance_msat,
-            pending_onchain_balance_msat,
-            max_receivable_single_payment_msat,
-            total_inbound_liquidity_msat,
-            connected_channel_peers,
-            utxos,
-            total_onchain_msat,
-            total_balance_msat,
-            spendable_balance_msat,
-        })
+        exec(self.node_state_async())
     }
 
     /// List invoices (received payment requests).
@@ -800,23 +392,15 @@ impl Node {
         start: Option<u64>,
         limit: Option<u32>,
     ) -> Result<ListInvoicesResponse, Error> {
-        self.check_connected()?;
-        let mut cln_client = exec(self.get_cln_client())?.clone();
-
-        let req = clnpb::ListinvoicesRequest {
+        exec(self.list_invoices_async(
             label,
             invstring,
             payment_hash,
             offer_id,
-            index: index.map(|i| i.to_i32()),
+            index,
             start,
             limit,
-        };
-
-        let res = exec(cln_client.list_invoices(req))
-            .map_err(|e| Error::rpc(e.to_string()))?
-            .into_inner();
-        Ok(res.into())
+        ))
     }
 
     /// List outgoing payments.
@@ -830,113 +414,39 @@ impl Node {
         start: Option<u64>,
         limit: Option<u32>,
     ) -> Result<ListPaysResponse, Error> {
-        self.check_connected()?;
-        let mut cln_client = exec(self.get_cln_client())?.clone();
-
-        // ListpaysRequest.ListpaysStatus: PENDING=0, COMPLETE=1, FAILED=2
-        let cln_status = status.map(|s| match s {
-            PayStatus::PENDING => 0,
-            PayStatus::COMPLETE => 1,
-            PayStatus::FAILED => 2,
-        });
-
-        let req = clnpb::ListpaysRequest {
-            bolt11,
-            payment_hash,
-            status: cln_status,
-            index: index.map(|i| i.to_i32()),
-            start,
-            limit,
-        };
-
-        let res = exec(cln_client.list_pays(req))
-            .map_err(|e| Error::rpc(e.to_string()))?
-            .into_inner();
-        Ok(res.into())
-    }
+        exec(self.list_pays_async(bolt11, payment_hash, status, index, start, limit))
+    }
 
     /// List payments (sent and received), merged into a single timeline.
     ///
-    /// Fetches invoices and outgoing payments from the node, merges
-    /// them into a unified list, and applies optional filters.
+    /// Merges paid invoices, outgoing payments, the on-chain
+    /// transactions of the wallet and the opening fees of JIT channels
+    /// into a unified list, and applies optional filters.
     /// Use `list_invoices`/`list_pays` for direct CLN access.
     /// Results are sorted newest-first.
+    ///
+    /// To page through the history, pass the `cursor` of the last
+    /// payment as `ListPaymentsRequest::cursor`. Payments made in
+    /// between don't shift the following pages, see
+    /// `list_payments_since` to pick them up.
+    ///
+    /// On-chain transactions come from the bookkeeper and are left out
+    /// when the credentials don't allow reading it, e.g. read-only ones.
     pub fn list_payments(&self, req: ListPaymentsRequest) -> Result<Vec<Payment>, Error> {
-        self.check_connected()?;
-        let mut cln_client = exec(self.get_cln_client())?.clone();
-
-        let invoices = exec(cln_client.list_invoices(clnpb::ListinvoicesRequest::default()))
-            .map_err(|e| Error::rpc(e.to_string()))?
-            .into_inner();
-
-        let mut cln_client = exec(self.get_cln_client())?.clone();
-        let pays = exec(cln_client.list_pays(clnpb::ListpaysRequest::default()))
-            .map_err(|e| Error::rpc(e.to_string()))?
-            .into_inner();
-
-        let mut payments: Vec<Payment> = Vec::new();
-
-        // Should we include received payments?
-        let include_received = req
-            .filters
-            .as_ref()
-            .map(|f| f.is_empty() || f.iter().any(|t| matches!(t, PaymentTypeFilter::Received)))
-            .unwrap_or(true);
-
-        // Should we include sent payments?
-        let include_sent = req
-            .filters
-            .as_ref()
-            .map(|f| f.is_empty() || f.iter().any(|t| matches!(t, PaymentTypeFilter::Sent)))
-            .unwrap_or(true);
-
-        if include_received {
-            // Only paid invoices belong in payment history. Open
-            // (unpaid) and expired invoices live behind list_invoices()
-            // for callers that want to inspect them directly.
-            payments.extend(
-                invoices
-                    .invoices
-                    .into_iter()
-                    .filter(|i| {
-                        i.status()
-                            == clnpb::listinvoices_invoices::ListinvoicesInvoicesStatus::Paid
-                    })
-                    .map(|i| -> Payment { i.into() }),
-            );
-        }
-        if include_sent {
-            payments.extend(pays.pays.into_iter().map(|p| -> Payment { p.into() }));
-        }
-
-        let include_failures = req.include_failures.unwrap_or(false);
-
-        payments.retain(|p| {
-            if !include_failures && matches!(p.status, PaymentStatus::Failed) {
-                return false;
-            }
-            if let Some(from) = req.from_timestamp {
-                if p.payment_time < from {
-                    return false;
-                }
-            }
-            if let Some(to) = req.to_timestamp {
-                if p.payment_time > to {
-                    return false;
-                }
-            }
-            true
-        });
-
-        // Sort newest first
-        payments.sort_by(|a, b| b.payment_time.cmp(&a.payment_time));
-
-        // Apply pagination
-        let offset = req.offset.unwrap_or(0) as usize;
-        let limit = req.limit.unwrap_or(u32::MAX) as usize;
-        let payments = payments.into_iter().skip(offset).take(limit).collect();
+        exec(self.list_payments_async(req))
+    }
 
-        Ok(payments)
+    /// List the payments created or changed since `cursor`, to keep a
+    /// local copy of the payment history up to date.
+    ///
+    /// Pass `None` to list all payments, then the `cursor` of the
+    /// previous response. Unlike `list_payments`, failed payments are
+    /// included, so that status changes show up.
+    pub fn list_payments_since(
+        &self,
+        cursor: Option<String>,
+    ) -> Result<ListPaymentsSinceResponse, Error> {
+        exec(self.list_payments_since_async(cursor))
     }
 
     /// Stream real-time events from the node.
@@ -949,15 +459,31 @@ impl Node {
     /// so other node methods can be called concurrently from other
     /// threads.
     pub fn stream_node_events(&self) -> Result<Arc<NodeEventStream>, Error> {
-        self.check_connected()?;
-        let mut gl_client = exec(self.get_gl_client())?.clone();
-        let req = glpb::NodeEventsRequest {};
-        let stream = exec(gl_client.stream_node_events(req))
-            .map_err(|e| Error::rpc(e.to_string()))?
-            .into_inner();
-        Ok(Arc::new(NodeEventStream {
-            inner: Mutex::new(stream),
-        }))
+        self.open_node_event_stream(None)
+    }
+
+    /// Stream node events, starting with the events the node retained
+    /// after the event with index `since`.
+    ///
+    /// Pass the value of `NodeEventStream::last_index()` or
+    /// `Node::last_event_index()` from a previous stream to receive
+    /// the events that were emitted while disconnected, e.g. while
+    /// the app was in the background. Pass `0` to replay all retained
+    /// events. If the node no longer retains all the events after
+    /// `since`, `NodeEventStream::next()` fails with
+    /// `Error::CursorTooOld`.
+    pub fn stream_node_events_since(&self, since: u64) -> Result<Arc<NodeEventStream>, Error> {
+        self.open_node_event_stream(Some(since))
+    }
+
+    /// Index of the last event delivered to the event listener, if
+    /// any. Persist it to resume the events with
+    /// `NodeBuilder::with_event_cursor()` in a later session.
+    pub fn last_event_index(&self) -> Option<u64> {
+        match self.event_cursor.load(Ordering::Relaxed) {
+            0 => None,
+            i => Some(i),
+        }
     }
 
     /// Collect a diagnostic snapshot of the node and SDK state.
@@ -993,6 +519,145 @@ impl Node {
         )
     }
 
+    // ── BOLT12 offer methods ────────────────────────────────────
+
+    /// Create a BOLT12 offer, a reusable payment code.
+    ///
+    /// Unlike a BOLT11 invoice an offer can be paid any number of
+    /// times: each payer fetches a fresh invoice from this node over
+    /// onion messages. Share the returned `bolt12` string (e.g. as a
+    /// QR code) to receive payments.
+    ///
+    /// # Arguments
+    /// * `amount_msat` — Amount to request per payment. Pass `None`
+    ///   for an "any amount" offer where the payer chooses.
+    /// * `description` — Shown to the payer. Required by the spec when
+    ///   `amount_msat` is set.
+    /// * `label` — Internal label, not shown to payers.
+    /// * `single_use` — If `Some(true)`, the offer is disabled after
+    ///   the first successful payment.
+    ///
+    /// Creating an offer with the same parameters twice returns the
+    /// existing offer rather than a duplicate.
+    pub fn create_offer(
+        &self,
+        amount_msat: Option<u64>,
+        description: Option<String>,
+        label: Option<String>,
+        single_use: Option<bool>,
+    ) -> Result<Offer, Error> {
+        self.check_connected()?;
+        self.check_writable("create_offer")?;
+        let mut cln_client = exec(self.get_cln_client())?.clone();
+
+        let req = clnpb::OfferRequest {
+            amount: offer_amount_from_msat(amount_msat),
+            description,
+            label,
+            single_use,
+            ..Default::default()
+        };
+
+        let res = exec(cln_client.offer(req))
+            .map_err(|e| Error::rpc(e.to_string()))?
+            .into_inner();
+        Ok(res.into())
+    }
+
+    /// List the BOLT12 offers created by this node.
+    ///
+    /// Pass `active_only = Some(true)` to skip offers that were
+    /// disabled or that were single-use and already paid.
+    pub fn list_offers(&self, active_only: Option<bool>) -> Result<ListOffersResponse, Error> {
+        self.check_connected()?;
+        let mut cln_client = exec(self.get_cln_client())?.clone();
+
+        let req = clnpb::ListoffersRequest {
+            offer_id: None,
+            active_only,
+        };
+
+        let res = exec(cln_client.list_offers(req))
+            .map_err(|e| Error::rpc(e.to_string()))?
+            .into_inner();
+        Ok(res.into())
+    }
+
+    /// Disable an offer so that no further invoices are issued for it.
+    ///
+    /// `offer_id` is the lowercase hex id from `Offer::offer_id`.
+    /// Invoices already fetched by payers remain payable until they
+    /// expire.
+    pub fn disable_offer(&self, offer_id: String) -> Result<Offer, Error> {
+        self.check_connected()?;
+        self.check_writable("disable_offer")?;
+        let mut cln_client = exec(self.get_cln_client())?.clone();
+
+        let req = clnpb::DisableofferRequest {
+            offer_id: offer_id_to_bytes(&offer_id)?,
+        };
+
+        let res = exec(cln_client.disable_offer(req))
+            .map_err(|e| Error::rpc(e.to_string()))?
+            .into_inner();
+        Ok(res.into())
+    }
+
+    /// Re-enable a previously disabled offer.
+    pub fn enable_offer(&self, offer_id: String) -> Result<Offer, Error> {
+        self.check_connected()?;
+        self.check_writable("enable_offer")?;
+        let mut cln_client = exec(self.get_cln_client())?.clone();
+
+        let req = clnpb::EnableofferRequest {
+            offer_id: offer_id_to_bytes(&offer_id)?,
+        };
+
+        let res = exec(cln_client.enable_offer(req))
+            .map_err(|e| Error::rpc(e.to_string()))?
+            .into_inner();
+        Ok(res.into())
+    }
+
+    /// Pay a BOLT12 offer.
+    ///
+    /// Fetches an invoice from the offer's issuer over onion messages,
+    /// then pays it. `amount_msat` is required for "any amount" offers
+    /// and must be omitted (or match) otherwise. `payer_note` is an
+    /// optional message delivered to the recipient with the invoice
+    /// request.
+    ///
+    /// Call the top-level `parse_input` first to inspect the offer
+    /// (amount, description, issuer) before confirming with the user.
+    pub fn pay_offer(
+        &self,
+        offer: String,
+        amount_msat: Option<u64>,
+        payer_note: Option<String>,
+    ) -> Result<SendResponse, Error> {
+        self.check_connected()?;
+        self.check_writable("pay_offer")?;
+        let mut cln_client = exec(self.get_cln_client())?.clone();
+
+        let fetched = exec(cln_client.fetch_invoice(clnpb::FetchinvoiceRequest {
+            offer,
+            amount_msat: amount_msat.map(|msat| clnpb::Amount { msat }),
+            payer_note,
+            ..Default::default()
+        }))
+        .map_err(|e| Error::rpc(e.to_string()))?
+        .into_inner();
+
+        // `pay` accepts BOLT12 invoices in its `bolt11` field. The
+        // amount is already fixed by the fetched invoice.
+        exec(cln_client.pay(clnpb::PayRequest {
+            bolt11: fetched.invoice,
+            ..Default::default()
+        }))
+        .map_err(|e| Error::rpc(e.to_string()))
+        .map(|r| r.into_inner().into())
+    }
+
     // ── LNURL methods ───────────────────────────────────────────
 
     /// Execute an LNURL-pay flow (LUD-06).
@@ -1009,6 +674,7 @@ impl Node {
         request: crate::lnurl::LnUrlPayRequest,
     ) -> Result<crate::lnurl::LnUrlPayResult, Error> {
         self.check_connected()?;
+        self.check_writable("lnurl_pay")?;
         validate_lnurl_pay_input(&request)?;
 
         let http_client = gl_client::lnurl::models::LnUrlHttpClearnetClient::new();
@@ -1110,6 +776,7 @@ impl Node {
         request: crate::lnurl::LnUrlWithdrawRequest,
     ) -> Result<crate::lnurl::LnUrlWithdrawResult, Error> {
         self.check_connected()?;
+        self.check_writable("lnurl_withdraw")?;
 
         let http_client = gl_client::lnurl::models::LnUrlHttpClearnetClient::new();
 
@@ -1146,6 +813,49 @@ impl Node {
             }),
         }
     }
+
+    /// Log in to an LNURL-auth service (LUD-04).
+    ///
+    /// Call the top-level `resolve_input` first to obtain the
+    /// `LnUrlAuthRequestData`, and confirm the domain with the user.
+    /// The challenge is signed with a linking key derived from the
+    /// seed for that domain (LUD-05), so the same wallet always
+    /// presents the same key to a given service. Requires a node
+    /// connected with its mnemonic.
+    pub fn lnurl_auth(
+        &self,
+        data: crate::lnurl::LnUrlAuthRequestData,
+    ) -> Result<crate::lnurl::LnUrlCallbackStatus, Error> {
+        self.check_connected()?;
+        self.check_writable("lnurl_auth")?;
+        let signer = self.signer.as_ref().ok_or_else(|| {
+            Error::other("LNURL-auth requires a node connected with its mnemonic".to_string())
+        })?;
+
+        let request: gl_client::lnurl::models::LnUrlAuthRequestData = data.into();
+        let linking_key = signer
+            .lnurl_auth_linking_key(&request.domain)
+            .map_err(|e| Error::other(e.to_string()))?;
+        let http_client = gl_client::lnurl::models::LnUrlHttpClearnetClient::new();
+
+        match exec(gl_client::lnurl::auth::perform_auth(
+            &http_client,
+            &linking_key,
+            &request,
+        )) {
+            Ok(()) => Ok(crate::lnurl::LnUrlCallbackStatus::Ok),
+            Err(e) => {
+                let msg = e.to_string();
+                let reason = msg
+                    .strip_prefix(gl_client::lnurl::pay::LNURL_SERVICE_ERROR_PREFIX)
+                    .unwrap_or(&msg)
+                    .to_string();
+                Ok(crate::lnurl::LnUrlCallbackStatus::ErrorStatus {
+                    data: crate::lnurl::LnUrlErrorData { reason },
+                })
+            }
+        }
+    }
 }
 
 fn render_section<T: serde::Serialize>(result: Result<T, Error>) -> serde_json::Value {
@@ -1210,74 +920,1200 @@ fn invoice_network_mismatch(
     }
 }
 
-fn url_action_domain_mismatch(callback_url: &str, action_url: &str) -> Option<String> {
-    let cb = url::Url::parse(callback_url).ok()?;
-    let action = url::Url::parse(action_url).ok()?;
-    let cb_domain = cb.domain()?;
-    let action_domain = action.domain()?;
-    if cb_domain == action_domain {
-        None
-    } else {
-        Some(format!(
-            "success action URL domain ({}) does not match the callback domain ({})",
-            action_domain, cb_domain
-        ))
+fn url_action_domain_mismatch(callback_url: &str, action_url: &str) -> Option<String> {
+    let cb = url::Url::parse(callback_url).ok()?;
+    let action = url::Url::parse(action_url).ok()?;
+    let cb_domain = cb.domain()?;
+    let action_domain = action.domain()?;
+    if cb_domain == action_domain {
+        None
+    } else {
+        Some(format!(
+            "success action URL domain ({}) does not match the callback domain ({})",
+            action_domain, cb_domain
+        ))
+    }
+}
+
+fn validate_lnurl_pay_input(request: &crate::lnurl::LnUrlPayRequest) -> Result<(), Error> {
+    let data = &request.data;
+    if request.amount_msat < data.min_sendable {
+        return Err(Error::other(format!(
+            "amount_msat {} is below the service's min_sendable ({})",
+            request.amount_msat, data.min_sendable
+        )));
+    }
+    if request.amount_msat > data.max_sendable {
+        return Err(Error::other(format!(
+            "amount_msat {} is above the service's max_sendable ({})",
+            request.amount_msat, data.max_sendable
+        )));
+    }
+    if let Some(comment) = request.comment.as_deref() {
+        if data.comment_allowed == 0 && !comment.is_empty() {
+            return Err(Error::other(
+                "this LNURL service does not accept comments".to_string(),
+            ));
+        }
+        if (comment.len() as u64) > data.comment_allowed {
+            return Err(Error::other(format!(
+                "comment length {} exceeds the service's comment_allowed ({})",
+                comment.len(),
+                data.comment_allowed
+            )));
+        }
+    }
+    Ok(())
+}
+
+// The async implementations behind the blocking `Node` methods and
+// their `AsyncNode` counterparts. Not exported through uniffi.
+impl Node {
+    /// Async implementation of [`Node::derive_readonly_credentials`].
+    pub(crate) async fn derive_readonly_credentials_async(
+        &self,
+        device_name: String,
+    ) -> Result<Arc<Credentials>, Error> {
+        use gl_client::pairing::{PairingSessionData, attestation_device, new_device};
+
+        self.check_connected()?;
+        self.check_writable("derive_readonly_credentials")?;
+        let creds = self
+            .stored_credentials
+            .as_ref()
+            .ok_or_else(|| Error::other("No credentials stored to approve the pairing"))?
+            .inner
+            .clone();
+        let restrictions = readonly_restrictions()?;
+
+        // Play both sides of the pairing protocol: the new device
+        // asks for a certificate and rune, and this node approves.
+        let device = new_device::Client::new(gl_client::credentials::Nobody::new())
+            .connect()
+            .await
+            .map_err(|e| Error::other(e.to_string()))?;
+        let attestation = attestation_device::Client::new(creds)
+            .map_err(|e| Error::other(e.to_string()))?
+            .connect()
+            .await
+            .map_err(|e| Error::other(e.to_string()))?;
+        let mut session = device
+            .pair_device(&device_name, "Read-only access", &restrictions)
+            .await
+            .map_err(|e| Error::other(e.to_string()))?;
+
+        while let Some(data) = session.recv().await {
+            match data {
+                PairingSessionData::PairingQr(qr) => {
+                    let device_id = qr.trim_start_matches("gl-pairing:");
+                    let data = attestation
+                        .get_pairing_data(device_id)
+                        .await
+                        .map_err(|e| Error::rpc(e.to_string()))?;
+                    // Only approve exactly what we asked for.
+                    if data.restrictions != format!("pubkey={}&{}", device_id, restrictions) {
+                        return Err(Error::other(format!(
+                            "pairing session requested unexpected restrictions: {}",
+                            data.restrictions
+                        )));
+                    }
+                    attestation_device::Client::<
+                        attestation_device::Connected,
+                        gl_client::credentials::Device,
+                    >::verify_pairing_data(data.clone())
+                    .map_err(|e| Error::other(e.to_string()))?;
+                    attestation
+                        .approve_pairing(&data.device_id, &data.device_name, &data.restrictions)
+                        .await
+                        .map_err(|e| Error::rpc(e.to_string()))?;
+                }
+                PairingSessionData::PairingResponse(res) => {
+                    return Ok(Arc::new(Credentials::load(res.creds)?));
+                }
+                PairingSessionData::PairingError(status) => {
+                    return Err(Error::rpc(status.to_string()));
+                }
+            }
+        }
+        Err(Error::other("Pairing session closed without credentials"))
+    }
+
+    /// Async implementation of [`Node::receive`].
+    pub(crate) async fn receive_async(
+        &self,
+        label: String,
+        description: String,
+        amount_msat: Option<u64>,
+    ) -> Result<ReceiveResponse, Error> {
+        self.check_connected()?;
+        self.check_writable("receive")?;
+        let mut gl_client = self.get_gl_client().await?.clone();
+
+        let req = gl_client::pb::LspInvoiceRequest {
+            amount_msat: amount_msat.unwrap_or_default(),
+            description: description,
+            label: label,
+            lsp_id: "".to_owned(),
+            token: "".to_owned(),
+        };
+        let res = gl_client
+            .lsp_invoice(req)
+            .await
+            .map_err(|s| Error::rpc(s.to_string()))?
+            .into_inner();
+        Ok(ReceiveResponse {
+            bolt11: res.bolt11,
+            opening_fee_msat: res.opening_fee_msat,
+        })
+    }
+
+    /// Async implementation of [`Node::send`].
+    pub(crate) async fn send_async(
+        &self,
+        invoice: String,
+        amount_msat: Option<u64>,
+        options: Option<SendOptions>,
+    ) -> Result<SendResponse, Error> {
+        self.check_connected()?;
+        self.check_writable("send")?;
+        let options = options.unwrap_or_default();
+        options.validate()?;
+
+        if options.trampoline != Some(false) {
+            match self.send_trampoline(&invoice, amount_msat, &options).await {
+                Ok(Some(res)) => return Ok(res),
+                Ok(None) if options.trampoline == Some(true) => {
+                    return Err(Error::other(
+                        "No connected peer with enough balance supports trampoline payments",
+                    ));
+                }
+                Ok(None) => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        let mut cln_client = self.get_cln_client().await?.clone();
+        let req = clnpb::PayRequest {
+            amount_msat: match amount_msat {
+                Some(a) => Some(clnpb::Amount { msat: a }),
+                None => None,
+            },
+
+            bolt11: invoice,
+            description: None,
+            exclude: options.exclude,
+            exemptfee: options.exempt_fee_msat.map(|msat| clnpb::Amount { msat }),
+            label: options.label,
+            localinvreqid: None,
+            maxdelay: None,
+            maxfee: options.max_fee_msat.map(|msat| clnpb::Amount { msat }),
+            maxfeepercent: options.max_fee_percent,
+            partial_msat: None,
+            retry_for: options.timeout_secs,
+            riskfactor: None,
+        };
+        cln_client
+            .pay(req)
+            .await
+            .map_err(|e| Error::rpc(e.to_string()))
+            .map(|r| r.into_inner().into())
+    }
+
+    /// Pays `invoice` through a trampoline peer, if there is one we
+    /// can use. Returns `Ok(None)` without paying otherwise, or if no
+    /// trampoline could take the payment and `pay` may still succeed.
+    async fn send_trampoline(
+        &self,
+        invoice: &str,
+        amount_msat: Option<u64>,
+        options: &SendOptions,
+    ) -> Result<Option<SendResponse>, Error> {
+        // Only BOLT11 invoices can be handed to a trampoline, and it
+        // can't honor route exclusions.
+        let parsed = match Bolt11Invoice::from_str(invoice.trim()) {
+            Ok(parsed) => parsed,
+            Err(_) => return Ok(None),
+        };
+        if !options.exclude.is_empty() {
+            return Ok(None);
+        }
+        let amount = match parsed.amount_milli_satoshis().or(amount_msat) {
+            Some(amount) => amount,
+            None => return Ok(None),
+        };
+        let maxfeepercent = match options.trampoline_fee_percent(amount) {
+            Some(pct) => pct,
+            None => return Ok(None),
+        };
+
+        let mut cln_client = self.get_cln_client().await?.clone();
+        let peers = cln_client
+            .list_peers(clnpb::ListpeersRequest {
+                id: None,
+                level: None,
+            })
+            .await
+            .map_err(|e| Error::rpc(e.to_string()))?
+            .into_inner();
+        let channels = cln_client
+            .list_peer_channels(clnpb::ListpeerchannelsRequest { id: None })
+            .await
+            .map_err(|e| Error::rpc(e.to_string()))?
+            .into_inner();
+        // The plugin splits the payment across the trampolines, and
+        // sends failed parts again through the others.
+        let send_msat = amount + (amount as f64 * maxfeepercent as f64 / 100.0) as u64;
+        let mut trampolines =
+            select_trampolines(&peers.peers, &channels.channels, send_msat).into_iter();
+        let node_id = match trampolines.next() {
+            Some(node_id) => node_id,
+            None => return Ok(None),
+        };
+
+        let mut gl_client = self.get_gl_client().await?.clone();
+        let res = match gl_client
+            .trampoline_pay(glpb::TrampolinePayRequest {
+                bolt11: invoice.to_owned(),
+                trampoline_node_id: node_id.clone(),
+                amount_msat: amount_msat.unwrap_or_default(),
+                label: options.label.clone().unwrap_or_default(),
+                maxfeepercent,
+                maxdelay: 0,
+                description: String::new(),
+                trampoline_node_ids: trampolines.collect(),
+            })
+            .await
+        {
+            Ok(res) => res.into_inner(),
+            Err(e) if falls_back_to_pay(&options, &e) => {
+                log::debug!("Trampoline payment failed, falling back to pay: {e}");
+                return Ok(None);
+            }
+            Err(e) => return Err(Error::rpc(e.to_string())),
+        };
+        Ok(Some(SendResponse::from_trampoline(res, &node_id, amount)))
+    }
+
+    /// Async implementation of [`Node::prepare_send`].
+    pub(crate) async fn prepare_send_async(
+        &self,
+        invoice: String,
+        amount_msat: Option<u64>,
+        options: Option<SendOptions>,
+    ) -> Result<PreparedSend, Error> {
+        self.check_connected()?;
+        let options = options.unwrap_or_default();
+        options.validate()?;
+        let parsed = Bolt11Invoice::from_str(invoice.trim())
+            .map_err(|e| Error::argument("invoice", e.to_string()))?;
+        let amount_msat = match (parsed.amount_milli_satoshis(), amount_msat) {
+            (Some(a), None) => a,
+            (None, Some(a)) => a,
+            (Some(_), Some(a)) => {
+                return Err(Error::argument("amount_msat", a.to_string()));
+            }
+            (None, None) => {
+                return Err(Error::argument("amount_msat", "None"));
+            }
+        };
+        let cltv = parsed.min_final_cltv_expiry_delta() as u32;
+
+        // Route to the recipient directly, and to the entry point of
+        // each route hint, which covers recipients behind private
+        // channels (e.g. LSP clients).
+        let mut targets = vec![RouteTarget {
+            id: parsed.get_payee_pub_key().serialize().to_vec(),
+            amount_msat,
+            hint_fee_msat: 0,
+            hint_hops: 0,
+            cltv,
+        }];
+        targets.extend(
+            parsed
+                .route_hints()
+                .iter()
+                .filter_map(|h| RouteTarget::from_hint(h, amount_msat, cltv)),
+        );
+
+        let mut cln_client = self.get_cln_client().await?.clone();
+        let mut best: Option<(u64, u32)> = None;
+        let mut last_err = None;
+        for t in targets {
+            let req = clnpb::GetrouteRequest {
+                id: t.id,
+                amount_msat: Some(clnpb::Amount {
+                    msat: t.amount_msat,
+                }),
+                riskfactor: 1,
+                cltv: Some(t.cltv),
+                fromid: None,
+                fuzzpercent: Some(0),
+                exclude: options.exclude.clone(),
+                maxhops: None,
+            };
+            let route = match cln_client.get_route(req).await {
+                Ok(r) => r.into_inner().route,
+                Err(e) => {
+                    last_err = Some(e);
+                    continue;
+                }
+            };
+            // The first hop carries the amount plus all fees.
+            let sent_msat = route
+                .first()
+                .and_then(|h| h.amount_msat.as_ref())
+                .map(|a| a.msat)
+                .unwrap_or(t.amount_msat);
+            let fee = sent_msat.saturating_sub(t.amount_msat) + t.hint_fee_msat;
+            let hops = route.len() as u32 + t.hint_hops;
+            if best.is_none_or(|(f, _)| fee < f) {
+                best = Some((fee, hops));
+            }
+        }
+
+        let (estimated_fee_msat, hops) = best.ok_or_else(|| {
+            Error::rpc(match last_err {
+                Some(e) => e.to_string(),
+                None => "no route found".to_string(),
+            })
+        })?;
+        Ok(PreparedSend {
+            amount_msat,
+            estimated_fee_msat,
+            total_msat: amount_msat + estimated_fee_msat,
+            fee_limit_msat: options.fee_limit_msat(amount_msat),
+            hops,
+        })
+    }
+
+    /// Async implementation of [`Node::send_spontaneous`].
+    pub(crate) async fn send_spontaneous_async(
+        &self,
+        node_id: String,
+        amount_msat: u64,
+        extra_tlvs: Option<Vec<TlvEntry>>,
+    ) -> Result<SendResponse, Error> {
+        self.check_connected()?;
+        self.check_writable("send_spontaneous")?;
+        let destination = gl_client::bitcoin::secp256k1::PublicKey::from_str(&node_id)
+            .map_err(|_| Error::argument("node_id", &node_id))?;
+        for tlv in extra_tlvs.iter().flatten() {
+            tlv.validate()?;
+        }
+        let mut cln_client = self.get_cln_client().await?.clone();
+
+        let req = clnpb::KeysendRequest {
+            destination: destination.serialize().to_vec(),
+            amount_msat: Some(clnpb::Amount { msat: amount_msat }),
+            extratlvs: extra_tlvs.map(|tlvs| clnpb::TlvStream {
+                entries: tlvs
+                    .into_iter()
+                    .map(|t| clnpb::TlvEntry {
+                        r#type: t.tlv_type,
+                        value: t.value,
+                    })
+                    .collect(),
+            }),
+            ..Default::default()
+        };
+        cln_client
+            .key_send(req)
+            .await
+            .map_err(|e| Error::rpc(e.to_string()))
+            .map(|r| r.into_inner().into())
+    }
+
+    /// Async implementation of [`Node::onchain_send`].
+    pub(crate) async fn onchain_send_async(
+        &self,
+        destination: String,
+        amount_or_all: String,
+        sat_per_vbyte: Option<u32>,
+        utxos: Option<Vec<Outpoint>>,
+    ) -> Result<OnchainSendResponse, Error> {
+        self.check_connected()?;
+        self.check_writable("onchain_send")?;
+        let mut cln_client = self.get_cln_client().await?.clone();
+
+        let satoshi = parse_amount_or_all(&amount_or_all)?;
+
+        let req = clnpb::WithdrawRequest {
+            destination,
+            minconf: None,
+            feerate: sat_per_vbyte.map(feerate_perkw_from_sat_per_vbyte),
+            satoshi: Some(satoshi),
+            utxos: utxos
+                .unwrap_or_default()
+                .into_iter()
+                .map(outpoint_to_pb)
+                .collect::<Result<Vec<_>, _>>()?,
+        };
+
+        cln_client
+            .withdraw(req)
+            .await
+            .map_err(|e| Error::rpc(e.to_string()))
+            .map(|r| r.into_inner().into())
+    }
+
+    /// Async implementation of [`Node::prepare_onchain_send`].
+    pub(crate) async fn prepare_onchain_send_async(
+        &self,
+        destination: String,
+        amount_or_all: String,
+        sat_per_vbyte: Option<u32>,
+    ) -> Result<PreparedOnchainSend, Error> {
+        self.check_connected()?;
+        let cln_client = self.get_cln_client().await?.clone();
+
+        let satoshi = parse_amount_or_all(&amount_or_all)?;
+        let is_sweep = matches!(satoshi.value, Some(clnpb::amount_or_all::Value::All(true)));
+
+        // `startweight` must cover everything CLN does NOT add itself
+        // during fundpsbt: the base tx overhead and the destination
+        // output. CLN accumulates per-input spend weights and the
+        // change output weight on top of this. See
+        // lightning/plugins/spender/multiwithdraw.c:339 for the
+        // canonical formula CLN uses for its own withdraw plugin.
+        let startweight = BASE_TX_CORE_WEIGHT + output_weight_for_address(&destination);
+
+        let feerate = match sat_per_vbyte {
+            Some(rate) => feerate_perkw_from_sat_per_vbyte(rate),
+            None => clnpb::Feerate {
+                style: Some(clnpb::feerate::Style::Normal(true)),
+            },
+        };
+
+        let req = clnpb::FundpsbtRequest {
+            satoshi: Some(satoshi),
+            feerate: Some(feerate),
+            startweight,
+            // `reserve = 0` is the whole point: CLN runs coin selection
+            // and returns the would-be inputs but does not lock them.
+            reserve: Some(0),
+            minconf: None,
+            locktime: None,
+            min_witness_weight: None,
+            // For non-sweep sends any leftover after the requested
+            // amount + fee becomes change. For sweeps there is no
+            // requested amount so the leftover is the recipient amount
+            // and CLN reports it via `excess_msat`.
+            excess_as_change: Some(!is_sweep),
+            nonwrapped: None,
+            opening_anchor_channel: None,
+        };
+
+        // Run fund_psbt and feerates concurrently. The latter is used
+        // only to validate the requested rate against the network's
+        // relay floor — without this check, a too-low `sat_per_vbyte`
+        // produces a confusing post-broadcast `min relay fee not met`
+        // failure instead of a clean pre-confirmation error.
+        let (fund_res, feerates_res) = async {
+            let mut c_fund = cln_client.clone();
+            let mut c_rates = cln_client.clone();
+            tokio::join!(
+                c_fund.fund_psbt(req),
+                c_rates.feerates(clnpb::FeeratesRequest {
+                    style: clnpb::feerates_request::FeeratesStyle::Perkw as i32,
+                }),
+            )
+        }
+        .await;
+
+        // Reject below-relay rates up front when the caller specified
+        // one. If `feerates` itself failed, skip the check — a stale
+        // bitcoind connection shouldn't block a prepare.
+        if let (Some(rate), Ok(rates)) = (sat_per_vbyte, feerates_res.as_ref())
+            && let Some(perkw) = rates.get_ref().perkw.as_ref()
+        {
+            let min_sat_per_vbyte = sat_per_vbyte_from_perkw(perkw.min_acceptable).max(1);
+            if (rate as u64) < min_sat_per_vbyte {
+                return Err(Error::argument(
+                    "sat_per_vbyte",
+                    format!(
+                        "{} sat/vbyte is below the network minimum of {} sat/vbyte",
+                        rate, min_sat_per_vbyte
+                    ),
+                ));
+            }
+        }
+
+        let res = fund_res
+            .map_err(|e| Error::rpc(e.to_string()))?
+            .into_inner();
+
+        // CLN only emits the `reservations` array when `reserve > 0`
+        // (see lightning/wallet/reservation.c:421 — `if (reserve)`).
+        // We deliberately pass `reserve=0` to avoid locking UTXOs, so
+        // we extract the chosen inputs from the returned PSBT instead.
+        let psbt = bitcoin::Psbt::from_str(&res.psbt)
+            .map_err(|e| Error::rpc(format!("invalid psbt from fund_psbt: {}", e)))?;
+        let utxos: Vec<Outpoint> = psbt
+            .unsigned_tx
+            .input
+            .iter()
+            .map(|tx_in| Outpoint {
+                txid: tx_in.previous_output.txid.to_string(),
+                vout: tx_in.previous_output.vout,
+            })
+            .collect();
+
+        // BIP-141: feerate_per_kw is sats per 1000 weight units, so
+        // fee_sat = weight_wu × feerate_per_kw / 1000. The proto-level
+        // `estimated_final_weight` already includes the destination
+        // output we declared via `startweight`, plus any change output.
+        let fee_sat: u64 = (res.estimated_final_weight as u64 * res.feerate_per_kw as u64) / 1000;
+
+        // Sum input values directly from the PSBT. Each PSBT input
+        // carries its prevout amount in `witness_utxo` (segwit) or
+        // `non_witness_utxo` (legacy). This is the one source of truth
+        // and works for sweeps that include an emergency-reserve
+        // change output (anchor-channel wallets) — see
+        // lightning/wallet/reservation.c:443 `change_for_emergency`,
+        // which carves out `emergency_sat` even from `satoshi=All`.
+        let mut total_input_sat: u64 = 0;
+        for (i, input) in psbt.inputs.iter().enumerate() {
+            let value = if let Some(ref txout) = input.witness_utxo {
+                txout.value
+            } else if let Some(ref tx) = input.non_witness_utxo {
+                let vout = psbt.unsigned_tx.input[i].previous_output.vout as usize;
+                tx.output
+                    .get(vout)
+                    .map(|o| o.value)
+                    .ok_or_else(|| Error::rpc("psbt non_witness_utxo missing vout"))?
+            } else {
+                return Err(Error::rpc(format!(
+                    "psbt input {} has no witness_utxo or non_witness_utxo",
+                    i
+                )));
+            };
+            total_input_sat = total_input_sat.saturating_add(value.to_sat());
+        }
+
+        let recipient_sat: u64 = if is_sweep {
+            // For `satoshi=All` CLN reports the post-fee, post-emergency
+            // leftover via `excess_msat`; that's what the recipient
+            // receives. Any difference between `total_input_sat` and
+            // `recipient_sat + fee_sat` is the emergency-reserve change
+            // CLN keeps in the wallet for anchor channels.
+            res.excess_msat.as_ref().map(|a| a.msat).unwrap_or(0) / 1000
+        } else {
+            match parse_amount_or_all(&amount_or_all)?.value {
+                Some(clnpb::amount_or_all::Value::Amount(a)) => a.msat / 1000,
+                _ => 0,
+            }
+        };
+
+        // Round up so passing this back to `onchain_send` produces a
+        // feerate at least as high as the previewed one; that way the
+        // broadcast fee is never below what the user agreed to.
+        let effective_sat_per_vbyte: u32 = (res.feerate_per_kw as u64).div_ceil(250) as u32;
+
+        Ok(PreparedOnchainSend {
+            utxos,
+            total_input_sat,
+            fee_sat,
+            recipient_sat,
+            sat_per_vbyte: effective_sat_per_vbyte,
+        })
+    }
+
+    /// Async implementation of [`Node::onchain_balance_state`].
+    pub(crate) async fn onchain_balance_state_async(&self) -> Result<OnchainBalanceState, Error> {
+        self.check_connected()?;
+        let cln_client = self.get_cln_client().await?.clone();
+
+        // Run the three RPCs concurrently. The probe is allowed to
+        // fail (e.g. empty wallet, insufficient funds for any spend);
+        // we treat that as "no reserve applicable" and let the rest
+        // of the classification proceed.
+        let (funds_res, channels_res, probe_res) = async {
+            let mut c_funds = cln_client.clone();
+            let mut c_channels = cln_client.clone();
+            let mut c_probe = cln_client.clone();
+            let probe_req = clnpb::FundpsbtRequest {
+                satoshi: Some(clnpb::AmountOrAll {
+                    value: Some(clnpb::amount_or_all::Value::All(true)),
+                }),
+                feerate: Some(clnpb::Feerate {
+                    style: Some(clnpb::feerate::Style::Normal(true)),
+                }),
+                // Assume a P2WPKH destination for the probe — we don't
+                // have a real address here. The output type only
+                // affects fee estimation by a handful of weight units;
+                // it does not affect the carved emergency reserve.
+                startweight: BASE_TX_CORE_WEIGHT + 124,
+                reserve: Some(0),
+                minconf: None,
+                locktime: None,
+                min_witness_weight: None,
+                excess_as_change: Some(false),
+                nonwrapped: None,
+                opening_anchor_channel: None,
+            };
+            tokio::join!(
+                c_funds.list_funds(clnpb::ListfundsRequest { spent: None }),
+                c_channels.list_peer_channels(clnpb::ListpeerchannelsRequest { id: None }),
+                c_probe.fund_psbt(probe_req),
+            )
+        }
+        .await;
+
+        let funds: ListFundsResponse = funds_res
+            .map_err(|e| Error::rpc(e.to_string()))?
+            .into_inner()
+            .into();
+        let channels: ListPeerChannelsResponse = channels_res
+            .map_err(|e| Error::rpc(e.to_string()))?
+            .into_inner()
+            .into();
+
+        let mut confirmed_sat: u64 = 0;
+        let mut unconfirmed_sat: u64 = 0;
+        let mut immature_sat: u64 = 0;
+        for output in &funds.outputs {
+            if output.reserved {
+                continue;
+            }
+            let value_sat = output.amount_msat / 1000;
+            match output.status {
+                OutputStatus::Confirmed => confirmed_sat += value_sat,
+                OutputStatus::Unconfirmed => unconfirmed_sat += value_sat,
+                OutputStatus::Immature => immature_sat += value_sat,
+                OutputStatus::Spent => {}
+            }
+        }
+
+        let mut pending_close_sat: u64 = 0;
+        for ch in &channels.channels {
+            if channel_payout_still_pending(ch) {
+                pending_close_sat += ch.to_us_msat.unwrap_or(0) / 1000;
+            }
+        }
+
+        // Derive the actual emergency reserve from the probe. CLN's
+        // `change_for_emergency` runs server-side in the same
+        // `fund_psbt` call we just made; the difference between the
+        // input total and (recipient + fee) is exactly what would be
+        // carved as change on a real sweep.
+        let reserve_sat = match probe_res {
+            Ok(resp) => {
+                let resp = resp.into_inner();
+                // CLN-managed UTXOs are always segwit, so each PSBT
+                // input carries `witness_utxo` with the prevout
+                // amount. Sum to get total input value.
+                let total_input_sat = bitcoin::Psbt::from_str(&resp.psbt)
+                    .ok()
+                    .map(|p| {
+                        p.inputs
+                            .iter()
+                            .filter_map(|i| i.witness_utxo.as_ref().map(|t| t.value.to_sat()))
+                            .sum::<u64>()
+                    })
+                    .unwrap_or(0);
+                let excess_sat = resp
+                    .excess_msat
+                    .as_ref()
+                    .map(|a| a.msat / 1000)
+                    .unwrap_or(0);
+                let fee_sat =
+                    (resp.estimated_final_weight as u64 * resp.feerate_per_kw as u64) / 1000;
+                total_input_sat
+                    .saturating_sub(excess_sat)
+                    .saturating_sub(fee_sat)
+            }
+            // `fund_psbt` errors are expected on empty wallets or when
+            // every UTXO is dust-uneconomic at the chosen feerate;
+            // treat as "no reserve applicable."
+            Err(_) => 0,
+        };
+
+        Ok(classify_onchain_balance(
+            confirmed_sat,
+            reserve_sat,
+            unconfirmed_sat,
+            immature_sat,
+            pending_close_sat,
+        ))
+    }
+
+    /// Async implementation of [`Node::onchain_fee_rates`].
+    pub(crate) async fn onchain_fee_rates_async(&self) -> Result<OnchainFeeRates, Error> {
+        self.check_connected()?;
+        let mut cln_client = self.get_cln_client().await?.clone();
+
+        let req = clnpb::FeeratesRequest {
+            style: clnpb::feerates_request::FeeratesStyle::Perkw as i32,
+        };
+        let res = cln_client
+            .feerates(req)
+            .await
+            .map_err(|e| Error::rpc(e.to_string()))?
+            .into_inner();
+        Ok(compute_fee_rates(res.perkw.as_ref()))
+    }
+
+    /// Async implementation of [`Node::onchain_receive`].
+    pub(crate) async fn onchain_receive_async(&self) -> Result<OnchainReceiveResponse, Error> {
+        self.check_connected()?;
+        self.check_writable("onchain_receive")?;
+        let mut cln_client = self.get_cln_client().await?.clone();
+
+        let req = clnpb::NewaddrRequest {
+            addresstype: Some(clnpb::newaddr_request::NewaddrAddresstype::All.into()),
+        };
+
+        let res = cln_client
+            .new_addr(req)
+            .await
+            .map_err(|e| Error::rpc(e.to_string()))?
+            .into_inner();
+        Ok(res.into())
+    }
+
+    /// Async implementation of [`Node::get_info`].
+    pub(crate) async fn get_info_async(&self) -> Result<GetInfoResponse, Error> {
+        self.check_connected()?;
+        let mut cln_client = self.get_cln_client().await?.clone();
+
+        let req = clnpb::GetinfoRequest {};
+
+        let res = cln_client
+            .getinfo(req)
+            .await
+            .map_err(|e| Error::rpc(e.to_string()))?
+            .into_inner();
+        Ok(res.into())
+    }
+
+    /// Async implementation of [`Node::list_peers`].
+    pub(crate) async fn list_peers_async(&self) -> Result<ListPeersResponse, Error> {
+        self.check_connected()?;
+        let mut cln_client = self.get_cln_client().await?.clone();
+
+        let req = clnpb::ListpeersRequest {
+            id: None,
+            level: None,
+        };
+
+        let res = cln_client
+            .list_peers(req)
+            .await
+            .map_err(|e| Error::rpc(e.to_string()))?
+            .into_inner();
+        Ok(res.into())
+    }
+
+    /// Async implementation of [`Node::list_peer_channels`].
+    pub(crate) async fn list_peer_channels_async(&self) -> Result<ListPeerChannelsResponse, Error> {
+        self.check_connected()?;
+        let mut cln_client = self.get_cln_client().await?.clone();
+
+        let req = clnpb::ListpeerchannelsRequest { id: None };
+
+        let res = cln_client
+            .list_peer_channels(req)
+            .await
+            .map_err(|e| Error::rpc(e.to_string()))?
+            .into_inner();
+        Ok(res.into())
+    }
+
+    /// Async implementation of [`Node::list_funds`].
+    pub(crate) async fn list_funds_async(&self) -> Result<ListFundsResponse, Error> {
+        self.check_connected()?;
+        let mut cln_client = self.get_cln_client().await?.clone();
+
+        let req = clnpb::ListfundsRequest { spent: None };
+
+        let res = cln_client
+            .list_funds(req)
+            .await
+            .map_err(|e| Error::rpc(e.to_string()))?
+            .into_inner();
+        Ok(res.into())
+    }
+
+    /// Async implementation of [`Node::node_state`].
+    pub(crate) async fn node_state_async(&self) -> Result<NodeState, Error> {
+        self.check_connected()?;
+        let cln_client = self.get_cln_client().await?.clone();
+
+        let (info_res, channels_res, funds_res) = async {
+            let mut c_info = cln_client.clone();
+            let mut c_channels = cln_client.clone();
+            let mut c_funds = cln_client.clone();
+            tokio::join!(
+                c_info.getinfo(clnpb::GetinfoRequest {}),
+                c_channels.list_peer_channels(clnpb::ListpeerchannelsRequest { id: None }),
+                c_funds.list_funds(clnpb::ListfundsRequest { spent: None }),
+            )
+        }
+        .await;
+
+        let info: GetInfoResponse = info_res
+            .map_err(|e| Error::rpc(e.to_string()))?
+            .into_inner()
+            .into();
+        let channels: ListPeerChannelsResponse = channels_res
+            .map_err(|e| Error::rpc(e.to_string()))?
+            .into_inner()
+            .into();
+        let funds: ListFundsResponse = funds_res
+            .map_err(|e| Error::rpc(e.to_string()))?
+            .into_inner()
+            .into();
+
+        let mut channels_balance_msat: u64 = 0;
+        let mut max_payable_msat: u64 = 0;
+        let mut total_channel_capacity_msat: u64 = 0;
+        let mut max_receivable_single_payment_msat: u64 = 0;
+        let mut total_inbound_liquidity_msat: u64 = 0;
+        let mut pending_onchain_balance_msat: u64 = 0;
+        let mut connected_channel_peer_set: std::collections::HashSet<String> =
+            std::collections::HashSet::new();
+
+        for ch in &channels.channels {
+            if ch.state.is_open() {
+                channels_balance_msat += ch.to_us_msat.unwrap_or(0);
+                max_payable_msat += ch.spendable_msat.unwrap_or(0);
+                total_channel_capacity_msat += ch.total_msat.unwrap_or(0);
+                let receivable = ch.receivable_msat.unwrap_or(0);
+                if receivable > max_receivable_single_payment_msat {
+                    max_receivable_single_payment_msat = receivable;
+                }
+                total_inbound_liquidity_msat += receivable;
+            }
+            if channel_payout_still_pending(ch) {
+                pending_onchain_balance_msat += ch.to_us_msat.unwrap_or(0);
+            }
+            if ch.peer_connected {
+                connected_channel_peer_set.insert(ch.peer_id.clone());
+            }
+        }
+
+        let connected_channel_peers: Vec<String> = connected_channel_peer_set.into_iter().collect();
+
+        let max_chan_reserve_msat = channels_balance_msat.saturating_sub(max_payable_msat);
+
+        let mut onchain_balance_msat: u64 = 0;
+        let mut unconfirmed_onchain_balance_msat: u64 = 0;
+        let mut immature_onchain_balance_msat: u64 = 0;
+        let mut utxos: Vec<FundOutput> = Vec::with_capacity(funds.outputs.len());
+        for output in &funds.outputs {
+            if !matches!(output.status, OutputStatus::Spent) {
+                utxos.push(output.clone());
+            }
+            if output.reserved {
+                continue;
+            }
+            match output.status {
+                OutputStatus::Confirmed => onchain_balance_msat += output.amount_msat,
+                OutputStatus::Unconfirmed => unconfirmed_onchain_balance_msat += output.amount_msat,
+                OutputStatus::Immature => immature_onchain_balance_msat += output.amount_msat,
+                OutputStatus::Spent => {}
+            }
+        }
+
+        let total_onchain_msat = onchain_balance_msat
+            .saturating_add(unconfirmed_onchain_balance_msat)
+            .saturating_add(immature_onchain_balance_msat);
+        let total_balance_msat = channels_balance_msat
+            .saturating_add(total_onchain_msat)
+            .saturating_add(pending_onchain_balance_msat);
+        let spendable_balance_msat = max_payable_msat.saturating_add(onchain_balance_msat);
+
+        Ok(NodeState {
+            id: info.id,
+            block_height: info.blockheight,
+            network: info.network,
+            version: info.version,
+            alias: info.alias,
+            color: info.color,
+            num_active_channels: info.num_active_channels,
+            num_pending_channels: info.num_pending_channels,
+            num_inactive_channels: info.num_inactive_channels,
+            channels_balance_msat,
+            max_payable_msat,
+            total_channel_capacity_msat,
+            max_chan_reserve_msat,
+            onchain_balance_msat,
+            unconfirmed_onchain_balance_msat,
+            immature_onchain_balance_msat,
+            pending_onchain_balance_msat,
+            max_receivable_single_payment_msat,
+            total_inbound_liquidity_msat,
+            connected_channel_peers,
+            utxos,
+            total_onchain_msat,
+            total_balance_msat,
+            spendable_balance_msat,
+        })
+    }
+
+    /// Async implementation of [`Node::list_invoices`].
+    pub(crate) async fn list_invoices_async(
+        &self,
+        label: Option<String>,
+        invstring: Option<String>,
+        payment_hash: Option<Vec<u8>>,
+        offer_id: Option<String>,
+        index: Option<ListIndex>,
+        start: Option<u64>,
+        limit: Option<u32>,
+    ) -> Result<ListInvoicesResponse, Error> {
+        self.check_connected()?;
+        let mut cln_client = self.get_cln_client().await?.clone();
+
+        let req = clnpb::ListinvoicesRequest {
+            label,
+            invstring,
+            payment_hash,
+            offer_id,
+            index: index.map(|i| i.to_i32()),
+            start,
+            limit,
+        };
+
+        let res = cln_client
+            .list_invoices(req)
+            .await
+            .map_err(|e| Error::rpc(e.to_string()))?
+            .into_inner();
+        Ok(res.into())
     }
-}
 
-fn validate_lnurl_pay_input(request: &crate::lnurl::LnUrlPayRequest) -> Result<(), Error> {
-    let data = &request.data;
-    if request.amount_msat < data.min_sendable {
-        return Err(Error::other(format!(
-            "amount_msat {} is below the service's min_sendable ({})",
-            request.amount_msat, data.min_sendable
-        )));
+    /// Async implementation of [`Node::list_pays`].
+    pub(crate) async fn list_pays_async(
+        &self,
+        bolt11: Option<String>,
+        payment_hash: Option<Vec<u8>>,
+        status: Option<PayStatus>,
+        index: Option<ListIndex>,
+        start: Option<u64>,
+        limit: Option<u32>,
+    ) -> Result<ListPaysResponse, Error> {
+        self.check_connected()?;
+        let mut cln_client = self.get_cln_client().await?.clone();
+
+        // ListpaysRequest.ListpaysStatus: PENDING=0, COMPLETE=1, FAILED=2
+        let cln_status = status.map(|s| match s {
+            PayStatus::PENDING => 0,
+            PayStatus::COMPLETE => 1,
+            PayStatus::FAILED => 2,
+        });
+
+        let req = clnpb::ListpaysRequest {
+            bolt11,
+            payment_hash,
+            status: cln_status,
+            index: index.map(|i| i.to_i32()),
+            start,
+            limit,
+        };
+
+        let res = cln_client
+            .list_pays(req)
+            .await
+            .map_err(|e| Error::rpc(e.to_string()))?
+            .into_inner();
+        Ok(res.into())
     }
-    if request.amount_msat > data.max_sendable {
-        return Err(Error::other(format!(
-            "amount_msat {} is above the service's max_sendable ({})",
-            request.amount_msat, data.max_sendable
-        )));
+
+    /// Async implementation of [`Node::list_payments`].
+    pub(crate) async fn list_payments_async(
+        &self,
+        req: ListPaymentsRequest,
+    ) -> Result<Vec<Payment>, Error> {
+        self.check_connected()?;
+        let cursor = match req.cursor.as_deref() {
+            Some(cursor) => FeedCursor::decode(cursor)?,
+            None => FeedCursor::default(),
+        };
+        let offset = req.offset.unwrap_or(0) as usize;
+        let limit = req.limit.unwrap_or(u32::MAX) as usize;
+        // Each source has to be able to fill the page on its own.
+        let needed = offset.saturating_add(limit);
+
+        let mut sources: [Vec<payments::Entry>; 3] = Default::default();
+        if payments::wants(&req, PaymentKind::Lightning, PaymentType::Received)
+            || payments::wants(&req, PaymentKind::LspFee, PaymentType::Sent)
+        {
+            sources[payments::INVOICES] = self
+                .history_invoices(&req, cursor.bounds[payments::INVOICES], needed)
+                .await?;
+        }
+        if payments::wants(&req, PaymentKind::Lightning, PaymentType::Sent) {
+            sources[payments::PAYS] = self
+                .history_pays(&req, cursor.bounds[payments::PAYS], needed)
+                .await?;
+        }
+        if payments::wants(&req, PaymentKind::Onchain, PaymentType::Sent)
+            || payments::wants(&req, PaymentKind::Onchain, PaymentType::Received)
+        {
+            let bound = cursor.bounds[payments::ONCHAIN];
+            sources[payments::ONCHAIN] = payments::onchain_entries(&self.wallet_events().await?)
+                .into_iter()
+                .filter(|e| bound.is_none_or(|b| e.pos < b) && payments::matches(&req, &e.payment))
+                .collect();
+        }
+
+        Ok(payments::merge_page(sources, cursor, offset, limit))
     }
-    if let Some(comment) = request.comment.as_deref() {
-        if data.comment_allowed == 0 && !comment.is_empty() {
-            return Err(Error::other(
-                "this LNURL service does not accept comments".to_string(),
-            ));
+
+    /// Async implementation of [`Node::list_payments_since`].
+    pub(crate) async fn list_payments_since_async(
+        &self,
+        cursor: Option<String>,
+    ) -> Result<ListPaymentsSinceResponse, Error> {
+        self.check_connected()?;
+        let since = cursor.as_deref().map(SyncCursor::decode).transpose()?;
+        let mut next = since.unwrap_or_default();
+        let mut cln_client = self.get_cln_client().await?.clone();
+        let mut changed: Vec<Payment> = vec![];
+
+        // Paying an invoice bumps its `updated_index`.
+        let opening_fees = self.opening_fees().await?;
+        let invoices = cln_client
+            .list_invoices(clnpb::ListinvoicesRequest {
+                index: since.map(|_| ListIndex::UPDATED.to_i32()),
+                start: since.map(|c| c.invoices_updated + 1),
+                ..Default::default()
+            })
+            .await
+            .map_err(|e| Error::rpc(e.to_string()))?
+            .into_inner()
+            .invoices;
+        for invoice in invoices {
+            next.invoices_updated = next
+                .invoices_updated
+                .max(invoice.updated_index.unwrap_or_default());
+            if invoice.status() == clnpb::listinvoices_invoices::ListinvoicesInvoicesStatus::Paid {
+                changed.extend(
+                    payments::invoice_entries(invoice, &opening_fees)
+                        .into_iter()
+                        .map(|e| e.payment),
+                );
+            }
         }
-        if (comment.len() as u64) > data.comment_allowed {
-            return Err(Error::other(format!(
-                "comment length {} exceeds the service's comment_allowed ({})",
-                comment.len(),
-                data.comment_allowed
-            )));
+
+        let pays = match since {
+            None => {
+                cln_client
+                    .list_pays(clnpb::ListpaysRequest::default())
+                    .await
+                    .map_err(|e| Error::rpc(e.to_string()))?
+                    .into_inner()
+                    .pays
+            }
+            Some(since) => {
+                let mut payment_hashes = vec![];
+                for (index, start) in [
+                    (ListIndex::CREATED, since.pays_created + 1),
+                    (ListIndex::UPDATED, since.pays_updated + 1),
+                ] {
+                    let res = cln_client
+                        .list_pays(clnpb::ListpaysRequest {
+                            index: Some(index.to_i32()),
+                            start: Some(start),
+                            ..Default::default()
+                        })
+                        .await
+                        .map_err(|e| Error::rpc(e.to_string()))?
+                        .into_inner();
+                    for pay in res.pays {
+                        if !payment_hashes.contains(&pay.payment_hash) {
+                            payment_hashes.push(pay.payment_hash);
+                        }
+                    }
+                }
+                // Only some parts of a payment may have changed, read
+                // all of them.
+                let mut pays = vec![];
+                for payment_hash in payment_hashes {
+                    let res = cln_client
+                        .list_pays(clnpb::ListpaysRequest {
+                            payment_hash: Some(payment_hash),
+                            ..Default::default()
+                        })
+                        .await
+                        .map_err(|e| Error::rpc(e.to_string()))?
+                        .into_inner();
+                    pays.extend(res.pays);
+                }
+                pays
+            }
+        };
+        for pay in pays {
+            next.pays_created = next.pays_created.max(pay.created_index.unwrap_or_default());
+            next.pays_updated = next.pays_updated.max(pay.updated_index.unwrap_or_default());
+            changed.push(pay.into());
         }
+
+        changed.extend(payments::onchain_changes(
+            &self.wallet_events().await?,
+            &mut next,
+        ));
+
+        changed.sort_by_key(|p| std::cmp::Reverse(p.payment_time));
+        Ok(ListPaymentsSinceResponse {
+            payments: changed,
+            cursor: next.encode(),
+        })
     }
-    Ok(())
 }
 
 // Not exported through uniffi
 impl Node {
+    fn open_node_event_stream(&self, since: Option<u64>) -> Result<Arc<NodeEventStream>, Error> {
+        self.check_connected()?;
+        let mut gl_client = exec(self.get_gl_client())?.clone();
+        let req = glpb::NodeEventsRequest { since };
+        let res = exec(gl_client.stream_node_events(req)).map_err(|e| Error::rpc(e.to_string()))?;
+        let since = since.or_else(|| event_stream_start(&res));
+        Ok(Arc::new(NodeEventStream {
+            inner: Mutex::new(res.into_inner()),
+            last_index: AtomicU64::new(since.unwrap_or(0)),
+        }))
+    }
+
     /// Install a listener that receives real-time node events.
     ///
     /// Spawns a background task that tails the gRPC event stream and
     /// invokes `listener.on_event(...)` for every event. Each `Node`
-    /// holds at most one listener — calling again replaces it. The task
-    /// stops when the stream ends, errors, or the `Node` is dropped.
+    /// holds at most one listener — calling again replaces it.
     ///
     /// Crate-private — installed via `NodeBuilder::with_event_listener`
     /// at construction time so events emitted during node bring-up
     /// aren't missed.
+    ///
+    /// The events after the index `since` are replayed first. Without
+    /// `since` the listener resumes after the last event delivered to
+    /// a previous listener, if any.
+    ///
+    /// The first stream is opened before returning, so that errors
+    /// are reported to the caller. If the stream is interrupted
+    /// afterwards, the listener task re-schedules the node and
+    /// reconnects with exponential backoff, resuming after the last
+    /// delivered event, or where the stream started if no event was
+    /// delivered yet. It stops on `disconnect()`, when the node is
+    /// dropped, or when the node rejects the stream for a reason
+    /// reconnecting does not fix, see `is_permanent_event_error()`.
     pub(crate) fn set_event_listener(
         &self,
         listener: std::sync::Arc<dyn NodeEventListener>,
+        since: Option<u64>,
     ) -> Result<(), Error> {
         self.check_connected()?;
+        let since = since.or_else(|| self.last_event_index());
         let mut gl_client = exec(self.get_gl_client())?.clone();
-        let req = glpb::NodeEventsRequest {};
-        let stream = exec(gl_client.stream_node_events(req))
-            .map_err(|e| Error::rpc(e.to_string()))?
-            .into_inner();
+        let req = glpb::NodeEventsRequest { since };
+        let res = exec(gl_client.stream_node_events(req)).map_err(|e| Error::rpc(e.to_string()))?;
+        let since = since.or_else(|| event_stream_start(&res));
+        if let Some(since) = since {
+            self.event_cursor.store(since, Ordering::Relaxed);
+        }
+        let stream = res.into_inner();
 
         let mut guard = self
             .event_task
@@ -1287,19 +2123,63 @@ impl Node {
             prev.abort();
         }
 
+        let cursor = Arc::clone(&self.event_cursor);
+        let inner = self.inner.clone();
         let task = crate::util::get_runtime().spawn(async move {
-            let mut stream = stream;
+            let mut since = since;
+            let mut stream = Some(stream);
+            let mut backoff = EVENT_RECONNECT_INITIAL_BACKOFF;
             loop {
-                match stream.message().await {
-                    Ok(Some(raw)) => {
-                        if let Some(event) = node_event_from_pb(raw) {
-                            listener.on_event(event);
+                let mut s = match stream.take() {
+                    Some(s) => s,
+                    None => match open_event_stream(inner.clone(), since).await {
+                        Ok((s, start)) => {
+                            if since.is_none() {
+                                since = start;
+                                if let Some(start) = start {
+                                    cursor.store(start, Ordering::Relaxed);
+                                }
+                            }
+                            s
+                        }
+                        Err(e) if is_permanent_event_error(&e) => {
+                            listener.on_disconnected(e.to_string());
+                            return;
+                        }
+                        Err(e) => {
+                            log::debug!("Failed to reconnect the event stream: {e}");
+                            tokio::time::sleep(backoff).await;
+                            backoff = next_backoff(backoff);
+                            continue;
+                        }
+                    },
+                };
+                backoff = EVENT_RECONNECT_INITIAL_BACKOFF;
+                listener.on_connected();
+
+                let err = loop {
+                    match s.message().await {
+                        Ok(Some(raw)) => {
+                            since = Some(raw.index);
+                            cursor.store(raw.index, Ordering::Relaxed);
+                            if let Some(event) = node_event_from_pb(raw) {
+                                listener.on_event(event);
+                            }
                         }
+                        Ok(None) => break None,
+                        Err(e) => break Some(e),
+                    }
+                };
+                match err {
+                    Some(e) if is_permanent_event_error(&e) => {
+                        listener.on_disconnected(e.to_string());
+                        return;
                     }
-                    Ok(None) => break,
-                    Err(e) if e.code() == tonic::Code::Unknown => break,
-                    Err(_) => break,
+                    Some(e) => listener.on_disconnected(e.to_string()),
+                    None => listener.on_disconnected("event stream closed by the node".to_string()),
                 }
+                tokio::time::sleep(backoff).await;
+                backoff = next_backoff(backoff);
             }
         });
         *guard = Some(task);
@@ -1313,11 +2193,24 @@ impl Node {
         Ok(())
     }
 
+    fn check_writable(&self, method: &str) -> Result<(), Error> {
+        if self.read_only.load(Ordering::Relaxed) {
+            return Err(Error::read_only(method));
+        }
+        Ok(())
+    }
+
+    /// Put the node in read-only mode. Crate-private — applied via
+    /// `NodeBuilder::with_read_only` at construction time.
+    pub(crate) fn set_read_only(&self) {
+        self.read_only.store(true, Ordering::Relaxed);
+    }
+
     /// Internal constructor used by the high-level register/recover/connect functions.
-    /// Creates a Node with credentials and signer handle attached.
+    /// Creates a Node with credentials attached and spawns the signer.
     pub(crate) fn with_signer(
         credentials: Credentials,
-        handle: Handle,
+        signer: gl_client::signer::Signer,
         network: gl_client::bitcoin::Network,
     ) -> Result<Self, Error> {
         let node_id = credentials
@@ -1334,9 +2227,12 @@ impl Node {
             cln_client,
             gl_client,
             stored_credentials: Some(credentials),
-            signer_handle: Some(handle),
+            signer_handle: Some(Handle::spawn(signer.clone())),
+            signer: Some(signer),
             disconnected: AtomicBool::new(false),
+            read_only: AtomicBool::new(false),
             event_task: Mutex::new(None),
+            event_cursor: Arc::new(AtomicU64::new(0)),
             network,
         })
     }
@@ -1357,6 +2253,240 @@ impl Node {
             .await
             .map_err(|e| Error::rpc(e.to_string()))
     }
+
+    /// The current value of one of CLN's list indexes, or `None` if the
+    /// node denies us reading it, e.g. with read-only credentials.
+    async fn current_index(
+        &self,
+        subsystem: clnpb::wait_request::WaitSubsystem,
+        indexname: clnpb::wait_request::WaitIndexname,
+    ) -> Result<Option<u64>, Error> {
+        use clnpb::wait_request::WaitIndexname;
+
+        let mut cln_client = self.get_cln_client().await?.clone();
+        // Returns right away, as every index is at least 0.
+        let res = cln_client
+            .wait(clnpb::WaitRequest {
+                subsystem: subsystem as i32,
+                indexname: indexname as i32,
+                nextvalue: 0,
+            })
+            .await;
+        let res = match res {
+            Ok(res) => res.into_inner(),
+            Err(e) if is_permission_denied(&e) => {
+                log::debug!("Could not read the current index: {}", e);
+                return Ok(None);
+            }
+            Err(e) => return Err(Error::rpc(e.to_string())),
+        };
+        let index = match indexname {
+            WaitIndexname::Created => res.created,
+            WaitIndexname::Updated => res.updated,
+            WaitIndexname::Deleted => res.deleted,
+        };
+        Ok(Some(index.unwrap_or_default()))
+    }
+
+    /// Paid invoices below `bound`, newest first, until `needed` of them
+    /// pass the filters of `req` or there are no more.
+    async fn history_invoices(
+        &self,
+        req: &ListPaymentsRequest,
+        bound: Option<payments::Position>,
+        needed: usize,
+    ) -> Result<Vec<payments::Entry>, Error> {
+        let opening_fees = self.opening_fees().await?;
+        let mut cln_client = self.get_cln_client().await?.clone();
+        let keep = |invoices: Vec<clnpb::ListinvoicesInvoices>| {
+            let mut entries = invoices
+                .into_iter()
+                .filter(|i| {
+                    i.status() == clnpb::listinvoices_invoices::ListinvoicesInvoicesStatus::Paid
+                })
+                .flat_map(|i| payments::invoice_entries(i, &opening_fees))
+                .filter(|e| bound.is_none_or(|b| e.pos < b) && payments::matches(req, &e.payment))
+                .collect::<Vec<_>>();
+            entries.sort_by_key(|e| std::cmp::Reverse(e.pos));
+            entries
+        };
+
+        // Invoices are read by `updated_index`, which is bumped when
+        // an invoice is paid, below `upper`.
+        let mut upper = match bound {
+            // The opening fee of the last invoice may not be read yet.
+            Some((index, sub)) => index + u64::from(sub > 0),
+            None => {
+                use clnpb::wait_request::{WaitIndexname, WaitSubsystem};
+                match self
+                    .current_index(WaitSubsystem::Invoices, WaitIndexname::Updated)
+                    .await?
+                {
+                    Some(index) => index + 1,
+                    None => {
+                        let res = cln_client
+                            .list_invoices(clnpb::ListinvoicesRequest::default())
+                            .await
+                            .map_err(|e| Error::rpc(e.to_string()))?
+                            .into_inner();
+                        return Ok(keep(res.invoices));
+                    }
+                }
+            }
+        };
+        let mut entries = vec![];
+        while entries.len() < needed && upper > 1 {
+            let start = upper.saturating_sub(payments::window(needed)).max(1);
+            let res = cln_client
+                .list_invoices(clnpb::ListinvoicesRequest {
+                    index: Some(ListIndex::UPDATED.to_i32()),
+                    start: Some(start),
+                    limit: Some((upper - start) as u32),
+                    ..Default::default()
+                })
+                .await
+                .map_err(|e| Error::rpc(e.to_string()))?
+                .into_inner();
+            entries.extend(keep(res.invoices));
+            upper = start;
+        }
+        Ok(entries)
+    }
+
+    /// Outgoing payments below `bound`, newest first, until `needed` of
+    /// them pass the filters of `req` or there are no more.
+    async fn history_pays(
+        &self,
+        req: &ListPaymentsRequest,
+        bound: Option<payments::Position>,
+        needed: usize,
+    ) -> Result<Vec<payments::Entry>, Error> {
+        let mut cln_client = self.get_cln_client().await?.clone();
+        let keep = |pays: Vec<clnpb::ListpaysPays>| {
+            let mut entries = pays
+                .into_iter()
+                .filter_map(|p| {
+                    Some(payments::Entry {
+                        pos: (p.created_index?, 0),
+                        // Payments are read in the order they were
+                        // created.
+                        time: p.created_at,
+                        payment: p.into(),
+                    })
+                })
+                .filter(|e| bound.is_none_or(|b| e.pos < b) && payments::matches(req, &e.payment))
+                .collect::<Vec<_>>();
+            entries.sort_by_key(|e| std::cmp::Reverse(e.pos));
+            entries
+        };
+
+        // Payments are read by `created_index`, below `upper`.
+        let mut upper = match bound {
+            Some((index, _)) => index,
+            None => {
+                use clnpb::wait_request::{WaitIndexname, WaitSubsystem};
+                match self
+                    .current_index(WaitSubsystem::Sendpays, WaitIndexname::Created)
+                    .await?
+                {
+                    Some(index) => index + 1,
+                    None => {
+                        let res = cln_client
+                            .list_pays(clnpb::ListpaysRequest::default())
+                            .await
+                            .map_err(|e| Error::rpc(e.to_string()))?
+                            .into_inner();
+                        return Ok(keep(res.pays));
+                    }
+                }
+            }
+        };
+        let mut entries = vec![];
+        while entries.len() < needed && upper > 1 {
+            let start = upper.saturating_sub(payments::window(needed)).max(1);
+            let mut pays = cln_client
+                .list_pays(clnpb::ListpaysRequest {
+                    index: Some(ListIndex::CREATED.to_i32()),
+                    start: Some(start),
+                    limit: Some((upper - start) as u32),
+                    ..Default::default()
+                })
+                .await
+                .map_err(|e| Error::rpc(e.to_string()))?
+                .into_inner()
+                .pays;
+            // The parts of a payment at the edges of the window may
+            // straddle it. Read all of their parts, and leave a payment
+            // that started below the window to the next one.
+            pays.sort_by_key(|p| p.created_index);
+            if let Some(pay) = pays.last_mut() {
+                self.complete_pay(pay).await?;
+            }
+            if start > 1 && pays.len() > 1 {
+                self.complete_pay(&mut pays[0]).await?;
+            }
+            pays.retain(|p| p.created_index.is_some_and(|i| (start..upper).contains(&i)));
+            entries.extend(keep(pays));
+            upper = start;
+        }
+        Ok(entries)
+    }
+
+    /// Replaces `pay` with all of its parts.
+    async fn complete_pay(&self, pay: &mut clnpb::ListpaysPays) -> Result<(), Error> {
+        let mut cln_client = self.get_cln_client().await?.clone();
+        let res = cln_client
+            .list_pays(clnpb::ListpaysRequest {
+                payment_hash: Some(pay.payment_hash.clone()),
+                ..Default::default()
+            })
+            .await
+            .map_err(|e| Error::rpc(e.to_string()))?
+            .into_inner();
+        if let Some(full) = res.pays.into_iter().next() {
+            *pay = full;
+        }
+        Ok(())
+    }
+
+    /// The bookkeeper's events of the on-chain wallet. Empty if the
+    /// node denies us reading them, e.g. with read-only credentials.
+    async fn wallet_events(&self) -> Result<Vec<clnpb::BkprlistaccounteventsEvents>, Error> {
+        let mut cln_client = self.get_cln_client().await?.clone();
+        let res = cln_client
+            .bkpr_list_account_events(clnpb::BkprlistaccounteventsRequest {
+                account: Some(payments::WALLET_ACCOUNT.to_string()),
+                payment_id: None,
+            })
+            .await;
+        match res {
+            Ok(res) => Ok(res.into_inner().events),
+            Err(e) if is_permission_denied(&e) => {
+                log::debug!("Could not read the on-chain wallet events: {}", e);
+                Ok(vec![])
+            }
+            Err(e) => Err(Error::rpc(e.to_string())),
+        }
+    }
+
+    /// The LSP opening fees of JIT channel invoices, by payment hash.
+    /// Empty if the node denies us reading them.
+    async fn opening_fees(&self) -> Result<HashMap<String, u64>, Error> {
+        let mut cln_client = self.get_cln_client().await?.clone();
+        let res = cln_client
+            .list_datastore(clnpb::ListdatastoreRequest {
+                key: payments::opening_fees_key(),
+            })
+            .await;
+        match res {
+            Ok(res) => Ok(payments::opening_fees(res.into_inner().datastore)),
+            Err(e) if is_permission_denied(&e) => {
+                log::debug!("Could not read the LSP opening fees: {}", e);
+                Ok(HashMap::new())
+            }
+            Err(e) => Err(Error::rpc(e.to_string())),
+        }
+    }
 }
 
 /// A specific on-chain output, identified by its outpoint.
@@ -1698,21 +2828,319 @@ pub struct SendResponse {
     /// Recipient node pubkey as lowercase hex (66 chars), if known.
     pub destination_pubkey: Option<String>,
     pub amount_msat: u64,
-    pub amount_sent_msat: u64,
-    pub parts: u32,
+    pub amount_sent_msat: u64,
+    pub parts: u32,
+    /// How the payment was routed.
+    pub route: SendRoute,
+    /// The fee paid on top of `amount_msat`. For trampoline payments
+    /// this is the overpay granted to the trampoline node.
+    pub fee_msat: u64,
+}
+
+/// How a payment was routed, see `SendResponse::route`.
+#[derive(Clone, Debug, PartialEq, uniffi::Enum)]
+pub enum SendRoute {
+    /// Our node found the route to the recipient.
+    Regular,
+    /// A trampoline peer found the route for us.
+    Trampoline {
+        /// The trampoline node's pubkey as lowercase hex (66 chars).
+        node_id: String,
+    },
+}
+
+impl From<clnpb::PayResponse> for SendResponse {
+    fn from(other: clnpb::PayResponse) -> Self {
+        let amount_msat = other.amount_msat.unwrap().msat;
+        let amount_sent_msat = other.amount_sent_msat.unwrap().msat;
+        Self {
+            status: other.status.into(),
+            preimage: hex::encode(&other.payment_preimage),
+            payment_hash: hex::encode(&other.payment_hash),
+            destination_pubkey: other.destination.as_deref().map(hex::encode),
+            amount_msat,
+            amount_sent_msat,
+            parts: other.parts,
+            route: SendRoute::Regular,
+            fee_msat: amount_sent_msat.saturating_sub(amount_msat),
+        }
+    }
+}
+
+impl SendResponse {
+    /// Converts the result of paying `amount_msat` through the
+    /// trampoline `node_id`, or a fallback the plugin reports.
+    fn from_trampoline(
+        other: glpb::TrampolinePayResponse,
+        node_id: &[u8],
+        amount_msat: u64,
+    ) -> Self {
+        let node_id = if other.trampoline_node_id.is_empty() {
+            node_id
+        } else {
+            &other.trampoline_node_id[..]
+        };
+        Self {
+            // `trampoline_pay` only returns once the payment completed.
+            status: PayStatus::COMPLETE,
+            preimage: hex::encode(&other.payment_preimage),
+            payment_hash: hex::encode(&other.payment_hash),
+            destination_pubkey: Some(other.destination)
+                .filter(|d| !d.is_empty())
+                .map(hex::encode),
+            amount_msat,
+            amount_sent_msat: other.amount_sent_msat,
+            parts: other.parts,
+            route: SendRoute::Trampoline {
+                node_id: hex::encode(node_id),
+            },
+            fee_msat: other.amount_sent_msat.saturating_sub(amount_msat),
+        }
+    }
+}
+
+/// Controls for `Node::send`. Unset fields use the node's defaults.
+#[derive(Clone, Debug, Default, uniffi::Record)]
+pub struct SendOptions {
+    /// Maximum total fee in millisatoshis. Cannot be combined with
+    /// `max_fee_percent` or `exempt_fee_msat`.
+    #[uniffi(default = None)]
+    pub max_fee_msat: Option<u64>,
+    /// Maximum fee as a percentage of the amount (node default 0.5).
+    #[uniffi(default = None)]
+    pub max_fee_percent: Option<f64>,
+    /// Fees up to this amount are accepted regardless of
+    /// `max_fee_percent` (node default 5000 msat).
+    #[uniffi(default = None)]
+    pub exempt_fee_msat: Option<u64>,
+    /// Stop retrying along other routes after this many seconds
+    /// (node default 60). Does not apply to trampoline payments,
+    /// where the trampoline node finds the route.
+    #[uniffi(default = None)]
+    pub timeout_secs: Option<u32>,
+    /// Node IDs, or short channel IDs with a direction (e.g.
+    /// `103x1x0/1`), that must not be used to route the payment.
+    #[uniffi(default = [])]
+    pub exclude: Vec<String>,
+    /// A label to find the payment by later.
+    #[uniffi(default = None)]
+    pub label: Option<String>,
+    /// Whether to pay through a trampoline peer. Unset uses one if
+    /// possible and falls back to a regular payment, `false` never
+    /// uses one and `true` fails rather than falling back.
+    #[uniffi(default = None)]
+    pub trampoline: Option<bool>,
+}
+
+/// CLN's default `maxfeepercent` for `pay`.
+const DEFAULT_MAX_FEE_PERCENT: f64 = 0.5;
+
+/// CLN's default `exemptfee` for `pay`.
+const DEFAULT_EXEMPT_FEE_MSAT: u64 = 5000;
+
+impl SendOptions {
+    fn validate(&self) -> Result<(), Error> {
+        if let Some(max_fee_msat) = self.max_fee_msat {
+            if self.max_fee_percent.is_some() || self.exempt_fee_msat.is_some() {
+                return Err(Error::argument("max_fee_msat", max_fee_msat.to_string()));
+            }
+        }
+        if let Some(pct) = self.max_fee_percent {
+            if !(0.0..=100.0).contains(&pct) {
+                return Err(Error::argument("max_fee_percent", pct.to_string()));
+            }
+        }
+        Ok(())
+    }
+
+    /// The highest fee `pay` accepts for `amount_msat` with these
+    /// options.
+    fn fee_limit_msat(&self, amount_msat: u64) -> u64 {
+        if let Some(max_fee_msat) = self.max_fee_msat {
+            return max_fee_msat;
+        }
+        let pct = self.max_fee_percent.unwrap_or(DEFAULT_MAX_FEE_PERCENT);
+        let exempt = self.exempt_fee_msat.unwrap_or(DEFAULT_EXEMPT_FEE_MSAT);
+        std::cmp::max((amount_msat as f64 * pct / 100.0) as u64, exempt)
+    }
+
+    /// The most to grant a trampoline for `amount_msat`, as a
+    /// percentage of the same fee limit `pay` uses. `None` if the fee
+    /// limit can't be expressed that way.
+    fn trampoline_fee_percent(&self, amount_msat: u64) -> Option<f32> {
+        if amount_msat == 0 {
+            return None;
+        }
+        let pct = self.fee_limit_msat(amount_msat) as f64 * 100.0 / amount_msat as f64;
+        // The plugin takes 0 to mean its default.
+        (pct > 0.0).then_some(pct as f32)
+    }
+}
+
+/// Feature bit a peer sets to offer trampoline payments.
+const TRAMPOLINE_FEATURE_BIT: usize = 427;
+
+/// `TrampolinePay` error codes after which a regular payment may still
+/// succeed: no trampoline supports the feature, is connected, has a
+/// channel or has enough funds (42701, 42704, 42707, 42708), asks for
+/// a fee within the limit (42715), or the payment through them failed
+/// (203, 204).
+const TRAMPOLINE_UNUSABLE_CODES: &[i64] = &[42701, 42704, 42707, 42708, 42715, 203, 204];
+
+/// Whether the `TrampolinePay` error is one after which falling back
+/// to `pay` makes sense. The plugin reports its error code in the
+/// JSON status details.
+fn trampoline_unusable(status: &tonic::Status) -> bool {
+    serde_json::from_slice::<serde_json::Value>(status.details())
+        .ok()
+        .and_then(|d| d["code"].as_i64())
+        .map(|code| TRAMPOLINE_UNUSABLE_CODES.contains(&code))
+        .unwrap_or(false)
+}
+
+/// Whether to retry a failed `TrampolinePay` with `pay`. Not when the
+/// caller insisted on a trampoline payment.
+fn falls_back_to_pay(options: &SendOptions, status: &tonic::Status) -> bool {
+    options.trampoline != Some(true) && trampoline_unusable(status)
+}
+
+/// Whether `bit` is set in a BOLT#9 feature vector.
+fn has_feature(features: &[u8], bit: usize) -> bool {
+    // Feature vectors are big-endian, bit 0 is in the last byte.
+    match features.len().checked_sub(bit / 8 + 1) {
+        Some(pos) => features[pos] & (1 << (bit % 8)) != 0,
+        None => false,
+    }
+}
+
+/// The trampolines to split a payment of `amount_msat` across:
+/// connected peers advertising trampoline support with spendable
+/// balance in their normal channels, sorted by balance, highest
+/// first. Empty if their balance together is not enough.
+fn select_trampolines(
+    peers: &[clnpb::ListpeersPeers],
+    channels: &[clnpb::ListpeerchannelsChannels],
+    amount_msat: u64,
+) -> Vec<Vec<u8>> {
+    let mut trampolines: Vec<(&clnpb::ListpeersPeers, u64)> = peers
+        .iter()
+        .filter(|p| p.connected)
+        .filter(|p| {
+            p.features
+                .as_deref()
+                .is_some_and(|f| has_feature(f, TRAMPOLINE_FEATURE_BIT))
+        })
+        .map(|p| {
+            let spendable_msat: u64 = channels
+                .iter()
+                .filter(|c| c.peer_id == p.id)
+                .filter(|c| c.state == clnpb::ChannelState::ChanneldNormal as i32)
+                .filter_map(|c| c.spendable_msat.as_ref().map(|a| a.msat))
+                .sum();
+            (p, spendable_msat)
+        })
+        .filter(|(_, spendable_msat)| *spendable_msat > 0)
+        .collect();
+    if trampolines.iter().map(|(_, msat)| msat).sum::<u64>() < amount_msat {
+        return vec![];
+    }
+    trampolines.sort_by_key(|(_, spendable_msat)| std::cmp::Reverse(*spendable_msat));
+    trampolines.into_iter().map(|(p, _)| p.id.clone()).collect()
+}
+
+/// The estimated cost of a payment, see `Node::prepare_send`.
+#[derive(Clone, Debug, uniffi::Record)]
+pub struct PreparedSend {
+    /// The amount the recipient receives.
+    pub amount_msat: u64,
+    /// The routing fee of the cheapest route found.
+    pub estimated_fee_msat: u64,
+    /// `amount_msat` plus `estimated_fee_msat`.
+    pub total_msat: u64,
+    /// The highest fee `send` accepts with the same options. If
+    /// `estimated_fee_msat` exceeds it, the payment is likely to fail.
+    pub fee_limit_msat: u64,
+    /// The number of hops of the cheapest route.
+    pub hops: u32,
 }
 
-impl From<clnpb::PayResponse> for SendResponse {
-    fn from(other: clnpb::PayResponse) -> Self {
+/// A node to find a route to when estimating fees: the recipient, or
+/// the entry point of one of the invoice's route hints.
+struct RouteTarget {
+    id: Vec<u8>,
+    amount_msat: u64,
+    /// The fees charged along the route hint.
+    hint_fee_msat: u64,
+    hint_hops: u32,
+    cltv: u32,
+}
+
+impl RouteTarget {
+    fn from_hint(
+        hint: &lightning_invoice::RouteHint,
+        amount_msat: u64,
+        cltv: u32,
+    ) -> Option<RouteTarget> {
+        let entry = hint.0.first()?;
+        // Walk the hint backwards, each hop charges its fee on the
+        // amount it forwards. Hints whose fees or CLTV deltas overflow
+        // are bogus and skipped.
+        let mut amount = amount_msat;
+        let mut cltv = cltv;
+        for hop in hint.0.iter().rev() {
+            let fee = hop.fees.base_msat as u128
+                + amount as u128 * hop.fees.proportional_millionths as u128 / 1_000_000;
+            amount = u64::try_from((amount as u128).checked_add(fee)?).ok()?;
+            cltv = cltv.checked_add(hop.cltv_expiry_delta as u32)?;
+        }
+        Some(RouteTarget {
+            id: entry.src_node_id.serialize().to_vec(),
+            amount_msat: amount,
+            hint_fee_msat: amount - amount_msat,
+            hint_hops: hint.0.len() as u32,
+            cltv,
+        })
+    }
+}
+
+impl From<clnpb::KeysendResponse> for SendResponse {
+    fn from(other: clnpb::KeysendResponse) -> Self {
+        let amount_msat = other.amount_msat.map(|a| a.msat).unwrap_or_default();
+        let amount_sent_msat = other.amount_sent_msat.map(|a| a.msat).unwrap_or_default();
         Self {
-            status: other.status.into(),
+            // `keysend` only returns once the payment completed.
+            status: PayStatus::COMPLETE,
             preimage: hex::encode(&other.payment_preimage),
             payment_hash: hex::encode(&other.payment_hash),
             destination_pubkey: other.destination.as_deref().map(hex::encode),
-            amount_msat: other.amount_msat.unwrap().msat,
-            amount_sent_msat: other.amount_sent_msat.unwrap().msat,
+            amount_msat,
+            amount_sent_msat,
             parts: other.parts,
+            route: SendRoute::Regular,
+            fee_msat: amount_sent_msat.saturating_sub(amount_msat),
+        }
+    }
+}
+
+/// A custom TLV record attached to a spontaneous payment.
+#[derive(Clone, Debug, uniffi::Record)]
+pub struct TlvEntry {
+    /// The TLV type. Custom types must be odd and at least 65536.
+    pub tlv_type: u64,
+    pub value: Vec<u8>,
+}
+
+/// The lowest TLV type reserved for custom records.
+const MIN_CUSTOM_TLV_TYPE: u64 = 65536;
+
+impl TlvEntry {
+    /// Check that the type is a custom type: odd, so that recipients
+    /// that don't understand it ignore it, and at least 65536.
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.tlv_type < MIN_CUSTOM_TLV_TYPE || self.tlv_type.is_multiple_of(2) {
+            return Err(Error::argument("tlv_type", self.tlv_type.to_string()));
         }
+        Ok(())
     }
 }
 
@@ -1752,6 +3180,108 @@ impl From<i32> for PayStatus {
     }
 }
 
+// ============================================================
+// BOLT12 offer types
+// ============================================================
+
+/// A BOLT12 offer created by this node.
+#[derive(Clone, serde::Serialize, uniffi::Record)]
+pub struct Offer {
+    /// Offer id as lowercase hex (64 chars).
+    pub offer_id: String,
+    /// The offer string (`lno1...`) to share with payers.
+    pub bolt12: String,
+    /// False once the offer has been disabled.
+    pub active: bool,
+    /// True if the offer can only be paid once.
+    pub single_use: bool,
+    /// True once at least one invoice for this offer has been paid.
+    pub used: bool,
+    pub label: Option<String>,
+}
+
+#[derive(Clone, serde::Serialize, uniffi::Record)]
+pub struct ListOffersResponse {
+    pub offers: Vec<Offer>,
+}
+
+/// Format an optional msat amount the way CLN's `offer` RPC expects
+/// it: `"any"` or `"<n>msat"`.
+fn offer_amount_from_msat(amount_msat: Option<u64>) -> String {
+    match amount_msat {
+        Some(msat) => format!("{msat}msat"),
+        None => "any".to_string(),
+    }
+}
+
+/// Decode a hex offer id into the raw 32 bytes CLN expects.
+fn offer_id_to_bytes(offer_id: &str) -> Result<Vec<u8>, Error> {
+    match hex::decode(offer_id) {
+        Ok(bytes) if bytes.len() == 32 => Ok(bytes),
+        _ => Err(Error::argument("offer_id", offer_id)),
+    }
+}
+
+impl From<clnpb::OfferResponse> for Offer {
+    fn from(other: clnpb::OfferResponse) -> Self {
+        Self {
+            offer_id: hex::encode(&other.offer_id),
+            bolt12: other.bolt12,
+            active: other.active,
+            single_use: other.single_use,
+            used: other.used,
+            label: other.label,
+        }
+    }
+}
+
+impl From<clnpb::ListoffersOffers> for Offer {
+    fn from(other: clnpb::ListoffersOffers) -> Self {
+        Self {
+            offer_id: hex::encode(&other.offer_id),
+            bolt12: other.bolt12,
+            active: other.active,
+            single_use: other.single_use,
+            used: other.used,
+            label: other.label,
+        }
+    }
+}
+
+impl From<clnpb::DisableofferResponse> for Offer {
+    fn from(other: clnpb::DisableofferResponse) -> Self {
+        Self {
+            offer_id: hex::encode(&other.offer_id),
+            bolt12: other.bolt12,
+            active: other.active,
+            single_use: other.single_use,
+            used: other.used,
+            label: other.label,
+        }
+    }
+}
+
+impl From<clnpb::EnableofferResponse> for Offer {
+    fn from(other: clnpb::EnableofferResponse) -> Self {
+        Self {
+            offer_id: hex::encode(&other.offer_id),
+            bolt12: other.bolt12,
+            active: other.active,
+            single_use: other.single_use,
+            used: other.used,
+            label: other.label,
+        }
+    }
+}
+
+impl From<clnpb::ListoffersResponse> for ListOffersResponse {
+    fn from(other: clnpb::ListoffersResponse) -> Self {
+        Self {
+            offers: other.offers.into_iter().map(|o| o.into()).collect(),
+        }
+    }
+}
+
 // ============================================================
 // GetInfo response types
 // ============================================================
@@ -2281,10 +3811,28 @@ pub struct ListPaymentsRequest {
     pub to_timestamp: Option<u64>,
     /// Include failed payments. Default: false.
     pub include_failures: Option<bool>,
-    /// Pagination offset.
+    /// Pagination offset, applied after `cursor`.
     pub offset: Option<u32>,
     /// Pagination limit.
     pub limit: Option<u32>,
+    /// Filter by status. None or empty = all but failed payments,
+    /// unless `include_failures` is set.
+    #[uniffi(default = None)]
+    pub statuses: Option<Vec<PaymentStatus>>,
+    /// Filter by kind (Lightning, Onchain, LspFee). None or empty =
+    /// Lightning only.
+    #[uniffi(default = None)]
+    pub kinds: Option<Vec<PaymentKind>>,
+    /// Include only payments of at least this amount.
+    #[uniffi(default = None)]
+    pub min_amount_msat: Option<u64>,
+    /// Include only payments of at most this amount.
+    #[uniffi(default = None)]
+    pub max_amount_msat: Option<u64>,
+    /// Continue after the payment with this `Payment::cursor`, e.g.
+    /// the last one of the previous page.
+    #[uniffi(default = None)]
+    pub cursor: Option<String>,
 }
 
 #[derive(Clone, uniffi::Enum)]
@@ -2295,6 +3843,9 @@ pub enum PaymentTypeFilter {
 
 #[derive(Clone, uniffi::Record)]
 pub struct Payment {
+    /// Identifies the entry: the payment hash of lightning payments,
+    /// the txid of on-chain transactions and the payment hash followed
+    /// by `:opening_fee` for LSP opening fees.
     pub id: String,
     pub payment_type: PaymentType,
     pub payment_time: u64,
@@ -2318,21 +3869,53 @@ pub struct Payment {
     /// derivable from a paid invoice is the *payee* (i.e. our own
     /// node), which is uninteresting to display per-row.
     pub destination: Option<String>,
+    pub kind: PaymentKind,
+    /// The transaction id as lowercase hex, for `PaymentKind::Onchain`.
+    #[uniffi(default = None)]
+    pub txid: Option<String>,
+    /// Position of the payment in the `list_payments` history. Pass it
+    /// as `ListPaymentsRequest::cursor` to list the payments after it.
+    /// `None` for payments returned by `list_payments_since`.
+    #[uniffi(default = None)]
+    pub cursor: Option<String>,
 }
 
-#[derive(Clone, uniffi::Enum)]
+#[derive(Clone, Copy, Debug, PartialEq, uniffi::Enum)]
 pub enum PaymentType {
     Sent,
     Received,
 }
 
-#[derive(Clone, uniffi::Enum)]
+#[derive(Clone, Copy, Debug, PartialEq, uniffi::Enum)]
 pub enum PaymentStatus {
     Pending,
     Complete,
     Failed,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, uniffi::Enum)]
+pub enum PaymentKind {
+    /// A lightning payment.
+    Lightning,
+    /// An on-chain transaction of the wallet. `amount_msat` is the
+    /// change of the wallet's balance, minus the fee.
+    Onchain,
+    /// The fee an LSP deducted from a received payment to open a JIT
+    /// channel. Reported as sent, after the received payment, whose
+    /// `amount_msat` includes the fee.
+    LspFee,
+}
+
+/// Payments created or changed since a cursor, see
+/// `Node::list_payments_since`.
+#[derive(Clone, uniffi::Record)]
+pub struct ListPaymentsSinceResponse {
+    /// Newest first. Replaces the earlier payments with the same `id`.
+    pub payments: Vec<Payment>,
+    /// Pass to the next `list_payments_since` call.
+    pub cursor: String,
+}
+
 impl From<clnpb::ListinvoicesInvoices> for Payment {
     fn from(inv: clnpb::ListinvoicesInvoices) -> Self {
         let status = match inv.status() {
@@ -2365,6 +3948,9 @@ impl From<clnpb::ListinvoicesInvoices> for Payment {
             bolt11: inv.bolt11,
             preimage: inv.payment_preimage.as_deref().map(hex::encode),
             destination: None,
+            kind: PaymentKind::Lightning,
+            txid: None,
+            cursor: None,
         }
     }
 }
@@ -2393,6 +3979,9 @@ impl From<clnpb::ListpaysPays> for Payment {
             bolt11: pay.bolt11,
             preimage: pay.preimage.as_deref().map(hex::encode),
             destination: pay.destination.as_deref().map(hex::encode),
+            kind: PaymentKind::Lightning,
+            txid: None,
+            cursor: None,
         }
     }
 }
@@ -2515,19 +4104,97 @@ pub struct NodeState {
 // NodeEvent streaming types
 // ============================================================
 
+/// The delay before the first attempt to reconnect a listener's
+/// event stream. Doubles with every failed attempt.
+const EVENT_RECONNECT_INITIAL_BACKOFF: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// The longest delay between attempts to reconnect a listener's
+/// event stream.
+const EVENT_RECONNECT_MAX_BACKOFF: std::time::Duration = std::time::Duration::from_secs(60);
+
+fn next_backoff(backoff: std::time::Duration) -> std::time::Duration {
+    std::cmp::min(backoff * 2, EVENT_RECONNECT_MAX_BACKOFF)
+}
+
+/// Response header of `StreamNodeEvents` with the index the stream
+/// starts after, set by nodes that track it.
+const EVENT_INDEX_HEADER: &str = "gleventindex";
+
+/// The index the event stream starts after, as reported by the node.
+fn event_stream_start<T>(res: &tonic::Response<T>) -> Option<u64> {
+    res.metadata()
+        .get(EVENT_INDEX_HEADER)?
+        .to_str()
+        .ok()?
+        .parse()
+        .ok()
+}
+
+/// Whether the node denied a call, e.g. one that the rune of read-only
+/// credentials doesn't allow.
+fn is_permission_denied(status: &tonic::Status) -> bool {
+    status.code() == tonic::Code::PermissionDenied
+}
+
+/// Whether the event stream failed for a reason that reconnecting
+/// does not fix, e.g. the credentials are rejected or the node no
+/// longer retains the events after the cursor.
+fn is_permanent_event_error(status: &tonic::Status) -> bool {
+    matches!(
+        status.code(),
+        tonic::Code::Unauthenticated
+            | tonic::Code::PermissionDenied
+            | tonic::Code::OutOfRange
+            | tonic::Code::Unimplemented
+    )
+}
+
+/// Schedule the node and open its event stream, replaying the events
+/// after `since`. Returns the stream and the index it starts after,
+/// if the node reported it.
+async fn open_event_stream(
+    node: ClientNode,
+    since: Option<u64>,
+) -> Result<(tonic::codec::Streaming<glpb::NodeEvent>, Option<u64>), tonic::Status> {
+    let mut client: GlClient = node.schedule().await.map_err(|e| {
+        e.downcast::<tonic::Status>()
+            .unwrap_or_else(|e| tonic::Status::unavailable(e.to_string()))
+    })?;
+    let res = client
+        .stream_node_events(glpb::NodeEventsRequest { since })
+        .await?;
+    let start = event_stream_start(&res);
+    Ok((res.into_inner(), start))
+}
+
 /// Callback interface for receiving node events.
 ///
-/// `on_event` is invoked from the SDK's internal event-dispatch task.
-/// Implementations should be cheap and non-blocking; to update UI,
-/// dispatch to the main thread from inside the handler.
+/// All methods are invoked from the SDK's internal event-dispatch
+/// task. Implementations should be cheap and non-blocking; to update
+/// UI, dispatch to the main thread from inside the handler.
 ///
 /// Installed via `NodeBuilder::with_event_listener(...)` so events
 /// emitted during node bring-up are captured. The polling-style
 /// `Node::stream_node_events()` API is still available for callers
 /// that prefer to drive events themselves.
+///
+/// If the connection to the node is lost, the SDK reconnects with
+/// exponential backoff and replays the events emitted in the
+/// meantime, so `on_event` sees every event once.
 #[uniffi::export(callback_interface)]
 pub trait NodeEventListener: Send + Sync {
     fn on_event(&self, event: NodeEvent);
+
+    /// The event stream was (re-)established.
+    fn on_connected(&self);
+
+    /// The event stream was interrupted. `reason` describes the
+    /// error. The SDK keeps trying to reconnect until the node is
+    /// disconnected, unless the node rejected the stream for good,
+    /// e.g. because the credentials are no longer valid or the
+    /// cursor is older than the events the node retains. In that
+    /// case this is the last call to the listener.
+    fn on_disconnected(&self, reason: String);
 }
 
 /// A stream of node events. Call `next()` to receive the next event.
@@ -2539,6 +4206,8 @@ pub trait NodeEventListener: Send + Sync {
 #[derive(uniffi::Object)]
 pub struct NodeEventStream {
     inner: Mutex<tonic::codec::Streaming<glpb::NodeEvent>>,
+    /// Index of the last event received, 0 if none.
+    last_index: AtomicU64,
 }
 
 #[uniffi::export]
@@ -2557,6 +4226,7 @@ impl NodeEventStream {
         loop {
             match exec(stream.message()) {
                 Ok(Some(raw)) => {
+                    self.last_index.store(raw.index, Ordering::Relaxed);
                     if let Some(event) = node_event_from_pb(raw) {
                         return Ok(Some(event));
                     }
@@ -2564,10 +4234,25 @@ impl NodeEventStream {
                 }
                 Ok(None) => return Ok(None),
                 Err(e) if e.code() == tonic::Code::Unknown => return Ok(None),
+                Err(e) if e.code() == tonic::Code::OutOfRange => {
+                    return Err(Error::cursor_too_old(e.message()));
+                }
                 Err(e) => return Err(Error::rpc(e.to_string())),
             }
         }
     }
+
+    /// Index of the last event received from the node, including
+    /// events this SDK version does not recognise. Pass it to
+    /// `Node::stream_node_events_since()` to resume the stream after
+    /// a disconnect. If no event was received yet this is the cursor
+    /// the stream was opened with, if any.
+    pub fn last_index(&self) -> Option<u64> {
+        match self.last_index.load(Ordering::Relaxed) {
+            0 => None,
+            i => Some(i),
+        }
+    }
 }
 
 /// A real-time event from the node.
@@ -2575,6 +4260,24 @@ impl NodeEventStream {
 pub enum NodeEvent {
     /// An invoice was paid.
     InvoicePaid { details: InvoicePaidEvent },
+    /// An outgoing payment succeeded. Reported once per payment, even
+    /// if it was split into multiple parts.
+    PaymentSent { details: PaymentSentEvent },
+    /// An outgoing payment was rejected by the recipient.
+    PaymentFailed { details: PaymentFailedEvent },
+    /// A channel's funding confirmed and it is ready for use.
+    ChannelOpened { details: ChannelEvent },
+    /// A channel's closing transaction was seen on-chain.
+    ChannelClosed { details: ChannelClosedEvent },
+    /// A channel changed state. Also sent alongside `ChannelOpened`
+    /// and `ChannelClosed`.
+    ChannelStateChanged { details: ChannelStateChangedEvent },
+    /// A peer connected. `peer_id` is lowercase hex (66 chars).
+    PeerConnected { peer_id: String },
+    /// A peer disconnected. `peer_id` is lowercase hex (66 chars).
+    PeerDisconnected { peer_id: String },
+    /// An on-chain deposit to the wallet confirmed.
+    DepositConfirmed { details: DepositConfirmedEvent },
 }
 
 /// Details of a paid invoice.
@@ -2592,6 +4295,87 @@ pub struct InvoicePaidEvent {
     pub amount_msat: u64,
 }
 
+/// Details of a successful outgoing payment.
+#[derive(Clone, uniffi::Record)]
+pub struct PaymentSentEvent {
+    /// Payment hash as lowercase hex (64 chars).
+    pub payment_hash: String,
+    /// Preimage that proves payment as lowercase hex (64 chars).
+    pub preimage: String,
+    /// Recipient public key as lowercase hex (66 chars), if known.
+    pub destination: Option<String>,
+    /// Amount delivered to the recipient in millisatoshis.
+    pub amount_msat: u64,
+    /// Amount sent including fees in millisatoshis.
+    pub amount_sent_msat: u64,
+    /// The bolt11 invoice that was paid, if any.
+    pub bolt11: Option<String>,
+}
+
+/// Details of a failed outgoing payment.
+#[derive(Clone, uniffi::Record)]
+pub struct PaymentFailedEvent {
+    /// Payment hash as lowercase hex (64 chars).
+    pub payment_hash: String,
+    /// Recipient public key as lowercase hex (66 chars), if known.
+    pub destination: Option<String>,
+    /// Amount that was attempted, in millisatoshis.
+    pub amount_msat: u64,
+    /// Human-readable reason for the failure.
+    pub message: String,
+}
+
+/// Identifies the channel an event is about.
+#[derive(Clone, uniffi::Record)]
+pub struct ChannelEvent {
+    /// Peer public key as lowercase hex (66 chars).
+    pub peer_id: String,
+    /// Channel id as lowercase hex (64 chars).
+    pub channel_id: String,
+    /// Short channel id, once the funding is confirmed.
+    pub short_channel_id: Option<String>,
+}
+
+/// Details of a closed channel.
+#[derive(Clone, uniffi::Record)]
+pub struct ChannelClosedEvent {
+    /// The channel that was closed.
+    pub channel: ChannelEvent,
+    /// What caused the close: "user", "local", "remote", "protocol",
+    /// "onchain" or "unknown".
+    pub cause: String,
+}
+
+/// Details of a channel state change.
+#[derive(Clone, uniffi::Record)]
+pub struct ChannelStateChangedEvent {
+    /// The channel that changed state.
+    pub channel: ChannelEvent,
+    /// Previous state (e.g. "CHANNELD_AWAITING_LOCKIN"), None for
+    /// newly created channels.
+    pub old_state: Option<String>,
+    /// New state (e.g. "CHANNELD_NORMAL").
+    pub new_state: String,
+    /// What caused the change: "user", "local", "remote", "protocol",
+    /// "onchain" or "unknown".
+    pub cause: String,
+    /// Human-readable description of the change.
+    pub message: String,
+}
+
+/// Details of a confirmed on-chain deposit.
+#[derive(Clone, uniffi::Record)]
+pub struct DepositConfirmedEvent {
+    /// Transaction id as lowercase hex (64 chars).
+    pub txid: String,
+    /// Output index of the deposit.
+    pub outnum: u32,
+    /// Amount deposited in millisatoshis.
+    pub amount_msat: u64,
+    /// Block height at which the deposit confirmed.
+    pub blockheight: u32,
+}
+
 /// Convert a wire-level `glpb::NodeEvent` into the typed SDK enum.
 ///
 /// Returns `None` for events the SDK doesn't recognise (e.g. a future
@@ -2609,14 +4393,153 @@ fn node_event_from_pb(other: glpb::NodeEvent) -> Option<NodeEvent> {
                 amount_msat: paid.amount_msat,
             },
         }),
+        Some(glpb::node_event::Event::PaymentSent(p)) => Some(NodeEvent::PaymentSent {
+            details: PaymentSentEvent {
+                payment_hash: hex::encode(&p.payment_hash),
+                preimage: hex::encode(&p.preimage),
+                destination: p.destination.map(hex::encode),
+                amount_msat: p.amount_msat,
+                amount_sent_msat: p.amount_sent_msat,
+                bolt11: p.bolt11,
+            },
+        }),
+        Some(glpb::node_event::Event::PaymentFailed(p)) => Some(NodeEvent::PaymentFailed {
+            details: PaymentFailedEvent {
+                payment_hash: hex::encode(&p.payment_hash),
+                destination: p.destination.map(hex::encode),
+                amount_msat: p.amount_msat,
+                message: p.message,
+            },
+        }),
+        Some(glpb::node_event::Event::ChannelOpened(c)) => Some(NodeEvent::ChannelOpened {
+            details: ChannelEvent {
+                peer_id: hex::encode(&c.peer_id),
+                channel_id: hex::encode(&c.channel_id),
+                short_channel_id: c.short_channel_id,
+            },
+        }),
+        Some(glpb::node_event::Event::ChannelClosed(c)) => Some(NodeEvent::ChannelClosed {
+            details: ChannelClosedEvent {
+                channel: ChannelEvent {
+                    peer_id: hex::encode(&c.peer_id),
+                    channel_id: hex::encode(&c.channel_id),
+                    short_channel_id: c.short_channel_id,
+                },
+                cause: c.cause,
+            },
+        }),
+        Some(glpb::node_event::Event::ChannelStateChanged(c)) => {
+            Some(NodeEvent::ChannelStateChanged {
+                details: ChannelStateChangedEvent {
+                    channel: ChannelEvent {
+                        peer_id: hex::encode(&c.peer_id),
+                        channel_id: hex::encode(&c.channel_id),
+                        short_channel_id: c.short_channel_id,
+                    },
+                    old_state: c.old_state,
+                    new_state: c.new_state,
+                    cause: c.cause,
+                    message: c.message,
+                },
+            })
+        }
+        Some(glpb::node_event::Event::PeerConnected(p)) => Some(NodeEvent::PeerConnected {
+            peer_id: hex::encode(&p.peer_id),
+        }),
+        Some(glpb::node_event::Event::PeerDisconnected(p)) => Some(NodeEvent::PeerDisconnected {
+            peer_id: hex::encode(&p.peer_id),
+        }),
+        Some(glpb::node_event::Event::DepositConfirmed(d)) => Some(NodeEvent::DepositConfirmed {
+            details: DepositConfirmedEvent {
+                txid: hex::encode(&d.txid),
+                outnum: d.outnum,
+                amount_msat: d.amount_msat,
+                blockheight: d.blockheight,
+            },
+        }),
+        // Operator alerts about the signer, not exposed to apps.
+        Some(glpb::node_event::Event::SignerStuck(_)) => None,
         None => None,
     }
 }
 
+/// The rune restrictions of read-only credentials, in the format the
+/// pairing protocol expects.
+fn readonly_restrictions() -> Result<String, Error> {
+    use gl_client::runes::{DefRules, Restrictor as _};
+    Ok(DefRules::ReadOnly
+        .generate()
+        .map_err(|e| Error::other(e.to_string()))?
+        .iter()
+        .map(|r| r.encode())
+        .collect::<Vec<_>>()
+        .join("&"))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn readonly_restrictions_only_allow_get_and_list() {
+        assert_eq!(readonly_restrictions().unwrap(), "method^get|method^list");
+    }
+
+    #[test]
+    fn node_event_from_pb_converts_new_event_types() {
+        let event = |e| glpb::NodeEvent {
+            event: Some(e),
+            index: 1,
+        };
+
+        match node_event_from_pb(event(glpb::node_event::Event::PaymentSent(
+            glpb::PaymentSent {
+                payment_hash: vec![1; 32],
+                preimage: vec![2; 32],
+                destination: Some(vec![3; 33]),
+                amount_msat: 1000,
+                amount_sent_msat: 1001,
+                bolt11: None,
+            },
+        ))) {
+            Some(NodeEvent::PaymentSent { details }) => {
+                assert_eq!(details.payment_hash, "01".repeat(32));
+                assert_eq!(details.destination, Some("03".repeat(33)));
+                assert_eq!(details.amount_sent_msat, 1001);
+            }
+            _ => panic!("expected PaymentSent"),
+        }
+
+        match node_event_from_pb(event(glpb::node_event::Event::ChannelClosed(
+            glpb::ChannelClosed {
+                peer_id: vec![2; 33],
+                channel_id: vec![4; 32],
+                short_channel_id: Some("103x1x0".to_string()),
+                cause: "remote".to_string(),
+            },
+        ))) {
+            Some(NodeEvent::ChannelClosed { details }) => {
+                assert_eq!(details.channel.channel_id, "04".repeat(32));
+                assert_eq!(details.cause, "remote");
+            }
+            _ => panic!("expected ChannelClosed"),
+        }
+
+        match node_event_from_pb(event(glpb::node_event::Event::PeerDisconnected(
+            glpb::PeerDisconnected {
+                peer_id: vec![2; 33],
+            },
+        ))) {
+            Some(NodeEvent::PeerDisconnected { peer_id }) => assert_eq!(peer_id, "02".repeat(33)),
+            _ => panic!("expected PeerDisconnected"),
+        }
+
+        assert!(node_event_from_pb(glpb::NodeEvent {
+            event: None,
+            index: 1,
+        }).is_none());
+    }
+
     #[test]
     fn parse_amount_or_all_handles_all_variants() {
         let all = parse_amount_or_all("all").unwrap();
@@ -2869,6 +4792,20 @@ mod tests {
         assert_eq!(r.day_sat_per_vbyte, 10);
     }
 
+    #[test]
+    fn offer_amount_formats_any_and_msat() {
+        assert_eq!(offer_amount_from_msat(None), "any");
+        assert_eq!(offer_amount_from_msat(Some(21_000)), "21000msat");
+    }
+
+    #[test]
+    fn offer_id_to_bytes_rejects_malformed_ids() {
+        let id = "11".repeat(32);
+        assert_eq!(offer_id_to_bytes(&id).unwrap(), vec![0x11; 32]);
+        assert!(offer_id_to_bytes("zz").is_err());
+        assert!(offer_id_to_bytes(&"11".repeat(31)).is_err());
+    }
+
     #[test]
     fn output_weight_for_address_per_script_type() {
         // P2WPKH — script_pubkey is 22 bytes, output = (8+1+22)*4 = 124
@@ -2896,4 +4833,245 @@ mod tests {
         // Garbage falls back to the conservative 172 wu.
         assert_eq!(output_weight_for_address("not-an-address"), 172);
     }
+
+    #[test]
+    fn event_stream_errors_are_permanent_only_if_reconnecting_cannot_help() {
+        use tonic::{Code, Status};
+        let permanent = |code| is_permanent_event_error(&Status::new(code, "error"));
+        assert!(permanent(Code::Unauthenticated));
+        assert!(permanent(Code::OutOfRange));
+        assert!(!permanent(Code::Unavailable));
+        assert!(!permanent(Code::Unknown));
+    }
+
+    #[test]
+    fn event_stream_start_is_read_from_the_response_header() {
+        let mut res = tonic::Response::new(());
+        assert_eq!(event_stream_start(&res), None);
+        res.metadata_mut().insert(EVENT_INDEX_HEADER, 42u64.into());
+        assert_eq!(event_stream_start(&res), Some(42));
+    }
+
+    #[test]
+    fn event_reconnect_backoff_doubles_up_to_the_maximum() {
+        let mut backoff = EVENT_RECONNECT_INITIAL_BACKOFF;
+        let mut delays = vec![];
+        for _ in 0..8 {
+            delays.push(backoff.as_secs());
+            backoff = next_backoff(backoff);
+        }
+        assert_eq!(delays, vec![1, 2, 4, 8, 16, 32, 60, 60]);
+    }
+
+    #[test]
+    fn tlv_entries_must_have_an_odd_custom_type() {
+        let tlv = |tlv_type| TlvEntry {
+            tlv_type,
+            value: vec![],
+        };
+        assert!(tlv(7629169).validate().is_ok());
+        assert!(tlv(65537).validate().is_ok());
+        assert!(tlv(65536).validate().is_err());
+        assert!(tlv(7629168).validate().is_err());
+        // The keysend preimage is set by the node itself.
+        assert!(tlv(5482373484).validate().is_err());
+        assert!(tlv(5).validate().is_err());
+    }
+
+    #[test]
+    fn send_options_reject_fixed_fee_combined_with_percent() {
+        assert!(SendOptions::default().validate().is_ok());
+        let fixed = SendOptions {
+            max_fee_msat: Some(1000),
+            ..Default::default()
+        };
+        assert!(fixed.validate().is_ok());
+        let with_percent = SendOptions {
+            max_fee_percent: Some(1.0),
+            ..fixed.clone()
+        };
+        assert!(with_percent.validate().is_err());
+        let with_exempt = SendOptions {
+            exempt_fee_msat: Some(0),
+            ..fixed
+        };
+        assert!(with_exempt.validate().is_err());
+        let over_100 = SendOptions {
+            max_fee_percent: Some(101.0),
+            ..Default::default()
+        };
+        assert!(over_100.validate().is_err());
+    }
+
+    #[test]
+    fn send_options_fee_limit_matches_pay_defaults() {
+        // 0.5% of 10M msat is 50k msat, above the 5k exemption.
+        assert_eq!(SendOptions::default().fee_limit_msat(10_000_000), 50_000);
+        // Small payments fall back to the exemption.
+        assert_eq!(SendOptions::default().fee_limit_msat(10_000), 5_000);
+        let opts = SendOptions {
+            max_fee_percent: Some(1.0),
+            exempt_fee_msat: Some(0),
+            ..Default::default()
+        };
+        assert_eq!(opts.fee_limit_msat(10_000), 100);
+        let opts = SendOptions {
+            max_fee_msat: Some(42),
+            ..Default::default()
+        };
+        assert_eq!(opts.fee_limit_msat(10_000_000), 42);
+    }
+
+    #[test]
+    fn send_options_trampoline_fee_percent() {
+        assert_eq!(
+            SendOptions::default().trampoline_fee_percent(10_000_000),
+            Some(0.5)
+        );
+        // Small payments may pay the exempt fee, as with `pay`.
+        assert_eq!(
+            SendOptions::default().trampoline_fee_percent(100_000),
+            Some(5.0)
+        );
+        let opts = SendOptions {
+            max_fee_msat: Some(100),
+            ..Default::default()
+        };
+        assert_eq!(opts.trampoline_fee_percent(10_000), Some(1.0));
+        assert_eq!(opts.trampoline_fee_percent(0), None);
+        let opts = SendOptions {
+            max_fee_percent: Some(0.0),
+            exempt_fee_msat: Some(0),
+            ..Default::default()
+        };
+        assert_eq!(opts.trampoline_fee_percent(10_000), None);
+    }
+
+    #[test]
+    fn trampoline_unusable_only_for_routing_failures() {
+        let status = |code: i64| {
+            tonic::Status::with_details(
+                tonic::Code::Unknown,
+                "failed",
+                format!("{{\"code\":{code}}}").into(),
+            )
+        };
+        for code in [42701, 42704, 42707, 42708, 42715, 203, 204] {
+            assert!(trampoline_unusable(&status(code)), "{code}");
+        }
+        // E.g. an invalid invoice or a payment already in flight.
+        for code in [42709, 201, -1] {
+            assert!(!trampoline_unusable(&status(code)), "{code}");
+        }
+        assert!(!trampoline_unusable(&tonic::Status::unavailable("down")));
+    }
+
+    #[test]
+    fn fee_insufficient_falls_back_to_pay() {
+        // Every trampoline asked for more than `maxfeepercent` allows,
+        // a regular payment may still find a cheaper route.
+        let status = tonic::Status::with_details(
+            tonic::Code::FailedPrecondition,
+            "fee insufficient",
+            "{\"code\":42715}".into(),
+        );
+        assert!(falls_back_to_pay(&SendOptions::default(), &status));
+        // Unless the caller asked for a trampoline payment.
+        let options = SendOptions {
+            trampoline: Some(true),
+            ..Default::default()
+        };
+        assert!(!falls_back_to_pay(&options, &status));
+    }
+
+    #[test]
+    fn has_feature_reads_big_endian_feature_vectors() {
+        // Bit 427 is bit 3 of byte 53, counted from the end.
+        let mut features = vec![0u8; 54];
+        features[0] = 1 << 3;
+        assert!(has_feature(&features, TRAMPOLINE_FEATURE_BIT));
+        assert!(!has_feature(&features, 426));
+        assert!(!has_feature(&features[1..], TRAMPOLINE_FEATURE_BIT));
+        assert!(!has_feature(&[], 0));
+    }
+
+    #[test]
+    fn select_trampolines_sorts_connected_peers_by_balance() {
+        let mut trampoline = vec![0u8; 54];
+        trampoline[0] = 1 << 3;
+        let peer = |id: u8, connected, features: &Vec<u8>| clnpb::ListpeersPeers {
+            id: vec![id; 33],
+            connected,
+            features: Some(features.clone()),
+            ..Default::default()
+        };
+        let channel = |id: u8, state: clnpb::ChannelState, msat| clnpb::ListpeerchannelsChannels {
+            peer_id: vec![id; 33],
+            state: state as i32,
+            spendable_msat: Some(clnpb::Amount { msat }),
+            ..Default::default()
+        };
+        let normal = clnpb::ChannelState::ChanneldNormal;
+        let peers = vec![
+            peer(1, true, &trampoline),
+            peer(2, true, &trampoline),
+            peer(3, false, &trampoline),
+            peer(4, true, &vec![0u8; 54]),
+        ];
+        let channels = vec![
+            channel(1, normal, 1_000),
+            channel(2, normal, 3_000),
+            channel(2, clnpb::ChannelState::ChanneldAwaitingLockin, 5_000),
+            channel(3, normal, 10_000),
+            channel(4, normal, 10_000),
+        ];
+        assert_eq!(
+            select_trampolines(&peers, &channels, 500),
+            vec![vec![2; 33], vec![1; 33]]
+        );
+        // More than any one trampoline can carry is split across them.
+        assert_eq!(
+            select_trampolines(&peers, &channels, 4_000),
+            vec![vec![2; 33], vec![1; 33]]
+        );
+        assert!(select_trampolines(&peers, &channels, 4_001).is_empty());
+    }
+
+    #[test]
+    fn route_target_from_hint_adds_hint_fees_and_cltv() {
+        let hop = |node_id: &str, base_msat, ppm, cltv| lightning_invoice::RouteHintHop {
+            src_node_id: node_id.parse().unwrap(),
+            short_channel_id: 1,
+            fees: lightning_invoice::RoutingFees {
+                base_msat,
+                proportional_millionths: ppm,
+            },
+            cltv_expiry_delta: cltv,
+            htlc_minimum_msat: None,
+            htlc_maximum_msat: None,
+        };
+        let entry = "02eec7245d6b7d2ccb30380bfbe2a3648cd7a942653f5aa340edcea1f283686619";
+        let second = "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+        let hint = lightning_invoice::RouteHint(vec![
+            hop(entry, 1000, 100, 40),
+            hop(second, 10, 1000, 20),
+        ]);
+
+        let t = RouteTarget::from_hint(&hint, 1_000_000, 18).unwrap();
+        assert_eq!(hex::encode(&t.id), entry);
+        // The last hop charges 10 + 1000 on 1M, the first 1000 + 100
+        // on the resulting 1_001_010.
+        assert_eq!(t.amount_msat, 1_001_010 + 1000 + 100);
+        assert_eq!(t.hint_fee_msat, t.amount_msat - 1_000_000);
+        assert_eq!(t.hint_hops, 2);
+        assert_eq!(t.cltv, 78);
+
+        assert!(RouteTarget::from_hint(&lightning_invoice::RouteHint(vec![]), 1, 18).is_none());
+
+        // Fees that overflow the amount reject the hint.
+        let mut greedy = hint.clone();
+        greedy.0[0].fees.base_msat = u32::MAX;
+        greedy.0[0].fees.proportional_millionths = u32::MAX;
+        assert!(RouteTarget::from_hint(&greedy, u64::MAX - 1, 18).is_none());
+    }
 }
@@ -45,6 +45,8 @@ use crate::{
 pub struct NodeBuilder {
     config: Arc<Config>,
     event_listener: Option<Arc<dyn NodeEventListener>>,
+    event_cursor: Option<u64>,
+    read_only: bool,
 }
 
 #[uniffi::export]
@@ -57,6 +59,8 @@ impl NodeBuilder {
         Arc::new(Self {
             config: Arc::new(config.clone()),
             event_listener: None,
+            event_cursor: None,
+            read_only: false,
         })
     }
 
@@ -82,6 +86,40 @@ impl NodeBuilder {
         Arc::new(Self {
             config: Arc::clone(&self.config),
             event_listener: Some(Arc::from(listener)),
+            event_cursor: self.event_cursor,
+            read_only: self.read_only,
+        })
+    }
+
+    /// Replay the events the node retained after the event with index
+    /// `since` to the event listener, before delivering new ones.
+    ///
+    /// Pass the `Node::last_event_index()` persisted at the end of a
+    /// previous session to receive the events emitted in between.
+    /// Without a cursor the listener only receives new events.
+    pub fn with_event_cursor(self: Arc<Self>, since: u64) -> Arc<Self> {
+        Arc::new(Self {
+            config: Arc::clone(&self.config),
+            event_listener: self.event_listener.clone(),
+            event_cursor: Some(since),
+            read_only: self.read_only,
+        })
+    }
+
+    /// Build Nodes in read-only mode: calls that spend, receive or
+    /// otherwise change the node fail with `Error::ReadOnly` without
+    /// reaching the node.
+    ///
+    /// This is enforced client-side only. The signer checks the rune
+    /// of credentials from `Node::derive_readonly_credentials` only
+    /// for requests it has to sign, so calls that need no signature
+    /// are not restricted by it.
+    pub fn with_read_only(self: Arc<Self>) -> Arc<Self> {
+        Arc::new(Self {
+            config: Arc::clone(&self.config),
+            event_listener: self.event_listener.clone(),
+            event_cursor: self.event_cursor,
+            read_only: true,
         })
     }
 
@@ -154,8 +192,11 @@ impl NodeBuilder {
     /// Modifiers are shared (not consumed) — the same builder can
     /// drive multiple builds and they all get the same listener.
     fn attach_observers(&self, node: &Arc<Node>) -> Result<(), Error> {
+        if self.read_only {
+            node.set_read_only();
+        }
         if let Some(listener) = self.event_listener.as_ref() {
-            node.set_event_listener(Arc::clone(listener))?;
+            node.set_event_listener(Arc::clone(listener), self.event_cursor)?;
         }
         Ok(())
     }
@@ -0,0 +1,793 @@
+// Payment history for `Node::list_payments` and
+// `Node::list_payments_since`.
+//
+// The history is merged from three sources: paid invoices, ordered by
+// CLN's `updated_index` (bumped when an invoice is paid), outgoing
+// payments, ordered by their `created_index`, and the bookkeeper's
+// events of the on-chain wallet, ordered by time. Each source is read
+// newest first and the sources are merged by the time matching their
+// order: when an invoice was paid, when an outgoing payment was
+// created and when a transaction was recorded. The cursor
+// of an entry records how far each source was read, so the next page
+// neither repeats nor skips entries, even when payments arrive in
+// between.
+//
+// This module only holds the conversions and the merging, the RPC
+// calls live in `node.rs`.
+
+use crate::Error;
+use crate::node::{
+    ListPaymentsRequest, Payment, PaymentKind, PaymentStatus, PaymentType, PaymentTypeFilter,
+};
+use gl_client::pb::cln as clnpb;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// Position of an entry within its source. A source is read in
+/// decreasing position.
+pub(crate) type Position = (u64, u64);
+
+/// Index of each source in `FeedCursor::bounds`, which also breaks
+/// ties between entries with the same payment time.
+pub(crate) const INVOICES: usize = 0;
+pub(crate) const PAYS: usize = 1;
+pub(crate) const ONCHAIN: usize = 2;
+
+/// The bookkeeper account of the on-chain wallet.
+pub(crate) const WALLET_ACCOUNT: &str = "wallet";
+
+/// Datastore key under which the plugin stores the LSPS2 opening fee
+/// of JIT channel invoices, by payment hash.
+pub(crate) fn opening_fees_key() -> Vec<String> {
+    vec!["greenlight".to_string(), "lsps2_opening_fees".to_string()]
+}
+
+/// How many entries of a source to read per call, to fill a page of
+/// `needed` entries.
+pub(crate) fn window(needed: usize) -> u64 {
+    (needed as u64).clamp(100, 1000)
+}
+
+pub(crate) struct Entry {
+    pub(crate) pos: Position,
+    /// The time the source is ordered by, which the sources are merged
+    /// by. Not necessarily the payment time, e.g. an outgoing payment
+    /// completes after it was created.
+    pub(crate) time: u64,
+    pub(crate) payment: Payment,
+}
+
+/// How far each source of the history has been read.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) struct FeedCursor {
+    /// The position of the last entry read from each source. Only
+    /// entries below it are read next. `None` if the source was not
+    /// read yet.
+    pub(crate) bounds: [Option<Position>; 3],
+}
+
+impl FeedCursor {
+    pub(crate) fn encode(&self) -> String {
+        let bounds = self.bounds.iter().map(|b| match b {
+            Some((index, sub)) => format!("{index}-{sub}"),
+            None => String::new(),
+        });
+        std::iter::once("p1".to_string())
+            .chain(bounds)
+            .collect::<Vec<_>>()
+            .join(".")
+    }
+
+    pub(crate) fn decode(cursor: &str) -> Result<Self, Error> {
+        let invalid = || Error::argument("cursor", cursor);
+        let mut parts = cursor.split('.');
+        if parts.next() != Some("p1") {
+            return Err(invalid());
+        }
+        let mut bounds = [None; 3];
+        for bound in bounds.iter_mut() {
+            let part = parts.next().ok_or_else(invalid)?;
+            if part.is_empty() {
+                continue;
+            }
+            let (index, sub) = part.split_once('-').ok_or_else(invalid)?;
+            *bound = Some((
+                index.parse().map_err(|_| invalid())?,
+                sub.parse().map_err(|_| invalid())?,
+            ));
+        }
+        if parts.next().is_some() {
+            return Err(invalid());
+        }
+        Ok(Self { bounds })
+    }
+}
+
+/// How far `list_payments_since` has read the node's payments.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) struct SyncCursor {
+    /// The highest `updated_index` of the invoices read.
+    pub(crate) invoices_updated: u64,
+    /// The highest `created_index` of the outgoing payments read.
+    pub(crate) pays_created: u64,
+    /// The highest `updated_index` of the outgoing payments read.
+    pub(crate) pays_updated: u64,
+    /// The time of the newest wallet event read.
+    pub(crate) onchain_time: u64,
+    /// How many wallet events at `onchain_time` were read.
+    pub(crate) onchain_seen: u64,
+}
+
+impl SyncCursor {
+    pub(crate) fn encode(&self) -> String {
+        format!(
+            "s1.{}.{}.{}.{}.{}",
+            self.invoices_updated,
+            self.pays_created,
+            self.pays_updated,
+            self.onchain_time,
+            self.onchain_seen
+        )
+    }
+
+    pub(crate) fn decode(cursor: &str) -> Result<Self, Error> {
+        let invalid = || Error::argument("cursor", cursor);
+        let parts = cursor.split('.').collect::<Vec<_>>();
+        let ["s1", values @ ..] = parts.as_slice() else {
+            return Err(invalid());
+        };
+        let values = values
+            .iter()
+            .map(|v| v.parse::<u64>().map_err(|_| invalid()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let [
+            invoices_updated,
+            pays_created,
+            pays_updated,
+            onchain_time,
+            onchain_seen,
+        ] = values[..]
+        else {
+            return Err(invalid());
+        };
+        Ok(Self {
+            invoices_updated,
+            pays_created,
+            pays_updated,
+            onchain_time,
+            onchain_seen,
+        })
+    }
+}
+
+/// Whether `req` lets payments of this kind and type through, to skip
+/// reading sources that can't contribute.
+pub(crate) fn wants(
+    req: &ListPaymentsRequest,
+    kind: PaymentKind,
+    payment_type: PaymentType,
+) -> bool {
+    let type_ok = match req.filters.as_deref() {
+        None | Some([]) => true,
+        Some(filters) => filters.iter().any(|f| match f {
+            PaymentTypeFilter::Sent => payment_type == PaymentType::Sent,
+            PaymentTypeFilter::Received => payment_type == PaymentType::Received,
+        }),
+    };
+    let kind_ok = match req.kinds.as_deref() {
+        None | Some([]) => kind == PaymentKind::Lightning,
+        Some(kinds) => kinds.contains(&kind),
+    };
+    type_ok && kind_ok
+}
+
+/// Whether `payment` passes the filters of `req`.
+pub(crate) fn matches(req: &ListPaymentsRequest, payment: &Payment) -> bool {
+    if !wants(req, payment.kind, payment.payment_type) {
+        return false;
+    }
+    let status_ok = match req.statuses.as_deref() {
+        None | Some([]) => {
+            req.include_failures.unwrap_or(false) || payment.status != PaymentStatus::Failed
+        }
+        Some(statuses) => statuses.contains(&payment.status),
+    };
+    status_ok
+        && req
+            .from_timestamp
+            .is_none_or(|from| payment.payment_time >= from)
+        && req.to_timestamp.is_none_or(|to| payment.payment_time <= to)
+        && req
+            .min_amount_msat
+            .is_none_or(|min| payment.amount_msat >= min)
+        && req
+            .max_amount_msat
+            .is_none_or(|max| payment.amount_msat <= max)
+}
+
+/// Merges the entries of the sources newest first, skips `offset` of
+/// them and returns at most `limit`, each with the cursor to continue
+/// after it.
+///
+/// The entries of each source have to be below its bound in `cursor`,
+/// in decreasing position. A source holds at least `offset + limit`
+/// entries, unless it has no more.
+pub(crate) fn merge_page(
+    sources: [Vec<Entry>; 3],
+    mut cursor: FeedCursor,
+    offset: usize,
+    limit: usize,
+) -> Vec<Payment> {
+    // A source that was not read yet starts at its newest entry, so
+    // that payments arriving later don't show up in the next pages.
+    for (bound, entries) in cursor.bounds.iter_mut().zip(&sources) {
+        if bound.is_none() {
+            *bound = entries.first().map(|e| (e.pos.0, e.pos.1 + 1));
+        }
+    }
+
+    let mut sources = sources.map(|s| s.into_iter().peekable());
+    let mut payments = vec![];
+    let mut skipped = 0;
+    while payments.len() < limit {
+        let newest = (0..sources.len())
+            .filter_map(|i| sources[i].peek().map(|e| (i, e.time)))
+            .max_by(|a, b| a.1.cmp(&b.1).then(b.0.cmp(&a.0)));
+        let Some((i, _)) = newest else {
+            break;
+        };
+        let Some(entry) = sources[i].next() else {
+            break;
+        };
+        cursor.bounds[i] = Some(entry.pos);
+        if skipped < offset {
+            skipped += 1;
+            continue;
+        }
+        let mut payment = entry.payment;
+        payment.cursor = Some(cursor.encode());
+        payments.push(payment);
+    }
+    payments
+}
+
+/// The opening fees stored by the plugin, by payment hash.
+pub(crate) fn opening_fees(entries: Vec<clnpb::ListdatastoreDatastore>) -> HashMap<String, u64> {
+    entries
+        .into_iter()
+        .filter_map(|e| {
+            let [_, _, payment_hash] = e.key.as_slice() else {
+                return None;
+            };
+            let fee = e.string.as_deref()?.parse().ok()?;
+            Some((payment_hash.clone(), fee))
+        })
+        .collect()
+}
+
+/// The entries of a paid invoice. For a JIT channel invoice, the LSP
+/// deducted the opening fee from the payment: it is reported as an
+/// entry of its own, after the received payment, which is reported
+/// before the deduction.
+pub(crate) fn invoice_entries(
+    invoice: clnpb::ListinvoicesInvoices,
+    opening_fees: &HashMap<String, u64>,
+) -> Vec<Entry> {
+    let index = invoice.updated_index.unwrap_or_default();
+    let mut received: Payment = invoice.into();
+    // Invoices are read in the order they were paid.
+    let time = received.payment_time;
+    let Some(&fee) = opening_fees.get(&received.id) else {
+        return vec![Entry {
+            pos: (index, 1),
+            time,
+            payment: received,
+        }];
+    };
+    received.amount_msat = received.amount_msat.saturating_add(fee);
+    let fee = Payment {
+        id: format!("{}:opening_fee", received.id),
+        payment_type: PaymentType::Sent,
+        payment_time: received.payment_time,
+        amount_msat: fee,
+        fee_msat: 0,
+        status: received.status,
+        description: received.description.clone(),
+        bolt11: received.bolt11.clone(),
+        preimage: None,
+        destination: None,
+        kind: PaymentKind::LspFee,
+        txid: None,
+        cursor: None,
+    };
+    vec![
+        Entry {
+            pos: (index, 1),
+            time,
+            payment: received,
+        },
+        Entry {
+            pos: (index, 0),
+            time,
+            payment: fee,
+        },
+    ]
+}
+
+/// The transaction of a wallet event. Deposits are identified by the
+/// output they created, the other events by the transaction causing
+/// them.
+fn event_txid(e: &clnpb::BkprlistaccounteventsEvents) -> Option<String> {
+    use clnpb::bkprlistaccountevents_events::BkprlistaccounteventsEventsType as EventType;
+
+    let credit = e.credit_msat.as_ref().map_or(0, |a| a.msat);
+    match (e.item_type(), &e.outpoint, &e.txid) {
+        (EventType::Chain, Some(outpoint), _) if credit > 0 => {
+            outpoint.split(':').next().map(str::to_string)
+        }
+        (EventType::Chain | EventType::OnchainFee, _, Some(txid)) => Some(hex::encode(txid)),
+        _ => None,
+    }
+}
+
+/// The on-chain payments of the wallet, one per transaction, from the
+/// bookkeeper's events. Transactions that didn't change the wallet's
+/// balance, e.g. splicing a channel, are left out.
+pub(crate) fn onchain_entries(events: &[clnpb::BkprlistaccounteventsEvents]) -> Vec<Entry> {
+    use clnpb::bkprlistaccountevents_events::BkprlistaccounteventsEventsType as EventType;
+
+    #[derive(Default)]
+    struct Tx {
+        received: u64,
+        spent: u64,
+        fee: i128,
+        time: u64,
+    }
+
+    let mut txs: BTreeMap<String, Tx> = BTreeMap::new();
+    for e in events.iter().filter(|e| e.account == WALLET_ACCOUNT) {
+        let Some(txid) = event_txid(e) else {
+            continue;
+        };
+        let credit = e.credit_msat.as_ref().map_or(0, |a| a.msat);
+        let debit = e.debit_msat.as_ref().map_or(0, |a| a.msat);
+        let tx = txs.entry(txid).or_default();
+        tx.time = tx.time.max(e.timestamp.into());
+        match e.item_type() {
+            EventType::OnchainFee => tx.fee += debit as i128 - credit as i128,
+            _ => {
+                tx.received += credit;
+                tx.spent += debit;
+            }
+        }
+    }
+
+    let mut entries = txs
+        .into_iter()
+        .filter_map(|(txid, tx)| {
+            let (payment_type, amount_msat, fee_msat) = if tx.spent > tx.received {
+                let net = tx.spent - tx.received;
+                let fee = tx.fee.clamp(0, net as i128) as u64;
+                (PaymentType::Sent, net - fee, fee)
+            } else if tx.received > tx.spent {
+                (PaymentType::Received, tx.received - tx.spent, 0)
+            } else {
+                return None;
+            };
+            // Break ties between transactions at the same time by
+            // their txid.
+            let tie = u64::from_str_radix(txid.get(..16)?, 16).ok()?;
+            Some(Entry {
+                pos: (tx.time, tie),
+                time: tx.time,
+                payment: Payment {
+                    id: txid.clone(),
+                    payment_type,
+                    payment_time: tx.time,
+                    amount_msat,
+                    fee_msat,
+                    // The bookkeeper records confirmed transactions.
+                    status: PaymentStatus::Complete,
+                    description: None,
+                    bolt11: None,
+                    preimage: None,
+                    destination: None,
+                    kind: PaymentKind::Onchain,
+                    txid: Some(txid),
+                    cursor: None,
+                },
+            })
+        })
+        .collect::<Vec<_>>();
+    entries.sort_by_key(|e| std::cmp::Reverse(e.pos));
+    entries
+}
+
+/// The on-chain payments with wallet events that `cursor` hasn't
+/// read yet. Advances `cursor` past the events.
+pub(crate) fn onchain_changes(
+    events: &[clnpb::BkprlistaccounteventsEvents],
+    cursor: &mut SyncCursor,
+) -> Vec<Payment> {
+    let wallet_events = events.iter().filter(|e| e.account == WALLET_ACCOUNT);
+    let count_at = |time: u64| {
+        wallet_events
+            .clone()
+            .filter(|e| u64::from(e.timestamp) == time)
+            .count() as u64
+    };
+    // Events at the time of the cursor are read again if more of them
+    // showed up since.
+    let reread = count_at(cursor.onchain_time) > cursor.onchain_seen;
+    let txids = wallet_events
+        .clone()
+        .filter(|e| {
+            let time = u64::from(e.timestamp);
+            time > cursor.onchain_time || (reread && time == cursor.onchain_time)
+        })
+        .filter_map(event_txid)
+        .collect::<HashSet<_>>();
+
+    if let Some(time) = wallet_events.clone().map(|e| u64::from(e.timestamp)).max() {
+        cursor.onchain_time = time;
+        cursor.onchain_seen = count_at(time);
+    }
+
+    onchain_entries(events)
+        .into_iter()
+        .map(|e| e.payment)
+        .filter(|p| txids.contains(&p.id))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn payment(id: &str, payment_time: u64) -> Payment {
+        Payment {
+            id: id.to_string(),
+            payment_type: PaymentType::Sent,
+            payment_time,
+            amount_msat: 1000,
+            fee_msat: 0,
+            status: PaymentStatus::Complete,
+            description: None,
+            bolt11: None,
+            preimage: None,
+            destination: None,
+            kind: PaymentKind::Lightning,
+            txid: None,
+            cursor: None,
+        }
+    }
+
+    fn entries(items: &[(u64, &str, u64)]) -> Vec<Entry> {
+        items
+            .iter()
+            .map(|&(index, id, time)| Entry {
+                pos: (index, 0),
+                time,
+                payment: payment(id, time),
+            })
+            .collect()
+    }
+
+    fn ids(payments: &[Payment]) -> Vec<&str> {
+        payments.iter().map(|p| p.id.as_str()).collect()
+    }
+
+    fn event(
+        item_type: i32,
+        credit: u64,
+        debit: u64,
+        timestamp: u32,
+    ) -> clnpb::BkprlistaccounteventsEvents {
+        clnpb::BkprlistaccounteventsEvents {
+            account: WALLET_ACCOUNT.to_string(),
+            item_type,
+            credit_msat: Some(clnpb::Amount { msat: credit }),
+            debit_msat: Some(clnpb::Amount { msat: debit }),
+            timestamp,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn cursors_round_trip() {
+        let cursor = FeedCursor {
+            bounds: [Some((5, 1)), None, Some((1700000000, 42))],
+        };
+        assert_eq!(FeedCursor::decode(&cursor.encode()).unwrap(), cursor);
+        assert_eq!(FeedCursor::decode("p1...").unwrap(), FeedCursor::default());
+
+        let cursor = SyncCursor {
+            invoices_updated: 3,
+            pays_created: 4,
+            pays_updated: 5,
+            onchain_time: 1700000000,
+            onchain_seen: 2,
+        };
+        assert_eq!(SyncCursor::decode(&cursor.encode()).unwrap(), cursor);
+    }
+
+    #[test]
+    fn invalid_cursors_are_rejected() {
+        for cursor in [
+            "",
+            "p1",
+            "p1.1..",
+            "p2...",
+            "p1.a-1..",
+            "p1....",
+            "s1.1.2.3.4",
+        ] {
+            assert!(FeedCursor::decode(cursor).is_err(), "{cursor}");
+        }
+        for cursor in ["", "s1.1.2.3.4", "s1.1.2.3.4.x", "p1..."] {
+            assert!(SyncCursor::decode(cursor).is_err(), "{cursor}");
+        }
+    }
+
+    #[test]
+    fn merge_page_interleaves_sources_by_time() {
+        let invoices = entries(&[(9, "inv9", 50), (7, "inv7", 30)]);
+        let pays = entries(&[(4, "pay4", 40), (3, "pay3", 10)]);
+        let onchain = entries(&[(45, "tx", 45)]);
+        let page = merge_page([invoices, pays, onchain], FeedCursor::default(), 0, 10);
+        assert_eq!(ids(&page), ["inv9", "tx", "pay4", "inv7", "pay3"]);
+    }
+
+    #[test]
+    fn merge_page_keeps_the_order_of_each_source() {
+        // The older payment completed last, it still comes after the
+        // newer one it was read after.
+        let mut pays = entries(&[(4, "pay4", 40), (3, "pay3", 30)]);
+        pays[1].payment.payment_time = 60;
+        let invoices = entries(&[(9, "inv9", 50), (7, "inv7", 35)]);
+        let page = merge_page([invoices, pays, vec![]], FeedCursor::default(), 0, 10);
+        assert_eq!(ids(&page), ["inv9", "pay4", "inv7", "pay3"]);
+    }
+
+    #[test]
+    fn merge_page_cursor_continues_after_the_entry() {
+        let sources = || {
+            [
+                entries(&[(9, "inv9", 50), (7, "inv7", 30)]),
+                entries(&[(4, "pay4", 40), (3, "pay3", 10)]),
+                vec![],
+            ]
+        };
+        let first = merge_page(sources(), FeedCursor::default(), 0, 2);
+        assert_eq!(ids(&first), ["inv9", "pay4"]);
+
+        let cursor = FeedCursor::decode(first[1].cursor.as_deref().unwrap()).unwrap();
+        assert_eq!(cursor.bounds, [Some((9, 0)), Some((4, 0)), None]);
+
+        // The next page only holds the entries below the bounds.
+        let [invoices, pays, onchain] = sources();
+        let below = |entries: Vec<Entry>, bound: Option<Position>| {
+            entries
+                .into_iter()
+                .filter(|e| bound.is_none_or(|b| e.pos < b))
+                .collect::<Vec<_>>()
+        };
+        let second = merge_page(
+            [
+                below(invoices, cursor.bounds[INVOICES]),
+                below(pays, cursor.bounds[PAYS]),
+                onchain,
+            ],
+            cursor,
+            0,
+            2,
+        );
+        assert_eq!(ids(&second), ["inv7", "pay3"]);
+    }
+
+    #[test]
+    fn merge_page_pins_unread_sources() {
+        let page = merge_page(
+            [
+                entries(&[(9, "inv9", 50)]),
+                entries(&[(4, "pay4", 40)]),
+                vec![],
+            ],
+            FeedCursor::default(),
+            0,
+            1,
+        );
+        let cursor = FeedCursor::decode(page[0].cursor.as_deref().unwrap()).unwrap();
+        // Payments made after the first page was read stay out of the
+        // following pages.
+        assert_eq!(cursor.bounds, [Some((9, 0)), Some((4, 1)), None]);
+    }
+
+    #[test]
+    fn merge_page_applies_offset() {
+        let page = merge_page(
+            [
+                entries(&[(3, "a", 30), (2, "b", 20), (1, "c", 10)]),
+                vec![],
+                vec![],
+            ],
+            FeedCursor::default(),
+            1,
+            1,
+        );
+        assert_eq!(ids(&page), ["b"]);
+    }
+
+    #[test]
+    fn matches_applies_filters() {
+        let mut req = ListPaymentsRequest::default();
+        let mut p = payment("a", 100);
+        assert!(matches(&req, &p));
+
+        p.status = PaymentStatus::Failed;
+        assert!(!matches(&req, &p));
+        req.include_failures = Some(true);
+        assert!(matches(&req, &p));
+        req.statuses = Some(vec![PaymentStatus::Pending]);
+        assert!(!matches(&req, &p));
+        req.statuses = Some(vec![PaymentStatus::Pending, PaymentStatus::Failed]);
+        assert!(matches(&req, &p));
+
+        req.min_amount_msat = Some(1001);
+        assert!(!matches(&req, &p));
+        req.min_amount_msat = Some(1000);
+        req.max_amount_msat = Some(999);
+        assert!(!matches(&req, &p));
+        req.max_amount_msat = None;
+
+        req.from_timestamp = Some(101);
+        assert!(!matches(&req, &p));
+        req.from_timestamp = None;
+
+        // Only Lightning payments unless asked for other kinds.
+        let onchain = Payment {
+            kind: PaymentKind::Onchain,
+            ..p.clone()
+        };
+        assert!(!matches(&req, &onchain));
+        req.kinds = Some(vec![PaymentKind::Onchain]);
+        assert!(matches(&req, &onchain));
+        assert!(!matches(&req, &p));
+        req.kinds = Some(vec![PaymentKind::Lightning]);
+        req.filters = Some(vec![PaymentTypeFilter::Received]);
+        assert!(!matches(&req, &p));
+        req.filters = Some(vec![PaymentTypeFilter::Sent]);
+        assert!(matches(&req, &p));
+    }
+
+    #[test]
+    fn invoice_entries_report_the_opening_fee() {
+        let invoice = clnpb::ListinvoicesInvoices {
+            payment_hash: vec![1; 32],
+            status: clnpb::listinvoices_invoices::ListinvoicesInvoicesStatus::Paid as i32,
+            amount_msat: Some(clnpb::Amount { msat: 100_000 }),
+            amount_received_msat: Some(clnpb::Amount { msat: 98_000 }),
+            paid_at: Some(1_700_000_000),
+            updated_index: Some(7),
+            ..Default::default()
+        };
+
+        let plain = invoice_entries(invoice.clone(), &HashMap::new());
+        assert_eq!(plain.len(), 1);
+        assert_eq!(plain[0].payment.amount_msat, 98_000);
+
+        let fees = HashMap::from([("01".repeat(32), 2_000)]);
+        let jit = invoice_entries(invoice, &fees);
+        assert_eq!(jit.len(), 2);
+        assert_eq!(jit[0].pos, (7, 1));
+        assert_eq!(jit[0].payment.amount_msat, 100_000);
+        assert_eq!(jit[1].pos, (7, 0));
+        assert_eq!(
+            jit[1].payment.id,
+            format!("{}:opening_fee", "01".repeat(32))
+        );
+        assert_eq!(jit[1].payment.kind, PaymentKind::LspFee);
+        assert_eq!(jit[1].payment.payment_type, PaymentType::Sent);
+        assert_eq!(jit[1].payment.amount_msat, 2_000);
+    }
+
+    #[test]
+    fn opening_fees_are_read_by_payment_hash() {
+        let entry = |key: &[&str], string: Option<&str>| clnpb::ListdatastoreDatastore {
+            key: key.iter().map(|k| k.to_string()).collect(),
+            string: string.map(str::to_string),
+            ..Default::default()
+        };
+        let fees = opening_fees(vec![
+            entry(&["greenlight", "lsps2_opening_fees", "aa"], Some("2000")),
+            entry(&["greenlight", "lsps2_opening_fees", "bb"], Some("x")),
+            entry(&["greenlight", "lsps2_opening_fees"], None),
+        ]);
+        assert_eq!(fees, HashMap::from([("aa".to_string(), 2000)]));
+    }
+
+    #[test]
+    fn onchain_changes_only_report_transactions_with_new_events() {
+        let deposit = |txid: u8, timestamp| {
+            let mut e = event(1, 1_000, 0, timestamp);
+            e.outpoint = Some(format!("{}:0", hex::encode([txid; 32])));
+            e
+        };
+        let ids = |payments: Vec<Payment>| {
+            let mut ids = payments.into_iter().map(|p| p.id).collect::<Vec<_>>();
+            ids.sort();
+            ids
+        };
+
+        let mut cursor = SyncCursor::default();
+        let events = vec![deposit(1, 100), deposit(2, 200)];
+        assert_eq!(
+            ids(onchain_changes(&events, &mut cursor)),
+            ["01".repeat(32), "02".repeat(32)]
+        );
+        assert_eq!((cursor.onchain_time, cursor.onchain_seen), (200, 1));
+        assert!(onchain_changes(&events, &mut cursor).is_empty());
+
+        // Another event at the time of the cursor.
+        let events = vec![deposit(1, 100), deposit(2, 200), deposit(3, 200)];
+        assert_eq!(
+            ids(onchain_changes(&events, &mut cursor)),
+            ["02".repeat(32), "03".repeat(32)]
+        );
+        assert_eq!((cursor.onchain_time, cursor.onchain_seen), (200, 2));
+
+        let events = vec![
+            deposit(1, 100),
+            deposit(2, 200),
+            deposit(3, 200),
+            deposit(4, 300),
+        ];
+        assert_eq!(
+            ids(onchain_changes(&events, &mut cursor)),
+            ["04".repeat(32)]
+        );
+    }
+
+    #[test]
+    fn onchain_entries_group_wallet_events_by_transaction() {
+        let deposit_txid = "11".repeat(32);
+        let spend_txid = "22".repeat(32);
+
+        let mut deposit = event(1, 50_000_000, 0, 100);
+        deposit.tag = "deposit".to_string();
+        deposit.outpoint = Some(format!("{deposit_txid}:0"));
+
+        // Spending the deposit: 20k to someone else, 29k of change and
+        // a 1k fee.
+        let mut withdrawal = event(1, 0, 50_000_000, 200);
+        withdrawal.tag = "withdrawal".to_string();
+        withdrawal.outpoint = Some(format!("{deposit_txid}:0"));
+        withdrawal.txid = Some(vec![0x22; 32]);
+        let mut change = event(1, 29_000_000, 0, 200);
+        change.tag = "deposit".to_string();
+        change.outpoint = Some(format!("{spend_txid}:1"));
+        let mut fee = event(0, 0, 1_000_000, 200);
+        fee.tag = "onchain_fee".to_string();
+        fee.txid = Some(vec![0x22; 32]);
+
+        let mut channel = event(2, 0, 5_000, 300);
+        channel.account = "abcd".to_string();
+
+        let entries = onchain_entries(&[deposit, withdrawal, change, fee, channel]);
+        assert_eq!(entries.len(), 2);
+
+        let spend = &entries[0].payment;
+        assert_eq!(spend.id, spend_txid);
+        assert_eq!(spend.kind, PaymentKind::Onchain);
+        assert_eq!(spend.payment_type, PaymentType::Sent);
+        assert_eq!(spend.amount_msat, 20_000_000);
+        assert_eq!(spend.fee_msat, 1_000_000);
+        assert_eq!(spend.payment_time, 200);
+
+        let received = &entries[1].payment;
+        assert_eq!(received.txid.as_deref(), Some(deposit_txid.as_str()));
+        assert_eq!(received.payment_type, PaymentType::Received);
+        assert_eq!(received.amount_msat, 50_000_000);
+        assert_eq!(received.fee_msat, 0);
+        assert_eq!(entries[1].pos, (100, 0x1111_1111_1111_1111));
+    }
+}
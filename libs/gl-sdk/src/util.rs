@@ -1,4 +1,6 @@
+use crate::Error;
 use ::tokio::runtime::{Builder, Runtime};
+use ::tokio::task::JoinHandle;
 use once_cell::sync::OnceCell;
 use std::future::Future;
 
@@ -20,6 +22,28 @@ where
     get_runtime().block_on(f)
 }
 
+/// Run `f` on the SDK runtime and await its result from any executor,
+/// such as the one driving a foreign `async` call. Dropping the
+/// returned future aborts the task, which cancels the call.
+pub(crate) async fn spawn<F, T>(f: F) -> Result<T, Error>
+where
+    F: Future<Output = Result<T, Error>> + Send + 'static,
+    T: Send + 'static,
+{
+    struct AbortOnDrop<T>(JoinHandle<T>);
+
+    impl<T> Drop for AbortOnDrop<T> {
+        fn drop(&mut self) {
+            self.0.abort();
+        }
+    }
+
+    let mut task = AbortOnDrop(get_runtime().spawn(f));
+    (&mut task.0)
+        .await
+        .map_err(|e| Error::other(format!("task failed: {e}")))?
+}
+
 // Dedicated runtime for signer tasks. Separate from the RPC runtime
 // because exec() uses block_on(), and calling block_on from within
 // a runtime deadlocks.
@@ -34,3 +58,54 @@ pub(crate) fn get_signer_runtime() -> &'static Runtime {
             .expect("Unable to build signer runtime")
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::time::Duration;
+
+    // A runtime without IO or timers, standing in for a foreign
+    // executor.
+    fn foreign_executor() -> Runtime {
+        Builder::new_current_thread().build().unwrap()
+    }
+
+    #[test]
+    fn spawn_runs_on_the_sdk_runtime() {
+        let res = foreign_executor().block_on(spawn(async {
+            ::tokio::time::sleep(Duration::from_millis(1)).await;
+            Ok(42)
+        }));
+        assert_eq!(res.unwrap(), 42);
+    }
+
+    #[test]
+    fn dropping_spawn_cancels_the_task() {
+        let finished = Arc::new(AtomicBool::new(false));
+        let flag = Arc::clone(&finished);
+        let call = spawn(async move {
+            ::tokio::time::sleep(Duration::from_millis(50)).await;
+            flag.store(true, Ordering::SeqCst);
+            Ok(())
+        });
+        foreign_executor().block_on(async {
+            let mut call = Box::pin(call);
+            // Poll once so the task is started, then drop it.
+            assert!(poll_once(call.as_mut()).await.is_none());
+        });
+        std::thread::sleep(Duration::from_millis(100));
+        assert!(!finished.load(Ordering::SeqCst));
+    }
+
+    async fn poll_once<F: Future + Unpin>(mut f: F) -> Option<F::Output> {
+        std::future::poll_fn(|cx| {
+            std::task::Poll::Ready(match std::pin::Pin::new(&mut f).poll(cx) {
+                std::task::Poll::Ready(v) => Some(v),
+                std::task::Poll::Pending => None,
+            })
+        })
+        .await
+    }
+}
@@ -0,0 +1,45 @@
+"""Tests for the non-blocking AsyncNode bindings.
+
+These only verify the shape of the bindings; the calls themselves
+are the same as the blocking `Node` methods exercised elsewhere.
+"""
+
+import inspect
+
+import glsdk
+
+
+ASYNC_METHODS = [
+    "receive",
+    "send",
+    "prepare_send",
+    "send_spontaneous",
+    "onchain_send",
+    "prepare_onchain_send",
+    "onchain_balance_state",
+    "onchain_fee_rates",
+    "onchain_receive",
+    "get_info",
+    "list_peers",
+    "list_peer_channels",
+    "list_funds",
+    "node_state",
+    "list_invoices",
+    "list_pays",
+    "list_payments",
+    "list_payments_since",
+    "derive_readonly_credentials",
+]
+
+
+class TestAsyncNodeTypes:
+    def test_async_node_type_exists(self):
+        assert hasattr(glsdk, "AsyncNode")
+
+    def test_async_node_exposes_coroutines(self):
+        for name in ASYNC_METHODS:
+            method = getattr(glsdk.AsyncNode, name)
+            assert inspect.iscoroutinefunction(method), name
+
+    def test_async_node_wraps_a_node(self):
+        assert hasattr(glsdk.AsyncNode, "node")
@@ -189,8 +189,7 @@ class TestConnectBadCredentials:
     def test_connect_empty_credentials(self, scheduler, nobody_id):
         dev_cert = glsdk.DeveloperCert(nobody_id.cert_chain, nobody_id.private_key)
         config = glsdk.Config().with_developer_cert(dev_cert)
-        # Empty credentials should fail (Credentials.load returns nobody creds,
-        # but the signer won't be able to authenticate with them)
+        # Empty credentials are rejected when they are loaded
         with pytest.raises(glsdk.Error):
             glsdk.NodeBuilder(config).connect(b"", MNEMONIC)
 
@@ -257,3 +256,34 @@ class TestLowLevelCredentials:
         node2 = glsdk.Node(creds_obj)
         roundtripped = node2.credentials()
         assert len(roundtripped) > 0
+
+
+class TestReadOnly:
+    """Test read-only credentials and the read-only Node mode."""
+
+    def test_read_only_node_rejects_mutating_calls(self, scheduler, nobody_id):
+        dev_cert = glsdk.DeveloperCert(nobody_id.cert_chain, nobody_id.private_key)
+        config = glsdk.Config().with_developer_cert(dev_cert)
+        node = glsdk.NodeBuilder(config).register(MNEMONIC, None)
+        creds = node.credentials()
+
+        ro = glsdk.NodeBuilder(config).with_read_only().connect(creds, None)
+        assert ro.is_read_only()
+        assert not node.is_read_only()
+        with pytest.raises(glsdk.Error.ReadOnly):
+            ro.receive("label", "description", 1000)
+        with pytest.raises(glsdk.Error.ReadOnly):
+            ro.onchain_receive()
+        ro.disconnect()
+        node.disconnect()
+
+    def test_derive_readonly_credentials(self, scheduler, nobody_id):
+        dev_cert = glsdk.DeveloperCert(nobody_id.cert_chain, nobody_id.private_key)
+        config = glsdk.Config().with_developer_cert(dev_cert)
+        node = glsdk.NodeBuilder(config).register(MNEMONIC, None)
+
+        creds = node.derive_readonly_credentials("dashboard")
+        assert isinstance(creds, glsdk.Credentials)
+        assert creds.node_id() == glsdk.Credentials.load(node.credentials()).node_id()
+        assert creds.save() != node.credentials()
+        node.disconnect()
@@ -18,19 +18,32 @@ def test_import_glsdk():
     assert hasattr(glsdk, "Error")
 
 
-def test_credentials_load_empty():
-    """Test loading credentials with empty data doesn't crash."""
-    # This should not crash - Device::from_bytes defaults to nobody creds
-    credentials = glsdk.Credentials.load(b"")
-    assert credentials is not None
+def _credentials_blob():
+    """A protobuf encoded credentials blob with a placeholder certificate, key and rune."""
+
+    def field(tag, value):
+        return bytes([tag, len(value)]) + value
+
+    return b"\x08\x01" + field(0x12, b"cert") + field(0x1A, b"key") + field(0x2A, b"rune")
+
+
+def test_credentials_load():
+    """Test loading and saving credentials round-trips."""
+    credentials = glsdk.Credentials.load(_credentials_blob())
     assert isinstance(credentials, glsdk.Credentials)
+    assert glsdk.Credentials.load(credentials.save()).save() == credentials.save()
+
+
+def test_credentials_load_empty():
+    """Test that empty credentials are rejected."""
+    with pytest.raises(glsdk.Error):
+        glsdk.Credentials.load(b"")
 
 
 def test_credentials_load_invalid():
-    """Test loading credentials with invalid data doesn't crash."""
-    # This should not crash either
-    credentials = glsdk.Credentials.load(b"invalid data")
-    assert credentials is not None
+    """Test that corrupt credentials are rejected rather than loaded as nobody."""
+    with pytest.raises(glsdk.Error):
+        glsdk.Credentials.load(b"invalid data")
 
 
 def test_credentials_type_error():
@@ -41,16 +54,30 @@ def test_credentials_type_error():
 
 def test_credentials_multiple_loads():
     """Test that we can create multiple credentials objects."""
-    creds1 = glsdk.Credentials.load(b"test1")
-    creds2 = glsdk.Credentials.load(b"test2")
-    creds3 = glsdk.Credentials.load(b"test3")
+    creds = [glsdk.Credentials.load(_credentials_blob()) for _ in range(3)]
+
+    assert all(isinstance(c, glsdk.Credentials) for c in creds)
+
 
-    assert all(isinstance(c, glsdk.Credentials) for c in [creds1, creds2, creds3])
+def test_credentials_encrypted_roundtrip():
+    """Test that encrypted credentials need the passphrase to load."""
+    creds = glsdk.Credentials.load(_credentials_blob())
+    blob = creds.save_encrypted("correct horse")
+    assert blob != creds.save()
+
+    restored = glsdk.Credentials.load_encrypted(blob, "correct horse")
+    assert restored.save() == creds.save()
+
+    with pytest.raises(glsdk.Error):
+        glsdk.Credentials.load_encrypted(blob, "battery staple")
+    # Encrypted credentials are not mistaken for plain ones.
+    with pytest.raises(glsdk.Error):
+        glsdk.Credentials.load(blob)
 
 
-def test_node_creation_fails_with_empty_creds():
-    """Test that creating a Node with empty credentials fails as expected."""
-    creds = glsdk.Credentials.load(b"")
+def test_node_creation_fails_with_placeholder_creds():
+    """Test that creating a Node with unusable credentials fails as expected."""
+    creds = glsdk.Credentials.load(_credentials_blob())
 
     # Node creation should fail with these invalid credentials
     with pytest.raises(glsdk.Error):
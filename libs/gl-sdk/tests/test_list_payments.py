@@ -161,6 +161,47 @@ class TestPaymentTypes:
         assert req.offset == 10
         assert req.limit == 50
 
+    def test_list_payments_request_with_history_filters(self):
+        req = glsdk.ListPaymentsRequest(
+            filters=None,
+            from_timestamp=None,
+            to_timestamp=None,
+            include_failures=None,
+            offset=None,
+            limit=20,
+            statuses=[glsdk.PaymentStatus.COMPLETE],
+            kinds=[glsdk.PaymentKind.ONCHAIN, glsdk.PaymentKind.LSP_FEE],
+            min_amount_msat=1000,
+            max_amount_msat=2000,
+            cursor="p1...",
+        )
+        assert req.statuses == [glsdk.PaymentStatus.COMPLETE]
+        assert len(req.kinds) == 2
+        assert req.min_amount_msat == 1000
+        assert req.max_amount_msat == 2000
+        assert req.cursor == "p1..."
+
+    def test_list_payments_request_history_filters_default_to_none(self):
+        req = glsdk.ListPaymentsRequest(
+            filters=None,
+            from_timestamp=None,
+            to_timestamp=None,
+            include_failures=None,
+            offset=None,
+            limit=None,
+        )
+        assert req.statuses is None
+        assert req.kinds is None
+        assert req.min_amount_msat is None
+        assert req.max_amount_msat is None
+        assert req.cursor is None
+
+    def test_payment_kind_enum_exists(self):
+        assert hasattr(glsdk, "PaymentKind")
+        assert hasattr(glsdk.PaymentKind, "LIGHTNING")
+        assert hasattr(glsdk.PaymentKind, "ONCHAIN")
+        assert hasattr(glsdk.PaymentKind, "LSP_FEE")
+
     def test_payment_type_enum_exists(self):
         assert hasattr(glsdk, "PaymentType")
         assert hasattr(glsdk.PaymentType, "SENT")
@@ -189,6 +230,7 @@ class TestPaymentTypes:
             bolt11="lnbc1...",
             preimage=b"\xab" * 32,
             destination=None,
+            kind=glsdk.PaymentKind.LIGHTNING,
         )
         assert payment.payment_type == glsdk.PaymentType.RECEIVED
         assert payment.status == glsdk.PaymentStatus.COMPLETE
@@ -209,6 +251,7 @@ class TestPaymentTypes:
             bolt11="lnbc1...",
             preimage=b"\x01" * 32,
             destination=b"\x02" * 33,
+            kind=glsdk.PaymentKind.LIGHTNING,
         )
         assert payment.payment_type == glsdk.PaymentType.SENT
         assert payment.amount_msat == 1000
@@ -228,10 +271,35 @@ class TestPaymentTypes:
             bolt11=None,
             preimage=None,
             destination=None,
+            kind=glsdk.PaymentKind.LIGHTNING,
         )
         assert payment.status == glsdk.PaymentStatus.PENDING
         assert payment.amount_msat == 0
 
+    def test_payment_record_onchain(self):
+        payment = glsdk.Payment(
+            id="11" * 32,
+            payment_type=glsdk.PaymentType.RECEIVED,
+            payment_time=1234567890,
+            amount_msat=50_000_000,
+            fee_msat=0,
+            status=glsdk.PaymentStatus.COMPLETE,
+            description=None,
+            bolt11=None,
+            preimage=None,
+            destination=None,
+            kind=glsdk.PaymentKind.ONCHAIN,
+            txid="11" * 32,
+        )
+        assert payment.kind == glsdk.PaymentKind.ONCHAIN
+        assert payment.txid == "11" * 32
+        assert payment.cursor is None
+
+    def test_list_payments_since_response_exists(self):
+        res = glsdk.ListPaymentsSinceResponse(payments=[], cursor="s1.0.0.0.0.0")
+        assert res.payments == []
+        assert res.cursor == "s1.0.0.0.0.0"
+
 
 class TestListPaymentsMethod:
     """Test list_payments() method."""
@@ -252,6 +320,48 @@ class TestListPaymentsMethod:
         assert result == []
         node.disconnect()
 
+    def test_list_payments_rejects_invalid_cursor(self, scheduler, nobody_id):
+        dev_cert = glsdk.DeveloperCert(nobody_id.cert_chain, nobody_id.private_key)
+        config = glsdk.Config().with_developer_cert(dev_cert)
+        node = glsdk.NodeBuilder(config).register_or_recover(MNEMONIC, None)
+        req = glsdk.ListPaymentsRequest(
+            filters=None, from_timestamp=None, to_timestamp=None,
+            include_failures=None, offset=None, limit=None,
+            cursor="not-a-cursor",
+        )
+        with pytest.raises(glsdk.Error.Argument):
+            node.list_payments(req)
+        node.disconnect()
+
+
+class TestListPaymentsSinceMethod:
+    """Test list_payments_since() method."""
+
+    def test_node_has_list_payments_since_method(self):
+        assert hasattr(glsdk.Node, "list_payments_since")
+
+    def test_list_payments_since_empty(self, scheduler, nobody_id):
+        dev_cert = glsdk.DeveloperCert(nobody_id.cert_chain, nobody_id.private_key)
+        config = glsdk.Config().with_developer_cert(dev_cert)
+        node = glsdk.NodeBuilder(config).register_or_recover(MNEMONIC, None)
+
+        first = node.list_payments_since(None)
+        assert first.payments == []
+        assert first.cursor
+
+        # Nothing changed in between.
+        second = node.list_payments_since(first.cursor)
+        assert second.payments == []
+        node.disconnect()
+
+    def test_list_payments_since_rejects_invalid_cursor(self, scheduler, nobody_id):
+        dev_cert = glsdk.DeveloperCert(nobody_id.cert_chain, nobody_id.private_key)
+        config = glsdk.Config().with_developer_cert(dev_cert)
+        node = glsdk.NodeBuilder(config).register_or_recover(MNEMONIC, None)
+        with pytest.raises(glsdk.Error.Argument):
+            node.list_payments_since("p1...")
+        node.disconnect()
+
 
 class TestListInvoicesIntegration:
     """Test that created invoices appear in list_invoices and list_payments."""
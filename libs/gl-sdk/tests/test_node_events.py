@@ -39,6 +39,19 @@ class TestEventStreamTypes:
         """Test that NodeEventStream class has next method."""
         assert hasattr(glsdk.NodeEventStream, "next")
 
+    def test_node_has_resumable_stream_methods(self):
+        """Test that the cursor based resume API is exposed."""
+        assert hasattr(glsdk.Node, "stream_node_events_since")
+        assert hasattr(glsdk.Node, "last_event_index")
+        assert hasattr(glsdk.NodeEventStream, "last_index")
+        assert hasattr(glsdk.NodeBuilder, "with_event_cursor")
+
+    def test_node_event_listener_has_connection_callbacks(self):
+        """Test that listeners are told about connection changes."""
+        assert hasattr(glsdk.NodeEventListener, "on_event")
+        assert hasattr(glsdk.NodeEventListener, "on_connected")
+        assert hasattr(glsdk.NodeEventListener, "on_disconnected")
+
 
 class TestInvoicePaidEventFields:
     """Test that InvoicePaidEvent has expected fields."""
@@ -112,4 +125,50 @@ class TestNodeEventVariants:
         assert not event.is_invoice_paid()
 
 
-
+class TestNodeEventNewVariants:
+    """Test the payment, channel, peer and deposit NodeEvent variants."""
+
+    @pytest.mark.parametrize(
+        "variant",
+        [
+            "PAYMENT_SENT",
+            "PAYMENT_FAILED",
+            "CHANNEL_OPENED",
+            "CHANNEL_CLOSED",
+            "CHANNEL_STATE_CHANGED",
+            "PEER_CONNECTED",
+            "PEER_DISCONNECTED",
+            "DEPOSIT_CONFIRMED",
+        ],
+    )
+    def test_node_event_has_variant(self, variant):
+        assert hasattr(glsdk.NodeEvent, variant)
+
+    def test_payment_sent_variant_construction(self):
+        details = glsdk.PaymentSentEvent(
+            payment_hash="00" * 32,
+            preimage="01" * 32,
+            destination="02" * 33,
+            amount_msat=100000,
+            amount_sent_msat=100100,
+            bolt11=None,
+        )
+        event = glsdk.NodeEvent.PAYMENT_SENT(details=details)
+        assert event.is_payment_sent()
+        assert event.details.amount_sent_msat == 100100
+
+    def test_channel_closed_variant_construction(self):
+        channel = glsdk.ChannelEvent(
+            peer_id="02" * 33,
+            channel_id="03" * 32,
+            short_channel_id="103x1x0",
+        )
+        details = glsdk.ChannelClosedEvent(channel=channel, cause="remote")
+        event = glsdk.NodeEvent.CHANNEL_CLOSED(details=details)
+        assert event.is_channel_closed()
+        assert event.details.channel.short_channel_id == "103x1x0"
+
+    def test_peer_disconnected_variant_construction(self):
+        event = glsdk.NodeEvent.PEER_DISCONNECTED(peer_id="02" * 33)
+        assert event.is_peer_disconnected()
+        assert event.peer_id == "02" * 33
@@ -88,6 +88,38 @@ class TestNodeMethods:
         """Test that Node class has list_funds method."""
         assert hasattr(glsdk.Node, "list_funds")
 
+    def test_node_has_send_spontaneous_method(self):
+        """Test that Node class has send_spontaneous method."""
+        assert hasattr(glsdk.Node, "send_spontaneous")
+
+    def test_tlv_entry_can_be_constructed(self):
+        """Test that TlvEntry carries custom keysend records."""
+        tlv = glsdk.TlvEntry(tlv_type=7629169, value=b"{}")
+        assert tlv.tlv_type == 7629169
+        assert tlv.value == b"{}"
+
+    def test_node_has_prepare_send_method(self):
+        """Test that Node class has prepare_send method."""
+        assert hasattr(glsdk.Node, "prepare_send")
+
+    def test_send_options_default_to_node_defaults(self):
+        """Test that SendOptions fields are optional."""
+        options = glsdk.SendOptions()
+        assert options.max_fee_msat is None
+        assert options.max_fee_percent is None
+        assert options.timeout_secs is None
+        assert options.exclude == []
+        assert options.trampoline is None
+
+    def test_send_options_can_cap_fee(self):
+        """Test that SendOptions carries a fee cap and exclusions."""
+        options = glsdk.SendOptions(max_fee_msat=1000, exclude=["103x1x0/1"])
+        assert options.max_fee_msat == 1000
+        assert options.exclude == ["103x1x0/1"]
+
+    def test_prepared_send_type_exists(self):
+        assert hasattr(glsdk, "PreparedSend")
+
 
 class TestSendResponseFields:
     """Test that SendResponse includes payment_hash and destination_pubkey."""
@@ -104,6 +136,8 @@ class TestSendResponseFields:
             amount_msat=1000,
             amount_sent_msat=1010,
             parts=1,
+            route=glsdk.SendRoute.REGULAR(),
+            fee_msat=10,
         )
         assert response.payment_hash == payment_hash_hex
         assert response.destination_pubkey == destination_hex
@@ -117,9 +151,28 @@ class TestSendResponseFields:
             amount_msat=1000,
             amount_sent_msat=1010,
             parts=1,
+            route=glsdk.SendRoute.REGULAR(),
+            fee_msat=10,
         )
         assert response.destination_pubkey is None
 
+    def test_send_response_reports_trampoline_route(self):
+        node_id_hex = "03" * 33
+        response = glsdk.SendResponse(
+            status=glsdk.PayStatus.COMPLETE,
+            preimage="01" * 32,
+            payment_hash="00" * 32,
+            destination_pubkey=None,
+            amount_msat=1000,
+            amount_sent_msat=1005,
+            parts=1,
+            route=glsdk.SendRoute.TRAMPOLINE(node_id=node_id_hex),
+            fee_msat=5,
+        )
+        assert response.route.is_trampoline()
+        assert response.route.node_id == node_id_hex
+        assert response.fee_msat == 5
+
 
 class TestResponseTypeFields:
     """Test that response types have expected fields."""
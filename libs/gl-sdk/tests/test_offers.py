@@ -0,0 +1,136 @@
+"""Tests for BOLT12 offer support on Node.
+
+Covers the exported types, the create / list / disable / enable
+round-trip against a freshly registered node, and paying an offer of a
+CLN node the Greenlight node has a channel with.
+"""
+
+import pytest
+import glsdk
+from gltesting.fixtures import *
+from pyln.testing.utils import wait_for
+
+
+MNEMONIC = (
+    "abandon abandon abandon abandon abandon abandon "
+    "abandon abandon abandon abandon abandon about"
+)
+
+
+class TestOfferTypes:
+    """Test that offer types and methods are exposed in the bindings."""
+
+    def test_offer_type_exists(self):
+        assert hasattr(glsdk, "Offer")
+
+    def test_list_offers_response_type_exists(self):
+        assert hasattr(glsdk, "ListOffersResponse")
+
+    @pytest.mark.parametrize(
+        "method",
+        ["create_offer", "list_offers", "disable_offer", "enable_offer", "pay_offer"],
+    )
+    def test_node_has_offer_method(self, method):
+        assert hasattr(glsdk.Node, method)
+
+    def test_offer_record_has_expected_fields(self):
+        offer = glsdk.Offer(
+            offer_id="11" * 32,
+            bolt12="lno1...",
+            active=True,
+            single_use=False,
+            used=False,
+            label=None,
+        )
+        assert offer.offer_id == "11" * 32
+        assert offer.active
+        assert offer.label is None
+
+
+class TestOfferMethods:
+    """Test the offer lifecycle on a live node."""
+
+    def test_create_list_disable_enable(self, scheduler, nobody_id):
+        dev_cert = glsdk.DeveloperCert(nobody_id.cert_chain, nobody_id.private_key)
+        config = glsdk.Config().with_developer_cert(dev_cert)
+        node = glsdk.NodeBuilder(config).register_or_recover(MNEMONIC, None)
+
+        offer = node.create_offer(21000, "coffee", "offer-1", None)
+        assert offer.bolt12.startswith("lno1")
+        assert len(offer.offer_id) == 64
+        assert offer.active
+
+        parsed = glsdk.parse_input(offer.bolt12)
+        assert isinstance(parsed, glsdk.ParsedInput.BOLT12_OFFER)
+        assert parsed.offer.offer_id == offer.offer_id
+        assert parsed.offer.amount_msat == 21000
+
+        offers = node.list_offers(None).offers
+        assert [o.offer_id for o in offers] == [offer.offer_id]
+
+        disabled = node.disable_offer(offer.offer_id)
+        assert not disabled.active
+        assert node.list_offers(True).offers == []
+
+        enabled = node.enable_offer(offer.offer_id)
+        assert enabled.active
+
+        node.disconnect()
+
+    def test_disable_offer_rejects_malformed_id(self, scheduler, nobody_id):
+        dev_cert = glsdk.DeveloperCert(nobody_id.cert_chain, nobody_id.private_key)
+        config = glsdk.Config().with_developer_cert(dev_cert)
+        node = glsdk.NodeBuilder(config).register_or_recover(MNEMONIC, None)
+
+        with pytest.raises(glsdk.Error.Argument):
+            node.disable_offer("not-hex")
+
+        node.disconnect()
+
+
+class TestPayOffer:
+    """Test paying an offer end to end, with the signer in the loop."""
+
+    def test_pay_offer(self, clients, node_factory, bitcoind):
+        payee = node_factory.get_node()
+        payee_id = payee.info["id"]
+
+        c = clients.new()
+        c.register(configure=True)
+        gl1 = c.node()
+        s = c.signer().run_in_thread()
+
+        # Open a channel to the payee, the invoice request and the
+        # payment go over it.
+        gl1.connect_peer(payee_id, f"127.0.0.1:{payee.daemon.port}")
+        bitcoind.rpc.sendtoaddress(gl1.new_address().bech32, 0.1)
+        bitcoind.generate_block(1, wait_for_mempool=1)
+        wait_for(lambda: len(gl1.list_funds().outputs) > 0)
+
+        from pyln import grpc as clnpb
+
+        gl1.fund_channel(
+            bytes.fromhex(payee_id),
+            clnpb.AmountOrAll(amount=clnpb.Amount(msat=1_000_000_000)),
+        )
+        bitcoind.generate_block(6, wait_for_mempool=1)
+        wait_for(
+            lambda: any(
+                ch.state == 2  # CHANNELD_NORMAL
+                for ch in gl1.list_peer_channels().channels
+            )
+        )
+
+        offer = payee.rpc.offer(amount="21000msat", description="coffee")["bolt12"]
+
+        node = glsdk.Node(glsdk.Credentials.load(c.creds().to_bytes()))
+        try:
+            res = node.pay_offer(offer, None, "thanks")
+        finally:
+            node.disconnect()
+
+        assert res.status == glsdk.PayStatus.COMPLETE
+        assert res.amount_msat == 21000
+        invoices = payee.rpc.listinvoices()["invoices"]
+        assert [i["status"] for i in invoices] == ["paid"]
+        assert invoices[0]["payment_preimage"] == res.preimage
@@ -1,7 +1,7 @@
 """Tests for the synchronous parse_input() free function.
 
 `parse_input` is offline — no HTTP, no I/O. Tests cover BOLT11
-invoices, node IDs, and the offline LNURL / Lightning Address
+invoices, BOLT12 offers, node IDs, and the offline LNURL / Lightning Address
 classification (not the HTTP resolution — that's `resolve_input`,
 exercised in test_lnurl.py against the live LNURL fixture).
 """
@@ -18,6 +18,18 @@ BOLT11_INVOICE = (
     "22mss2yexunagm5r2gqczh8k24cwrqml3njskm548aruhpwssq9nvrvz"
 )
 
+# BOLT12 offer for 21 sats, description "coffee", issuer "Satoshi's Cafe"
+BOLT12_OFFER = (
+    "lno1pqp9yzq2qe3k7enxv4j3yrjnv96x7umgdynhxgzrv9nx293pqvjx204vgdzgsqpvcp4m"
+    "ldl3plscny0rt707gvpdh6ndydfacz43c"
+)
+
+# Same issuer, "any amount" offer
+BOLT12_OFFER_ANY_AMOUNT = (
+    "lno1pgrxxmmxvejk2ysw2dshgmmndp5jwueqgdskvegkyypjgef743p5fzqq9nqxh0ah7y87"
+    "rzv3ud0eleps9kl2d5348hq2k8q"
+)
+
 # Valid compressed secp256k1 public key (starts with 02 or 03, 33 bytes = 66 hex chars)
 VALID_NODE_ID = "02eec7245d6b7d2ccb30380bfbe2a3648cd7a942653f5aa340edcea1f283686619"
 
@@ -26,6 +38,14 @@ VALID_LNURL_BECH32 = (
     "LNURL1DP68GURN8GHJ7CMFWP5X2UNSW4HXKTNRDAKJ7CTSDYHHVVF0D3H82UNV9UCSAXQZE2"
 )
 
+# Bech32-encoded LNURL-auth login URL for site.com (LUD-04)
+LNURL_AUTH_K1 = "e2af6254a8df433264fa23f67eb8188635d15ce883e8fc020989d5f82ae6f11e"
+LNURL_AUTH_BECH32 = (
+    "LNURL1DP68GURN8GHJ7UMFW3JJUCM0D5HKZAT5DQLHGCT884KX7EMFDCNXKVFAV5EXZE3KXG6N"
+    "GCFCV3NRGVENXGMRGENPXGEKVD3HV43RSVFC8QMRXDTYXY6KXEFC8QEK2WRXVVCRYVPE8QUKGD"
+    "TX8QEXZEFKVCCNZEFXV93HG6T0DC7KCMM8D9HQZ37EJU"
+)
+
 
 class TestParseInputTypes:
     """Test that parse_input types exist in the bindings."""
@@ -39,6 +59,9 @@ class TestParseInputTypes:
     def test_bolt11_invoice_type_exists(self):
         assert hasattr(glsdk, "ParsedInvoice")
 
+    def test_bolt12_offer_type_exists(self):
+        assert hasattr(glsdk, "ParsedOffer")
+
     def test_parse_input_function_exists(self):
         assert hasattr(glsdk, "parse_input")
 
@@ -92,6 +115,35 @@ class TestParseInputBolt11:
         assert isinstance(result, glsdk.ParsedInput.BOLT11)
 
 
+class TestParseInputBolt12:
+    """Test BOLT12 offer parsing — no HTTP required."""
+
+    def test_parse_valid_offer(self):
+        result = glsdk.parse_input(BOLT12_OFFER)
+        assert isinstance(result, glsdk.ParsedInput.BOLT12_OFFER)
+        assert result.offer.amount_msat == 21000
+        assert result.offer.description == "coffee"
+        assert result.offer.issuer == "Satoshi's Cafe"
+        assert len(result.offer.offer_id) == 64
+
+    def test_parse_any_amount_offer(self):
+        result = glsdk.parse_input(BOLT12_OFFER_ANY_AMOUNT)
+        assert isinstance(result, glsdk.ParsedInput.BOLT12_OFFER)
+        assert result.offer.amount_msat is None
+
+    def test_parse_offer_with_lightning_prefix(self):
+        result = glsdk.parse_input("lightning:" + BOLT12_OFFER)
+        assert isinstance(result, glsdk.ParsedInput.BOLT12_OFFER)
+
+    def test_resolve_offer_passes_through(self):
+        result = glsdk.resolve_input(BOLT12_OFFER)
+        assert isinstance(result, glsdk.ResolvedInput.BOLT12_OFFER)
+
+    def test_invalid_offer_returns_error(self):
+        with pytest.raises(glsdk.Error):
+            glsdk.parse_input("lno1notanoffer")
+
+
 class TestParseInputLnUrl:
     """Test LNURL bech32 / Lightning Address classification — offline."""
 
@@ -118,6 +170,15 @@ class TestParseInputLnUrl:
         result = glsdk.parse_input("sat.oshi-99@example.com")
         assert isinstance(result, glsdk.ParsedInput.LN_URL_ADDRESS)
 
+    def test_resolve_lnurl_auth_without_http(self):
+        # Login URLs carry their tag in the query string, so they are
+        # resolved offline.
+        result = glsdk.resolve_input(LNURL_AUTH_BECH32)
+        assert isinstance(result, glsdk.ResolvedInput.LN_URL_AUTH)
+        assert result.data.k1 == LNURL_AUTH_K1
+        assert result.data.action == "login"
+        assert result.data.domain == "site.com"
+
 
 class TestParseInputErrors:
     """Test error cases that don't require HTTP."""